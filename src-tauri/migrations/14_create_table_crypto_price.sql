@@ -0,0 +1,27 @@
+CREATE TABLE IF NOT EXISTS crypto_price (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    -- coin id as known by the crypto exchange provider e.g. 'bitcoin'
+    coin_id TEXT NOT NULL,
+    fiat_id INTEGER NOT NULL,
+    date DATE NOT NULL,
+    -- decimal stored as text to keep the precision of the provider
+    price TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (fiat_id) REFERENCES fiat (id),
+    UNIQUE (coin_id, fiat_id, date)
+);
+
+-- Create index on coin_id
+CREATE INDEX idx_crypto_price_coin_id ON crypto_price (coin_id);
+
+-- Create index on date
+CREATE INDEX idx_crypto_price_date ON crypto_price (date);
+
+-- Update updated_at column on insert and update
+CREATE TRIGGER update_crypto_price_updated_at
+    BEFORE UPDATE ON crypto_price
+    FOR EACH ROW
+    BEGIN
+        UPDATE crypto_price SET updated_at = CURRENT_TIMESTAMP WHERE id = NEW.id;
+    END;
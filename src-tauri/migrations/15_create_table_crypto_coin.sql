@@ -0,0 +1,36 @@
+-- Catalogue of coins merged from every crypto exchange provider
+CREATE TABLE IF NOT EXISTS crypto_coin (
+    -- canonical id, taken from the first provider that listed the coin
+    id TEXT PRIMARY KEY,
+    symbol TEXT NOT NULL,
+    name TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Create index on symbol
+CREATE INDEX idx_crypto_coin_symbol ON crypto_coin (symbol);
+
+-- Create index on name
+CREATE INDEX idx_crypto_coin_name ON crypto_coin (name);
+
+-- Update updated_at column on insert and update
+CREATE TRIGGER update_crypto_coin_updated_at
+    BEFORE UPDATE ON crypto_coin
+    FOR EACH ROW
+    BEGIN
+        UPDATE crypto_coin SET updated_at = CURRENT_TIMESTAMP WHERE id = NEW.id;
+    END;
+
+-- Which provider id maps to which coin of the catalogue
+CREATE TABLE IF NOT EXISTS crypto_coin_provider (
+    provider TEXT NOT NULL,
+    provider_coin_id TEXT NOT NULL,
+    coin_id TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (provider, provider_coin_id),
+    FOREIGN KEY (coin_id) REFERENCES crypto_coin (id) ON DELETE CASCADE
+);
+
+-- Create index on coin_id
+CREATE INDEX idx_crypto_coin_provider_coin_id ON crypto_coin_provider (coin_id);
@@ -0,0 +1,6 @@
+-- Link an asset to a coin of the crypto_coin catalogue (and through it to the provider coin ids)
+ALTER TABLE asset
+ADD COLUMN coin_id TEXT REFERENCES crypto_coin (id) ON DELETE SET NULL;
+
+-- Create index on coin_id
+CREATE INDEX idx_asset_coin_id ON asset (coin_id);
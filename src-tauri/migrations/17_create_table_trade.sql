@@ -0,0 +1,56 @@
+CREATE TABLE IF NOT EXISTS trade (
+    id TEXT PRIMARY KEY,
+    kind VARCHAR(4) NOT NULL CHECK (
+        kind IN ('buy', 'sell', 'swap')
+    ),
+    trade_date DATE NOT NULL DEFAULT CURRENT_DATE,
+    -- asset bought (buy), sold (sell) or given up (swap)
+    asset_id TEXT NOT NULL,
+    -- decimals are stored as text to keep their precision
+    quantity TEXT NOT NULL,
+    -- price of one unit of asset_id in fiat_id
+    price TEXT NOT NULL,
+    fiat_id INTEGER NOT NULL,
+    -- asset received in a swap
+    to_asset_id TEXT,
+    to_quantity TEXT,
+    -- fee paid in fee_asset_id, or in fiat_id when fee_asset_id is NULL
+    fee_asset_id TEXT,
+    fee_amount TEXT,
+    via_exchange VARCHAR(255),
+    fiat_ramp_id TEXT,
+    note TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (asset_id) REFERENCES asset (id),
+    FOREIGN KEY (fiat_id) REFERENCES fiat (id),
+    FOREIGN KEY (to_asset_id) REFERENCES asset (id),
+    FOREIGN KEY (fee_asset_id) REFERENCES asset (id),
+    FOREIGN KEY (fiat_ramp_id) REFERENCES fiat_ramp (id) ON DELETE SET NULL,
+    CHECK (
+        (kind = 'swap') = (to_asset_id IS NOT NULL AND to_quantity IS NOT NULL)
+    )
+);
+
+-- Create index on asset_id
+CREATE INDEX idx_trade_asset_id ON trade (asset_id);
+
+-- Create index on to_asset_id
+CREATE INDEX idx_trade_to_asset_id ON trade (to_asset_id);
+
+-- Create index on trade_date
+CREATE INDEX idx_trade_trade_date ON trade (trade_date);
+
+-- Create index on via_exchange
+CREATE INDEX idx_trade_via_exchange ON trade (via_exchange);
+
+-- Create index on fiat_ramp_id
+CREATE INDEX idx_trade_fiat_ramp_id ON trade (fiat_ramp_id);
+
+-- Update updated_at column on insert and update
+CREATE TRIGGER update_trade_updated_at
+    BEFORE UPDATE ON trade
+    FOR EACH ROW
+    BEGIN
+        UPDATE trade SET updated_at = CURRENT_TIMESTAMP WHERE id = NEW.id;
+    END;
@@ -0,0 +1,3 @@
+-- Lot matching method used to compute the cost basis of disposals
+ALTER TABLE user_settings ADD COLUMN cost_basis_method VARCHAR(16) NOT NULL DEFAULT 'fifo'
+    CHECK (cost_basis_method IN ('fifo', 'lifo', 'hifo', 'average'));
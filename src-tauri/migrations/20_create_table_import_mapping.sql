@@ -0,0 +1,17 @@
+-- Saved column mappings of the fiat ramp file import
+CREATE TABLE IF NOT EXISTS import_mapping (
+    id TEXT PRIMARY KEY,
+    name VARCHAR(255) NOT NULL UNIQUE COLLATE NOCASE,
+    -- json of the column mapping, see import::ColumnMapping
+    mapping TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Update updated_at column on insert and update
+CREATE TRIGGER update_import_mapping_updated_at
+    BEFORE UPDATE ON import_mapping
+    FOR EACH ROW
+    BEGIN
+        UPDATE import_mapping SET updated_at = CURRENT_TIMESTAMP WHERE id = NEW.id;
+    END;
@@ -0,0 +1,13 @@
+-- Reference of the ramp at the exchange, e.g. the transaction id of a statement
+ALTER TABLE fiat_ramp ADD COLUMN external_ref TEXT;
+
+-- Deterministic identity of the ramp built from its date, signed amount, currency and exchange,
+-- used to detect duplicates on re-import. Filled in by the application, NULL until then
+ALTER TABLE fiat_ramp ADD COLUMN fingerprint TEXT;
+
+-- Create index on fingerprint
+CREATE INDEX idx_fiat_ramp_fingerprint ON fiat_ramp (fingerprint);
+
+-- An external reference is unique per exchange
+CREATE UNIQUE INDEX idx_fiat_ramp_exchange_external_ref ON fiat_ramp (exchange_id, external_ref)
+WHERE external_ref IS NOT NULL;
@@ -0,0 +1,9 @@
+-- Hours between scheduled backups of the database, 0 turns them off
+ALTER TABLE user_settings ADD COLUMN backup_interval_hours INTEGER NOT NULL DEFAULT 24
+    CHECK (backup_interval_hours >= 0);
+
+-- Number of days, and of weeks, whose last backup is kept
+ALTER TABLE user_settings ADD COLUMN backup_keep_daily INTEGER NOT NULL DEFAULT 7
+    CHECK (backup_keep_daily >= 0);
+ALTER TABLE user_settings ADD COLUMN backup_keep_weekly INTEGER NOT NULL DEFAULT 4
+    CHECK (backup_keep_weekly >= 0);
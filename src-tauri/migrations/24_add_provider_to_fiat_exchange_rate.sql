@@ -0,0 +1,5 @@
+-- id of the exchanger which supplied the rates, see FiatExchanger::id
+ALTER TABLE fiat_exchange_rate ADD COLUMN provider TEXT;
+
+-- every rate so far came from Frankfurter
+UPDATE fiat_exchange_rate SET provider = 'frankfurter';
@@ -0,0 +1,146 @@
+-- Rates entered by the user, the bank may have given another rate than the providers or no
+-- provider may quote the currency
+-- - a rate of one ramp has a fiat_ramp_id and no date, a rate of a pair on a date has a date
+CREATE TABLE IF NOT EXISTS fiat_manual_rate (
+    id TEXT PRIMARY KEY,
+    from_fiat_id INTEGER NOT NULL REFERENCES fiat (id),
+    to_fiat_id INTEGER NOT NULL REFERENCES fiat (id),
+    date DATE,
+    fiat_ramp_id TEXT REFERENCES fiat_ramp (id) ON DELETE CASCADE,
+    -- amount of to_fiat for one from_fiat
+    rate REAL NOT NULL CHECK (rate > 0),
+    source VARCHAR(8) NOT NULL DEFAULT 'manual' CHECK (source = 'manual'),
+    note TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CHECK ((fiat_ramp_id IS NULL) != (date IS NULL)),
+    CHECK (from_fiat_id != to_fiat_id)
+);
+
+CREATE UNIQUE INDEX idx_fiat_manual_rate_fiat_ramp_id ON fiat_manual_rate (fiat_ramp_id)
+WHERE fiat_ramp_id IS NOT NULL;
+
+CREATE UNIQUE INDEX idx_fiat_manual_rate_pair_date ON fiat_manual_rate (from_fiat_id, to_fiat_id, date)
+WHERE date IS NOT NULL;
+
+-- Update updated_at column on update
+CREATE TRIGGER update_fiat_manual_rate_updated_at
+    BEFORE UPDATE ON fiat_manual_rate
+    FOR EACH ROW
+    BEGIN
+        UPDATE fiat_manual_rate SET updated_at = CURRENT_TIMESTAMP WHERE id = NEW.id;
+    END;
+
+-- The manual rate of each ramp in the default currency, the rate of the ramp comes before the
+-- rate of its pair on its date, then the inverse of the rate of the reversed pair
+CREATE VIEW IF NOT EXISTS fiat_ramp_manual_rate AS
+SELECT
+    fiat_ramp.id as fiat_ramp_id,
+    COALESCE(
+        by_ramp.rate,
+        by_pair.rate,
+        1.0 / by_inverse.rate
+    ) as rate
+FROM
+    fiat_ramp
+    JOIN user_settings ON user_settings.id = 1
+    LEFT JOIN fiat_manual_rate as by_ramp ON by_ramp.fiat_ramp_id = fiat_ramp.id
+    AND by_ramp.from_fiat_id = fiat_ramp.fiat_id
+    AND by_ramp.to_fiat_id = user_settings.default_fiat_id
+    LEFT JOIN fiat_manual_rate as by_pair ON by_pair.date = fiat_ramp.ramp_date
+    AND by_pair.from_fiat_id = fiat_ramp.fiat_id
+    AND by_pair.to_fiat_id = user_settings.default_fiat_id
+    LEFT JOIN fiat_manual_rate as by_inverse ON by_inverse.date = fiat_ramp.ramp_date
+    AND by_inverse.from_fiat_id = user_settings.default_fiat_id
+    AND by_inverse.to_fiat_id = fiat_ramp.fiat_id
+WHERE
+    COALESCE(by_ramp.id, by_pair.id, by_inverse.id) IS NOT NULL;
+
+-- The manual rate of a ramp comes before the provider rates
+DROP VIEW IF EXISTS fiat_ramp_view;
+
+CREATE VIEW IF NOT EXISTS fiat_ramp_view AS
+SELECT
+    t2.id as fiat_ramp_id,
+    t2.fiat_id as `from_fiat_id`,
+    t2.fiat_symbol as `from_fiat_symbol`,
+    t2.fiat_name as `from_fiat_name`,
+    t2.target_fiat_id as `to_fiat_id`,
+    t2.target_fiat_symbol as `to_fiat_symbol`,
+    t2.target_fiat_name as `to_fiat_name`,
+    t2.conversion_rate as conversion_rate,
+    t2.ramp_date as `ramp_date`,
+    t2.fiat_amount as `fiat_amount`,
+    t2.kind as `kind`,
+    t2.via_exchange as `via_exchange`,
+    t2.exchange_id as `exchange_id`,
+    COALESCE(rate.is_estimated, 0) as `is_estimated`,
+    COALESCE(rate.is_non_working_day, 0) as `is_non_working_day`,
+    rate.non_working_day_reason as `non_working_day_reason`,
+    CASE
+        WHEN t2.manual_rate IS NOT NULL THEN 'manual'
+        WHEN t2.rate_id IS NOT NULL THEN 'provider'
+    END as `rate_source`,
+    ROUND(t2.fiat_amount * t2.conversion_rate, 2) as converted_amount
+FROM (
+        SELECT
+            t1.*, CASE
+                WHEN t1.fiat_id = t1.target_fiat_id THEN 1.0
+                WHEN t1.manual_rate IS NOT NULL THEN t1.manual_rate
+                WHEN t1.direct_rate IS NOT NULL THEN t1.direct_rate
+                WHEN t1.inverse_rate > 0 THEN 1.0 / t1.inverse_rate
+                ELSE t1.usd_to_rate / t1.usd_from_rate
+            END as conversion_rate,
+            CASE
+                WHEN t1.fiat_id = t1.target_fiat_id THEN NULL
+                WHEN t1.manual_rate IS NOT NULL THEN NULL
+                WHEN t1.direct_rate IS NOT NULL THEN t1.direct_rate_id
+                WHEN t1.inverse_rate > 0 THEN t1.inverse_rate_id
+                ELSE t1.usd_rate_id
+            END as rate_id
+        FROM (
+                SELECT
+                    fiat_ramp.id,
+                    fiat_ramp.fiat_id,
+                    fiat_ramp.fiat_amount,
+                    fiat_ramp.ramp_date,
+                    fiat_ramp.kind,
+                    COALESCE(exchange.name, fiat_ramp.via_exchange) as via_exchange,
+                    fiat_ramp.exchange_id,
+                    fiat.symbol as fiat_symbol,
+                    fiat.name as fiat_name,
+                    user_settings.default_fiat_id as target_fiat_id,
+                    default_fiat.symbol as target_fiat_symbol,
+                    default_fiat.name as target_fiat_name,
+                    fiat_ramp_manual_rate.rate as manual_rate,
+                    direct.id as direct_rate_id,
+                    json_extract(
+                        direct.rates, '$.' || default_fiat.symbol
+                    ) as direct_rate,
+                    inverse.id as inverse_rate_id,
+                    json_extract(
+                        inverse.rates, '$.' || fiat.symbol
+                    ) as inverse_rate,
+                    usd.id as usd_rate_id,
+                    json_extract(usd.rates, '$.' || fiat.symbol) as usd_from_rate,
+                    json_extract(
+                        usd.rates, '$.' || default_fiat.symbol
+                    ) as usd_to_rate
+                FROM
+                    fiat_ramp
+                    JOIN fiat ON fiat.id = fiat_ramp.fiat_id
+                    LEFT JOIN exchange ON exchange.id = fiat_ramp.exchange_id
+                    JOIN user_settings ON user_settings.id = 1
+                    JOIN fiat as default_fiat ON default_fiat.id = user_settings.default_fiat_id
+                    LEFT JOIN fiat_ramp_manual_rate ON fiat_ramp_manual_rate.fiat_ramp_id = fiat_ramp.id
+                    LEFT JOIN fiat_exchange_rate as direct ON direct.base_fiat_id = fiat_ramp.fiat_id
+                    AND direct.date = fiat_ramp.ramp_date
+                    LEFT JOIN fiat_exchange_rate as inverse ON inverse.base_fiat_id = user_settings.default_fiat_id
+                    AND inverse.date = fiat_ramp.ramp_date
+                    LEFT JOIN fiat as usd_fiat ON usd_fiat.symbol = 'USD'
+                    LEFT JOIN fiat_exchange_rate as usd ON usd.base_fiat_id = usd_fiat.id
+                    AND usd.date = fiat_ramp.ramp_date
+            ) as t1
+    ) as t2
+    LEFT JOIN fiat_exchange_rate as rate ON rate.id = t2.rate_id
+ORDER BY t2.ramp_date ASC;
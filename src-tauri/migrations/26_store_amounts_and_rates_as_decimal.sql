@@ -0,0 +1,190 @@
+-- Amounts and rates are stored as decimal text like the trades, the conversions of the ramps are
+-- computed by the application in decimals and rounded to the minor units of the currency
+-- - the views give the rate of a ramp as the pair to_rate / from_rate, e.g. 1 / rate for the
+--   inverse of a rate, so that sqlite does no float arithmetic on them
+-- - the totals of fiat_ramp_summary are computed by the application, the view is dropped
+DROP VIEW IF EXISTS fiat_ramp_summary;
+
+DROP VIEW IF EXISTS fiat_ramp_view;
+
+DROP VIEW IF EXISTS fiat_ramp_manual_rate;
+
+-- the conversion is not an edit, updated_at is left as it is
+DROP TRIGGER IF EXISTS update_fiat_ramp_updated_at;
+
+DROP TRIGGER IF EXISTS update_fiat_exchange_rate_updated_at;
+
+DROP TRIGGER IF EXISTS update_fiat_manual_rate_updated_at;
+
+-- 15 significant digits drop the noise of the floats, e.g. 0.30000000000000004 becomes 0.3
+ALTER TABLE fiat_ramp ADD COLUMN fiat_amount_decimal TEXT NOT NULL DEFAULT '0';
+
+UPDATE fiat_ramp SET fiat_amount_decimal = printf('%.15g', fiat_amount);
+
+ALTER TABLE fiat_ramp DROP COLUMN fiat_amount;
+
+ALTER TABLE fiat_ramp RENAME COLUMN fiat_amount_decimal TO fiat_amount;
+
+ALTER TABLE fiat_manual_rate ADD COLUMN rate_decimal TEXT NOT NULL DEFAULT '1' CHECK (CAST(rate_decimal AS REAL) > 0);
+
+UPDATE fiat_manual_rate SET rate_decimal = printf('%.15g', rate);
+
+ALTER TABLE fiat_manual_rate DROP COLUMN rate;
+
+ALTER TABLE fiat_manual_rate RENAME COLUMN rate_decimal TO rate;
+
+-- the rates of a day become JSON strings, e.g. {"EUR": "0.85"}
+UPDATE fiat_exchange_rate SET rates = (
+    SELECT json_group_object(
+        key,
+        CASE WHEN type IN ('integer', 'real') THEN printf('%.15g', value) ELSE value END
+    )
+    FROM json_each(fiat_exchange_rate.rates)
+);
+
+-- Update updated_at column on insert and update
+CREATE TRIGGER update_fiat_ramp_updated_at
+    BEFORE UPDATE ON fiat_ramp
+    FOR EACH ROW
+    BEGIN
+        UPDATE fiat_ramp SET updated_at = CURRENT_TIMESTAMP WHERE id = NEW.id;
+    END;
+
+-- Update updated_at column on insert and update
+CREATE TRIGGER update_fiat_exchange_rate_updated_at
+    BEFORE UPDATE ON fiat_exchange_rate
+    FOR EACH ROW
+    BEGIN
+        UPDATE fiat_exchange_rate SET updated_at = CURRENT_TIMESTAMP WHERE id = NEW.id;
+    END;
+
+-- Update updated_at column on update
+CREATE TRIGGER update_fiat_manual_rate_updated_at
+    BEFORE UPDATE ON fiat_manual_rate
+    FOR EACH ROW
+    BEGIN
+        UPDATE fiat_manual_rate SET updated_at = CURRENT_TIMESTAMP WHERE id = NEW.id;
+    END;
+
+-- The manual rate of each ramp in the default currency, the rate of the ramp comes before the
+-- rate of its pair on its date, then the inverse of the rate of the reversed pair
+CREATE VIEW IF NOT EXISTS fiat_ramp_manual_rate AS
+SELECT
+    fiat_ramp.id as fiat_ramp_id,
+    CASE
+        WHEN by_ramp.id IS NOT NULL THEN by_ramp.rate
+        WHEN by_pair.id IS NOT NULL THEN by_pair.rate
+        ELSE '1'
+    END as to_rate,
+    CASE
+        WHEN by_ramp.id IS NOT NULL OR by_pair.id IS NOT NULL THEN '1'
+        ELSE by_inverse.rate
+    END as from_rate
+FROM
+    fiat_ramp
+    JOIN user_settings ON user_settings.id = 1
+    LEFT JOIN fiat_manual_rate as by_ramp ON by_ramp.fiat_ramp_id = fiat_ramp.id
+    AND by_ramp.from_fiat_id = fiat_ramp.fiat_id
+    AND by_ramp.to_fiat_id = user_settings.default_fiat_id
+    LEFT JOIN fiat_manual_rate as by_pair ON by_pair.date = fiat_ramp.ramp_date
+    AND by_pair.from_fiat_id = fiat_ramp.fiat_id
+    AND by_pair.to_fiat_id = user_settings.default_fiat_id
+    LEFT JOIN fiat_manual_rate as by_inverse ON by_inverse.date = fiat_ramp.ramp_date
+    AND by_inverse.from_fiat_id = user_settings.default_fiat_id
+    AND by_inverse.to_fiat_id = fiat_ramp.fiat_id
+WHERE
+    COALESCE(by_ramp.id, by_pair.id, by_inverse.id) IS NOT NULL;
+
+CREATE VIEW IF NOT EXISTS fiat_ramp_view AS
+SELECT
+    t2.id as fiat_ramp_id,
+    t2.fiat_id as `from_fiat_id`,
+    t2.fiat_symbol as `from_fiat_symbol`,
+    t2.fiat_name as `from_fiat_name`,
+    t2.target_fiat_id as `to_fiat_id`,
+    t2.target_fiat_symbol as `to_fiat_symbol`,
+    t2.target_fiat_name as `to_fiat_name`,
+    t2.to_rate as `to_rate`,
+    t2.from_rate as `from_rate`,
+    t2.ramp_date as `ramp_date`,
+    t2.fiat_amount as `fiat_amount`,
+    t2.kind as `kind`,
+    t2.via_exchange as `via_exchange`,
+    t2.exchange_id as `exchange_id`,
+    COALESCE(rate.is_estimated, 0) as `is_estimated`,
+    COALESCE(rate.is_non_working_day, 0) as `is_non_working_day`,
+    rate.non_working_day_reason as `non_working_day_reason`,
+    CASE
+        WHEN t2.manual_to_rate IS NOT NULL THEN 'manual'
+        WHEN t2.rate_id IS NOT NULL THEN 'provider'
+    END as `rate_source`
+FROM (
+        SELECT
+            t1.*, CASE
+                WHEN t1.fiat_id = t1.target_fiat_id THEN '1'
+                WHEN t1.manual_to_rate IS NOT NULL THEN t1.manual_to_rate
+                WHEN t1.direct_rate IS NOT NULL THEN t1.direct_rate
+                WHEN CAST(t1.inverse_rate AS REAL) > 0 THEN '1'
+                ELSE t1.usd_to_rate
+            END as to_rate,
+            CASE
+                WHEN t1.fiat_id = t1.target_fiat_id THEN '1'
+                WHEN t1.manual_to_rate IS NOT NULL THEN t1.manual_from_rate
+                WHEN t1.direct_rate IS NOT NULL THEN '1'
+                WHEN CAST(t1.inverse_rate AS REAL) > 0 THEN t1.inverse_rate
+                ELSE t1.usd_from_rate
+            END as from_rate,
+            CASE
+                WHEN t1.fiat_id = t1.target_fiat_id THEN NULL
+                WHEN t1.manual_to_rate IS NOT NULL THEN NULL
+                WHEN t1.direct_rate IS NOT NULL THEN t1.direct_rate_id
+                WHEN CAST(t1.inverse_rate AS REAL) > 0 THEN t1.inverse_rate_id
+                ELSE t1.usd_rate_id
+            END as rate_id
+        FROM (
+                SELECT
+                    fiat_ramp.id,
+                    fiat_ramp.fiat_id,
+                    fiat_ramp.fiat_amount,
+                    fiat_ramp.ramp_date,
+                    fiat_ramp.kind,
+                    COALESCE(exchange.name, fiat_ramp.via_exchange) as via_exchange,
+                    fiat_ramp.exchange_id,
+                    fiat.symbol as fiat_symbol,
+                    fiat.name as fiat_name,
+                    user_settings.default_fiat_id as target_fiat_id,
+                    default_fiat.symbol as target_fiat_symbol,
+                    default_fiat.name as target_fiat_name,
+                    fiat_ramp_manual_rate.to_rate as manual_to_rate,
+                    fiat_ramp_manual_rate.from_rate as manual_from_rate,
+                    direct.id as direct_rate_id,
+                    json_extract(
+                        direct.rates, '$.' || default_fiat.symbol
+                    ) as direct_rate,
+                    inverse.id as inverse_rate_id,
+                    json_extract(
+                        inverse.rates, '$.' || fiat.symbol
+                    ) as inverse_rate,
+                    usd.id as usd_rate_id,
+                    json_extract(usd.rates, '$.' || fiat.symbol) as usd_from_rate,
+                    json_extract(
+                        usd.rates, '$.' || default_fiat.symbol
+                    ) as usd_to_rate
+                FROM
+                    fiat_ramp
+                    JOIN fiat ON fiat.id = fiat_ramp.fiat_id
+                    LEFT JOIN exchange ON exchange.id = fiat_ramp.exchange_id
+                    JOIN user_settings ON user_settings.id = 1
+                    JOIN fiat as default_fiat ON default_fiat.id = user_settings.default_fiat_id
+                    LEFT JOIN fiat_ramp_manual_rate ON fiat_ramp_manual_rate.fiat_ramp_id = fiat_ramp.id
+                    LEFT JOIN fiat_exchange_rate as direct ON direct.base_fiat_id = fiat_ramp.fiat_id
+                    AND direct.date = fiat_ramp.ramp_date
+                    LEFT JOIN fiat_exchange_rate as inverse ON inverse.base_fiat_id = user_settings.default_fiat_id
+                    AND inverse.date = fiat_ramp.ramp_date
+                    LEFT JOIN fiat as usd_fiat ON usd_fiat.symbol = 'USD'
+                    LEFT JOIN fiat_exchange_rate as usd ON usd.base_fiat_id = usd_fiat.id
+                    AND usd.date = fiat_ramp.ramp_date
+            ) as t1
+    ) as t2
+    LEFT JOIN fiat_exchange_rate as rate ON rate.id = t2.rate_id
+ORDER BY t2.ramp_date ASC;
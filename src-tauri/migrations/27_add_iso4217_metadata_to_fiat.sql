@@ -0,0 +1,15 @@
+-- ISO 4217 metadata of the currencies, filled in by FiatService::update_currencies
+-- - numeric code with its leading zeros, e.g. '008'
+-- - decimal places of the minor unit, amounts in the currency are rounded to them
+-- - display symbol, e.g. '€', NULL outside ISO 4217
+-- - 0 once the currency was replaced, e.g. HRK by EUR
+ALTER TABLE fiat ADD COLUMN numeric_code TEXT;
+
+ALTER TABLE fiat ADD COLUMN minor_units INTEGER NOT NULL DEFAULT 2 CHECK (minor_units >= 0);
+
+ALTER TABLE fiat ADD COLUMN display_symbol TEXT;
+
+ALTER TABLE fiat ADD COLUMN is_active BOOLEAN NOT NULL DEFAULT 1;
+
+-- the currencies are updated again on the next start to fill it in
+DELETE FROM sys_tracker WHERE name = 'fiat';
@@ -0,0 +1,142 @@
+-- The peg of a stablecoin, a ramp in the stablecoin is recorded in the fiat it is pegged to and
+-- converted from it like any ramp in that fiat
+CREATE TABLE IF NOT EXISTS stablecoin_peg (
+    asset_id TEXT PRIMARY KEY REFERENCES asset (id) ON DELETE CASCADE,
+    fiat_id INTEGER NOT NULL REFERENCES fiat (id),
+    -- 'peg' prices a coin at the rate below, 'market' at the price of the coin of the asset in
+    -- crypto_price on the date of the ramp
+    price_source VARCHAR(6) NOT NULL DEFAULT 'peg' CHECK (price_source IN ('peg', 'market')),
+    -- amount of the fiat for one coin, decimal stored as text
+    rate TEXT NOT NULL DEFAULT '1' CHECK (CAST(rate AS REAL) > 0),
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Update updated_at column on update
+CREATE TRIGGER update_stablecoin_peg_updated_at
+    BEFORE UPDATE ON stablecoin_peg
+    FOR EACH ROW
+    BEGIN
+        UPDATE stablecoin_peg SET updated_at = CURRENT_TIMESTAMP WHERE asset_id = NEW.asset_id;
+    END;
+
+-- The stablecoin a ramp was made in, fiat_id is then the fiat of its peg
+ALTER TABLE fiat_ramp ADD COLUMN asset_id TEXT REFERENCES asset (id);
+
+-- Create index on asset_id
+CREATE INDEX idx_fiat_ramp_asset_id ON fiat_ramp (asset_id);
+
+-- the view gives asset_rate, the amount of the fiat for one coin of the ramp, '1' for a ramp in
+-- fiat, the conversion is fiat_amount * asset_rate * to_rate / from_rate
+DROP VIEW IF EXISTS fiat_ramp_view;
+
+CREATE VIEW IF NOT EXISTS fiat_ramp_view AS
+SELECT
+    t2.id as fiat_ramp_id,
+    t2.fiat_id as `from_fiat_id`,
+    t2.fiat_symbol as `from_fiat_symbol`,
+    t2.fiat_name as `from_fiat_name`,
+    t2.target_fiat_id as `to_fiat_id`,
+    t2.target_fiat_symbol as `to_fiat_symbol`,
+    t2.target_fiat_name as `to_fiat_name`,
+    t2.to_rate as `to_rate`,
+    t2.from_rate as `from_rate`,
+    t2.asset_id as `asset_id`,
+    t2.asset_symbol as `asset_symbol`,
+    t2.asset_name as `asset_name`,
+    t2.asset_rate as `asset_rate`,
+    t2.ramp_date as `ramp_date`,
+    t2.fiat_amount as `fiat_amount`,
+    t2.kind as `kind`,
+    t2.via_exchange as `via_exchange`,
+    t2.exchange_id as `exchange_id`,
+    COALESCE(rate.is_estimated, 0) as `is_estimated`,
+    COALESCE(rate.is_non_working_day, 0) as `is_non_working_day`,
+    rate.non_working_day_reason as `non_working_day_reason`,
+    CASE
+        WHEN t2.manual_to_rate IS NOT NULL THEN 'manual'
+        WHEN t2.rate_id IS NOT NULL THEN 'provider'
+    END as `rate_source`
+FROM (
+        SELECT
+            t1.*, CASE
+                WHEN t1.fiat_id = t1.target_fiat_id THEN '1'
+                WHEN t1.manual_to_rate IS NOT NULL THEN t1.manual_to_rate
+                WHEN t1.direct_rate IS NOT NULL THEN t1.direct_rate
+                WHEN CAST(t1.inverse_rate AS REAL) > 0 THEN '1'
+                ELSE t1.usd_to_rate
+            END as to_rate,
+            CASE
+                WHEN t1.fiat_id = t1.target_fiat_id THEN '1'
+                WHEN t1.manual_to_rate IS NOT NULL THEN t1.manual_from_rate
+                WHEN t1.direct_rate IS NOT NULL THEN '1'
+                WHEN CAST(t1.inverse_rate AS REAL) > 0 THEN t1.inverse_rate
+                ELSE t1.usd_from_rate
+            END as from_rate,
+            CASE
+                WHEN t1.fiat_id = t1.target_fiat_id THEN NULL
+                WHEN t1.manual_to_rate IS NOT NULL THEN NULL
+                WHEN t1.direct_rate IS NOT NULL THEN t1.direct_rate_id
+                WHEN CAST(t1.inverse_rate AS REAL) > 0 THEN t1.inverse_rate_id
+                ELSE t1.usd_rate_id
+            END as rate_id
+        FROM (
+                SELECT
+                    fiat_ramp.id,
+                    fiat_ramp.fiat_id,
+                    fiat_ramp.fiat_amount,
+                    fiat_ramp.ramp_date,
+                    fiat_ramp.kind,
+                    COALESCE(exchange.name, fiat_ramp.via_exchange) as via_exchange,
+                    fiat_ramp.exchange_id,
+                    fiat_ramp.asset_id,
+                    asset.symbol as asset_symbol,
+                    asset.name as asset_name,
+                    CASE
+                        WHEN fiat_ramp.asset_id IS NULL THEN '1'
+                        WHEN stablecoin_peg.price_source = 'market' THEN crypto_price.price
+                        ELSE stablecoin_peg.rate
+                    END as asset_rate,
+                    fiat.symbol as fiat_symbol,
+                    fiat.name as fiat_name,
+                    user_settings.default_fiat_id as target_fiat_id,
+                    default_fiat.symbol as target_fiat_symbol,
+                    default_fiat.name as target_fiat_name,
+                    fiat_ramp_manual_rate.to_rate as manual_to_rate,
+                    fiat_ramp_manual_rate.from_rate as manual_from_rate,
+                    direct.id as direct_rate_id,
+                    json_extract(
+                        direct.rates, '$.' || default_fiat.symbol
+                    ) as direct_rate,
+                    inverse.id as inverse_rate_id,
+                    json_extract(
+                        inverse.rates, '$.' || fiat.symbol
+                    ) as inverse_rate,
+                    usd.id as usd_rate_id,
+                    json_extract(usd.rates, '$.' || fiat.symbol) as usd_from_rate,
+                    json_extract(
+                        usd.rates, '$.' || default_fiat.symbol
+                    ) as usd_to_rate
+                FROM
+                    fiat_ramp
+                    JOIN fiat ON fiat.id = fiat_ramp.fiat_id
+                    LEFT JOIN exchange ON exchange.id = fiat_ramp.exchange_id
+                    LEFT JOIN asset ON asset.id = fiat_ramp.asset_id
+                    LEFT JOIN stablecoin_peg ON stablecoin_peg.asset_id = fiat_ramp.asset_id
+                    LEFT JOIN crypto_price ON crypto_price.coin_id = asset.coin_id
+                    AND crypto_price.fiat_id = fiat_ramp.fiat_id
+                    AND crypto_price.date = fiat_ramp.ramp_date
+                    JOIN user_settings ON user_settings.id = 1
+                    JOIN fiat as default_fiat ON default_fiat.id = user_settings.default_fiat_id
+                    LEFT JOIN fiat_ramp_manual_rate ON fiat_ramp_manual_rate.fiat_ramp_id = fiat_ramp.id
+                    LEFT JOIN fiat_exchange_rate as direct ON direct.base_fiat_id = fiat_ramp.fiat_id
+                    AND direct.date = fiat_ramp.ramp_date
+                    LEFT JOIN fiat_exchange_rate as inverse ON inverse.base_fiat_id = user_settings.default_fiat_id
+                    AND inverse.date = fiat_ramp.ramp_date
+                    LEFT JOIN fiat as usd_fiat ON usd_fiat.symbol = 'USD'
+                    LEFT JOIN fiat_exchange_rate as usd ON usd.base_fiat_id = usd_fiat.id
+                    AND usd.date = fiat_ramp.ramp_date
+            ) as t1
+    ) as t2
+    LEFT JOIN fiat_exchange_rate as rate ON rate.id = t2.rate_id
+ORDER BY t2.ramp_date ASC;
@@ -0,0 +1,42 @@
+-- Template of a ramp repeated on a schedule, e.g. a monthly deposit
+-- - its ramps are created on app start up to the current date, or proposed for confirmation
+CREATE TABLE IF NOT EXISTS recurring_ramp (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    fiat_id INTEGER NOT NULL REFERENCES fiat (id),
+    asset_id TEXT REFERENCES asset (id),
+    -- decimals stored as text
+    fiat_amount TEXT NOT NULL CHECK (CAST(fiat_amount AS REAL) > 0),
+    fee_fiat_id INTEGER REFERENCES fiat (id),
+    fee_amount TEXT CHECK (CAST(fee_amount AS REAL) >= 0),
+    via_exchange TEXT NOT NULL,
+    exchange_id TEXT REFERENCES exchange (id),
+    kind VARCHAR(8) NOT NULL CHECK (kind IN ('deposit', 'withdraw')),
+    -- RRULE of RFC 5545 without DTSTART, e.g. FREQ=MONTHLY;BYMONTHDAY=1
+    rrule TEXT NOT NULL,
+    start_date DATE NOT NULL,
+    -- the occurrences are proposed rather than created
+    needs_confirmation BOOLEAN NOT NULL DEFAULT 0,
+    is_paused BOOLEAN NOT NULL DEFAULT 0,
+    -- the occurrences up to this date are created or proposed already
+    materialized_until DATE,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Update updated_at column on update
+CREATE TRIGGER update_recurring_ramp_updated_at
+    BEFORE UPDATE ON recurring_ramp
+    FOR EACH ROW
+    BEGIN
+        UPDATE recurring_ramp SET updated_at = CURRENT_TIMESTAMP WHERE id = NEW.id;
+    END;
+
+-- Occurrences of a recurring ramp waiting to be confirmed or dismissed
+CREATE TABLE IF NOT EXISTS recurring_ramp_proposal (
+    id TEXT PRIMARY KEY,
+    recurring_ramp_id TEXT NOT NULL REFERENCES recurring_ramp (id) ON DELETE CASCADE,
+    ramp_date DATE NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (recurring_ramp_id, ramp_date)
+);
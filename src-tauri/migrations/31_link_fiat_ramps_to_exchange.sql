@@ -0,0 +1,4 @@
+-- Link the fiat ramps recorded with a free-text exchange name to an exchange, once
+-- - done by ExchangeService::link_unlinked_once on app start, names are matched typos included
+-- - clearing the tracker makes it run again on databases linked before this migration
+DELETE FROM sys_tracker WHERE name = 'exchange_link';
@@ -0,0 +1,8 @@
+-- Link trades to an exchange rather than a free-text name, via_exchange is kept as a copy of the
+-- canonical name like for fiat ramps
+-- - existing trades are linked on app start with the fiat ramps, see migration 31
+ALTER TABLE trade ADD COLUMN exchange_id TEXT REFERENCES exchange (id);
+
+CREATE INDEX idx_trade_exchange_id ON trade (exchange_id);
+
+DELETE FROM sys_tracker WHERE name = 'exchange_link';
@@ -0,0 +1,13 @@
+-- Reference of the trade at the exchange, e.g. the order or ledger reference of a statement
+ALTER TABLE trade ADD COLUMN external_ref TEXT;
+
+-- Deterministic identity of the trade built from its date, kind, assets, quantities, price and
+-- exchange, used to detect duplicates on re-import. Filled in by the application, NULL until then
+ALTER TABLE trade ADD COLUMN fingerprint TEXT;
+
+-- Create index on fingerprint
+CREATE INDEX idx_trade_fingerprint ON trade (fingerprint);
+
+-- An external reference is unique per exchange
+CREATE UNIQUE INDEX idx_trade_exchange_external_ref ON trade (exchange_id, external_ref)
+WHERE external_ref IS NOT NULL;
@@ -0,0 +1,11 @@
+-- Rates a backfill requested from the providers, in the rates based on base_fiat_id of a date,
+-- for the quote_fiat_id, the default fiat at the time
+-- - a date whose rates lack the quote is not requested again on every start
+-- - a date is requested again for another default fiat
+CREATE TABLE IF NOT EXISTS fiat_rate_attempt (
+    base_fiat_id INTEGER NOT NULL REFERENCES fiat (id) ON DELETE CASCADE,
+    date DATE NOT NULL,
+    quote_fiat_id INTEGER NOT NULL REFERENCES fiat (id) ON DELETE CASCADE,
+    attempted_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (base_fiat_id, date, quote_fiat_id)
+);
@@ -0,0 +1,146 @@
+-- The view gives the minor units of the default fiat, the converted amounts are rounded to the
+-- ones stored in fiat.minor_units rather than to a table built into the app
+DROP VIEW IF EXISTS fiat_ramp_view;
+
+CREATE VIEW IF NOT EXISTS fiat_ramp_view AS
+SELECT
+    t2.id as fiat_ramp_id,
+    t2.fiat_id as `from_fiat_id`,
+    t2.fiat_symbol as `from_fiat_symbol`,
+    t2.fiat_name as `from_fiat_name`,
+    t2.target_fiat_id as `to_fiat_id`,
+    t2.target_fiat_symbol as `to_fiat_symbol`,
+    t2.target_fiat_name as `to_fiat_name`,
+    t2.target_fiat_minor_units as `to_fiat_minor_units`,
+    t2.to_rate as `to_rate`,
+    t2.from_rate as `from_rate`,
+    t2.asset_id as `asset_id`,
+    t2.asset_symbol as `asset_symbol`,
+    t2.asset_name as `asset_name`,
+    t2.asset_rate as `asset_rate`,
+    t2.fee_amount as `fee_amount`,
+    t2.fee_fiat_id as `fee_fiat_id`,
+    t2.fee_fiat_symbol as `fee_fiat_symbol`,
+    t2.fee_to_rate as `fee_to_rate`,
+    t2.fee_from_rate as `fee_from_rate`,
+    t2.ramp_date as `ramp_date`,
+    t2.fiat_amount as `fiat_amount`,
+    t2.kind as `kind`,
+    t2.via_exchange as `via_exchange`,
+    t2.exchange_id as `exchange_id`,
+    COALESCE(rate.is_estimated, 0) as `is_estimated`,
+    COALESCE(rate.is_non_working_day, 0) as `is_non_working_day`,
+    rate.non_working_day_reason as `non_working_day_reason`,
+    CASE
+        WHEN t2.manual_to_rate IS NOT NULL THEN 'manual'
+        WHEN t2.rate_id IS NOT NULL THEN 'provider'
+    END as `rate_source`
+FROM (
+        SELECT
+            t1.*, CASE
+                WHEN t1.fiat_id = t1.target_fiat_id THEN '1'
+                WHEN t1.manual_to_rate IS NOT NULL THEN t1.manual_to_rate
+                WHEN t1.direct_rate IS NOT NULL THEN t1.direct_rate
+                WHEN CAST(t1.inverse_rate AS REAL) > 0 THEN '1'
+            END as to_rate,
+            CASE
+                WHEN t1.fiat_id = t1.target_fiat_id THEN '1'
+                WHEN t1.manual_to_rate IS NOT NULL THEN t1.manual_from_rate
+                WHEN t1.direct_rate IS NOT NULL THEN '1'
+                WHEN CAST(t1.inverse_rate AS REAL) > 0 THEN t1.inverse_rate
+            END as from_rate,
+            CASE
+                WHEN t1.fiat_id = t1.target_fiat_id THEN NULL
+                WHEN t1.manual_to_rate IS NOT NULL THEN NULL
+                WHEN t1.direct_rate IS NOT NULL THEN t1.direct_rate_id
+                WHEN CAST(t1.inverse_rate AS REAL) > 0 THEN t1.inverse_rate_id
+            END as rate_id,
+            CASE
+                WHEN t1.fee_fiat_id IS NULL THEN NULL
+                WHEN t1.fee_fiat_id = t1.target_fiat_id THEN '1'
+                WHEN t1.fee_manual_rate IS NOT NULL THEN t1.fee_manual_rate
+                WHEN t1.fee_manual_inverse_rate IS NOT NULL THEN '1'
+                WHEN t1.fee_direct_rate IS NOT NULL THEN t1.fee_direct_rate
+                WHEN CAST(t1.fee_inverse_rate AS REAL) > 0 THEN '1'
+            END as fee_to_rate,
+            CASE
+                WHEN t1.fee_fiat_id IS NULL THEN NULL
+                WHEN t1.fee_fiat_id = t1.target_fiat_id THEN '1'
+                WHEN t1.fee_manual_rate IS NOT NULL THEN '1'
+                WHEN t1.fee_manual_inverse_rate IS NOT NULL THEN t1.fee_manual_inverse_rate
+                WHEN t1.fee_direct_rate IS NOT NULL THEN '1'
+                WHEN CAST(t1.fee_inverse_rate AS REAL) > 0 THEN t1.fee_inverse_rate
+            END as fee_from_rate
+        FROM (
+                SELECT
+                    fiat_ramp.id,
+                    fiat_ramp.fiat_id,
+                    fiat_ramp.fiat_amount,
+                    fiat_ramp.ramp_date,
+                    fiat_ramp.kind,
+                    COALESCE(exchange.name, fiat_ramp.via_exchange) as via_exchange,
+                    fiat_ramp.exchange_id,
+                    fiat_ramp.asset_id,
+                    asset.symbol as asset_symbol,
+                    asset.name as asset_name,
+                    CASE
+                        WHEN fiat_ramp.asset_id IS NULL THEN '1'
+                        WHEN stablecoin_peg.price_source = 'market' THEN crypto_price.price
+                        ELSE stablecoin_peg.rate
+                    END as asset_rate,
+                    fiat_ramp.fee_amount,
+                    fiat_ramp.fee_fiat_id,
+                    fee_fiat.symbol as fee_fiat_symbol,
+                    fee_by_pair.rate as fee_manual_rate,
+                    fee_by_inverse.rate as fee_manual_inverse_rate,
+                    json_extract(
+                        fee_direct.rates, '$.' || default_fiat.symbol
+                    ) as fee_direct_rate,
+                    json_extract(
+                        inverse.rates, '$.' || fee_fiat.symbol
+                    ) as fee_inverse_rate,
+                    fiat.symbol as fiat_symbol,
+                    fiat.name as fiat_name,
+                    user_settings.default_fiat_id as target_fiat_id,
+                    default_fiat.symbol as target_fiat_symbol,
+                    default_fiat.name as target_fiat_name,
+                    default_fiat.minor_units as target_fiat_minor_units,
+                    fiat_ramp_manual_rate.to_rate as manual_to_rate,
+                    fiat_ramp_manual_rate.from_rate as manual_from_rate,
+                    direct.id as direct_rate_id,
+                    json_extract(
+                        direct.rates, '$.' || default_fiat.symbol
+                    ) as direct_rate,
+                    inverse.id as inverse_rate_id,
+                    json_extract(
+                        inverse.rates, '$.' || fiat.symbol
+                    ) as inverse_rate
+                FROM
+                    fiat_ramp
+                    JOIN fiat ON fiat.id = fiat_ramp.fiat_id
+                    LEFT JOIN exchange ON exchange.id = fiat_ramp.exchange_id
+                    LEFT JOIN asset ON asset.id = fiat_ramp.asset_id
+                    LEFT JOIN stablecoin_peg ON stablecoin_peg.asset_id = fiat_ramp.asset_id
+                    LEFT JOIN crypto_price ON crypto_price.coin_id = asset.coin_id
+                    AND crypto_price.fiat_id = fiat_ramp.fiat_id
+                    AND crypto_price.date = fiat_ramp.ramp_date
+                    JOIN user_settings ON user_settings.id = 1
+                    JOIN fiat as default_fiat ON default_fiat.id = user_settings.default_fiat_id
+                    LEFT JOIN fiat_ramp_manual_rate ON fiat_ramp_manual_rate.fiat_ramp_id = fiat_ramp.id
+                    LEFT JOIN fiat_exchange_rate as direct ON direct.base_fiat_id = fiat_ramp.fiat_id
+                    AND direct.date = fiat_ramp.ramp_date
+                    LEFT JOIN fiat_exchange_rate as inverse ON inverse.base_fiat_id = user_settings.default_fiat_id
+                    AND inverse.date = fiat_ramp.ramp_date
+                    LEFT JOIN fiat as fee_fiat ON fee_fiat.id = fiat_ramp.fee_fiat_id
+                    LEFT JOIN fiat_manual_rate as fee_by_pair ON fee_by_pair.date = fiat_ramp.ramp_date
+                    AND fee_by_pair.from_fiat_id = fiat_ramp.fee_fiat_id
+                    AND fee_by_pair.to_fiat_id = user_settings.default_fiat_id
+                    LEFT JOIN fiat_manual_rate as fee_by_inverse ON fee_by_inverse.date = fiat_ramp.ramp_date
+                    AND fee_by_inverse.from_fiat_id = user_settings.default_fiat_id
+                    AND fee_by_inverse.to_fiat_id = fiat_ramp.fee_fiat_id
+                    LEFT JOIN fiat_exchange_rate as fee_direct ON fee_direct.base_fiat_id = fiat_ramp.fee_fiat_id
+                    AND fee_direct.date = fiat_ramp.ramp_date
+            ) as t1
+    ) as t2
+    LEFT JOIN fiat_exchange_rate as rate ON rate.id = t2.rate_id
+ORDER BY t2.ramp_date ASC;
@@ -0,0 +1,7 @@
+-- The recurring ramp a ramp was created from, at most one ramp per recurring ramp and date so
+-- that an occurrence materialised or confirmed twice is not recorded twice
+-- - the ramps are kept when their recurring ramp is deleted
+ALTER TABLE fiat_ramp ADD COLUMN recurring_ramp_id TEXT REFERENCES recurring_ramp (id) ON DELETE SET NULL;
+
+CREATE UNIQUE INDEX idx_fiat_ramp_recurring_ramp_date ON fiat_ramp (recurring_ramp_id, ramp_date)
+WHERE recurring_ramp_id IS NOT NULL;
@@ -0,0 +1,91 @@
+use crate::asset::peg::{SetStablecoinPeg, StablecoinPeg, StablecoinPegService};
+use crate::asset::{Asset, AssetService, CreateAsset, UpdateAsset};
+use crate::crypto_exchange::CoinProviderMapping;
+use crate::db::{Db, StringRowId};
+use tauri::State;
+
+#[tauri::command]
+pub async fn create_asset(create_asset: CreateAsset, db: State<'_, Db>) -> Result<Asset, String> {
+    AssetService::create(create_asset, &db)
+        .await
+        .map_err(|e| format!("failed to create asset: {e:#}"))
+}
+
+#[tauri::command]
+pub async fn get_assets(db: State<'_, Db>) -> Result<Vec<Asset>, String> {
+    AssetService::get_all(&db)
+        .await
+        .map_err(|e| format!("failed to get assets: {e}"))
+}
+
+#[tauri::command]
+pub async fn get_asset_by_symbol(symbol: String, db: State<'_, Db>) -> Result<Asset, String> {
+    AssetService::get_by_symbol(&symbol, &db)
+        .await
+        .map_err(|e| format!("failed to get asset by symbol: {e}"))
+}
+
+#[tauri::command]
+pub async fn update_asset(asset: UpdateAsset, db: State<'_, Db>) -> Result<Asset, String> {
+    AssetService::update(asset, &db)
+        .await
+        .map_err(|e| format!("failed to update asset: {e:#}"))
+}
+
+#[tauri::command]
+pub async fn delete_asset(id: StringRowId, db: State<'_, Db>) -> Result<u64, String> {
+    AssetService::delete(&id, &db)
+        .await
+        .map_err(|e| format!("failed to delete asset: {e}"))
+}
+
+/// Link an asset to a coin of the coin catalogue -- coin_id None removes the link
+#[tauri::command]
+pub async fn link_asset_coin(
+    id: StringRowId,
+    coin_id: Option<String>,
+    db: State<'_, Db>,
+) -> Result<Asset, String> {
+    AssetService::link_coin(&id, coin_id.as_deref(), &db)
+        .await
+        .map_err(|e| format!("failed to link asset to coin: {e:#}"))
+}
+
+/// Get the provider specific coin ids of an asset
+#[tauri::command]
+pub async fn get_asset_provider_coin_ids(
+    id: StringRowId,
+    db: State<'_, Db>,
+) -> Result<Vec<CoinProviderMapping>, String> {
+    AssetService::get_provider_coin_ids(&id, &db)
+        .await
+        .map_err(|e| format!("failed to get asset provider coin ids: {e}"))
+}
+
+/// Set how a stablecoin is priced in fiat, its ramps are moved to the fiat of the peg
+#[tauri::command]
+pub async fn set_stablecoin_peg(
+    peg: SetStablecoinPeg,
+    db: State<'_, Db>,
+) -> Result<StablecoinPeg, String> {
+    StablecoinPegService::set(peg, &db)
+        .await
+        .map_err(|e| format!("failed to set stablecoin peg: {e:#}"))
+}
+
+#[tauri::command]
+pub async fn get_stablecoin_pegs(db: State<'_, Db>) -> Result<Vec<StablecoinPeg>, String> {
+    StablecoinPegService::get_all(&db)
+        .await
+        .map_err(|e| format!("failed to get stablecoin pegs: {e:#}"))
+}
+
+#[tauri::command]
+pub async fn delete_stablecoin_peg(
+    asset_id: StringRowId,
+    db: State<'_, Db>,
+) -> Result<u64, String> {
+    StablecoinPegService::delete(&asset_id, &db)
+        .await
+        .map_err(|e| format!("failed to delete stablecoin peg: {e:#}"))
+}
@@ -0,0 +1,262 @@
+pub mod command;
+pub mod peg;
+use crate::crypto_exchange::{catalogue, CoinProviderMapping};
+use crate::db::{Db, StringRowId};
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use uuid::Uuid;
+
+#[derive(Debug, Serialize, Deserialize, sqlx::Type, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum AssetKind {
+    Stablecoin,
+    Cryptocoin,
+    Nft,
+}
+
+#[derive(Debug, FromRow, Serialize, Deserialize, Clone)]
+pub struct Asset {
+    pub id: StringRowId,
+    pub name: String,
+    pub symbol: String,
+    pub kind: AssetKind,
+    /// coin of the `crypto_coin` catalogue this asset is priced with
+    pub coin_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CreateAsset {
+    pub name: String,
+    pub symbol: String,
+    pub kind: AssetKind,
+    pub coin_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateAsset {
+    pub id: StringRowId,
+    pub name: Option<String>,
+    pub symbol: Option<String>,
+    pub kind: Option<AssetKind>,
+}
+
+pub struct AssetService {}
+
+impl AssetService {
+    /// Create a new asset
+    /// - symbol is stored in upper case and must be unique
+    pub async fn create(create_asset: CreateAsset, db: &Db) -> Result<Asset> {
+        if let Some(coin_id) = &create_asset.coin_id {
+            ensure_coin_exists(db, coin_id).await?;
+        }
+
+        let id = Uuid::now_v7().to_string();
+        sqlx::query_as::<sqlx::Sqlite, Asset>(
+            r#"
+            INSERT INTO asset (id, name, symbol, kind, coin_id)
+            VALUES (?, ?, ?, ?, ?)
+            RETURNING *
+            "#,
+        )
+        .bind(&id)
+        .bind(create_asset.name.trim())
+        .bind(normalize_symbol(&create_asset.symbol))
+        .bind(create_asset.kind)
+        .bind(create_asset.coin_id)
+        .fetch_one(&db.0)
+        .await
+        .context("failed to insert into asset table")
+    }
+
+    /// Get all assets ordered by symbol
+    pub async fn get_all(db: &Db) -> Result<Vec<Asset>> {
+        sqlx::query_as::<sqlx::Sqlite, Asset>("SELECT * FROM asset ORDER BY symbol")
+            .fetch_all(&db.0)
+            .await
+            .context("failed to get all assets")
+    }
+
+    /// Get the asset by id
+    pub async fn get_by_id(id: &str, db: &Db) -> Result<Asset> {
+        sqlx::query_as::<sqlx::Sqlite, Asset>("SELECT * FROM asset WHERE id = ?")
+            .bind(id)
+            .fetch_one(&db.0)
+            .await
+            .context("failed to get asset by id")
+    }
+
+    /// Get the asset by symbol -- case insensitive
+    pub async fn get_by_symbol(symbol: &str, db: &Db) -> Result<Asset> {
+        sqlx::query_as::<sqlx::Sqlite, Asset>("SELECT * FROM asset WHERE symbol = ?")
+            .bind(normalize_symbol(symbol))
+            .fetch_one(&db.0)
+            .await
+            .context("failed to get asset by symbol")
+    }
+
+    /// Update the asset, fields left as None are kept
+    pub async fn update(update_asset: UpdateAsset, db: &Db) -> Result<Asset> {
+        sqlx::query_as::<sqlx::Sqlite, Asset>(
+            r#"
+            UPDATE asset
+            SET
+            name = COALESCE(?, name),
+            symbol = COALESCE(?, symbol),
+            kind = COALESCE(?, kind)
+            WHERE id = ?
+            RETURNING *
+            "#,
+        )
+        .bind(update_asset.name.as_deref().map(str::trim))
+        .bind(update_asset.symbol.as_deref().map(normalize_symbol))
+        .bind(update_asset.kind)
+        .bind(update_asset.id)
+        .fetch_one(&db.0)
+        .await
+        .context("failed to update asset table")
+    }
+
+    /// Delete the asset
+    /// - returns the number of rows affected
+    pub async fn delete(id: &str, db: &Db) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM asset WHERE id = ?")
+            .bind(id)
+            .execute(&db.0)
+            .await
+            .context("failed to delete from asset table")?;
+        Ok(result.rows_affected())
+    }
+
+    /// Link the asset to a coin of the `crypto_coin` catalogue, None removes the link
+    pub async fn link_coin(id: &str, coin_id: Option<&str>, db: &Db) -> Result<Asset> {
+        if let Some(coin_id) = coin_id {
+            ensure_coin_exists(db, coin_id).await?;
+        }
+
+        sqlx::query_as::<sqlx::Sqlite, Asset>(
+            "UPDATE asset SET coin_id = ? WHERE id = ? RETURNING *",
+        )
+        .bind(coin_id)
+        .bind(id)
+        .fetch_one(&db.0)
+        .await
+        .context("failed to link asset to coin")
+    }
+
+    /// Get the provider specific coin ids of the asset, empty if the asset is not linked to a coin
+    pub async fn get_provider_coin_ids(id: &str, db: &Db) -> Result<Vec<CoinProviderMapping>> {
+        let asset = Self::get_by_id(id, db).await?;
+        match asset.coin_id {
+            Some(coin_id) => catalogue::get_provider_mappings(db, &coin_id).await,
+            None => Ok(Vec::new()),
+        }
+    }
+}
+
+fn normalize_symbol(symbol: &str) -> String {
+    symbol.trim().to_uppercase()
+}
+
+async fn ensure_coin_exists(db: &Db, coin_id: &str) -> Result<()> {
+    if catalogue::get_coin_by_id(db, coin_id).await?.is_none() {
+        bail!("coin '{coin_id}' is not in the coin catalogue");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto_exchange::SupportedCoin;
+
+    async fn init_db() -> Db {
+        let db = Db::in_memory().await.unwrap();
+        catalogue::merge_provider_coins(
+            &db,
+            "coingecko",
+            vec![SupportedCoin {
+                id: "bitcoin".to_string(),
+                symbol: "btc".to_string(),
+                name: "Bitcoin".to_string(),
+            }],
+        )
+        .await
+        .unwrap();
+        db
+    }
+
+    fn create_btc() -> CreateAsset {
+        CreateAsset {
+            name: "Bitcoin".to_string(),
+            symbol: "btc".to_string(),
+            kind: AssetKind::Cryptocoin,
+            coin_id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_and_get_by_symbol() {
+        let db = init_db().await;
+        let asset = AssetService::create(create_btc(), &db).await.unwrap();
+        assert_eq!(asset.symbol, "BTC");
+
+        let found = AssetService::get_by_symbol("Btc", &db).await.unwrap();
+        assert_eq!(found.id, asset.id);
+        assert_eq!(found.kind, AssetKind::Cryptocoin);
+
+        // symbol is unique
+        assert!(AssetService::create(create_btc(), &db).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_update_and_delete() {
+        let db = init_db().await;
+        let asset = AssetService::create(create_btc(), &db).await.unwrap();
+
+        let updated = AssetService::update(
+            UpdateAsset {
+                id: asset.id.clone(),
+                name: Some("Bitcoin Core".to_string()),
+                symbol: None,
+                kind: None,
+            },
+            &db,
+        )
+        .await
+        .unwrap();
+        assert_eq!(updated.name, "Bitcoin Core");
+        assert_eq!(updated.symbol, "BTC");
+
+        assert_eq!(AssetService::delete(&asset.id, &db).await.unwrap(), 1);
+        assert!(AssetService::get_all(&db).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_link_coin() {
+        let db = init_db().await;
+        let asset = AssetService::create(create_btc(), &db).await.unwrap();
+        assert!(AssetService::get_provider_coin_ids(&asset.id, &db)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // unknown coins are rejected
+        assert!(AssetService::link_coin(&asset.id, Some("not-a-coin"), &db)
+            .await
+            .is_err());
+
+        let linked = AssetService::link_coin(&asset.id, Some("bitcoin"), &db)
+            .await
+            .unwrap();
+        assert_eq!(linked.coin_id.as_deref(), Some("bitcoin"));
+
+        let provider_ids = AssetService::get_provider_coin_ids(&asset.id, &db)
+            .await
+            .unwrap();
+        assert_eq!(provider_ids.len(), 1);
+        assert_eq!(provider_ids[0].provider, "coingecko");
+        assert_eq!(provider_ids[0].provider_coin_id, "bitcoin");
+    }
+}
@@ -0,0 +1,432 @@
+use crate::asset::{AssetKind, AssetService};
+use crate::crypto_exchange::CryptoExchangeManager;
+use crate::crypto_price;
+use crate::db::{Db, RowId, SqlDecimal, StringRowId};
+use crate::fiat::Fiat;
+use crate::fiat_rate::manual::ManualRateService;
+use anyhow::{bail, Context, Result};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use sqlx::SqliteConnection;
+
+/// How a coin of a stablecoin is priced in the fiat it is pegged to
+#[derive(Debug, Serialize, Deserialize, sqlx::Type, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum PegPriceSource {
+    /// at the rate of the peg, e.g. 1 USD for 1 USDT
+    #[default]
+    Peg,
+    /// at the price of its coin on the date of the ramp, from the crypto exchange providers
+    Market,
+}
+
+/// The fiat a stablecoin is pegged to, its ramps are recorded and converted in that fiat
+#[derive(Debug, FromRow, Serialize, Deserialize, PartialEq)]
+pub struct StablecoinPeg {
+    pub asset_id: StringRowId,
+    pub asset_symbol: String,
+    pub fiat_id: RowId,
+    pub fiat_symbol: String,
+    pub price_source: PegPriceSource,
+    /// amount of the fiat for one coin, used by the `Peg` price source
+    #[sqlx(try_from = "SqlDecimal")]
+    pub rate: Decimal,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SetStablecoinPeg {
+    pub asset_id: StringRowId,
+    pub fiat_id: RowId,
+    #[serde(default)]
+    pub price_source: PegPriceSource,
+    /// amount of the fiat for one coin, 1 when None
+    pub rate: Option<Decimal>,
+}
+
+#[derive(Debug, FromRow)]
+struct MissingPrice {
+    coin_id: String,
+    fiat_id: RowId,
+    fiat_symbol: String,
+    date: NaiveDate,
+}
+
+pub struct StablecoinPegService {}
+
+impl StablecoinPegService {
+    /// Set the peg of a stablecoin, its ramps move to the fiat of the peg
+    /// - the `Market` price source needs the asset to be linked to a coin
+    /// - the manual rates of the moved ramps are deleted, see `ManualRateService::reset_ramps_in`
+    pub async fn set(peg: SetStablecoinPeg, db: &Db) -> Result<StablecoinPeg> {
+        let asset = AssetService::get_by_id(&peg.asset_id, db).await?;
+        if asset.kind != AssetKind::Stablecoin {
+            bail!("{} is not a stablecoin", asset.symbol);
+        }
+        if peg.price_source == PegPriceSource::Market && asset.coin_id.is_none() {
+            bail!(
+                "{} must be linked to a coin to be priced at its market price",
+                asset.symbol
+            );
+        }
+        let rate = peg.rate.unwrap_or(Decimal::ONE);
+        if rate <= Decimal::ZERO {
+            bail!("rate must be greater than zero");
+        }
+
+        let mut tx = db.0.begin().await.context("failed to begin transaction")?;
+        sqlx::query(
+            r#"
+            INSERT INTO stablecoin_peg (asset_id, fiat_id, price_source, rate)
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT (asset_id) DO UPDATE SET
+                fiat_id = excluded.fiat_id,
+                price_source = excluded.price_source,
+                rate = excluded.rate
+            "#,
+        )
+        .bind(&asset.id)
+        .bind(peg.fiat_id)
+        .bind(peg.price_source)
+        .bind(rate.to_string())
+        .execute(&mut *tx)
+        .await
+        .context("failed to insert into stablecoin_peg table")?;
+        let moved: Vec<StringRowId> = sqlx::query_scalar(
+            "UPDATE fiat_ramp SET fiat_id = ?1 WHERE asset_id = ?2 AND fiat_id != ?1 RETURNING id",
+        )
+        .bind(peg.fiat_id)
+        .bind(&asset.id)
+        .fetch_all(&mut *tx)
+        .await
+        .context("failed to move the ramps to the fiat of the peg")?;
+        ManualRateService::reset_ramps_in(&moved, &mut tx).await?;
+        tx.commit().await.context("failed to commit transaction")?;
+
+        Self::get(&asset.id, db).await
+    }
+
+    /// Get the peg of a stablecoin
+    pub async fn get(asset_id: &str, db: &Db) -> Result<StablecoinPeg> {
+        let mut conn =
+            db.0.acquire()
+                .await
+                .context("failed to acquire connection")?;
+        Self::get_in(asset_id, &mut conn).await
+    }
+
+    /// `get` on `conn`, e.g. inside the transaction of an import
+    pub(crate) async fn get_in(
+        asset_id: &str,
+        conn: &mut SqliteConnection,
+    ) -> Result<StablecoinPeg> {
+        sqlx::query_as::<sqlx::Sqlite, StablecoinPeg>(
+            r#"
+            SELECT
+                stablecoin_peg.asset_id,
+                asset.symbol as asset_symbol,
+                stablecoin_peg.fiat_id,
+                fiat.symbol as fiat_symbol,
+                stablecoin_peg.price_source,
+                stablecoin_peg.rate
+            FROM stablecoin_peg
+            JOIN asset ON asset.id = stablecoin_peg.asset_id
+            JOIN fiat ON fiat.id = stablecoin_peg.fiat_id
+            WHERE stablecoin_peg.asset_id = ?
+            "#,
+        )
+        .bind(asset_id)
+        .fetch_optional(&mut *conn)
+        .await
+        .context("failed to get stablecoin peg")?
+        .with_context(|| format!("asset {asset_id} has no peg, set one before recording its ramps"))
+    }
+
+    /// Get the pegs of all stablecoins ordered by symbol
+    pub async fn get_all(db: &Db) -> Result<Vec<StablecoinPeg>> {
+        sqlx::query_as::<sqlx::Sqlite, StablecoinPeg>(
+            r#"
+            SELECT
+                stablecoin_peg.asset_id,
+                asset.symbol as asset_symbol,
+                stablecoin_peg.fiat_id,
+                fiat.symbol as fiat_symbol,
+                stablecoin_peg.price_source,
+                stablecoin_peg.rate
+            FROM stablecoin_peg
+            JOIN asset ON asset.id = stablecoin_peg.asset_id
+            JOIN fiat ON fiat.id = stablecoin_peg.fiat_id
+            ORDER BY asset.symbol
+            "#,
+        )
+        .fetch_all(&db.0)
+        .await
+        .context("failed to get stablecoin pegs")
+    }
+
+    /// Delete the peg of a stablecoin without ramps
+    /// - returns the number of rows affected
+    pub async fn delete(asset_id: &str, db: &Db) -> Result<u64> {
+        let ramp_count: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM fiat_ramp WHERE asset_id = ?")
+                .bind(asset_id)
+                .fetch_one(&db.0)
+                .await
+                .context("failed to count the ramps of the stablecoin")?;
+        if ramp_count > 0 {
+            bail!("the stablecoin has {ramp_count} ramps, its peg cannot be removed");
+        }
+        let result = sqlx::query("DELETE FROM stablecoin_peg WHERE asset_id = ?")
+            .bind(asset_id)
+            .execute(&db.0)
+            .await
+            .context("failed to delete from stablecoin_peg table")?;
+        Ok(result.rows_affected())
+    }
+
+    /// Fetch the market price of every ramp date of the stablecoins priced at their market price
+    /// - prices the providers cannot serve are skipped, their ramps stay unconverted
+    /// - returns the number of prices fetched
+    pub async fn fetch_missing_prices(db: &Db, manager: &CryptoExchangeManager) -> Result<u64> {
+        let missing = sqlx::query_as::<sqlx::Sqlite, MissingPrice>(
+            r#"
+            SELECT DISTINCT
+                asset.coin_id,
+                fiat.id as fiat_id,
+                fiat.symbol as fiat_symbol,
+                fiat_ramp.ramp_date as date
+            FROM fiat_ramp
+            JOIN stablecoin_peg ON stablecoin_peg.asset_id = fiat_ramp.asset_id
+            JOIN asset ON asset.id = fiat_ramp.asset_id
+            JOIN fiat ON fiat.id = fiat_ramp.fiat_id
+            WHERE stablecoin_peg.price_source = 'market'
+            AND asset.coin_id IS NOT NULL
+            AND NOT EXISTS (
+                SELECT 1 FROM crypto_price
+                WHERE crypto_price.coin_id = asset.coin_id
+                AND crypto_price.fiat_id = fiat_ramp.fiat_id
+                AND crypto_price.date = fiat_ramp.ramp_date
+            )
+            ORDER BY fiat_ramp.ramp_date
+            "#,
+        )
+        .fetch_all(&db.0)
+        .await
+        .context("failed to get stablecoin ramps without a price")?;
+
+        let mut fetched = 0;
+        for item in missing {
+            let fiat = Fiat {
+                id: item.fiat_id,
+                symbol: item.fiat_symbol,
+                ..Default::default()
+            };
+            match crypto_price::get_price(db, manager, &item.coin_id, &fiat, Some(item.date)).await
+            {
+                Ok(_) => fetched += 1,
+                Err(e) => eprintln!("Skipping price of {} on {}: {e:#}", item.coin_id, item.date),
+            }
+        }
+        Ok(fetched)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset::CreateAsset;
+    use crate::crypto_exchange::{
+        catalogue, traits::MockCryptoExchange, ExchangeRateResponse, SupportedCoin,
+    };
+    use crate::exchange::ExchangeRef;
+    use crate::fiat_ramp::{CreateFiatRamp, FiatRampService, FiatRampWithConversionView, RampKind};
+
+    async fn setup() -> (Db, RowId, RowId) {
+        let db = Db::in_memory().await.unwrap();
+        catalogue::merge_provider_coins(
+            &db,
+            "coingecko",
+            vec![SupportedCoin {
+                id: "tether".to_string(),
+                symbol: "usdt".to_string(),
+                name: "Tether".to_string(),
+            }],
+        )
+        .await
+        .unwrap();
+        let usd_id: RowId = sqlx::query_scalar(
+            "INSERT INTO fiat (symbol, name) VALUES ('USD', 'US Dollar') RETURNING id",
+        )
+        .fetch_one(&db.0)
+        .await
+        .unwrap();
+        let eur_id: RowId = sqlx::query_scalar(
+            "INSERT INTO fiat (symbol, name) VALUES ('EUR', 'Euro') RETURNING id",
+        )
+        .fetch_one(&db.0)
+        .await
+        .unwrap();
+        sqlx::query("INSERT INTO user_settings (id, locale, default_fiat_id) VALUES (1, 'en', ?)")
+            .bind(eur_id)
+            .execute(&db.0)
+            .await
+            .unwrap();
+        (db, usd_id, eur_id)
+    }
+
+    async fn view(db: &Db) -> Vec<FiatRampWithConversionView> {
+        sqlx::query_as("SELECT * FROM fiat_ramp_view ORDER BY fiat_ramp_id")
+            .fetch_all(&db.0)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(FiatRampWithConversionView::with_conversion)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_stablecoin_ramps() {
+        let (db, usd_id, eur_id) = setup().await;
+        let date = NaiveDate::from_ymd_opt(2026, 2, 2).unwrap();
+        sqlx::query(
+            "INSERT INTO fiat_exchange_rate (base_fiat_id, date, rates) VALUES (?, ?, '{\"USD\": \"1\", \"EUR\": \"0.9\"}')",
+        )
+        .bind(usd_id)
+        .bind(date)
+        .execute(&db.0)
+        .await
+        .unwrap();
+        let usdt = AssetService::create(
+            CreateAsset {
+                name: "Tether".to_string(),
+                symbol: "USDT".to_string(),
+                kind: AssetKind::Stablecoin,
+                coin_id: None,
+            },
+            &db,
+        )
+        .await
+        .unwrap();
+        let ramp = |asset_id: Option<StringRowId>| CreateFiatRamp {
+            fiat_id: usd_id,
+            fiat_amount: Decimal::ONE_HUNDRED,
+            ramp_date: date,
+            via_exchange: ExchangeRef::Name("Binance".to_string()),
+            kind: RampKind::Deposit,
+            external_ref: None,
+            asset_id,
+            fee_fiat_id: None,
+            fee_amount: None,
+        };
+
+        // a stablecoin needs a peg before its ramps
+        assert!(FiatRampService::create(ramp(Some(usdt.id.clone())), &db)
+            .await
+            .is_err());
+        let peg = StablecoinPegService::set(
+            SetStablecoinPeg {
+                asset_id: usdt.id.clone(),
+                fiat_id: usd_id,
+                price_source: PegPriceSource::Peg,
+                rate: None,
+            },
+            &db,
+        )
+        .await
+        .unwrap();
+        assert_eq!(peg.rate, Decimal::ONE);
+        assert_eq!(peg.fiat_symbol, "USD");
+        // the fiat of the ramp is the fiat of the peg
+        let usdt_ramp_id = FiatRampService::create(
+            CreateFiatRamp {
+                fiat_id: eur_id,
+                ..ramp(Some(usdt.id.clone()))
+            },
+            &db,
+        )
+        .await
+        .unwrap();
+        FiatRampService::create(ramp(None), &db).await.unwrap();
+
+        let rows = view(&db).await;
+        let usdt_row = rows
+            .iter()
+            .find(|row| row.fiat_ramp_id == usdt_ramp_id)
+            .unwrap();
+        assert_eq!(usdt_row.from_fiat_symbol, "USD");
+        assert_eq!(usdt_row.asset_symbol.as_deref(), Some("USDT"));
+        assert_eq!(usdt_row.converted_amount, Some(Decimal::new(90, 0)));
+
+        let breakdown = FiatRampService::get_breakdown(None, None, &db)
+            .await
+            .unwrap();
+        assert_eq!(breakdown.by_currency.len(), 2);
+        let usdt_total = breakdown
+            .by_currency
+            .iter()
+            .find(|total| total.asset_id.as_ref() == Some(&usdt.id))
+            .unwrap();
+        assert_eq!(usdt_total.fiat_symbol, "USDT");
+        assert_eq!(usdt_total.source_deposit, Decimal::ONE_HUNDRED);
+        assert_eq!(usdt_total.deposit, Decimal::new(90, 0));
+
+        // the market price needs a coin
+        let market = || SetStablecoinPeg {
+            asset_id: usdt.id.clone(),
+            fiat_id: usd_id,
+            price_source: PegPriceSource::Market,
+            rate: None,
+        };
+        assert!(StablecoinPegService::set(market(), &db).await.is_err());
+        AssetService::link_coin(&usdt.id, Some("tether"), &db)
+            .await
+            .unwrap();
+        StablecoinPegService::set(market(), &db).await.unwrap();
+        let rows = view(&db).await;
+        let usdt_row = rows
+            .iter()
+            .find(|row| row.fiat_ramp_id == usdt_ramp_id)
+            .unwrap();
+        assert_eq!(usdt_row.converted_amount, None);
+
+        let mut mock_provider = MockCryptoExchange::new();
+        mock_provider.expect_id().return_const("mock_provider");
+        mock_provider
+            .expect_get_exchange_rate()
+            .withf(|request| request.coin_id == "tether" && request.fiat_currency == "USD")
+            .times(1)
+            .returning(|request| {
+                Ok(ExchangeRateResponse {
+                    rate: Decimal::new(998, 3),
+                    date: request.date.unwrap(),
+                })
+            });
+        let manager = CryptoExchangeManager::new(vec![Box::new(mock_provider)]);
+        assert_eq!(
+            StablecoinPegService::fetch_missing_prices(&db, &manager)
+                .await
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            StablecoinPegService::fetch_missing_prices(&db, &manager)
+                .await
+                .unwrap(),
+            0
+        );
+        let rows = view(&db).await;
+        let usdt_row = rows
+            .iter()
+            .find(|row| row.fiat_ramp_id == usdt_ramp_id)
+            .unwrap();
+        // 100 * 0.998 * 0.9
+        assert_eq!(usdt_row.converted_amount, Some(Decimal::new(8982, 2)));
+        assert_eq!(usdt_row.conversion_rate, Some(Decimal::new(8982, 4)));
+
+        // a peg is kept while the stablecoin has ramps
+        assert!(StablecoinPegService::delete(&usdt.id, &db).await.is_err());
+        assert_eq!(StablecoinPegService::get_all(&db).await.unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,83 @@
+use crate::backup::schedule::{self, BackupFile};
+use crate::backup::{BackupService, BackupSummary};
+use crate::db::{self, Db};
+use std::path::PathBuf;
+use tauri::{AppHandle, State};
+
+/// Write a backup of the user tables to `file_path`
+/// - the rate caches are left out unless `include_rate_cache` is set
+/// - the backup is encrypted when a passphrase is given
+#[tauri::command]
+pub async fn export_backup(
+    file_path: PathBuf,
+    include_rate_cache: Option<bool>,
+    passphrase: Option<String>,
+    db: State<'_, Db>,
+) -> Result<BackupSummary, String> {
+    let bundle = BackupService::export(include_rate_cache.unwrap_or(false), &db)
+        .await
+        .map_err(|e| format!("failed to export backup: {e:#}"))?;
+    BackupService::write(&bundle, &file_path, passphrase.as_deref())
+        .map_err(|e| format!("failed to export backup: {e:#}"))?;
+    Ok(BackupSummary::from(&bundle))
+}
+
+/// What the backup at `file_path` holds, nothing is restored
+#[tauri::command]
+pub async fn inspect_backup(
+    file_path: PathBuf,
+    passphrase: Option<String>,
+) -> Result<BackupSummary, String> {
+    BackupService::read(&file_path, passphrase.as_deref())
+        .map(|bundle| BackupSummary::from(&bundle))
+        .map_err(|e| format!("failed to read backup: {e:#}"))
+}
+
+/// Replace the user tables with the ones of the backup at `file_path`
+#[tauri::command]
+pub async fn restore_backup(
+    file_path: PathBuf,
+    passphrase: Option<String>,
+    db: State<'_, Db>,
+) -> Result<BackupSummary, String> {
+    let bundle = BackupService::read(&file_path, passphrase.as_deref())
+        .map_err(|e| format!("failed to read backup: {e:#}"))?;
+    BackupService::restore(&bundle, &db)
+        .await
+        .map_err(|e| format!("failed to restore backup: {e:#}"))
+}
+
+/// Scheduled backups of the database, newest first
+#[tauri::command]
+pub async fn list_database_backups(app: AppHandle) -> Result<Vec<BackupFile>, String> {
+    let db_path = db::get_db_path(&app).await?;
+    schedule::list(&db_path).map_err(|e| format!("failed to list backups: {e:#}"))
+}
+
+/// Back up the database now, outside of the schedule
+#[tauri::command]
+pub async fn create_database_backup(
+    app: AppHandle,
+    db: State<'_, Db>,
+) -> Result<BackupFile, String> {
+    let db_path = db::get_db_path(&app).await?;
+    schedule::create(&db, &db_path)
+        .await
+        .map_err(|e| format!("failed to create backup: {e:#}"))
+}
+
+/// Replace the database with the scheduled backup `file_name`
+/// - the app restarts to open the restored database
+#[tauri::command]
+pub async fn restore_database_backup(
+    file_name: String,
+    app: AppHandle,
+    db: State<'_, Db>,
+) -> Result<(), String> {
+    let db_path = db::get_db_path(&app).await?;
+    schedule::restore(&db, &db_path, &file_name)
+        .await
+        .map_err(|e| format!("failed to restore backup: {e:#}"))?;
+    app.request_restart();
+    Ok(())
+}
@@ -0,0 +1,462 @@
+pub mod command;
+pub mod schedule;
+use crate::db::{Db, MIGRATOR};
+use crate::exchange::ExchangeService;
+use crate::utils::crypto;
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::{
+    migrate::{Migrate, Migration},
+    sqlite::SqliteRow,
+    Column, Connection, Row, SqliteConnection, TypeInfo, ValueRef,
+};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Marks a file as a backup of this app
+const BACKUP_FORMAT: &str = "aura-backup";
+
+/// Version of the bundle layout, not of the database schema
+const BACKUP_FORMAT_VERSION: u32 = 1;
+
+/// Tables holding user data, parents before the tables referencing them
+const USER_TABLES: [&str; 14] = [
+    "fiat",
+    "crypto_coin",
+    "crypto_coin_provider",
+    "asset",
+    "stablecoin_peg",
+    "exchange",
+    "fiat_ramp",
+    "recurring_ramp",
+    "recurring_ramp_proposal",
+    "fiat_rate_missing",
+    "fiat_manual_rate",
+    "trade",
+    "user_settings",
+    "import_mapping",
+];
+
+/// Tables of rates fetched from providers, they can be fetched again
+const RATE_CACHE_TABLES: [&str; 3] = ["fiat_exchange_rate", "fiat_rate_attempt", "crypto_price"];
+
+/// Row of a table, column name to value
+pub type BackupRow = Map<String, Value>;
+
+/// Snapshot of the user tables, saved as JSON
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BackupBundle {
+    pub format: String,
+    pub format_version: u32,
+    /// last migration applied to the database the backup was made from
+    pub schema_version: i64,
+    pub app_version: String,
+    pub created_at: NaiveDateTime,
+    pub include_rate_cache: bool,
+    pub tables: BTreeMap<String, Vec<BackupRow>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BackupTableSummary {
+    pub name: String,
+    pub row_count: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BackupSummary {
+    pub schema_version: i64,
+    pub app_version: String,
+    pub created_at: NaiveDateTime,
+    pub include_rate_cache: bool,
+    pub tables: Vec<BackupTableSummary>,
+}
+
+impl From<&BackupBundle> for BackupSummary {
+    fn from(bundle: &BackupBundle) -> Self {
+        BackupSummary {
+            schema_version: bundle.schema_version,
+            app_version: bundle.app_version.clone(),
+            created_at: bundle.created_at,
+            include_rate_cache: bundle.include_rate_cache,
+            tables: bundle
+                .tables
+                .iter()
+                .map(|(name, rows)| BackupTableSummary {
+                    name: name.clone(),
+                    row_count: rows.len(),
+                })
+                .collect(),
+        }
+    }
+}
+
+pub struct BackupService {}
+
+impl BackupService {
+    /// Snapshot every user table, and the rate caches when `include_rate_cache` is set
+    pub async fn export(include_rate_cache: bool, db: &Db) -> Result<BackupBundle> {
+        let mut conn =
+            db.0.acquire()
+                .await
+                .context("failed to acquire connection")?;
+        let schema_version = schema_version(&mut conn).await?;
+
+        let mut tables = BTreeMap::new();
+        for table in backup_tables(include_rate_cache) {
+            tables.insert(table.to_string(), read_table(table, &mut conn).await?);
+        }
+        Ok(BackupBundle {
+            format: BACKUP_FORMAT.to_string(),
+            format_version: BACKUP_FORMAT_VERSION,
+            schema_version,
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            created_at: Utc::now().naive_utc(),
+            include_rate_cache,
+            tables,
+        })
+    }
+
+    /// Write the bundle to `file_path`, an existing file is replaced only once the bundle is written
+    /// - the bundle is encrypted when a passphrase is given, see `crypto::encrypt`
+    pub fn write(bundle: &BackupBundle, file_path: &Path, passphrase: Option<&str>) -> Result<()> {
+        let mut data = serde_json::to_vec(bundle).context("failed to serialize backup")?;
+        if let Some(passphrase) = passphrase {
+            data = crypto::encrypt(&data, passphrase)?;
+        }
+        let temp_path = file_path.with_extension("tmp");
+        std::fs::write(&temp_path, data)
+            .with_context(|| format!("failed to write {}", temp_path.display()))?;
+        std::fs::rename(&temp_path, file_path)
+            .with_context(|| format!("failed to write {}", file_path.display()))
+    }
+
+    /// Read a bundle written by `write`, by this or an older version of the app
+    /// - an encrypted bundle needs the passphrase it was written with
+    pub fn read(file_path: &Path, passphrase: Option<&str>) -> Result<BackupBundle> {
+        let mut data = std::fs::read(file_path)
+            .with_context(|| format!("failed to read {}", file_path.display()))?;
+        if crypto::is_encrypted(&data) {
+            let passphrase = passphrase
+                .ok_or_else(|| anyhow!("the backup is encrypted, enter its passphrase"))?;
+            data = crypto::decrypt(&data, passphrase)?;
+        }
+        let bundle: BackupBundle =
+            serde_json::from_slice(&data).context("the file is not a backup")?;
+        if bundle.format != BACKUP_FORMAT {
+            bail!("the file is not a backup");
+        }
+        if bundle.format_version > BACKUP_FORMAT_VERSION {
+            bail!("the backup was made by a newer version of the app");
+        }
+        Ok(bundle)
+    }
+
+    /// Replace the user tables with the ones of the bundle
+    /// - the rows are loaded into a database at the schema version of the backup, then the
+    ///   migrations made since bring them up to the current schema
+    /// - the rate caches are kept when the bundle has none
+    pub async fn restore(bundle: &BackupBundle, db: &Db) -> Result<BackupSummary> {
+        let latest = MIGRATOR.iter().map(|migration| migration.version).max();
+        if Some(bundle.schema_version) > latest {
+            bail!(
+                "the backup was made by a newer version of the app (schema {})",
+                bundle.schema_version
+            );
+        }
+
+        let mut staging = SqliteConnection::connect("sqlite::memory:")
+            .await
+            .context("failed to open staging database")?;
+        staging.ensure_migrations_table().await?;
+        let migrations: Vec<&Migration> = MIGRATOR
+            .iter()
+            .filter(|migration| {
+                migration.version <= bundle.schema_version
+                    && migration.migration_type.is_up_migration()
+            })
+            .collect();
+        for migration in migrations {
+            staging
+                .apply(migration)
+                .await
+                .with_context(|| format!("failed to apply migration {}", migration.version))?;
+        }
+
+        let staging_tables: Vec<String> =
+            sqlx::query_scalar("SELECT name FROM sqlite_master WHERE type = 'table'")
+                .fetch_all(&mut staging)
+                .await
+                .context("failed to get tables of staging database")?;
+        if let Some(table) = bundle
+            .tables
+            .keys()
+            .find(|table| table.starts_with("_sqlx") || !staging_tables.contains(table))
+        {
+            bail!("unknown table '{table}' in the backup");
+        }
+
+        // tables dropped by a later migration may still be in the bundle, they go last
+        let mut load_order: Vec<&str> = USER_TABLES
+            .iter()
+            .chain(RATE_CACHE_TABLES.iter())
+            .copied()
+            .filter(|table| bundle.tables.contains_key(*table))
+            .collect();
+        for table in bundle.tables.keys() {
+            if !load_order.contains(&table.as_str()) {
+                load_order.push(table);
+            }
+        }
+        let mut tx = staging
+            .begin()
+            .await
+            .context("failed to begin transaction")?;
+        sqlx::query("PRAGMA defer_foreign_keys = ON")
+            .execute(&mut *tx)
+            .await?;
+        for table in load_order {
+            write_table(table, &bundle.tables[table], &mut tx).await?;
+        }
+        tx.commit().await.context("failed to load backup")?;
+
+        MIGRATOR
+            .run_direct(&mut staging)
+            .await
+            .context("failed to migrate backup")?;
+
+        let mut tx = db.0.begin().await.context("failed to begin transaction")?;
+        sqlx::query("PRAGMA defer_foreign_keys = ON")
+            .execute(&mut *tx)
+            .await?;
+        let tables = backup_tables(bundle.include_rate_cache);
+        for table in tables.iter().rev() {
+            sqlx::query(&format!("DELETE FROM {}", quote(table)))
+                .execute(&mut *tx)
+                .await
+                .with_context(|| format!("failed to clear table {table}"))?;
+        }
+        for table in &tables {
+            let rows = read_table(table, &mut staging).await?;
+            write_table(table, &rows, &mut tx).await?;
+        }
+        // ramps and trades of backups made before exchanges existed only have the name
+        ExchangeService::link_unlinked_in(&mut tx).await?;
+        tx.commit().await.context("failed to restore backup")?;
+        Ok(BackupSummary::from(bundle))
+    }
+}
+
+fn backup_tables(include_rate_cache: bool) -> Vec<&'static str> {
+    let mut tables = USER_TABLES.to_vec();
+    if include_rate_cache {
+        tables.extend(RATE_CACHE_TABLES);
+    }
+    tables
+}
+
+/// Last migration applied to the database
+async fn schema_version(conn: &mut SqliteConnection) -> Result<i64> {
+    conn.list_applied_migrations()
+        .await?
+        .iter()
+        .map(|migration| migration.version)
+        .max()
+        .ok_or_else(|| anyhow!("the database has no migrations applied"))
+}
+
+/// Quote a table or column name
+fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+async fn read_table(table: &str, conn: &mut SqliteConnection) -> Result<Vec<BackupRow>> {
+    let rows = sqlx::query(&format!("SELECT * FROM {}", quote(table)))
+        .fetch_all(conn)
+        .await
+        .with_context(|| format!("failed to read table {table}"))?;
+    rows.iter()
+        .map(|row| to_json(row).with_context(|| format!("failed to read table {table}")))
+        .collect()
+}
+
+fn to_json(row: &SqliteRow) -> Result<BackupRow> {
+    let mut values = Map::new();
+    for column in row.columns() {
+        let index = column.ordinal();
+        let raw = row.try_get_raw(index)?;
+        let value = if raw.is_null() {
+            Value::Null
+        } else {
+            // the type of the stored value, columns do not constrain it in sqlite
+            match raw.type_info().name() {
+                "INTEGER" => Value::from(row.try_get::<i64, _>(index)?),
+                "REAL" => Value::from(row.try_get::<f64, _>(index)?),
+                "TEXT" => Value::from(row.try_get::<String, _>(index)?),
+                name => bail!("unsupported {name} value in column {}", column.name()),
+            }
+        };
+        values.insert(column.name().to_string(), value);
+    }
+    Ok(values)
+}
+
+async fn write_table(table: &str, rows: &[BackupRow], conn: &mut SqliteConnection) -> Result<()> {
+    for row in rows {
+        let columns: Vec<String> = row.keys().map(|column| quote(column)).collect();
+        let sql = format!(
+            "INSERT INTO {} ({}) VALUES ({})",
+            quote(table),
+            columns.join(", "),
+            vec!["?"; columns.len()].join(", ")
+        );
+        let mut query = sqlx::query(&sql);
+        for value in row.values() {
+            query = match value {
+                Value::Null => query.bind(None::<String>),
+                Value::Bool(value) => query.bind(*value),
+                Value::Number(number) => match number.as_i64() {
+                    Some(value) => query.bind(value),
+                    None => query.bind(number.as_f64()),
+                },
+                Value::String(value) => query.bind(value.clone()),
+                value => bail!("unsupported value {value} in table {table}"),
+            };
+        }
+        query
+            .execute(&mut *conn)
+            .await
+            .with_context(|| format!("failed to write table {table}"))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn row(value: Value) -> BackupRow {
+        value.as_object().unwrap().clone()
+    }
+
+    #[tokio::test]
+    async fn test_export_and_restore() {
+        let db = Db::in_memory().await.unwrap();
+        sqlx::query("INSERT INTO fiat (id, symbol, name) VALUES (1, 'EUR', 'Euro')")
+            .execute(&db.0)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO user_settings (locale, default_fiat_id) VALUES ('en-GB', 1)")
+            .execute(&db.0)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO exchange (id, name) VALUES ('e1', 'Kraken')")
+            .execute(&db.0)
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO fiat_ramp (id, fiat_id, fiat_amount, ramp_date, via_exchange, exchange_id, kind) VALUES ('r1', 1, '100.5', '2024-01-02', 'Kraken', 'e1', 'deposit')",
+        )
+        .execute(&db.0)
+        .await
+        .unwrap();
+
+        let bundle = BackupService::export(false, &db).await.unwrap();
+        assert_eq!(bundle.tables["fiat_ramp"].len(), 1);
+        assert!(!bundle.tables.contains_key("fiat_exchange_rate"));
+        let file_path = std::env::temp_dir().join(format!("{}.json", uuid::Uuid::now_v7()));
+        BackupService::write(&bundle, &file_path, None).unwrap();
+        let bundle = BackupService::read(&file_path, None).unwrap();
+        BackupService::write(&bundle, &file_path, Some("passphrase")).unwrap();
+        assert!(BackupService::read(&file_path, None).is_err());
+        assert!(BackupService::read(&file_path, Some("wrong")).is_err());
+        let bundle = BackupService::read(&file_path, Some("passphrase")).unwrap();
+        std::fs::remove_file(&file_path).unwrap();
+
+        let restored = Db::in_memory().await.unwrap();
+        sqlx::query("INSERT INTO fiat (id, symbol, name) VALUES (2, 'USD', 'US Dollar')")
+            .execute(&restored.0)
+            .await
+            .unwrap();
+        BackupService::restore(&bundle, &restored).await.unwrap();
+
+        let fiats: Vec<String> = sqlx::query_scalar("SELECT symbol FROM fiat")
+            .fetch_all(&restored.0)
+            .await
+            .unwrap();
+        assert_eq!(fiats, vec!["EUR"]);
+        let (amount, exchange_id): (String, Option<String>) =
+            sqlx::query_as("SELECT fiat_amount, exchange_id FROM fiat_ramp WHERE id = 'r1'")
+                .fetch_one(&restored.0)
+                .await
+                .unwrap();
+        assert_eq!(amount, "100.5");
+        assert_eq!(exchange_id.as_deref(), Some("e1"));
+    }
+
+    #[tokio::test]
+    async fn test_restore_older_schema() {
+        let db = Db::in_memory().await.unwrap();
+        // made before cost basis methods and exchanges existed
+        let bundle = BackupBundle {
+            format: BACKUP_FORMAT.to_string(),
+            format_version: 1,
+            schema_version: 17,
+            app_version: "0.1.0".to_string(),
+            created_at: Utc::now().naive_utc(),
+            include_rate_cache: false,
+            tables: BTreeMap::from([
+                (
+                    "fiat".to_string(),
+                    vec![row(json!({"id": 1, "symbol": "EUR", "name": "Euro"}))],
+                ),
+                (
+                    "user_settings".to_string(),
+                    vec![row(
+                        json!({"id": 1, "locale": "de-DE", "default_fiat_id": 1}),
+                    )],
+                ),
+                (
+                    "fiat_ramp".to_string(),
+                    vec![row(json!({
+                        "id": "r1",
+                        "fiat_id": 1,
+                        "fiat_amount": 250.0,
+                        "ramp_date": "2023-05-01",
+                        "via_exchange": "Bitstamp",
+                        "kind": "withdraw",
+                    }))],
+                ),
+            ]),
+        };
+
+        let summary = BackupService::restore(&bundle, &db).await.unwrap();
+        assert_eq!(summary.schema_version, 17);
+        let method: String = sqlx::query_scalar("SELECT cost_basis_method FROM user_settings")
+            .fetch_one(&db.0)
+            .await
+            .unwrap();
+        assert_eq!(method, "fifo");
+        let exchange: String = sqlx::query_scalar(
+            "SELECT exchange.name FROM fiat_ramp JOIN exchange ON exchange.id = fiat_ramp.exchange_id",
+        )
+        .fetch_one(&db.0)
+        .await
+        .unwrap();
+        assert_eq!(exchange, "Bitstamp");
+        // amounts stored as floats become decimal text
+        let amount: String = sqlx::query_scalar("SELECT fiat_amount FROM fiat_ramp")
+            .fetch_one(&db.0)
+            .await
+            .unwrap();
+        assert_eq!(amount, "250");
+
+        let newer = BackupBundle {
+            schema_version: i64::MAX,
+            ..bundle
+        };
+        assert!(BackupService::restore(&newer, &db).await.is_err());
+    }
+}
@@ -0,0 +1,381 @@
+use crate::db::{self, Db};
+use crate::sys_tracker::SysTracker;
+use crate::user_settings;
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{Datelike, Local, NaiveDateTime, Timelike};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Name of the scheduled backup in `sys_tracker`
+const TRACKER_NAME: &str = "scheduled_backup";
+
+/// How often the job checks whether a backup is due
+const CHECK_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// Local time of the backup in its file name, e.g. `aura-20240105-093000.sqlite`
+/// - a backup made within the same second gets a sequence suffix, e.g. `aura-20240105-093000-2.sqlite`
+const FILE_TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+/// Page size of an encrypted database, sqlcipher's default
+const SQLCIPHER_PAGE_SIZE: u64 = 4096;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BackupFile {
+    pub file_name: String,
+    pub created_at: NaiveDateTime,
+    pub size: u64,
+}
+
+/// Check every `CHECK_INTERVAL` whether a backup is due, runs for the lifetime of the app
+pub async fn run(db: Db, db_path: PathBuf) {
+    loop {
+        if let Err(e) = run_due(&db, &db_path).await {
+            eprintln!("Failed to run scheduled backup: {e:#}");
+        }
+        tokio::time::sleep(CHECK_INTERVAL).await;
+    }
+}
+
+/// Back up the database when the interval set in the user settings has passed since the last
+/// backup, then prune the backups beyond the retention
+pub async fn run_due(db: &Db, db_path: &Path) -> Result<Option<BackupFile>> {
+    let settings = user_settings::get(db).await?;
+    if settings.backup_interval_hours <= 0 {
+        return Ok(None);
+    }
+    if let Some(last) = SysTracker::get_last_updated_at(TRACKER_NAME, db).await? {
+        let elapsed = Local::now().naive_local() - last;
+        if elapsed < chrono::Duration::hours(settings.backup_interval_hours) {
+            return Ok(None);
+        }
+    }
+
+    let backup = create(db, db_path).await?;
+    prune(
+        db_path,
+        settings.backup_keep_daily as usize,
+        settings.backup_keep_weekly as usize,
+    )?;
+    Ok(Some(backup))
+}
+
+/// Folder of the backups, next to the database
+pub fn backups_dir(db_path: &Path) -> PathBuf {
+    db_path.with_file_name("backups")
+}
+
+/// Copy the database into the backups folder
+/// - `VACUUM INTO` makes a consistent copy while the app keeps writing, an encrypted database is
+///   copied encrypted with the same key
+pub async fn create(db: &Db, db_path: &Path) -> Result<BackupFile> {
+    let dir = backups_dir(db_path);
+    std::fs::create_dir_all(&dir).context("failed to create backups folder")?;
+    // the file name keeps whole seconds
+    let created_at = Local::now()
+        .naive_local()
+        .with_nanosecond(0)
+        .expect("valid nanosecond");
+    let stem = format!(
+        "{}-{}",
+        file_prefix(db_path),
+        created_at.format(FILE_TIMESTAMP_FORMAT)
+    );
+    let (file_name, path) = reserve_file(&dir, &stem)?;
+    let temp_path = path.with_extension("tmp");
+    if temp_path.exists() {
+        std::fs::remove_file(&temp_path).context("failed to remove unfinished backup")?;
+    }
+
+    let copied = sqlx::query("VACUUM INTO ?")
+        .bind(temp_path.to_string_lossy().to_string())
+        .execute(&db.0)
+        .await
+        .context("failed to copy database")
+        .and_then(|_| std::fs::rename(&temp_path, &path).context("failed to save backup"));
+    if let Err(e) = copied {
+        std::fs::remove_file(&path).ok();
+        return Err(e);
+    }
+    SysTracker::update_last_updated_at(TRACKER_NAME, db).await?;
+
+    Ok(BackupFile {
+        file_name,
+        created_at,
+        size: std::fs::metadata(&path)?.len(),
+    })
+}
+
+/// Backups in the backups folder, newest first
+/// - a file that is not a database, e.g. the empty file of a backup being made, is left out
+pub fn list(db_path: &Path) -> Result<Vec<BackupFile>> {
+    let dir = backups_dir(db_path);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let prefix = format!("{}-", file_prefix(db_path));
+
+    let mut backups = Vec::new();
+    for entry in std::fs::read_dir(&dir).context("failed to read backups folder")? {
+        let entry = entry?;
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let Some((created_at, sequence)) = file_name
+            .strip_prefix(&prefix)
+            .and_then(|name| name.strip_suffix(".sqlite"))
+            .and_then(parse_file_stem)
+        else {
+            continue;
+        };
+        if !is_database(&entry.path()).unwrap_or(false) {
+            continue;
+        }
+        let backup = BackupFile {
+            file_name,
+            created_at,
+            size: entry.metadata()?.len(),
+        };
+        backups.push((sequence, backup));
+    }
+    backups.sort_by_key(|(sequence, backup)| std::cmp::Reverse((backup.created_at, *sequence)));
+    Ok(backups.into_iter().map(|(_, backup)| backup).collect())
+}
+
+/// Create the empty file of a new backup, `stem` then `stem-2`, `stem-3`... when taken
+/// - the file is created new, two backups made within the same second cannot overwrite each other
+fn reserve_file(dir: &Path, stem: &str) -> Result<(String, PathBuf)> {
+    let mut sequence = 1;
+    loop {
+        let file_name = match sequence {
+            1 => format!("{stem}.sqlite"),
+            sequence => format!("{stem}-{sequence}.sqlite"),
+        };
+        let path = dir.join(&file_name);
+        match std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+        {
+            Ok(_) => return Ok((file_name, path)),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => sequence += 1,
+            Err(e) => return Err(e).context("failed to create backup file"),
+        }
+    }
+}
+
+/// Time and sequence number of a backup from its file name without prefix and extension
+fn parse_file_stem(stem: &str) -> Option<(NaiveDateTime, u32)> {
+    if let Ok(created_at) = NaiveDateTime::parse_from_str(stem, FILE_TIMESTAMP_FORMAT) {
+        return Some((created_at, 1));
+    }
+    let (timestamp, sequence) = stem.rsplit_once('-')?;
+    let created_at = NaiveDateTime::parse_from_str(timestamp, FILE_TIMESTAMP_FORMAT).ok()?;
+    Some((created_at, sequence.parse().ok()?))
+}
+
+/// Delete the backups beyond the retention, returns the number deleted
+/// - the last backup of each of the `keep_daily` most recent days is kept, so is the last backup
+///   of each of the `keep_weekly` most recent weeks, and always the newest one
+pub fn prune(db_path: &Path, keep_daily: usize, keep_weekly: usize) -> Result<usize> {
+    let backups = list(db_path)?;
+    let kept = retained(&backups, keep_daily, keep_weekly);
+
+    let dir = backups_dir(db_path);
+    let mut deleted = 0;
+    for backup in backups
+        .iter()
+        .filter(|backup| !kept.contains(backup.file_name.as_str()))
+    {
+        std::fs::remove_file(dir.join(&backup.file_name))
+            .with_context(|| format!("failed to delete backup {}", backup.file_name))?;
+        deleted += 1;
+    }
+    Ok(deleted)
+}
+
+/// Stage the backup to replace the database on the next start, see `db::init_db`
+/// - the current database is backed up first, so the restore can be undone
+/// - an encrypted backup opens with the passphrase in use when it was made
+pub async fn restore(db: &Db, db_path: &Path, file_name: &str) -> Result<()> {
+    let backup = list(db_path)?
+        .into_iter()
+        .find(|backup| backup.file_name == file_name)
+        .ok_or_else(|| anyhow!("backup '{file_name}' not found"))?;
+    let source = backups_dir(db_path).join(&backup.file_name);
+    if !is_database(&source)? {
+        bail!("backup '{file_name}' is not a database");
+    }
+
+    create(db, db_path).await?;
+    let pending = db::pending_replacement_path(db_path);
+    let temp_path = pending.with_extension("tmp");
+    std::fs::copy(&source, &temp_path).context("failed to copy backup")?;
+    std::fs::rename(&temp_path, &pending).context("failed to stage backup")?;
+    Ok(())
+}
+
+/// Backups of the dev and prod databases are told apart by their prefix
+fn file_prefix(db_path: &Path) -> String {
+    db_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| "aura".to_string())
+}
+
+/// A plain or encrypted database file
+/// - a plain file starts with the sqlite header and is made of pages of the size it records
+/// - the header of an encrypted file is encrypted too, it can only be checked to be made of
+///   whole sqlcipher pages
+fn is_database(path: &Path) -> Result<bool> {
+    let mut file = std::fs::File::open(path)?;
+    let len = file.metadata()?.len();
+    let mut header = [0u8; 100];
+    if len < header.len() as u64 {
+        return Ok(false);
+    }
+    file.read_exact(&mut header)?;
+    if !header.starts_with(db::SQLITE_HEADER) {
+        return Ok(len % SQLCIPHER_PAGE_SIZE == 0);
+    }
+    // big-endian page size at offset 16, 1 stands for 65536
+    let page_size = match u16::from_be_bytes([header[16], header[17]]) {
+        1 => 65_536,
+        size => u64::from(size),
+    };
+    Ok(page_size >= 512 && page_size.is_power_of_two() && len % page_size == 0)
+}
+
+/// File names of the backups to keep, `backups` newest first as returned by `list`
+/// - backups made within the same second are told apart by their file name
+fn retained(backups: &[BackupFile], keep_daily: usize, keep_weekly: usize) -> HashSet<&str> {
+    let mut kept: HashSet<&str> = backups
+        .first()
+        .map(|backup| backup.file_name.as_str())
+        .into_iter()
+        .collect();
+    let mut days = HashSet::new();
+    let mut weeks = HashSet::new();
+    for backup in backups {
+        let date = backup.created_at.date();
+        if days.len() < keep_daily && days.insert(date) {
+            kept.insert(&backup.file_name);
+        }
+        let week = (date.iso_week().year(), date.iso_week().week());
+        if weeks.len() < keep_weekly && weeks.insert(week) {
+            kept.insert(&backup.file_name);
+        }
+    }
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(day: u32, hour: u32, sequence: u32) -> BackupFile {
+        let created_at = NaiveDate::from_ymd_opt(2024, 1, day)
+            .unwrap()
+            .and_hms_opt(hour, 0, 0)
+            .unwrap();
+        BackupFile {
+            file_name: format!(
+                "aura-{}-{sequence}.sqlite",
+                created_at.format(FILE_TIMESTAMP_FORMAT)
+            ),
+            created_at,
+            size: 0,
+        }
+    }
+
+    fn names<'a>(backups: &[&'a BackupFile]) -> HashSet<&'a str> {
+        backups
+            .iter()
+            .map(|backup| backup.file_name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_retained() {
+        // 2024-01-01 is a monday, newest first, two backups made within the same second
+        let backups = vec![
+            at(16, 21, 2),
+            at(16, 21, 1),
+            at(16, 9, 1),
+            at(15, 9, 1),
+            at(8, 9, 1),
+            at(7, 9, 1),
+            at(3, 9, 1),
+            at(1, 9, 1),
+        ];
+
+        let kept = retained(&backups, 2, 0);
+        assert_eq!(kept, names(&[&backups[0], &backups[3]]));
+
+        let kept = retained(&backups, 1, 3);
+        assert_eq!(kept, names(&[&backups[0], &backups[4], &backups[5]]));
+
+        // the newest backup is kept whatever the retention
+        assert_eq!(retained(&backups, 0, 0), names(&[&backups[0]]));
+        assert!(retained(&[], 7, 4).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_create_list_and_prune() {
+        let dir = std::env::temp_dir().join(uuid::Uuid::now_v7().to_string());
+        std::fs::create_dir_all(&dir).unwrap();
+        let db_path = dir.join("aura.sqlite");
+        let db = Db(db::connect(&db_path, Some("secret")).await.unwrap());
+        sqlx::query("INSERT INTO fiat (id, symbol, name) VALUES (1, 'EUR', 'Euro')")
+            .execute(&db.0)
+            .await
+            .unwrap();
+
+        let backup = create(&db, &db_path).await.unwrap();
+        let path = backups_dir(&db_path).join(&backup.file_name);
+        assert!(db::is_encrypted(&path).unwrap());
+        let copy = db::connect(&path, Some("secret")).await.unwrap();
+        let symbol: String = sqlx::query_scalar("SELECT symbol FROM fiat")
+            .fetch_one(&copy)
+            .await
+            .unwrap();
+        assert_eq!(symbol, "EUR");
+        copy.close().await;
+
+        assert!(is_database(&path).unwrap());
+
+        // older backups of the same day, two of them within the same second
+        for name in [
+            "aura-20240101-090000.sqlite",
+            "aura-20240101-100000.sqlite",
+            "aura-20240101-100000-2.sqlite",
+        ] {
+            std::fs::copy(&path, backups_dir(&db_path).join(name)).unwrap();
+        }
+        std::fs::write(backups_dir(&db_path).join("notes.txt"), "").unwrap();
+        // the empty file of a backup being made is neither listed nor pruned
+        let placeholder = backups_dir(&db_path).join("aura-20991231-000000.sqlite");
+        std::fs::write(&placeholder, "").unwrap();
+        let backups = list(&db_path).unwrap();
+        assert_eq!(backups.len(), 4);
+        assert_eq!(backups[0], backup);
+        assert_eq!(backups[2].file_name, "aura-20240101-100000.sqlite");
+        assert_eq!(backups[1].file_name, "aura-20240101-100000-2.sqlite");
+
+        let (file_name, _) = reserve_file(&backups_dir(&db_path), "aura-20240101-100000").unwrap();
+        assert_eq!(file_name, "aura-20240101-100000-3.sqlite");
+        std::fs::remove_file(backups_dir(&db_path).join(file_name)).unwrap();
+
+        // the last backup of 2024-01-01 is the second one made at 10:00:00
+        assert_eq!(prune(&db_path, 2, 0).unwrap(), 2);
+        let backups = list(&db_path).unwrap();
+        assert_eq!(backups.len(), 2);
+        assert_eq!(backups[0], backup);
+        assert_eq!(backups[1].file_name, "aura-20240101-100000-2.sqlite");
+        assert!(placeholder.exists());
+
+        db.0.close().await;
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,197 @@
+use super::models::{CoinProviderMapping, SupportedCoin};
+use crate::db::Db;
+use anyhow::{Context, Result};
+use std::collections::{HashMap, HashSet};
+
+/// sqlite limits the number of bound variables per statement, so inserts are chunked
+const INSERT_CHUNK_SIZE: usize = 500;
+
+/// Merge the coins listed by a provider into the catalogue.
+/// - a provider coin id already mapped is left untouched
+/// - a coin with the same symbol and name as an existing catalogue coin is mapped onto it,
+///   unless that coin is already mapped for the same provider (e.g. bridged tokens sharing a name)
+/// - otherwise a new catalogue coin is created, using the provider coin id as canonical id when free,
+///   see `free_coin_id`
+/// - returns the number of new provider mappings
+/// - the mappings are read and written in one write transaction, so concurrent refreshes are run
+///   one after the other and cannot map the same provider coin twice
+pub async fn merge_provider_coins(
+    db: &Db,
+    provider: &str,
+    coins: Vec<SupportedCoin>,
+) -> Result<u64> {
+    let mut tx =
+        db.0.begin_with("BEGIN IMMEDIATE")
+            .await
+            .context("failed to begin transaction")?;
+
+    let mapped_provider_ids: HashSet<String> =
+        sqlx::query_scalar("SELECT provider_coin_id FROM crypto_coin_provider WHERE provider = ?")
+            .bind(provider)
+            .fetch_all(&mut *tx)
+            .await
+            .context("failed to get provider coin mappings")?
+            .into_iter()
+            .collect();
+
+    let mut coins_mapped_for_provider: HashSet<String> =
+        sqlx::query_scalar("SELECT coin_id FROM crypto_coin_provider WHERE provider = ?")
+            .bind(provider)
+            .fetch_all(&mut *tx)
+            .await
+            .context("failed to get provider coin mappings")?
+            .into_iter()
+            .collect();
+
+    let existing_coins =
+        sqlx::query_as::<_, SupportedCoin>("SELECT id, symbol, name FROM crypto_coin")
+            .fetch_all(&mut *tx)
+            .await
+            .context("failed to get all crypto coins")?;
+    let mut coin_ids: HashSet<String> = existing_coins.iter().map(|c| c.id.clone()).collect();
+    let mut coins_by_key: HashMap<(String, String), String> = existing_coins
+        .into_iter()
+        .map(|c| (coin_key(&c.symbol, &c.name), c.id))
+        .collect();
+
+    let mut new_coins: Vec<SupportedCoin> = Vec::new();
+    let mut new_mappings: Vec<CoinProviderMapping> = Vec::new();
+    let mut seen_provider_ids: HashSet<String> = HashSet::new();
+
+    for coin in coins {
+        if mapped_provider_ids.contains(&coin.id) || !seen_provider_ids.insert(coin.id.clone()) {
+            continue;
+        }
+
+        let key = coin_key(&coin.symbol, &coin.name);
+        let coin_id = match coins_by_key.get(&key) {
+            Some(id) if !coins_mapped_for_provider.contains(id) => id.clone(),
+            _ => {
+                let id = free_coin_id(&coin_ids, provider, &coin.id);
+                coin_ids.insert(id.clone());
+                coins_by_key.insert(key, id.clone());
+                new_coins.push(SupportedCoin {
+                    id: id.clone(),
+                    symbol: coin.symbol.clone(),
+                    name: coin.name.clone(),
+                });
+                id
+            }
+        };
+
+        coins_mapped_for_provider.insert(coin_id.clone());
+        new_mappings.push(CoinProviderMapping {
+            provider: provider.to_string(),
+            provider_coin_id: coin.id,
+            coin_id,
+        });
+    }
+
+    for chunk in new_coins.chunks(INSERT_CHUNK_SIZE) {
+        let mut query_builder =
+            sqlx::QueryBuilder::new("INSERT INTO crypto_coin (id, symbol, name) ");
+        query_builder.push_values(chunk, |mut b, coin| {
+            b.push_bind(&coin.id)
+                .push_bind(&coin.symbol)
+                .push_bind(&coin.name);
+        });
+        query_builder
+            .build()
+            .execute(&mut *tx)
+            .await
+            .context("failed to insert into crypto_coin table")?;
+    }
+
+    let mut total_row_affected = 0;
+    for chunk in new_mappings.chunks(INSERT_CHUNK_SIZE) {
+        let mut query_builder = sqlx::QueryBuilder::new(
+            "INSERT INTO crypto_coin_provider (provider, provider_coin_id, coin_id) ",
+        );
+        query_builder.push_values(chunk, |mut b, mapping| {
+            b.push_bind(&mapping.provider)
+                .push_bind(&mapping.provider_coin_id)
+                .push_bind(&mapping.coin_id);
+        });
+        total_row_affected += query_builder
+            .build()
+            .execute(&mut *tx)
+            .await
+            .context("failed to insert into crypto_coin_provider table")?
+            .rows_affected();
+    }
+
+    tx.commit().await.context("failed to commit transaction")?;
+    Ok(total_row_affected)
+}
+
+/// get all coins of the catalogue
+pub async fn get_all_coins(db: &Db) -> Result<Vec<SupportedCoin>> {
+    sqlx::query_as::<_, SupportedCoin>("SELECT id, symbol, name FROM crypto_coin ORDER BY id")
+        .fetch_all(&db.0)
+        .await
+        .context("failed to get all crypto coins")
+}
+
+/// search the catalogue by id, symbol or name -- exact symbol matches come first
+pub async fn search_coins(db: &Db, query: &str, limit: u32) -> Result<Vec<SupportedCoin>> {
+    let pattern = format!("%{}%", query.trim());
+    sqlx::query_as::<_, SupportedCoin>(
+        r#"
+        SELECT id, symbol, name FROM crypto_coin
+        WHERE id LIKE ? OR symbol LIKE ? OR name LIKE ?
+        ORDER BY
+            CASE WHEN symbol = ? COLLATE NOCASE THEN 0 ELSE 1 END,
+            length(name),
+            name
+        LIMIT ?
+        "#,
+    )
+    .bind(&pattern)
+    .bind(&pattern)
+    .bind(&pattern)
+    .bind(query.trim())
+    .bind(limit)
+    .fetch_all(&db.0)
+    .await
+    .context("failed to search crypto coins")
+}
+
+/// get the coin of the catalogue by its canonical id
+pub async fn get_coin_by_id(db: &Db, id: &str) -> Result<Option<SupportedCoin>> {
+    sqlx::query_as::<_, SupportedCoin>("SELECT id, symbol, name FROM crypto_coin WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&db.0)
+        .await
+        .context("failed to get crypto coin by id")
+}
+
+/// get the provider ids mapped to a coin of the catalogue
+pub async fn get_provider_mappings(db: &Db, coin_id: &str) -> Result<Vec<CoinProviderMapping>> {
+    sqlx::query_as::<_, CoinProviderMapping>(
+        "SELECT provider, provider_coin_id, coin_id FROM crypto_coin_provider WHERE coin_id = ?",
+    )
+    .bind(coin_id)
+    .fetch_all(&db.0)
+    .await
+    .context("failed to get crypto coin provider mappings")
+}
+
+/// Canonical id of a new coin: the provider coin id, or `provider:id` when taken, then
+/// `provider:id-2`, `provider:id-3`... until one is free
+fn free_coin_id(coin_ids: &HashSet<String>, provider: &str, provider_coin_id: &str) -> String {
+    if !coin_ids.contains(provider_coin_id) {
+        return provider_coin_id.to_string();
+    }
+    let prefixed = format!("{provider}:{provider_coin_id}");
+    let mut id = prefixed.clone();
+    let mut sequence = 1;
+    while coin_ids.contains(&id) {
+        sequence += 1;
+        id = format!("{prefixed}-{sequence}");
+    }
+    id
+}
+
+fn coin_key(symbol: &str, name: &str) -> (String, String) {
+    (symbol.trim().to_lowercase(), name.trim().to_lowercase())
+}
@@ -1,10 +1,11 @@
 use crate::crypto_exchange::{
-    CryptoExchange, CryptoExchangeError, ExchangeRateRequest, ExchangeRateResponse, SupportedCoin,
+    CryptoExchange, CryptoExchangeError, ExchangeRateRequest, ExchangeRateResponse,
+    PriceRangeRequest, SupportedCoin,
 };
 use async_trait::async_trait;
 use reqwest::{Client, StatusCode};
 use rust_decimal::Decimal;
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
 use serde_json::Value;
 use std::time::Duration;
 
@@ -39,6 +40,37 @@ impl CoinGeckoService {
     }
 }
 
+/// Prices of a coin as `[timestamp in ms, price]` points
+#[derive(Deserialize)]
+struct MarketChart {
+    prices: Vec<(f64, f64)>,
+}
+
+impl MarketChart {
+    /// First price of each UTC day, the daily price of the history endpoint is taken at 00:00 UTC
+    fn daily_prices(&self, request: &PriceRangeRequest) -> Vec<ExchangeRateResponse> {
+        let mut prices: Vec<ExchangeRateResponse> = Vec::new();
+        for (timestamp, price) in &self.prices {
+            let Some(date) = chrono::DateTime::from_timestamp_millis(*timestamp as i64)
+                .map(|date_time| date_time.date_naive())
+            else {
+                continue;
+            };
+            if date < request.start_date
+                || date > request.end_date
+                || prices.last().is_some_and(|last| last.date >= date)
+            {
+                continue;
+            }
+            use rust_decimal::prelude::FromPrimitive;
+            if let Some(rate) = Decimal::from_f64(*price) {
+                prices.push(ExchangeRateResponse { rate, date });
+            }
+        }
+        prices
+    }
+}
+
 #[derive(Deserialize)]
 struct CoinListEntry {
     id: String,
@@ -181,12 +213,80 @@ impl CryptoExchange for CoinGeckoService {
             }
         }
     }
+
+    async fn get_price_range(
+        &self,
+        request: PriceRangeRequest,
+    ) -> Result<Vec<ExchangeRateResponse>, CryptoExchangeError> {
+        let from = request
+            .start_date
+            .and_time(chrono::NaiveTime::MIN)
+            .and_utc();
+        let to = request
+            .end_date
+            .succ_opt()
+            .unwrap_or(request.end_date)
+            .and_time(chrono::NaiveTime::MIN)
+            .and_utc();
+        let url = format!(
+            "{}/coins/{}/market_chart/range?vs_currency={}&from={}&to={}",
+            COINGECKO_API_URL,
+            request.coin_id,
+            request.fiat_currency.to_lowercase(),
+            from.timestamp(),
+            to.timestamp()
+        );
+
+        let response = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| CryptoExchangeError::Network(e.to_string()))?;
+
+        if !response.status().is_success() {
+            return Err(self.handle_error(response).await);
+        }
+
+        let chart: MarketChart = response.json().await.map_err(|e| {
+            CryptoExchangeError::ApiError(format!("Failed to parse market chart: {}", e))
+        })?;
+        Ok(chart.daily_prices(&request))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use chrono::NaiveDate;
+
+    #[test]
+    fn test_market_chart_daily_prices() {
+        let chart: MarketChart = serde_json::from_str(
+            // 2024-01-01 00:00, 2024-01-01 12:00, 2024-01-02 00:05 and 2024-01-04 00:00 UTC
+            r#"{"prices": [[1704067200000, 42000.5], [1704110400000, 43000],
+                [1704153900000, 44000], [1704326400000, 45000]]}"#,
+        )
+        .unwrap();
+        let request = PriceRangeRequest {
+            coin_id: "bitcoin".to_string(),
+            fiat_currency: "usd".to_string(),
+            start_date: chrono::NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            end_date: chrono::NaiveDate::from_ymd_opt(2024, 1, 3).unwrap(),
+        };
+
+        let prices = chart.daily_prices(&request);
+        let prices: Vec<(String, Decimal)> = prices
+            .iter()
+            .map(|price| (price.date.to_string(), price.rate))
+            .collect();
+        assert_eq!(
+            prices,
+            [
+                ("2024-01-01".to_string(), Decimal::new(420_005, 1)),
+                ("2024-01-02".to_string(), Decimal::new(44_000, 0)),
+            ]
+        );
+    }
 
     #[tokio::test]
     #[ignore] // Integration test: hits external API
@@ -0,0 +1,42 @@
+use crate::crypto_exchange::{
+    catalogue, CoinProviderMapping, CryptoExchangeManager, SupportedCoin,
+};
+use crate::db::Db;
+use tauri::State;
+
+/// List every coin of the offline coin catalogue
+#[tauri::command]
+pub async fn list_crypto_coins(
+    db: State<'_, Db>,
+    manager: State<'_, CryptoExchangeManager>,
+) -> Result<Vec<SupportedCoin>, String> {
+    manager
+        .list_supported_coins(&db)
+        .await
+        .map_err(|e| format!("failed to list crypto coins: {e}"))
+}
+
+/// Search the offline coin catalogue -- limit is optional and defaults to 20
+#[tauri::command]
+pub async fn search_crypto_coins(
+    db: State<'_, Db>,
+    manager: State<'_, CryptoExchangeManager>,
+    query: String,
+    limit: Option<u32>,
+) -> Result<Vec<SupportedCoin>, String> {
+    manager
+        .search_supported_coins(&db, &query, limit.unwrap_or(20))
+        .await
+        .map_err(|e| format!("failed to search crypto coins: {e}"))
+}
+
+/// Get the provider specific ids of a coin of the catalogue
+#[tauri::command]
+pub async fn get_crypto_coin_providers(
+    db: State<'_, Db>,
+    coin_id: String,
+) -> Result<Vec<CoinProviderMapping>, String> {
+    catalogue::get_provider_mappings(&db, &coin_id)
+        .await
+        .map_err(|e| format!("failed to get crypto coin providers: {e}"))
+}
@@ -11,6 +11,8 @@ pub enum CryptoExchangeError {
     AssetNotFound(String),
     #[error("API error: {0}")]
     ApiError(String),
+    #[error("Database error: {0}")]
+    Database(String),
     #[error("Other error: {0}")]
     Other(String),
 }
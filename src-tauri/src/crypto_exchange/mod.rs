@@ -1,4 +1,6 @@
+pub mod catalogue;
 pub mod coingecko;
+pub mod command;
 pub mod error;
 pub mod models;
 pub mod service;
@@ -6,6 +8,9 @@ pub mod traits;
 
 pub use coingecko::CoinGeckoService;
 pub use error::CryptoExchangeError;
-pub use models::{ExchangeRateRequest, ExchangeRateResponse, SupportedCoin};
+pub use models::{
+    CoinProviderMapping, ExchangeRateRequest, ExchangeRateResponse, PriceRangeRequest,
+    SupportedCoin,
+};
 pub use service::CryptoExchangeManager;
 pub use traits::CryptoExchange;
@@ -2,13 +2,21 @@ use chrono::NaiveDate;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct SupportedCoin {
     pub id: String,
     pub symbol: String,
     pub name: String,
 }
 
+/// Provider specific coin id mapped to a coin of the catalogue
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct CoinProviderMapping {
+    pub provider: String,
+    pub provider_coin_id: String,
+    pub coin_id: String,
+}
+
 #[derive(Debug, Clone)]
 pub struct ExchangeRateRequest {
     pub coin_id: String,
@@ -16,6 +24,15 @@ pub struct ExchangeRateRequest {
     pub date: Option<NaiveDate>,
 }
 
+/// Daily prices of a coin between two dates, both inclusive
+#[derive(Debug, Clone)]
+pub struct PriceRangeRequest {
+    pub coin_id: String,
+    pub fiat_currency: String,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExchangeRateResponse {
     pub rate: Decimal,
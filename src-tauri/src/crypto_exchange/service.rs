@@ -1,6 +1,13 @@
+use super::catalogue;
 use super::error::CryptoExchangeError;
-use super::models::{ExchangeRateRequest, ExchangeRateResponse, SupportedCoin};
+use super::models::{ExchangeRateRequest, ExchangeRateResponse, PriceRangeRequest, SupportedCoin};
 use super::traits::CryptoExchange;
+use crate::{db::Db, sys_tracker::SysTracker, utils::date_utils};
+
+const CRYPTO_COIN_SYS_TRACKER_NAME: &str = "crypto_coin";
+
+/// The coin lists are large (CoinGecko alone has >10k entries) and rarely change
+const CRYPTO_COIN_UPDATE_INTERVAL_DAYS: i64 = 7;
 
 pub struct CryptoExchangeManager {
     providers: Vec<Box<dyn CryptoExchange>>,
@@ -35,10 +42,115 @@ impl CryptoExchangeManager {
         Err(last_error)
     }
 
-    pub async fn list_supported_coins(&self) -> Result<Vec<SupportedCoin>, CryptoExchangeError> {
-        // TODO: Implement of Asset and Asset-Exchange-Mapping is pending for supported coins
-        // set this to todo!() for now as DB on for Asset and exchange-Mapping implementation is pending
-        todo!("Implement database storage for supported coins")
+    /// Daily prices of a range from the first provider that can serve it
+    pub async fn get_price_range(
+        &self,
+        request: PriceRangeRequest,
+    ) -> Result<Vec<ExchangeRateResponse>, CryptoExchangeError> {
+        let mut last_error = CryptoExchangeError::Other("No providers configured".to_string());
+
+        for provider in &self.providers {
+            match provider.get_price_range(request.clone()).await {
+                Ok(prices) => return Ok(prices),
+                Err(e) => {
+                    eprintln!(
+                        "Provider {} failed to get price range for {}: {}",
+                        provider.id(),
+                        request.coin_id,
+                        e
+                    );
+                    last_error = e;
+                    // Continue to next provider
+                }
+            }
+        }
+        Err(last_error)
+    }
+
+    /// Refresh the coin catalogue from every provider, at most once per `CRYPTO_COIN_UPDATE_INTERVAL_DAYS`.
+    /// - a failing provider is skipped, the update only fails if every provider failed
+    /// - returns the number of new provider coin mappings
+    pub async fn update_supported_coins(&self, db: &Db) -> Result<u64, CryptoExchangeError> {
+        let last_updated_at = SysTracker::get_last_updated_at(CRYPTO_COIN_SYS_TRACKER_NAME, db)
+            .await
+            .map_err(|e| CryptoExchangeError::Database(e.to_string()))?;
+
+        let require_update = date_utils::require_update(
+            last_updated_at,
+            chrono::Duration::days(CRYPTO_COIN_UPDATE_INTERVAL_DAYS),
+        );
+
+        if !require_update {
+            return Ok(0);
+        }
+
+        self.refresh_supported_coins(db).await
+    }
+
+    /// List the coins of the catalogue, the catalogue is filled from the providers when empty
+    pub async fn list_supported_coins(
+        &self,
+        db: &Db,
+    ) -> Result<Vec<SupportedCoin>, CryptoExchangeError> {
+        let coins = catalogue::get_all_coins(db)
+            .await
+            .map_err(|e| CryptoExchangeError::Database(e.to_string()))?;
+
+        if !coins.is_empty() {
+            return Ok(coins);
+        }
+
+        self.refresh_supported_coins(db).await?;
+        catalogue::get_all_coins(db)
+            .await
+            .map_err(|e| CryptoExchangeError::Database(e.to_string()))
+    }
+
+    /// Search the coin catalogue offline by id, symbol or name
+    pub async fn search_supported_coins(
+        &self,
+        db: &Db,
+        query: &str,
+        limit: u32,
+    ) -> Result<Vec<SupportedCoin>, CryptoExchangeError> {
+        catalogue::search_coins(db, query, limit)
+            .await
+            .map_err(|e| CryptoExchangeError::Database(e.to_string()))
+    }
+
+    async fn refresh_supported_coins(&self, db: &Db) -> Result<u64, CryptoExchangeError> {
+        let mut last_error = CryptoExchangeError::Other("No providers configured".to_string());
+        let mut any_success = false;
+        let mut total_new_mappings = 0;
+
+        for provider in &self.providers {
+            match provider.list_supported_coins().await {
+                Ok(coins) => {
+                    total_new_mappings += catalogue::merge_provider_coins(db, provider.id(), coins)
+                        .await
+                        .map_err(|e| CryptoExchangeError::Database(e.to_string()))?;
+                    any_success = true;
+                }
+                Err(e) => {
+                    eprintln!(
+                        "Provider {} failed to list supported coins: {}",
+                        provider.id(),
+                        e
+                    );
+                    last_error = e;
+                    // Continue to next provider
+                }
+            }
+        }
+
+        if !any_success {
+            return Err(last_error);
+        }
+
+        SysTracker::update_last_updated_at(CRYPTO_COIN_SYS_TRACKER_NAME, db)
+            .await
+            .map_err(|e| CryptoExchangeError::Database(e.to_string()))?;
+        Ok(total_new_mappings)
     }
 }
 
@@ -135,4 +247,150 @@ mod tests {
             _ => panic!("Expected Network error"),
         }
     }
+
+    fn coin(id: &str, symbol: &str, name: &str) -> SupportedCoin {
+        SupportedCoin {
+            id: id.to_string(),
+            symbol: symbol.to_string(),
+            name: name.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_update_supported_coins_merges_providers() {
+        let db = Db::in_memory().await.unwrap();
+
+        let mut mock_provider1 = MockCryptoExchange::new();
+        mock_provider1.expect_id().return_const("mock_provider_1");
+        mock_provider1
+            .expect_list_supported_coins()
+            .times(1)
+            .returning(|| {
+                Ok(vec![
+                    coin("bitcoin", "btc", "Bitcoin"),
+                    coin("ethereum", "eth", "Ethereum"),
+                ])
+            });
+
+        let mut mock_provider2 = MockCryptoExchange::new();
+        mock_provider2.expect_id().return_const("mock_provider_2");
+        mock_provider2
+            .expect_list_supported_coins()
+            .times(1)
+            .returning(|| {
+                Ok(vec![
+                    // same coin under another provider id
+                    coin("BTC", "BTC", "Bitcoin"),
+                    // unknown coin whose id clashes with an existing canonical id
+                    coin("ethereum", "ETHW", "EthereumPoW"),
+                ])
+            });
+
+        let manager =
+            CryptoExchangeManager::new(vec![Box::new(mock_provider1), Box::new(mock_provider2)]);
+
+        let new_mappings = manager.update_supported_coins(&db).await.unwrap();
+        assert_eq!(new_mappings, 4);
+
+        let coins = manager.list_supported_coins(&db).await.unwrap();
+        assert_eq!(coins.len(), 3);
+        assert!(coins.iter().any(|c| c.id == "mock_provider_2:ethereum"));
+
+        let mappings = catalogue::get_provider_mappings(&db, "bitcoin")
+            .await
+            .unwrap();
+        assert_eq!(mappings.len(), 2);
+        assert!(mappings
+            .iter()
+            .any(|m| m.provider == "mock_provider_2" && m.provider_coin_id == "BTC"));
+
+        // within the update interval the providers are not hit again (times(1) above)
+        let new_mappings = manager.update_supported_coins(&db).await.unwrap();
+        assert_eq!(new_mappings, 0);
+    }
+
+    #[tokio::test]
+    async fn test_merge_provider_coins_clashing_ids() {
+        let db = Db::in_memory().await.unwrap();
+        catalogue::merge_provider_coins(
+            &db,
+            "mock_provider_1",
+            vec![
+                coin("ethereum", "eth", "Ethereum"),
+                // already holds the id a clashing coin of mock_provider_2 would fall back to
+                coin("mock_provider_2:ethereum", "ETC", "Ethereum Classic"),
+            ],
+        )
+        .await
+        .unwrap();
+
+        let new_mappings = catalogue::merge_provider_coins(
+            &db,
+            "mock_provider_2",
+            vec![coin("ethereum", "ETHW", "EthereumPoW")],
+        )
+        .await
+        .unwrap();
+        assert_eq!(new_mappings, 1);
+
+        let ethw = catalogue::get_coin_by_id(&db, "mock_provider_2:ethereum-2")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(ethw.name, "EthereumPoW");
+        assert_eq!(catalogue::get_all_coins(&db).await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_update_supported_coins_skips_failing_provider() {
+        let db = Db::in_memory().await.unwrap();
+
+        let mut mock_provider1 = MockCryptoExchange::new();
+        mock_provider1.expect_id().return_const("mock_provider_1");
+        mock_provider1
+            .expect_list_supported_coins()
+            .times(1)
+            .returning(|| Err(CryptoExchangeError::RateLimitExceeded));
+
+        let mut mock_provider2 = MockCryptoExchange::new();
+        mock_provider2.expect_id().return_const("mock_provider_2");
+        mock_provider2
+            .expect_list_supported_coins()
+            .times(1)
+            .returning(|| Ok(vec![coin("solana", "sol", "Solana")]));
+
+        let manager =
+            CryptoExchangeManager::new(vec![Box::new(mock_provider1), Box::new(mock_provider2)]);
+
+        let coins = manager.list_supported_coins(&db).await.unwrap();
+        assert_eq!(coins.len(), 1);
+
+        let found = manager
+            .search_supported_coins(&db, "sol", 10)
+            .await
+            .unwrap();
+        assert_eq!(found[0].id, "solana");
+    }
+
+    #[tokio::test]
+    async fn test_list_supported_coins_all_fail() {
+        let db = Db::in_memory().await.unwrap();
+
+        let mut mock_provider = MockCryptoExchange::new();
+        mock_provider.expect_id().return_const("mock_provider_1");
+        mock_provider
+            .expect_list_supported_coins()
+            .times(1)
+            .returning(|| Err(CryptoExchangeError::Network("Timeout".to_string())));
+
+        let manager = CryptoExchangeManager::new(vec![Box::new(mock_provider)]);
+
+        let result = manager.list_supported_coins(&db).await;
+        assert!(matches!(result, Err(CryptoExchangeError::Network(_))));
+        // a failed refresh must not be recorded as an update
+        let last_updated_at = SysTracker::get_last_updated_at(CRYPTO_COIN_SYS_TRACKER_NAME, &db)
+            .await
+            .unwrap();
+        assert!(last_updated_at.is_none());
+    }
 }
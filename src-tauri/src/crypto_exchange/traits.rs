@@ -1,5 +1,5 @@
 use super::error::CryptoExchangeError;
-use super::models::{ExchangeRateRequest, ExchangeRateResponse, SupportedCoin};
+use super::models::{ExchangeRateRequest, ExchangeRateResponse, PriceRangeRequest, SupportedCoin};
 use async_trait::async_trait;
 
 #[cfg_attr(test, mockall::automock)]
@@ -10,5 +10,10 @@ pub trait CryptoExchange: Send + Sync {
         &self,
         request: ExchangeRateRequest,
     ) -> Result<ExchangeRateResponse, CryptoExchangeError>;
+    /// Daily prices of the range in one call, a day the provider has no price for is left out
+    async fn get_price_range(
+        &self,
+        request: PriceRangeRequest,
+    ) -> Result<Vec<ExchangeRateResponse>, CryptoExchangeError>;
     fn id(&self) -> &'static str;
 }
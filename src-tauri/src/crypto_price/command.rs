@@ -0,0 +1,53 @@
+use crate::crypto_exchange::CryptoExchangeManager;
+use crate::crypto_price::{get_price, get_price_history, CryptoPrice, PriceHistory};
+use crate::db::{Db, RowId};
+use crate::fiat::{Fiat, FiatService};
+use crate::fiat_exchanger::frankfurter_exchanger::FrankfurterExchangerApi;
+use crate::user_settings;
+use chrono::NaiveDate;
+use tauri::State;
+
+/// Resolve the fiat to price in -- defaults to the user's default fiat
+async fn resolve_fiat(db: &Db, fiat_id: Option<RowId>) -> Result<Fiat, String> {
+    match fiat_id {
+        Some(id) => FiatService::<FrankfurterExchangerApi>::get_fiat_by_id(db, id)
+            .await
+            .map_err(|e| format!("failed to get fiat: {e}")),
+        None => user_settings::get(db)
+            .await
+            .map(|settings| settings.fiat)
+            .map_err(|e| format!("failed to get default fiat: {e}")),
+    }
+}
+
+/// Get the price of a coin -- date is optional and defaults to today
+#[tauri::command]
+pub async fn get_crypto_price(
+    db: State<'_, Db>,
+    manager: State<'_, CryptoExchangeManager>,
+    coin_id: String,
+    fiat_id: Option<RowId>,
+    date: Option<NaiveDate>,
+) -> Result<CryptoPrice, String> {
+    let fiat = resolve_fiat(&db, fiat_id).await?;
+    get_price(&db, &manager, &coin_id, &fiat, date)
+        .await
+        .map_err(|e| format!("failed to get crypto price: {e:#}"))
+}
+
+/// Get the daily price of a coin between start and end date (inclusive), with the dates that
+/// could not be priced
+#[tauri::command]
+pub async fn get_crypto_price_history(
+    db: State<'_, Db>,
+    manager: State<'_, CryptoExchangeManager>,
+    coin_id: String,
+    fiat_id: Option<RowId>,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+) -> Result<PriceHistory, String> {
+    let fiat = resolve_fiat(&db, fiat_id).await?;
+    get_price_history(&db, &manager, &coin_id, &fiat, start_date, end_date)
+        .await
+        .map_err(|e| format!("failed to get crypto price history: {e:#}"))
+}
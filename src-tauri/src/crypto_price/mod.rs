@@ -0,0 +1,398 @@
+pub mod command;
+use crate::crypto_exchange::{CryptoExchangeManager, ExchangeRateRequest, PriceRangeRequest};
+use crate::db::{Db, RowId, SqlDecimal};
+use crate::fiat::Fiat;
+use crate::utils::date_utils;
+use anyhow::{bail, Context, Result};
+use chrono::{NaiveDate, NaiveDateTime};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Price of a coin in a fiat on a given date, cached in the `crypto_price` table
+#[derive(Debug, sqlx::FromRow, Serialize, Deserialize)]
+pub struct CryptoPrice {
+    pub id: RowId,
+    pub coin_id: String,
+    pub fiat_id: RowId,
+    #[sqlx(skip)]
+    pub fiat_symbol: String,
+    pub date: NaiveDate,
+    #[sqlx(try_from = "SqlDecimal")]
+    pub price: Decimal,
+    pub updated_at: NaiveDateTime,
+}
+
+/// Today's price keeps moving, so it is refreshed once it is older than this
+const CURRENT_PRICE_TTL_MINUTES: i64 = 15;
+
+/// CoinGecko public API only serves the history of the last 365 days
+const MAX_HISTORY_DAYS: i64 = 365;
+
+/// Get the price of a coin in the given fiat.
+/// - `date` None means today's price
+/// - the price is fetched from the providers only once and then served from the `crypto_price` table,
+///   except for today's price which is refreshed after `CURRENT_PRICE_TTL_MINUTES`
+pub async fn get_price(
+    db: &Db,
+    manager: &CryptoExchangeManager,
+    coin_id: &str,
+    fiat: &Fiat,
+    date: Option<NaiveDate>,
+) -> Result<CryptoPrice> {
+    let today = chrono::Local::now().date_naive();
+    let date = date.unwrap_or(today);
+    if date > today {
+        bail!("cannot get the price of {coin_id} for a future date {date}");
+    }
+
+    // 1. Check DB first
+    if let Some(mut price) = get_cached_price(db, coin_id, fiat.id, &date).await? {
+        let is_fresh = date < today
+            || !date_utils::require_update(
+                Some(price.updated_at),
+                chrono::Duration::minutes(CURRENT_PRICE_TTL_MINUTES),
+            );
+        if is_fresh {
+            price.fiat_symbol = fiat.symbol.clone();
+            return Ok(price);
+        }
+    }
+
+    // 2. Not found (or stale) in DB, fetch from the providers
+    let response = manager
+        .get_exchange_rate(ExchangeRateRequest {
+            coin_id: coin_id.to_string(),
+            fiat_currency: fiat.symbol.clone(),
+            date: if date == today { None } else { Some(date) },
+        })
+        .await
+        .with_context(|| format!("failed to get price of {coin_id} in {}", fiat.symbol))?;
+
+    let mut price = insert_price(db, coin_id, fiat.id, &date, &response.rate).await?;
+    price.fiat_symbol = fiat.symbol.clone();
+    Ok(price)
+}
+
+/// Daily prices of a coin and the dates no provider could price
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PriceHistory {
+    pub prices: Vec<CryptoPrice>,
+    pub skipped_dates: Vec<NaiveDate>,
+}
+
+/// Get the daily price of a coin between `start_date` and `end_date` (both inclusive).
+/// - the dates missing from the `crypto_price` table are fetched in one range call and cached
+/// - today's price is refreshed like in `get_price`
+/// - dates the providers cannot serve are returned in `skipped_dates`, all the missing dates when
+///   the range call fails
+pub async fn get_price_history(
+    db: &Db,
+    manager: &CryptoExchangeManager,
+    coin_id: &str,
+    fiat: &Fiat,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+) -> Result<PriceHistory> {
+    let today = chrono::Local::now().date_naive();
+    let end_date = end_date.min(today);
+    if start_date > end_date {
+        bail!("start date {start_date} is after end date {end_date}");
+    }
+    if (end_date - start_date).num_days() >= MAX_HISTORY_DAYS {
+        bail!("price history is limited to {MAX_HISTORY_DAYS} days per request");
+    }
+
+    let mut prices: BTreeMap<NaiveDate, CryptoPrice> =
+        get_cached_prices(db, coin_id, fiat.id, &start_date, &end_date)
+            .await?
+            .into_iter()
+            .map(|price| (price.date, price))
+            .collect();
+    let mut skipped_dates = Vec::new();
+
+    let missing_dates: Vec<NaiveDate> = start_date
+        .iter_days()
+        .take_while(|date| *date <= end_date && *date < today)
+        .filter(|date| !prices.contains_key(date))
+        .collect();
+    if let (Some(first), Some(last)) = (missing_dates.first(), missing_dates.last()) {
+        let fetched: HashMap<NaiveDate, Decimal> = match manager
+            .get_price_range(PriceRangeRequest {
+                coin_id: coin_id.to_string(),
+                fiat_currency: fiat.symbol.clone(),
+                start_date: *first,
+                end_date: *last,
+            })
+            .await
+        {
+            Ok(responses) => responses
+                .into_iter()
+                .map(|response| (response.date, response.rate))
+                .collect(),
+            // the cached prices are still served, the missing dates are skipped
+            Err(e) => {
+                eprintln!("Failed to get prices of {coin_id} in {}: {e}", fiat.symbol);
+                HashMap::new()
+            }
+        };
+        for date in missing_dates {
+            match fetched.get(&date) {
+                Some(rate) => {
+                    let price = insert_price(db, coin_id, fiat.id, &date, rate).await?;
+                    prices.insert(date, price);
+                }
+                None => skipped_dates.push(date),
+            }
+        }
+    }
+
+    if end_date == today {
+        match get_price(db, manager, coin_id, fiat, None).await {
+            Ok(price) => {
+                prices.insert(today, price);
+            }
+            Err(_) => {
+                prices.remove(&today);
+                skipped_dates.push(today);
+            }
+        }
+    }
+
+    let prices = prices
+        .into_values()
+        .map(|mut price| {
+            price.fiat_symbol = fiat.symbol.clone();
+            price
+        })
+        .collect();
+    Ok(PriceHistory {
+        prices,
+        skipped_dates,
+    })
+}
+
+async fn get_cached_prices(
+    db: &Db,
+    coin_id: &str,
+    fiat_id: RowId,
+    start_date: &NaiveDate,
+    end_date: &NaiveDate,
+) -> Result<Vec<CryptoPrice>> {
+    sqlx::query_as::<sqlx::Sqlite, CryptoPrice>(
+        "SELECT * FROM crypto_price WHERE coin_id = ? AND fiat_id = ? AND date BETWEEN ? AND ?",
+    )
+    .bind(coin_id)
+    .bind(fiat_id)
+    .bind(start_date)
+    .bind(end_date)
+    .fetch_all(&db.0)
+    .await
+    .context("failed to get cached crypto prices")
+}
+
+async fn get_cached_price(
+    db: &Db,
+    coin_id: &str,
+    fiat_id: RowId,
+    date: &NaiveDate,
+) -> Result<Option<CryptoPrice>> {
+    sqlx::query_as::<sqlx::Sqlite, CryptoPrice>(
+        "SELECT * FROM crypto_price WHERE coin_id = ? AND fiat_id = ? AND date = ?",
+    )
+    .bind(coin_id)
+    .bind(fiat_id)
+    .bind(date)
+    .fetch_optional(&db.0)
+    .await
+    .context("failed to get cached crypto price")
+}
+
+// Helper: Insert (or Update) Price
+async fn insert_price(
+    db: &Db,
+    coin_id: &str,
+    fiat_id: RowId,
+    date: &NaiveDate,
+    price: &Decimal,
+) -> Result<CryptoPrice> {
+    sqlx::query_as::<sqlx::Sqlite, CryptoPrice>(
+        r#"
+        INSERT INTO crypto_price (coin_id, fiat_id, date, price, updated_at)
+        VALUES (?, ?, ?, ?, ?)
+        ON CONFLICT(coin_id, fiat_id, date) DO UPDATE SET
+            price = excluded.price,
+            updated_at = excluded.updated_at
+        RETURNING *
+        "#,
+    )
+    .bind(coin_id)
+    .bind(fiat_id)
+    .bind(date)
+    .bind(price.to_string())
+    .bind(chrono::Local::now().naive_local())
+    .fetch_one(&db.0)
+    .await
+    .context("failed to insert crypto price")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto_exchange::{
+        traits::MockCryptoExchange, CryptoExchangeError, ExchangeRateResponse,
+    };
+
+    async fn setup() -> (Db, Fiat) {
+        let db = Db::in_memory().await.unwrap();
+        let fiat = sqlx::query_as::<_, Fiat>(
+            "INSERT INTO fiat (symbol, name) VALUES ('USD', 'United States Dollar') RETURNING *",
+        )
+        .fetch_one(&db.0)
+        .await
+        .unwrap();
+        (db, fiat)
+    }
+
+    fn manager_returning(rate: Decimal, times: usize) -> CryptoExchangeManager {
+        let mut mock_provider = MockCryptoExchange::new();
+        mock_provider.expect_id().return_const("mock_provider");
+        mock_provider
+            .expect_get_exchange_rate()
+            .times(times)
+            .returning(move |request| {
+                Ok(ExchangeRateResponse {
+                    rate,
+                    date: request
+                        .date
+                        .unwrap_or_else(|| chrono::Local::now().date_naive()),
+                })
+            });
+        CryptoExchangeManager::new(vec![Box::new(mock_provider)])
+    }
+
+    #[tokio::test]
+    async fn test_get_price_is_cached() {
+        let (db, fiat) = setup().await;
+        // provider must only be hit once
+        let manager = manager_returning(Decimal::new(4_250_012, 2), 1);
+        let date = chrono::Local::now().date_naive() - chrono::Duration::days(3);
+
+        let first = get_price(&db, &manager, "bitcoin", &fiat, Some(date))
+            .await
+            .unwrap();
+        let second = get_price(&db, &manager, "bitcoin", &fiat, Some(date))
+            .await
+            .unwrap();
+
+        assert_eq!(first.price, Decimal::new(4_250_012, 2));
+        assert_eq!(second.price, first.price);
+        assert_eq!(second.fiat_symbol, "USD");
+    }
+
+    #[tokio::test]
+    async fn test_get_price_current_is_cached_within_ttl() {
+        let (db, fiat) = setup().await;
+        let manager = manager_returning(Decimal::new(3000, 0), 1);
+
+        get_price(&db, &manager, "ethereum", &fiat, None)
+            .await
+            .unwrap();
+        let price = get_price(&db, &manager, "ethereum", &fiat, None)
+            .await
+            .unwrap();
+
+        assert_eq!(price.date, chrono::Local::now().date_naive());
+        assert_eq!(price.price, Decimal::new(3000, 0));
+    }
+
+    #[tokio::test]
+    async fn test_get_price_future_date() {
+        let (db, fiat) = setup().await;
+        let manager = manager_returning(Decimal::ONE, 0);
+        let tomorrow = chrono::Local::now().date_naive() + chrono::Duration::days(1);
+
+        let result = get_price(&db, &manager, "bitcoin", &fiat, Some(tomorrow)).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_get_price_history_skips_failures() {
+        let (db, fiat) = setup().await;
+        let end_date = chrono::Local::now().date_naive() - chrono::Duration::days(1);
+        let start_date = end_date - chrono::Duration::days(2);
+
+        let mut mock_provider = MockCryptoExchange::new();
+        mock_provider.expect_id().return_const("mock_provider");
+        // one range call for the whole history, the first day has no price
+        mock_provider
+            .expect_get_price_range()
+            .times(1)
+            .returning(move |request| {
+                assert_eq!(request.start_date, start_date);
+                assert_eq!(request.end_date, end_date);
+                Ok(request
+                    .start_date
+                    .iter_days()
+                    .skip(1)
+                    .take_while(|date| *date <= request.end_date)
+                    .map(|date| ExchangeRateResponse {
+                        rate: Decimal::new(1, 0),
+                        date,
+                    })
+                    .collect())
+            });
+        let manager = CryptoExchangeManager::new(vec![Box::new(mock_provider)]);
+
+        let history = get_price_history(&db, &manager, "bitcoin", &fiat, start_date, end_date)
+            .await
+            .unwrap();
+
+        assert_eq!(history.prices.len(), 2);
+        assert!(history.prices.iter().all(|p| p.date > start_date));
+        assert_eq!(history.skipped_dates, [start_date]);
+
+        // the cached days are not fetched again, only the skipped one (times(1) above)
+        let history = get_price_history(
+            &db,
+            &manager,
+            "bitcoin",
+            &fiat,
+            start_date + chrono::Duration::days(1),
+            end_date,
+        )
+        .await
+        .unwrap();
+        assert_eq!(history.prices.len(), 2);
+        assert!(history.skipped_dates.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_price_history_serves_cache_on_provider_error() {
+        let (db, fiat) = setup().await;
+        let end_date = chrono::Local::now().date_naive() - chrono::Duration::days(1);
+        let start_date = end_date - chrono::Duration::days(2);
+        insert_price(&db, "bitcoin", fiat.id, &end_date, &Decimal::new(42, 0))
+            .await
+            .unwrap();
+
+        let mut mock_provider = MockCryptoExchange::new();
+        mock_provider.expect_id().return_const("mock_provider");
+        mock_provider
+            .expect_get_price_range()
+            .times(1)
+            .returning(|_| Err(CryptoExchangeError::Other("rate limited".to_string())));
+        let manager = CryptoExchangeManager::new(vec![Box::new(mock_provider)]);
+
+        let history = get_price_history(&db, &manager, "bitcoin", &fiat, start_date, end_date)
+            .await
+            .unwrap();
+
+        assert_eq!(history.prices.len(), 1);
+        assert_eq!(history.prices[0].price, Decimal::new(42, 0));
+        assert_eq!(
+            history.skipped_dates,
+            [start_date, start_date + chrono::Duration::days(1)]
+        );
+    }
+}
@@ -1,9 +1,25 @@
-use sqlx::{sqlite::SqlitePoolOptions, SqlitePool};
-use std::{path::PathBuf, str::FromStr};
+use rust_decimal::Decimal;
+use sqlx::{
+    error::BoxDynError,
+    migrate::Migrator,
+    sqlite::{SqliteConnectOptions, SqlitePoolOptions, SqliteTypeInfo, SqliteValueRef},
+    Sqlite, SqlitePool, ValueRef,
+};
+use std::io::Read;
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 use tauri::{AppHandle, Manager};
 
 use crate::AppConfig;
 
+/// Migrations of the schema, the version of a database is the last one applied
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+/// Header of a plain sqlite file, an encrypted database starts with random bytes
+pub(crate) const SQLITE_HEADER: &[u8; 16] = b"SQLite format 3\0";
+
 /// StringRowId is the primary key of the table
 pub type StringRowId = String;
 
@@ -12,26 +28,153 @@ pub type RowId = i64;
 
 pub struct Db(pub SqlitePool);
 
-pub async fn init_db(app: &AppHandle) -> Result<SqlitePool, String> {
+/// Decimal stored as TEXT, as sqlx has no native decimal support for sqlite.
+/// - use with `#[sqlx(try_from = "SqlDecimal")]` on `Decimal` fields
+/// - bind with `.bind(value.to_string())`
+pub struct SqlDecimal(pub Decimal);
+
+impl From<SqlDecimal> for Decimal {
+    fn from(value: SqlDecimal) -> Self {
+        value.0
+    }
+}
+
+impl sqlx::Type<Sqlite> for SqlDecimal {
+    fn type_info() -> SqliteTypeInfo {
+        <String as sqlx::Type<Sqlite>>::type_info()
+    }
+
+    // aggregates (SUM, json_extract ...) may hand back REAL or INTEGER instead of TEXT
+    fn compatible(ty: &SqliteTypeInfo) -> bool {
+        <String as sqlx::Type<Sqlite>>::compatible(ty)
+            || <f64 as sqlx::Type<Sqlite>>::compatible(ty)
+            || <i64 as sqlx::Type<Sqlite>>::compatible(ty)
+    }
+}
+
+impl<'r> sqlx::Decode<'r, Sqlite> for SqlDecimal {
+    fn decode(value: SqliteValueRef<'r>) -> Result<Self, BoxDynError> {
+        let text = <&str as sqlx::Decode<Sqlite>>::decode(value)?;
+        let decimal = Decimal::from_str(text)
+            .or_else(|_| Decimal::from_scientific(text))
+            .map_err(|e| format!("invalid decimal '{text}': {e}"))?;
+        Ok(SqlDecimal(decimal))
+    }
+}
+
+/// Nullable version of `SqlDecimal`
+/// - use with `#[sqlx(try_from = "SqlOptionDecimal")]` on `Option<Decimal>` fields
+pub struct SqlOptionDecimal(pub Option<Decimal>);
+
+impl From<SqlOptionDecimal> for Option<Decimal> {
+    fn from(value: SqlOptionDecimal) -> Self {
+        value.0
+    }
+}
+
+impl sqlx::Type<Sqlite> for SqlOptionDecimal {
+    fn type_info() -> SqliteTypeInfo {
+        <SqlDecimal as sqlx::Type<Sqlite>>::type_info()
+    }
+
+    fn compatible(ty: &SqliteTypeInfo) -> bool {
+        <SqlDecimal as sqlx::Type<Sqlite>>::compatible(ty)
+    }
+}
+
+impl<'r> sqlx::Decode<'r, Sqlite> for SqlOptionDecimal {
+    fn decode(value: SqliteValueRef<'r>) -> Result<Self, BoxDynError> {
+        if value.is_null() {
+            return Ok(SqlOptionDecimal(None));
+        }
+        let decimal = <SqlDecimal as sqlx::Decode<Sqlite>>::decode(value)?;
+        Ok(SqlOptionDecimal(Some(decimal.0)))
+    }
+}
+
+/// Open the database, `passphrase` unlocks an encrypted one
+/// - a pending replacement, a rekeyed copy or a restored backup, replaces the database first
+pub async fn init_db(app: &AppHandle, passphrase: Option<&str>) -> Result<SqlitePool, String> {
+    let db_path = get_db_path(app).await?;
+    apply_pending_replacement(&db_path)?;
+    connect(&db_path, passphrase).await
+}
+
+/// Path of the database file
+pub async fn get_db_path(app: &AppHandle) -> Result<PathBuf, String> {
     let config = app.state::<AppConfig>();
     // Put DB under the app data directory (in mobile it maps to the app sandbox)
     let app_data_dir = set_app_data_dir(app, &config).await?;
+    Ok(get_db_file(app_data_dir, &config))
+}
 
-    let db_url = get_db_url(app_data_dir, &config);
+/// Whether the database file is encrypted, a plain sqlite file starts with its header
+pub fn is_encrypted(db_path: &Path) -> Result<bool, String> {
+    let mut header = [0u8; SQLITE_HEADER.len()];
+    match std::fs::File::open(db_path) {
+        Ok(mut file) => match file.read_exact(&mut header) {
+            Ok(()) => Ok(&header != SQLITE_HEADER),
+            // a new database is empty until the first write
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => Ok(false),
+            Err(e) => Err(format!("failed to read database file: {e}")),
+        },
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(format!("failed to open database file: {e}")),
+    }
+}
+
+/// Path of the file that replaces the database on the next start
+pub fn pending_replacement_path(db_path: &Path) -> PathBuf {
+    db_path.with_extension("sqlite.pending")
+}
+
+/// Replace the database with the pending replacement when there is one
+pub fn apply_pending_replacement(db_path: &Path) -> Result<(), String> {
+    let pending = pending_replacement_path(db_path);
+    if !pending.exists() {
+        return Ok(());
+    }
+    for suffix in ["-wal", "-shm"] {
+        let mut journal = db_path.as_os_str().to_owned();
+        journal.push(suffix);
+        match std::fs::remove_file(&journal) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                return Err(format!("failed to remove database journal: {e}"))
+            }
+            _ => {}
+        }
+    }
+    std::fs::rename(&pending, db_path).map_err(|e| format!("failed to replace database: {e}"))
+}
+
+/// Open the database file and bring it up to the current schema
+/// - with a passphrase the file is a sqlcipher database, its key is derived from the passphrase
+///   with PBKDF2-HMAC-SHA512
+pub async fn connect(db_path: &Path, passphrase: Option<&str>) -> Result<SqlitePool, String> {
+    let mut options = SqliteConnectOptions::new()
+        .filename(db_path)
+        .create_if_missing(true)
+        .journal_mode(sqlx::sqlite::SqliteJournalMode::Wal);
+    if let Some(passphrase) = passphrase {
+        options = options.pragma("key", quote_literal(passphrase));
+    }
 
     let pool = SqlitePoolOptions::new()
         .max_connections(5)
-        .connect_with(
-            sqlx::sqlite::SqliteConnectOptions::from_str(&db_url)
-                .map_err(|e| format!("failed to parse db url: {e}"))?
-                .create_if_missing(true)
-                .journal_mode(sqlx::sqlite::SqliteJournalMode::Wal),
-        )
+        .connect_with(options)
+        .await
+        .map_err(|e| match passphrase {
+            Some(_) => format!("wrong passphrase or corrupted database: {e}"),
+            None => format!("db connect error: {e}"),
+        })?;
+    // the key is only checked on the first read
+    sqlx::query("SELECT count(*) FROM sqlite_master")
+        .execute(&pool)
         .await
-        .map_err(|e| format!("db connect error: {e}"))?;
+        .map_err(|_| "wrong passphrase or corrupted database".to_string())?;
 
     // Apply migrations
-    sqlx::migrate!("./migrations")
+    MIGRATOR
         .run(&pool)
         .await
         .map_err(|e| format!("db migrate error: {e}"))?;
@@ -68,18 +211,23 @@ async fn set_app_data_dir(app: &AppHandle, config: &AppConfig) -> Result<PathBuf
     Ok(app_data_dir)
 }
 
-fn get_db_url(app_data_dir: PathBuf, config: &AppConfig) -> String {
+/// Quote a value as a sql string literal, for the statements that take no bound parameters
+pub fn quote_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+fn get_db_file(app_data_dir: PathBuf, config: &AppConfig) -> PathBuf {
     let name = match config.env.as_str() {
         "dev" => "aura_dev".to_owned(),
         "prod" => "aura".to_owned(),
         _ => "aura".to_owned(),
     };
-    let path = app_data_dir.join(format!("{}.sqlite", name));
-    format!("sqlite:{}", path.to_string_lossy())
+    app_data_dir.join(format!("{}.sqlite", name))
 }
 
 impl Db {
     /// for unit test purpose only
+    #[cfg(test)]
     pub async fn in_memory() -> Result<Db, String> {
         let pool = SqlitePoolOptions::new()
             .max_connections(5)
@@ -93,7 +241,7 @@ impl Db {
             .map_err(|e| format!("db connect error: {e}"))?;
 
         // Apply migrations
-        sqlx::migrate!("./migrations")
+        MIGRATOR
             .run(&pool)
             .await
             .map_err(|e| format!("db migrate error: {e}"))?;
@@ -0,0 +1,51 @@
+use crate::db::{self, Db};
+use crate::encryption::{DatabaseStatus, EncryptionService};
+use tauri::{AppHandle, Manager, State};
+use tokio::sync::Mutex;
+
+#[tauri::command]
+pub async fn get_database_status(app: AppHandle) -> Result<DatabaseStatus, String> {
+    let db_path = db::get_db_path(&app).await?;
+    Ok(DatabaseStatus {
+        encrypted: db::is_encrypted(&db_path)?,
+        unlocked: app.try_state::<Db>().is_some(),
+    })
+}
+
+/// Held while the database is being unlocked, so that concurrent unlocks open it only once
+static UNLOCKING: Mutex<()> = Mutex::const_new(());
+
+/// Open the encrypted database with its passphrase and run the start up tasks
+/// - an unlock waits for the one in progress and returns once it has opened the database
+#[tauri::command]
+pub async fn unlock_database(passphrase: String, app: AppHandle) -> Result<(), String> {
+    let _unlocking = UNLOCKING.lock().await;
+    if app.try_state::<Db>().is_some() {
+        return Ok(());
+    }
+    let pool = db::init_db(&app, Some(&passphrase)).await?;
+    crate::on_db_ready(&app, Db(pool)).await;
+    Ok(())
+}
+
+/// Encrypt the database, change its passphrase, or decrypt it when `new_passphrase` is None
+/// - the app restarts to reopen the database with the new passphrase
+#[tauri::command]
+pub async fn change_database_passphrase(
+    current_passphrase: Option<String>,
+    new_passphrase: Option<String>,
+    app: AppHandle,
+    db: State<'_, Db>,
+) -> Result<(), String> {
+    let db_path = db::get_db_path(&app).await?;
+    EncryptionService::rekey(
+        &db,
+        &db_path,
+        current_passphrase.as_deref(),
+        new_passphrase.as_deref(),
+    )
+    .await
+    .map_err(|e| format!("failed to change database passphrase: {e:#}"))?;
+    app.request_restart();
+    Ok(())
+}
@@ -0,0 +1,158 @@
+pub mod command;
+use crate::db::{self, Db};
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::{sqlite::SqliteConnectOptions, ConnectOptions, Connection, SqliteConnection};
+use std::path::Path;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DatabaseStatus {
+    /// the database file is encrypted with a passphrase
+    pub encrypted: bool,
+    /// the database is open, an encrypted one stays closed until `unlock_database`
+    pub unlocked: bool,
+}
+
+pub struct EncryptionService {}
+
+impl EncryptionService {
+    /// Whether `passphrase` opens the database file, None for a plain database
+    pub async fn verify_passphrase(db_path: &Path, passphrase: Option<&str>) -> Result<bool> {
+        let mut options = SqliteConnectOptions::new().filename(db_path);
+        if let Some(passphrase) = passphrase {
+            options = options.pragma("key", db::quote_literal(passphrase));
+        }
+        let Ok(mut conn) = options.connect().await else {
+            return Ok(false);
+        };
+        let opened = sqlx::query("SELECT count(*) FROM sqlite_master")
+            .execute(&mut conn)
+            .await
+            .is_ok();
+        conn.close().await.ok();
+        Ok(opened)
+    }
+
+    /// Write a copy of the database encrypted with `new_passphrase`, or a plain copy when None
+    /// - the copy replaces the database on the next start, see `db::init_db`, so the app is to be
+    ///   restarted right after
+    /// - `current_passphrase` must open the database when it is encrypted
+    pub async fn rekey(
+        db: &Db,
+        db_path: &Path,
+        current_passphrase: Option<&str>,
+        new_passphrase: Option<&str>,
+    ) -> Result<()> {
+        if new_passphrase.is_some_and(|passphrase| passphrase.is_empty()) {
+            bail!("the passphrase must not be empty");
+        }
+        let encrypted = db::is_encrypted(db_path).map_err(|e| anyhow!(e))?;
+        if encrypted && !Self::verify_passphrase(db_path, current_passphrase).await? {
+            bail!("the current passphrase is wrong");
+        }
+        if !encrypted && new_passphrase.is_none() {
+            bail!("the database is not encrypted");
+        }
+
+        let pending = db::pending_replacement_path(db_path);
+        let temp_path = pending.with_extension("tmp");
+        if temp_path.exists() {
+            std::fs::remove_file(&temp_path).context("failed to remove previous copy")?;
+        }
+        // an empty key attaches a plain database
+        let key = db::quote_literal(new_passphrase.unwrap_or_default());
+        let target = db::quote_literal(&temp_path.to_string_lossy());
+
+        let mut conn =
+            db.0.acquire()
+                .await
+                .context("failed to acquire connection")?;
+        export(&mut conn, &target, &key).await?;
+
+        std::fs::rename(&temp_path, &pending).context("failed to save database copy")?;
+        Ok(())
+    }
+}
+
+/// Copy the database into the file `target` keyed with `key`, both quoted sql literals
+async fn export(conn: &mut SqliteConnection, target: &str, key: &str) -> Result<()> {
+    let attach = format!("ATTACH DATABASE {target} AS rekeyed KEY {key}");
+    sqlx::query(&attach)
+        .execute(&mut *conn)
+        .await
+        .context("failed to create database copy")?;
+    let exported = sqlx::query("SELECT sqlcipher_export('rekeyed')")
+        .execute(&mut *conn)
+        .await;
+    sqlx::query("DETACH DATABASE rekeyed")
+        .execute(&mut *conn)
+        .await
+        .context("failed to close database copy")?;
+    exported.context("failed to copy database")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_rekey_database() {
+        let dir = std::env::temp_dir().join(uuid::Uuid::now_v7().to_string());
+        std::fs::create_dir_all(&dir).unwrap();
+        let db_path = dir.join("aura.sqlite");
+
+        let db = Db(db::connect(&db_path, None).await.unwrap());
+        sqlx::query("INSERT INTO fiat (id, symbol, name) VALUES (1, 'EUR', 'Euro')")
+            .execute(&db.0)
+            .await
+            .unwrap();
+        assert!(!db::is_encrypted(&db_path).unwrap());
+
+        // plain to encrypted
+        EncryptionService::rekey(&db, &db_path, None, Some("secret"))
+            .await
+            .unwrap();
+        db.0.close().await;
+        db::apply_pending_replacement(&db_path).unwrap();
+        assert!(db::is_encrypted(&db_path).unwrap());
+        assert!(db::connect(&db_path, None).await.is_err());
+        assert!(db::connect(&db_path, Some("wrong")).await.is_err());
+        assert!(
+            EncryptionService::verify_passphrase(&db_path, Some("secret"))
+                .await
+                .unwrap()
+        );
+
+        // change of passphrase
+        let db = Db(db::connect(&db_path, Some("secret")).await.unwrap());
+        assert!(
+            EncryptionService::rekey(&db, &db_path, Some("wrong"), Some("other"))
+                .await
+                .is_err()
+        );
+        EncryptionService::rekey(&db, &db_path, Some("secret"), Some("other"))
+            .await
+            .unwrap();
+        db.0.close().await;
+        db::apply_pending_replacement(&db_path).unwrap();
+
+        let db = Db(db::connect(&db_path, Some("other")).await.unwrap());
+        let symbol: String = sqlx::query_scalar("SELECT symbol FROM fiat WHERE id = 1")
+            .fetch_one(&db.0)
+            .await
+            .unwrap();
+        assert_eq!(symbol, "EUR");
+
+        // back to plain
+        EncryptionService::rekey(&db, &db_path, Some("other"), None)
+            .await
+            .unwrap();
+        db.0.close().await;
+        db::apply_pending_replacement(&db_path).unwrap();
+        assert!(!db::is_encrypted(&db_path).unwrap());
+        assert!(db::connect(&db_path, None).await.is_ok());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
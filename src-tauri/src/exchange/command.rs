@@ -0,0 +1,60 @@
+use crate::db::{Db, StringRowId};
+use crate::exchange::{CreateExchange, Exchange, ExchangeService, UpdateExchange};
+use tauri::State;
+
+#[tauri::command]
+pub async fn create_exchange(
+    create_exchange: CreateExchange,
+    db: State<'_, Db>,
+) -> Result<Exchange, String> {
+    ExchangeService::create(create_exchange, &db)
+        .await
+        .map_err(|e| format!("failed to create exchange: {e:#}"))
+}
+
+#[tauri::command]
+pub async fn get_exchanges(db: State<'_, Db>) -> Result<Vec<Exchange>, String> {
+    ExchangeService::get_all(&db)
+        .await
+        .map_err(|e| format!("failed to get exchanges: {e}"))
+}
+
+/// Find the exchange a free-text name refers to, typos included -- None when there is no match
+#[tauri::command]
+pub async fn find_exchange_by_name(
+    name: String,
+    db: State<'_, Db>,
+) -> Result<Option<Exchange>, String> {
+    ExchangeService::find_by_name(&name, &db)
+        .await
+        .map_err(|e| format!("failed to find exchange: {e}"))
+}
+
+#[tauri::command]
+pub async fn update_exchange(
+    exchange: UpdateExchange,
+    db: State<'_, Db>,
+) -> Result<Exchange, String> {
+    ExchangeService::update(exchange, &db)
+        .await
+        .map_err(|e| format!("failed to update exchange: {e:#}"))
+}
+
+#[tauri::command]
+pub async fn delete_exchange(id: StringRowId, db: State<'_, Db>) -> Result<u64, String> {
+    ExchangeService::delete(&id, &db)
+        .await
+        .map_err(|e| format!("failed to delete exchange: {e:#}"))
+}
+
+/// Move every fiat ramp of `from_id` to `into_id` and delete `from_id`
+#[tauri::command]
+pub async fn merge_exchanges(
+    from_id: StringRowId,
+    into_id: StringRowId,
+    db: State<'_, Db>,
+) -> Result<Exchange, String> {
+    ExchangeService::merge(&from_id, &into_id, &db)
+        .await
+        .map_err(|e| format!("failed to merge exchanges: {e:#}"))
+}
@@ -0,0 +1,683 @@
+pub mod command;
+use crate::db::{Db, StringRowId};
+use crate::sys_tracker::SysTracker;
+use crate::utils::string_utils::{edit_distance, is_similar_name, normalize_name};
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use sqlx::SqliteConnection;
+use uuid::Uuid;
+
+const LINK_SYS_TRACKER_NAME: &str = "exchange_link";
+
+#[derive(Debug, Serialize, Deserialize, sqlx::Type, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum ExchangeKind {
+    /// centralized exchange
+    #[default]
+    Cex,
+    /// decentralized exchange
+    Dex,
+    Bank,
+    Wallet,
+}
+
+/// Venue where funds are ramped or traded
+#[derive(Debug, FromRow, Serialize, Deserialize, Clone)]
+pub struct Exchange {
+    pub id: StringRowId,
+    pub name: String,
+    pub kind: ExchangeKind,
+    /// id of the exchange at a data provider, e.g. coingecko
+    pub provider_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CreateExchange {
+    pub name: String,
+    pub kind: ExchangeKind,
+    pub provider_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateExchange {
+    pub id: StringRowId,
+    pub name: Option<String>,
+    pub kind: Option<ExchangeKind>,
+    pub provider_id: Option<String>,
+}
+
+/// Reference to an exchange, either by id (`{"id": "..."}`) or by name (`"Binance"`)
+/// - a name is matched against the existing exchanges, typos included, before a new exchange is created
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+#[serde(untagged)]
+pub enum ExchangeRef {
+    Id { id: StringRowId },
+    Name(String),
+}
+
+pub struct ExchangeService {}
+
+impl ExchangeService {
+    /// Create a new exchange
+    /// - the name must not be taken yet, ignoring case, spaces and punctuation
+    pub async fn create(create_exchange: CreateExchange, db: &Db) -> Result<Exchange> {
+        let mut conn =
+            db.0.acquire()
+                .await
+                .context("failed to acquire connection")?;
+        Self::create_in(create_exchange, &mut conn).await
+    }
+
+    /// Get all exchanges ordered by name
+    pub async fn get_all(db: &Db) -> Result<Vec<Exchange>> {
+        let mut conn =
+            db.0.acquire()
+                .await
+                .context("failed to acquire connection")?;
+        Self::get_all_in(&mut conn).await
+    }
+
+    /// Get the exchange by id
+    pub async fn get_by_id(id: &str, db: &Db) -> Result<Exchange> {
+        let mut conn =
+            db.0.acquire()
+                .await
+                .context("failed to acquire connection")?;
+        Self::get_by_id_in(id, &mut conn).await
+    }
+
+    /// Find the exchange a free-text name refers to
+    /// - an exact match ignoring case, spaces and punctuation wins
+    /// - otherwise the closest name within a typo or two, see `is_similar_name`
+    pub async fn find_by_name(name: &str, db: &Db) -> Result<Option<Exchange>> {
+        let mut conn =
+            db.0.acquire()
+                .await
+                .context("failed to acquire connection")?;
+        Self::find_by_name_in(name, &mut conn).await
+    }
+
+    /// Resolve a reference to an exchange, creating a new exchange for an unknown name
+    pub async fn resolve(exchange_ref: &ExchangeRef, db: &Db) -> Result<Exchange> {
+        let mut conn =
+            db.0.acquire()
+                .await
+                .context("failed to acquire connection")?;
+        Self::resolve_in(exchange_ref, &mut conn).await
+    }
+
+    /// `create` on `conn`, e.g. inside the transaction of an import
+    pub(crate) async fn create_in(
+        create_exchange: CreateExchange,
+        conn: &mut SqliteConnection,
+    ) -> Result<Exchange> {
+        let name = clean_name(&create_exchange.name)?;
+        if let Some(existing) = Self::get_by_normalized_name(&name, conn).await? {
+            bail!("exchange '{}' already exists", existing.name);
+        }
+
+        let id = Uuid::now_v7().to_string();
+        sqlx::query_as::<sqlx::Sqlite, Exchange>(
+            r#"
+            INSERT INTO exchange (id, name, kind, provider_id)
+            VALUES (?, ?, ?, ?)
+            RETURNING *
+            "#,
+        )
+        .bind(&id)
+        .bind(name)
+        .bind(create_exchange.kind)
+        .bind(create_exchange.provider_id)
+        .fetch_one(&mut *conn)
+        .await
+        .context("failed to insert into exchange table")
+    }
+
+    async fn get_all_in(conn: &mut SqliteConnection) -> Result<Vec<Exchange>> {
+        sqlx::query_as::<sqlx::Sqlite, Exchange>("SELECT * FROM exchange ORDER BY name")
+            .fetch_all(&mut *conn)
+            .await
+            .context("failed to get all exchanges")
+    }
+
+    /// `get_by_id` on `conn`
+    pub(crate) async fn get_by_id_in(id: &str, conn: &mut SqliteConnection) -> Result<Exchange> {
+        sqlx::query_as::<sqlx::Sqlite, Exchange>("SELECT * FROM exchange WHERE id = ?")
+            .bind(id)
+            .fetch_one(&mut *conn)
+            .await
+            .context("failed to get exchange by id")
+    }
+
+    /// `find_by_name` on `conn`
+    pub(crate) async fn find_by_name_in(
+        name: &str,
+        conn: &mut SqliteConnection,
+    ) -> Result<Option<Exchange>> {
+        let normalized = normalize_name(name);
+        if normalized.is_empty() {
+            return Ok(None);
+        }
+
+        let exchanges = Self::get_all_in(conn).await?;
+        let closest = exchanges
+            .into_iter()
+            .map(|exchange| {
+                let candidate = normalize_name(&exchange.name);
+                (edit_distance(&normalized, &candidate), candidate, exchange)
+            })
+            .filter(|(_, candidate, _)| is_similar_name(&normalized, candidate))
+            .min_by_key(|(distance, _, _)| *distance)
+            .map(|(_, _, exchange)| exchange);
+        Ok(closest)
+    }
+
+    /// `resolve` on `conn`, the exchange created for an unknown name is rolled back with the transaction
+    pub(crate) async fn resolve_in(
+        exchange_ref: &ExchangeRef,
+        conn: &mut SqliteConnection,
+    ) -> Result<Exchange> {
+        match exchange_ref {
+            ExchangeRef::Id { id } => Self::get_by_id_in(id, conn).await,
+            ExchangeRef::Name(name) => match Self::find_by_name_in(name, conn).await? {
+                Some(exchange) => Ok(exchange),
+                None => {
+                    Self::create_in(
+                        CreateExchange {
+                            name: name.clone(),
+                            kind: ExchangeKind::default(),
+                            provider_id: None,
+                        },
+                        conn,
+                    )
+                    .await
+                }
+            },
+        }
+    }
+
+    /// Link the fiat ramps and trades without an exchange to one, based on their free-text `via_exchange`
+    /// - the most used spelling of a name becomes the canonical name, typos of it are merged into it
+    /// - a trade funded by a ramp takes the exchange of the ramp when its name matches or is missing
+    /// - returns the number of fiat ramps and trades linked
+    pub async fn link_unlinked(db: &Db) -> Result<u64> {
+        let mut tx =
+            db.0.begin_with("BEGIN IMMEDIATE")
+                .await
+                .context("failed to begin transaction")?;
+        let linked = Self::link_unlinked_in(&mut tx).await?;
+        tx.commit().await.context("failed to commit transaction")?;
+        Ok(linked)
+    }
+
+    /// `link_unlinked` once per database, on app start
+    /// - rows recorded before exchanges existed are only linked by a migration resetting the tracker
+    pub async fn link_unlinked_once(db: &Db) -> Result<u64> {
+        if SysTracker::get_last_updated_at(LINK_SYS_TRACKER_NAME, db)
+            .await?
+            .is_some()
+        {
+            return Ok(0);
+        }
+        let linked = Self::link_unlinked(db).await?;
+        SysTracker::update_last_updated_at(LINK_SYS_TRACKER_NAME, db).await?;
+        Ok(linked)
+    }
+
+    /// `link_unlinked` on `conn`, e.g. inside the transaction of a backup restore
+    pub(crate) async fn link_unlinked_in(conn: &mut SqliteConnection) -> Result<u64> {
+        let mut linked = 0;
+
+        let ramp_names: Vec<String> = sqlx::query_scalar(
+            r#"
+            SELECT via_exchange FROM fiat_ramp
+            WHERE exchange_id IS NULL
+            GROUP BY via_exchange
+            ORDER BY COUNT(*) DESC, via_exchange ASC
+            "#,
+        )
+        .fetch_all(&mut *conn)
+        .await
+        .context("failed to get unlinked exchange names of fiat ramps")?;
+        for name in ramp_names {
+            if normalize_name(&name).is_empty() {
+                continue;
+            }
+            let exchange = Self::resolve_in(&ExchangeRef::Name(name.clone()), conn).await?;
+            let result = sqlx::query(
+                "UPDATE fiat_ramp SET exchange_id = ?, via_exchange = ?, fingerprint = NULL WHERE exchange_id IS NULL AND via_exchange = ?",
+            )
+            .bind(&exchange.id)
+            .bind(&exchange.name)
+            .bind(&name)
+            .execute(&mut *conn)
+            .await
+            .context("failed to link fiat ramps to exchange")?;
+            linked += result.rows_affected();
+        }
+
+        let funded_trades: Vec<(StringRowId, Option<String>, StringRowId, String)> =
+            sqlx::query_as(
+                r#"
+                SELECT trade.id, trade.via_exchange, exchange.id, exchange.name
+                FROM trade
+                JOIN fiat_ramp ON fiat_ramp.id = trade.fiat_ramp_id
+                JOIN exchange ON exchange.id = fiat_ramp.exchange_id
+                WHERE trade.exchange_id IS NULL
+                "#,
+            )
+            .fetch_all(&mut *conn)
+            .await
+            .context("failed to get unlinked trades funded by a fiat ramp")?;
+        for (trade_id, via_exchange, exchange_id, exchange_name) in funded_trades {
+            let same_exchange = via_exchange.as_deref().is_none_or(|name| {
+                is_similar_name(&normalize_name(name), &normalize_name(&exchange_name))
+            });
+            if !same_exchange {
+                continue;
+            }
+            let result = sqlx::query(
+                "UPDATE trade SET exchange_id = ?, via_exchange = ?, fingerprint = NULL WHERE id = ?",
+            )
+            .bind(&exchange_id)
+            .bind(&exchange_name)
+            .bind(&trade_id)
+            .execute(&mut *conn)
+            .await
+            .context("failed to link trade to the exchange of its fiat ramp")?;
+            linked += result.rows_affected();
+        }
+
+        let trade_names: Vec<String> = sqlx::query_scalar(
+            r#"
+            SELECT via_exchange FROM trade
+            WHERE exchange_id IS NULL AND via_exchange IS NOT NULL
+            GROUP BY via_exchange
+            ORDER BY COUNT(*) DESC, via_exchange ASC
+            "#,
+        )
+        .fetch_all(&mut *conn)
+        .await
+        .context("failed to get unlinked exchange names of trades")?;
+        for name in trade_names {
+            if normalize_name(&name).is_empty() {
+                continue;
+            }
+            let exchange = Self::resolve_in(&ExchangeRef::Name(name.clone()), conn).await?;
+            let result = sqlx::query(
+                "UPDATE trade SET exchange_id = ?, via_exchange = ?, fingerprint = NULL WHERE exchange_id IS NULL AND via_exchange = ?",
+            )
+            .bind(&exchange.id)
+            .bind(&exchange.name)
+            .bind(&name)
+            .execute(&mut *conn)
+            .await
+            .context("failed to link trades to exchange")?;
+            linked += result.rows_affected();
+        }
+
+        Ok(linked)
+    }
+
+    /// Update the exchange, fields left as None are kept
+    /// - a new name is copied to the `via_exchange` of its fiat ramps, recurring ramps and trades
+    pub async fn update(update_exchange: UpdateExchange, db: &Db) -> Result<Exchange> {
+        let mut tx = db.0.begin().await.context("failed to begin transaction")?;
+        let name = match &update_exchange.name {
+            Some(name) => {
+                let name = clean_name(name)?;
+                if let Some(existing) = Self::get_by_normalized_name(&name, &mut tx).await? {
+                    if existing.id != update_exchange.id {
+                        bail!("exchange '{}' already exists", existing.name);
+                    }
+                }
+                Some(name)
+            }
+            None => None,
+        };
+
+        let exchange = sqlx::query_as::<sqlx::Sqlite, Exchange>(
+            r#"
+            UPDATE exchange
+            SET
+            name = COALESCE(?, name),
+            kind = COALESCE(?, kind),
+            provider_id = COALESCE(?, provider_id)
+            WHERE id = ?
+            RETURNING *
+            "#,
+        )
+        .bind(name)
+        .bind(update_exchange.kind)
+        .bind(update_exchange.provider_id)
+        .bind(&update_exchange.id)
+        .fetch_one(&mut *tx)
+        .await
+        .context("failed to update exchange table")?;
+
+        sqlx::query(
+            "UPDATE fiat_ramp SET via_exchange = ?, fingerprint = NULL WHERE exchange_id = ?",
+        )
+        .bind(&exchange.name)
+        .bind(&exchange.id)
+        .execute(&mut *tx)
+        .await
+        .context("failed to rename exchange of fiat ramps")?;
+        sqlx::query("UPDATE trade SET via_exchange = ?, fingerprint = NULL WHERE exchange_id = ?")
+            .bind(&exchange.name)
+            .bind(&exchange.id)
+            .execute(&mut *tx)
+            .await
+            .context("failed to rename exchange of trades")?;
+        sqlx::query("UPDATE recurring_ramp SET via_exchange = ? WHERE exchange_id = ?")
+            .bind(&exchange.name)
+            .bind(&exchange.id)
+            .execute(&mut *tx)
+            .await
+            .context("failed to rename exchange of recurring ramps")?;
+
+        tx.commit().await.context("failed to commit transaction")?;
+        Ok(exchange)
+    }
+
+    /// Delete the exchange, only allowed when no fiat ramp, recurring ramp or trade uses it
+    /// - returns the number of rows affected
+    pub async fn delete(id: &str, db: &Db) -> Result<u64> {
+        let ramp_count: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM fiat_ramp WHERE exchange_id = ?")
+                .bind(id)
+                .fetch_one(&db.0)
+                .await
+                .context("failed to count fiat ramps of exchange")?;
+        if ramp_count > 0 {
+            bail!("exchange is used by {ramp_count} fiat ramps, merge it into another exchange instead");
+        }
+        let trade_count: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM trade WHERE exchange_id = ?")
+                .bind(id)
+                .fetch_one(&db.0)
+                .await
+                .context("failed to count trades of exchange")?;
+        if trade_count > 0 {
+            bail!(
+                "exchange is used by {trade_count} trades, merge it into another exchange instead"
+            );
+        }
+        let recurring_count: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM recurring_ramp WHERE exchange_id = ?")
+                .bind(id)
+                .fetch_one(&db.0)
+                .await
+                .context("failed to count recurring ramps of exchange")?;
+        if recurring_count > 0 {
+            bail!("exchange is used by {recurring_count} recurring ramps, merge it into another exchange instead");
+        }
+
+        let result = sqlx::query("DELETE FROM exchange WHERE id = ?")
+            .bind(id)
+            .execute(&db.0)
+            .await
+            .context("failed to delete from exchange table")?;
+        Ok(result.rows_affected())
+    }
+
+    /// Move every fiat ramp, recurring ramp and trade of `from_id` to `into_id` and delete `from_id`
+    /// - for duplicates the fuzzy matching did not catch
+    pub async fn merge(from_id: &str, into_id: &str, db: &Db) -> Result<Exchange> {
+        if from_id == into_id {
+            bail!("cannot merge an exchange into itself");
+        }
+        let into = Self::get_by_id(into_id, db).await?;
+
+        let mut tx = db.0.begin().await.context("failed to begin transaction")?;
+        sqlx::query("UPDATE fiat_ramp SET exchange_id = ?, via_exchange = ?, fingerprint = NULL WHERE exchange_id = ?")
+            .bind(&into.id)
+            .bind(&into.name)
+            .bind(from_id)
+            .execute(&mut *tx)
+            .await
+            .context("failed to move fiat ramps")?;
+        sqlx::query("UPDATE trade SET exchange_id = ?, via_exchange = ?, fingerprint = NULL WHERE exchange_id = ?")
+            .bind(&into.id)
+            .bind(&into.name)
+            .bind(from_id)
+            .execute(&mut *tx)
+            .await
+            .context("failed to move trades")?;
+        sqlx::query(
+            "UPDATE recurring_ramp SET exchange_id = ?, via_exchange = ? WHERE exchange_id = ?",
+        )
+        .bind(&into.id)
+        .bind(&into.name)
+        .bind(from_id)
+        .execute(&mut *tx)
+        .await
+        .context("failed to move recurring ramps")?;
+        let result = sqlx::query("DELETE FROM exchange WHERE id = ?")
+            .bind(from_id)
+            .execute(&mut *tx)
+            .await
+            .context("failed to delete merged exchange")?;
+        if result.rows_affected() == 0 {
+            bail!("exchange {from_id} not found");
+        }
+        tx.commit().await.context("failed to commit transaction")?;
+        Ok(into)
+    }
+
+    async fn get_by_normalized_name(
+        name: &str,
+        conn: &mut SqliteConnection,
+    ) -> Result<Option<Exchange>> {
+        let normalized = normalize_name(name);
+        Ok(Self::get_all_in(conn)
+            .await?
+            .into_iter()
+            .find(|exchange| normalize_name(&exchange.name) == normalized))
+    }
+}
+
+/// Trim and collapse the whitespaces of a name
+fn clean_name(name: &str) -> Result<String> {
+    let name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+    if normalize_name(&name).is_empty() {
+        bail!("exchange name cannot be empty");
+    }
+    Ok(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn init_db() -> Db {
+        let db = Db::in_memory().await.unwrap();
+        sqlx::query("INSERT INTO fiat (id, symbol, name) VALUES (1, 'USD', 'US Dollar')")
+            .execute(&db.0)
+            .await
+            .unwrap();
+        db
+    }
+
+    async fn insert_ramp(db: &Db, via_exchange: &str) {
+        sqlx::query(
+            "INSERT INTO fiat_ramp (id, fiat_id, fiat_amount, ramp_date, via_exchange, kind) VALUES (?, 1, 100, '2024-01-01', ?, 'deposit')",
+        )
+        .bind(Uuid::now_v7().to_string())
+        .bind(via_exchange)
+        .execute(&db.0)
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_resolve_matches_typos() {
+        let db = init_db().await;
+        let binance = ExchangeService::resolve(&ExchangeRef::Name(" Binance ".to_string()), &db)
+            .await
+            .unwrap();
+        assert_eq!(binance.name, "Binance");
+        assert_eq!(binance.kind, ExchangeKind::Cex);
+
+        for name in ["binance", "BINANCE ", "Binanace"] {
+            let exchange = ExchangeService::resolve(&ExchangeRef::Name(name.to_string()), &db)
+                .await
+                .unwrap();
+            assert_eq!(exchange.id, binance.id, "{name} should resolve to Binance");
+        }
+
+        let by_id = ExchangeService::resolve(
+            &ExchangeRef::Id {
+                id: binance.id.clone(),
+            },
+            &db,
+        )
+        .await
+        .unwrap();
+        assert_eq!(by_id.name, "Binance");
+        assert_eq!(ExchangeService::get_all(&db).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_link_fiat_ramps() {
+        let db = init_db().await;
+        let luno = ExchangeService::resolve(&ExchangeRef::Name("Luno".to_string()), &db)
+            .await
+            .unwrap();
+        for name in ["Binance", "binance ", "Binanace", "Binance", "luno", " "] {
+            insert_ramp(&db, name).await;
+        }
+
+        ExchangeService::link_unlinked(&db).await.unwrap();
+        let exchanges = ExchangeService::get_all(&db).await.unwrap();
+        let names: Vec<&str> = exchanges.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["Binance", "Luno"]);
+        assert_eq!(exchanges[1].id, luno.id);
+
+        let binance_ramps: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM fiat_ramp WHERE exchange_id = ? AND via_exchange = 'Binance'",
+        )
+        .bind(&exchanges[0].id)
+        .fetch_one(&db.0)
+        .await
+        .unwrap();
+        assert_eq!(binance_ramps, 4);
+        let luno_ramps: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM fiat_ramp WHERE exchange_id = ?")
+                .bind(&luno.id)
+                .fetch_one(&db.0)
+                .await
+                .unwrap();
+        assert_eq!(luno_ramps, 1);
+
+        // a blank name is left unlinked, linking again changes nothing
+        assert_eq!(ExchangeService::link_unlinked(&db).await.unwrap(), 0);
+        assert_eq!(ExchangeService::get_all(&db).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_link_trades() {
+        let db = init_db().await;
+        insert_ramp(&db, "Binance").await;
+        let ramp_id: String = sqlx::query_scalar("SELECT id FROM fiat_ramp")
+            .fetch_one(&db.0)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO asset (id, name, symbol, kind) VALUES ('btc', 'Bitcoin', 'BTC', 'cryptocoin')")
+            .execute(&db.0)
+            .await
+            .unwrap();
+        for (id, via_exchange, fiat_ramp_id) in [
+            ("funded", None, Some(ramp_id.as_str())),
+            ("funded_typo", Some("binanace"), Some(ramp_id.as_str())),
+            ("funded_elsewhere", Some("Kraken"), Some(ramp_id.as_str())),
+            ("kraken_typo", Some("Krakn"), None),
+            ("unknown", None, None),
+        ] {
+            sqlx::query("INSERT INTO trade (id, kind, asset_id, quantity, price, fiat_id, via_exchange, fiat_ramp_id) VALUES (?, 'buy', 'btc', '0.001', '100000', 1, ?, ?)")
+                .bind(id)
+                .bind(via_exchange)
+                .bind(fiat_ramp_id)
+                .execute(&db.0)
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(ExchangeService::link_unlinked(&db).await.unwrap(), 5);
+        let trades: Vec<(String, Option<String>)> = sqlx::query_as(
+            "SELECT trade.id, exchange.name FROM trade LEFT JOIN exchange ON exchange.id = trade.exchange_id ORDER BY trade.id",
+        )
+        .fetch_all(&db.0)
+        .await
+        .unwrap();
+        let expected = [
+            ("funded", Some("Binance")),
+            ("funded_elsewhere", Some("Kraken")),
+            ("funded_typo", Some("Binance")),
+            ("kraken_typo", Some("Kraken")),
+            ("unknown", None),
+        ];
+        let trades: Vec<(&str, Option<&str>)> = trades
+            .iter()
+            .map(|(id, name)| (id.as_str(), name.as_deref()))
+            .collect();
+        assert_eq!(trades, expected);
+        assert_eq!(ExchangeService::get_all(&db).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_update_merge_and_delete() {
+        let db = init_db().await;
+        insert_ramp(&db, "Kraken").await;
+        insert_ramp(&db, "Krakn Pro").await;
+        ExchangeService::link_unlinked(&db).await.unwrap();
+        let exchanges = ExchangeService::get_all(&db).await.unwrap();
+        assert_eq!(exchanges.len(), 2);
+        let (kraken, kraken_pro) = (&exchanges[0], &exchanges[1]);
+
+        let renamed = ExchangeService::update(
+            UpdateExchange {
+                id: kraken.id.clone(),
+                name: Some("Kraken Exchange".to_string()),
+                kind: None,
+                provider_id: Some("kraken".to_string()),
+            },
+            &db,
+        )
+        .await
+        .unwrap();
+        assert_eq!(renamed.provider_id.as_deref(), Some("kraken"));
+
+        // still in use
+        assert!(ExchangeService::delete(&kraken_pro.id, &db).await.is_err());
+
+        // a recurring ramp alone keeps its exchange in use too
+        sqlx::query("INSERT INTO recurring_ramp (id, name, fiat_id, fiat_amount, via_exchange, exchange_id, kind, rrule, start_date) VALUES ('monthly', 'Monthly', 1, '100', ?, ?, 'deposit', 'FREQ=MONTHLY', '2024-01-01')")
+            .bind(&kraken_pro.name)
+            .bind(&kraken_pro.id)
+            .execute(&db.0)
+            .await
+            .unwrap();
+        sqlx::query("DELETE FROM fiat_ramp WHERE exchange_id = ?")
+            .bind(&kraken_pro.id)
+            .execute(&db.0)
+            .await
+            .unwrap();
+        assert!(ExchangeService::delete(&kraken_pro.id, &db).await.is_err());
+
+        ExchangeService::merge(&kraken_pro.id, &kraken.id, &db)
+            .await
+            .unwrap();
+        let names: Vec<String> = sqlx::query_scalar(
+            "SELECT via_exchange FROM fiat_ramp UNION SELECT via_exchange FROM recurring_ramp WHERE exchange_id = ?",
+        )
+        .bind(&kraken.id)
+        .fetch_all(&db.0)
+        .await
+        .unwrap();
+        assert_eq!(names, vec!["Kraken Exchange".to_string()]);
+        assert_eq!(ExchangeService::get_all(&db).await.unwrap().len(), 1);
+    }
+}
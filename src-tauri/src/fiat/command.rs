@@ -3,6 +3,7 @@ use crate::{
     fiat::{Fiat, FiatService},
     fiat_exchanger::frankfurter_exchanger::FrankfurterExchangerApi,
 };
+use rust_decimal::Decimal;
 use tauri::State;
 
 #[tauri::command]
@@ -23,3 +24,14 @@ pub async fn get_currencies_by_symbol(db: State<'_, Db>, symbol: String) -> Resu
     .map_err(|e| format!("failed to get available currencies: {e}"))?;
     Ok(fiat)
 }
+
+#[tauri::command]
+pub async fn format_fiat_amount(
+    db: State<'_, Db>,
+    amount: Decimal,
+    symbol: String,
+) -> Result<String, String> {
+    FiatService::<FrankfurterExchangerApi>::format_amount(&db, amount, &symbol)
+        .await
+        .map_err(|e| format!("failed to format amount: {e:#}"))
+}
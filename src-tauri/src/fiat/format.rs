@@ -0,0 +1,158 @@
+use crate::fiat::Fiat;
+use rust_decimal::Decimal;
+
+/// Non breaking space, amounts are not wrapped across lines
+const NBSP: char = '\u{a0}';
+
+/// How a locale writes an amount of money
+struct AmountStyle {
+    decimal: char,
+    group: char,
+    /// `1.234,50 €` rather than `€1,234.50`
+    symbol_after: bool,
+}
+
+impl AmountStyle {
+    /// Style of a BCP 47 locale such as `en`, `de-DE` or `pt_BR`, the English one when unknown
+    fn of(locale: &str) -> Self {
+        let locale = locale.trim().replace('_', "-").to_lowercase();
+        let mut parts = locale.split('-');
+        let language = parts.next().unwrap_or_default();
+        let region = parts.next().unwrap_or_default();
+        let (decimal, group, symbol_after) = match (language, region) {
+            ("de" | "it" | "rm", "ch" | "li") => ('.', '’', false),
+            ("es", "mx" | "us") => ('.', ',', false),
+            ("pt", "br") | ("nl" | "id" | "tr", _) => (',', '.', false),
+            ("pt" | "fr" | "ru" | "pl" | "cs" | "sk" | "sv" | "nb" | "nn" | "no" | "fi", _)
+            | ("uk" | "hu" | "bg" | "lt" | "lv" | "et", _) => (',', NBSP, true),
+            ("de" | "es" | "it" | "da" | "el" | "ro" | "hr" | "sl" | "sr" | "vi", _) => {
+                (',', '.', true)
+            }
+            _ => ('.', ',', false),
+        };
+        Self {
+            decimal,
+            group,
+            symbol_after,
+        }
+    }
+}
+
+/// Round an amount to the minor units of its currency, see `Fiat::minor_units`, half to even so
+/// that sums of rounded amounts do not drift
+pub fn round_amount(amount: Decimal, minor_units: u32) -> Decimal {
+    amount.round_dp(minor_units)
+}
+
+/// Write an amount with the symbol of its currency and the separators of a locale, rounded to
+/// the minor units of the currency
+/// - e.g. `-€1,234.50` in `en`, `-1.234,50 €` in `de-DE` or `¥1,235` for JPY
+/// - a currency outside ISO 4217 is written with its code
+pub fn format_amount(amount: Decimal, fiat: &Fiat, locale: &str) -> String {
+    let style = AmountStyle::of(locale);
+    let symbol = fiat.display_symbol.as_deref().unwrap_or(&fiat.symbol);
+    let rounded = round_amount(amount, fiat.minor_units);
+
+    let digits = format!("{:.*}", fiat.minor_units as usize, rounded.abs());
+    let (integer, fraction) = digits.split_once('.').unwrap_or((digits.as_str(), ""));
+    let mut number = String::new();
+    for (index, digit) in integer.chars().enumerate() {
+        if index > 0 && (integer.len() - index) % 3 == 0 {
+            number.push(style.group);
+        }
+        number.push(digit);
+    }
+    if !fraction.is_empty() {
+        number.push(style.decimal);
+        number.push_str(fraction);
+    }
+
+    let sign = if rounded.is_sign_negative() && !rounded.is_zero() {
+        "-"
+    } else {
+        ""
+    };
+    if style.symbol_after {
+        format!("{sign}{number}{NBSP}{symbol}")
+    } else if symbol.ends_with(char::is_alphabetic) {
+        // a symbol of letters is kept apart from the digits, e.g. `CHF 12.50`
+        format!("{sign}{symbol}{NBSP}{number}")
+    } else {
+        format!("{sign}{symbol}{number}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fiat::iso4217;
+
+    /// The fiat of a code as `FiatService::update_currencies` stores it
+    fn fiat(code: &str) -> Fiat {
+        let iso = iso4217::lookup(code);
+        Fiat {
+            symbol: code.to_string(),
+            minor_units: iso.map_or(2, |currency| currency.minor_units),
+            display_symbol: iso.map(|currency| currency.symbol.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_round_amount() {
+        assert_eq!(
+            round_amount(Decimal::new(1_234_565, 4), 2),
+            Decimal::new(12_346, 2)
+        );
+        assert_eq!(
+            round_amount(Decimal::new(12_345, 1), 0),
+            Decimal::new(1_234, 0)
+        );
+        assert_eq!(
+            round_amount(Decimal::new(1_234_567, 4), 3),
+            Decimal::new(123_457, 3)
+        );
+    }
+
+    #[test]
+    fn test_format_amount() {
+        let amount = Decimal::new(-1_234_565, 3);
+        assert_eq!(format_amount(amount, &fiat("EUR"), "en"), "-€1,234.56");
+        assert_eq!(
+            format_amount(amount, &fiat("EUR"), "de-DE"),
+            "-1.234,56\u{a0}€"
+        );
+        assert_eq!(
+            format_amount(amount, &fiat("EUR"), "fr_FR"),
+            "-1\u{a0}234,56\u{a0}€"
+        );
+        assert_eq!(
+            format_amount(amount, &fiat("CHF"), "de-CH"),
+            "-CHF\u{a0}1’234.56"
+        );
+        assert_eq!(format_amount(amount, &fiat("JPY"), "en-US"), "-¥1,235");
+        assert_eq!(
+            format_amount(amount, &fiat("KWD"), "en"),
+            "-KWD\u{a0}1,234.565"
+        );
+        assert_eq!(
+            format_amount(Decimal::new(1_000_000, 0), &fiat("USD"), "en"),
+            "$1,000,000.00"
+        );
+        assert_eq!(
+            format_amount(Decimal::new(-1, 3), &fiat("EUR"), "en"),
+            "€0.00"
+        );
+        assert_eq!(
+            format_amount(Decimal::new(5, 1), &fiat("XYZ"), "en"),
+            "XYZ\u{a0}0.50"
+        );
+
+        // the minor units stored for the fiat are used
+        let eur = Fiat {
+            minor_units: 0,
+            ..fiat("EUR")
+        };
+        assert_eq!(format_amount(amount, &eur, "en"), "-€1,235");
+    }
+}
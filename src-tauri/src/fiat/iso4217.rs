@@ -0,0 +1,251 @@
+/// A currency of ISO 4217, with the symbol it is usually written with
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Iso4217Currency {
+    /// alphabetic code, e.g. `EUR`
+    pub code: &'static str,
+    /// numeric code, e.g. `978`, the leading zeros are part of it
+    pub numeric_code: &'static str,
+    /// decimal places of the minor unit, e.g. 2 for the cents of EUR
+    pub minor_units: u32,
+    /// display symbol, e.g. `€`, the code when the currency has no symbol of its own
+    pub symbol: &'static str,
+    /// false once the currency was replaced, its rates are still kept for older ramps
+    pub is_active: bool,
+}
+
+const fn currency(
+    code: &'static str,
+    numeric_code: &'static str,
+    minor_units: u32,
+    symbol: &'static str,
+) -> Iso4217Currency {
+    Iso4217Currency {
+        code,
+        numeric_code,
+        minor_units,
+        symbol,
+        is_active: true,
+    }
+}
+
+const fn obsolete(
+    code: &'static str,
+    numeric_code: &'static str,
+    minor_units: u32,
+) -> Iso4217Currency {
+    Iso4217Currency {
+        code,
+        numeric_code,
+        minor_units,
+        symbol: code,
+        is_active: false,
+    }
+}
+
+/// Currencies of ISO 4217 sorted by code, without the funds and the precious metals
+/// - the obsolete ones are those an exchange rate provider still has a history of, e.g. the
+///   currencies replaced by the euro
+const CURRENCIES: &[Iso4217Currency] = &[
+    currency("AED", "784", 2, "AED"),
+    currency("AFN", "971", 2, "؋"),
+    currency("ALL", "008", 2, "ALL"),
+    currency("AMD", "051", 2, "֏"),
+    obsolete("ANG", "532", 2),
+    currency("AOA", "973", 2, "Kz"),
+    currency("ARS", "032", 2, "ARS"),
+    currency("AUD", "036", 2, "A$"),
+    currency("AWG", "533", 2, "AWG"),
+    currency("AZN", "944", 2, "₼"),
+    currency("BAM", "977", 2, "KM"),
+    currency("BBD", "052", 2, "BBD"),
+    currency("BDT", "050", 2, "৳"),
+    // replaced by the euro on 2026-01-01
+    obsolete("BGN", "975", 2),
+    currency("BHD", "048", 3, "BHD"),
+    currency("BIF", "108", 0, "BIF"),
+    currency("BMD", "060", 2, "BMD"),
+    currency("BND", "096", 2, "BND"),
+    currency("BOB", "068", 2, "Bs"),
+    currency("BRL", "986", 2, "R$"),
+    currency("BSD", "044", 2, "BSD"),
+    currency("BTN", "064", 2, "BTN"),
+    currency("BWP", "072", 2, "P"),
+    currency("BYN", "933", 2, "BYN"),
+    currency("BZD", "084", 2, "BZD"),
+    currency("CAD", "124", 2, "CA$"),
+    currency("CDF", "976", 2, "CDF"),
+    currency("CHF", "756", 2, "CHF"),
+    currency("CLF", "990", 4, "CLF"),
+    currency("CLP", "152", 0, "CLP"),
+    currency("CNY", "156", 2, "CN¥"),
+    currency("COP", "170", 2, "COP"),
+    currency("CRC", "188", 2, "₡"),
+    currency("CUP", "192", 2, "CUP"),
+    currency("CVE", "132", 2, "CVE"),
+    obsolete("CYP", "196", 2),
+    currency("CZK", "203", 2, "Kč"),
+    currency("DJF", "262", 0, "DJF"),
+    currency("DKK", "208", 2, "kr."),
+    currency("DOP", "214", 2, "RD$"),
+    currency("DZD", "012", 2, "DZD"),
+    obsolete("EEK", "233", 2),
+    currency("EGP", "818", 2, "E£"),
+    currency("ERN", "232", 2, "ERN"),
+    currency("ETB", "230", 2, "ETB"),
+    currency("EUR", "978", 2, "€"),
+    currency("FJD", "242", 2, "FJD"),
+    currency("FKP", "238", 2, "FKP"),
+    currency("GBP", "826", 2, "£"),
+    currency("GEL", "981", 2, "₾"),
+    currency("GHS", "936", 2, "GH₵"),
+    currency("GIP", "292", 2, "GIP"),
+    currency("GMD", "270", 2, "GMD"),
+    currency("GNF", "324", 0, "GNF"),
+    currency("GTQ", "320", 2, "Q"),
+    currency("GYD", "328", 2, "GYD"),
+    currency("HKD", "344", 2, "HK$"),
+    currency("HNL", "340", 2, "L"),
+    obsolete("HRK", "191", 2),
+    currency("HTG", "332", 2, "HTG"),
+    currency("HUF", "348", 2, "Ft"),
+    currency("IDR", "360", 2, "Rp"),
+    currency("ILS", "376", 2, "₪"),
+    currency("INR", "356", 2, "₹"),
+    currency("IQD", "368", 3, "IQD"),
+    currency("IRR", "364", 2, "IRR"),
+    currency("ISK", "352", 0, "kr"),
+    currency("JMD", "388", 2, "J$"),
+    currency("JOD", "400", 3, "JOD"),
+    currency("JPY", "392", 0, "¥"),
+    currency("KES", "404", 2, "KSh"),
+    currency("KGS", "417", 2, "KGS"),
+    currency("KHR", "116", 2, "៛"),
+    currency("KMF", "174", 0, "KMF"),
+    currency("KPW", "408", 2, "KPW"),
+    currency("KRW", "410", 0, "₩"),
+    currency("KWD", "414", 3, "KWD"),
+    currency("KYD", "136", 2, "KYD"),
+    currency("KZT", "398", 2, "₸"),
+    currency("LAK", "418", 2, "₭"),
+    currency("LBP", "422", 2, "LBP"),
+    currency("LKR", "144", 2, "Rs"),
+    currency("LRD", "430", 2, "LRD"),
+    currency("LSL", "426", 2, "LSL"),
+    obsolete("LTL", "440", 2),
+    obsolete("LVL", "428", 2),
+    currency("LYD", "434", 3, "LYD"),
+    currency("MAD", "504", 2, "MAD"),
+    currency("MDL", "498", 2, "MDL"),
+    currency("MGA", "969", 2, "Ar"),
+    currency("MKD", "807", 2, "MKD"),
+    currency("MMK", "104", 2, "K"),
+    currency("MNT", "496", 2, "₮"),
+    currency("MOP", "446", 2, "MOP"),
+    currency("MRU", "929", 2, "MRU"),
+    obsolete("MTL", "470", 2),
+    currency("MUR", "480", 2, "Rs"),
+    currency("MVR", "462", 2, "MVR"),
+    currency("MWK", "454", 2, "MWK"),
+    currency("MXN", "484", 2, "MX$"),
+    currency("MYR", "458", 2, "RM"),
+    currency("MZN", "943", 2, "MZN"),
+    currency("NAD", "516", 2, "NAD"),
+    currency("NGN", "566", 2, "₦"),
+    currency("NIO", "558", 2, "C$"),
+    currency("NOK", "578", 2, "kr"),
+    currency("NPR", "524", 2, "Rs"),
+    currency("NZD", "554", 2, "NZ$"),
+    currency("OMR", "512", 3, "OMR"),
+    currency("PAB", "590", 2, "B/."),
+    currency("PEN", "604", 2, "S/"),
+    currency("PGK", "598", 2, "PGK"),
+    currency("PHP", "608", 2, "₱"),
+    currency("PKR", "586", 2, "Rs"),
+    currency("PLN", "985", 2, "zł"),
+    currency("PYG", "600", 0, "₲"),
+    currency("QAR", "634", 2, "QAR"),
+    obsolete("ROL", "642", 2),
+    currency("RON", "946", 2, "lei"),
+    currency("RSD", "941", 2, "RSD"),
+    currency("RUB", "643", 2, "₽"),
+    currency("RWF", "646", 0, "RWF"),
+    currency("SAR", "682", 2, "SAR"),
+    currency("SBD", "090", 2, "SBD"),
+    currency("SCR", "690", 2, "SCR"),
+    currency("SDG", "938", 2, "SDG"),
+    currency("SEK", "752", 2, "kr"),
+    currency("SGD", "702", 2, "S$"),
+    currency("SHP", "654", 2, "SHP"),
+    obsolete("SIT", "705", 2),
+    obsolete("SKK", "703", 2),
+    currency("SLE", "925", 2, "SLE"),
+    obsolete("SLL", "694", 2),
+    currency("SOS", "706", 2, "SOS"),
+    currency("SRD", "968", 2, "SRD"),
+    currency("SSP", "728", 2, "SSP"),
+    currency("STN", "930", 2, "Db"),
+    currency("SVC", "222", 2, "SVC"),
+    currency("SYP", "760", 2, "SYP"),
+    currency("SZL", "748", 2, "SZL"),
+    currency("THB", "764", 2, "฿"),
+    currency("TJS", "972", 2, "TJS"),
+    currency("TMT", "934", 2, "TMT"),
+    currency("TND", "788", 3, "TND"),
+    currency("TOP", "776", 2, "T$"),
+    obsolete("TRL", "792", 0),
+    currency("TRY", "949", 2, "₺"),
+    currency("TTD", "780", 2, "TT$"),
+    currency("TWD", "901", 2, "NT$"),
+    currency("TZS", "834", 2, "TSh"),
+    currency("UAH", "980", 2, "₴"),
+    currency("UGX", "800", 0, "USh"),
+    currency("USD", "840", 2, "$"),
+    currency("UYI", "940", 0, "UYI"),
+    currency("UYU", "858", 2, "$U"),
+    currency("UYW", "927", 4, "UYW"),
+    currency("UZS", "860", 2, "UZS"),
+    currency("VED", "926", 2, "VED"),
+    obsolete("VEF", "937", 2),
+    currency("VES", "928", 2, "Bs.S"),
+    currency("VND", "704", 0, "₫"),
+    currency("VUV", "548", 0, "VT"),
+    currency("WST", "882", 2, "WS$"),
+    currency("XAF", "950", 0, "FCFA"),
+    currency("XCD", "951", 2, "EC$"),
+    currency("XCG", "532", 2, "Cg"),
+    currency("XOF", "952", 0, "F CFA"),
+    currency("XPF", "953", 0, "CFPF"),
+    currency("YER", "886", 2, "YER"),
+    currency("ZAR", "710", 2, "R"),
+    currency("ZMW", "967", 2, "K"),
+    currency("ZWG", "924", 2, "ZiG"),
+    obsolete("ZWL", "932", 2),
+];
+
+/// The ISO 4217 currency of an alphabetic code, in any case
+pub fn lookup(code: &str) -> Option<&'static Iso4217Currency> {
+    let code = code.trim().to_uppercase();
+    CURRENCIES
+        .binary_search_by(|currency| currency.code.cmp(code.as_str()))
+        .ok()
+        .map(|index| &CURRENCIES[index])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup() {
+        assert!(CURRENCIES
+            .windows(2)
+            .all(|pair| pair[0].code < pair[1].code));
+        let eur = lookup("eur").unwrap();
+        assert_eq!(eur.numeric_code, "978");
+        assert_eq!(eur.symbol, "€");
+        assert!(eur.is_active);
+        assert_eq!(lookup("ALL").unwrap().numeric_code, "008");
+        assert!(!lookup("HRK").unwrap().is_active);
+        assert!(lookup("BTC").is_none());
+    }
+}
@@ -1,7 +1,12 @@
 pub mod command;
+pub mod format;
+pub mod iso4217;
 use crate::db::RowId;
-use crate::{fiat_exchanger::FiatExchanger, sys_tracker::SysTracker, utils::date_utils, Db};
+use crate::{
+    fiat_exchanger::FiatExchanger, sys_tracker::SysTracker, user_settings, utils::date_utils, Db,
+};
 use anyhow::{Context, Result};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
 pub struct FiatService<A: FiatExchanger> {
@@ -13,6 +18,14 @@ pub struct Fiat {
     pub id: RowId,
     pub symbol: String,
     pub name: String,
+    /// ISO 4217 numeric code, e.g. `978`, None outside ISO 4217
+    pub numeric_code: Option<String>,
+    /// decimal places of the minor unit, amounts in the currency are rounded to them
+    pub minor_units: u32,
+    /// display symbol, e.g. `€`, None outside ISO 4217
+    pub display_symbol: Option<String>,
+    /// false once the currency was replaced, e.g. HRK by EUR
+    pub is_active: bool,
 }
 
 const FIAT_SYS_TRACKER_NAME: &str = "fiat";
@@ -32,10 +45,10 @@ impl<A: FiatExchanger> FiatService<A> {
 }
 
 impl<A: FiatExchanger> FiatService<A> {
-    // update database with supported currencies symbols and names
+    // update database with supported currencies symbols, names and ISO 4217 metadata
     pub async fn update_currencies(&self, db: &Db) -> Result<u8> {
         // last updated at
-        let last_updated_at = SysTracker::get_last_updated_at(FIAT_SYS_TRACKER_NAME, &db)
+        let last_updated_at = SysTracker::get_last_updated_at(FIAT_SYS_TRACKER_NAME, db)
             .await
             .context("failed to get last updated at")?;
 
@@ -61,14 +74,32 @@ impl<A: FiatExchanger> FiatService<A> {
         // there is only 188 possible work currencies in current point of time in the world
         // Frankfurter API only support 30 currencies per as of 2026-Jan-12
         // 2. Update database with supported currencies symbols and names
-        let mut query_builder =
-            sqlx::QueryBuilder::new("INSERT OR REPLACE INTO fiat (symbol, name, updated_at) ");
+        // an upsert keeps the id of a currency already stored
+        let mut query_builder = sqlx::QueryBuilder::new(
+            "INSERT INTO fiat (symbol, name, numeric_code, minor_units, display_symbol, is_active, updated_at) ",
+        );
 
         query_builder.push_values(currencies, |mut b, currency| {
+            let iso = iso4217::lookup(&currency.symbol);
             b.push_bind(currency.symbol)
                 .push_bind(currency.name)
+                .push_bind(iso.map(|iso| iso.numeric_code))
+                .push_bind(iso.map_or(2, |iso| iso.minor_units))
+                .push_bind(iso.map(|iso| iso.symbol))
+                .push_bind(iso.is_none_or(|iso| iso.is_active))
                 .push_bind(current_update_at);
         });
+        query_builder.push(
+            r#"
+            ON CONFLICT (symbol) DO UPDATE SET
+                name = excluded.name,
+                numeric_code = excluded.numeric_code,
+                minor_units = excluded.minor_units,
+                display_symbol = excluded.display_symbol,
+                is_active = excluded.is_active,
+                updated_at = excluded.updated_at
+            "#,
+        );
 
         let result = query_builder
             .build()
@@ -81,7 +112,7 @@ impl<A: FiatExchanger> FiatService<A> {
         tx.commit().await.context("failed to commit transaction")?;
 
         // 3. Update SysTracker
-        SysTracker::update_last_updated_at(FIAT_SYS_TRACKER_NAME, &db)
+        SysTracker::update_last_updated_at(FIAT_SYS_TRACKER_NAME, db)
             .await
             .context("SysTracker update error")?;
         Ok(total_row_affected)
@@ -115,6 +146,16 @@ impl<A: FiatExchanger> FiatService<A> {
             .context("failed to get fiat by symbol")?;
         Ok(fiat)
     }
+
+    /// Format an amount in the currency of the symbol for the locale of the user settings,
+    /// e.g. `€1,234.50` in `en` or `1.234,50 €` in `de-DE`
+    pub async fn format_amount(db: &Db, amount: Decimal, symbol: &str) -> Result<String> {
+        let settings = user_settings::get(db)
+            .await
+            .context("failed to get user settings")?;
+        let fiat = Self::get_fiat_by_symbol(db, &symbol.trim().to_uppercase()).await?;
+        Ok(format::format_amount(amount, &fiat, &settings.locale))
+    }
 }
 
 #[cfg(test)]
@@ -166,7 +207,7 @@ mod tests {
         let fiat_service = FiatService {
             fiat_api_client: mock_api,
         };
-        let _ = fiat_service.update_currencies(&db).await;
+        let _ = fiat_service.update_currencies(db).await;
     }
 
     #[tokio::test]
@@ -200,6 +241,37 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_update_currencies_metadata() {
+        let db = get_db().await;
+        update_db_with_mock_data(&db).await;
+        let eur = FiatService::<MockFiatExchanger>::get_fiat_by_symbol(&db, "EUR")
+            .await
+            .unwrap();
+        assert_eq!(eur.numeric_code.as_deref(), Some("978"));
+        assert_eq!(eur.minor_units, 2);
+        assert_eq!(eur.display_symbol.as_deref(), Some("€"));
+        assert!(eur.is_active);
+
+        // a later update keeps the ids
+        sqlx::query("DELETE FROM sys_tracker")
+            .execute(&db.0)
+            .await
+            .unwrap();
+        update_db_with_mock_data(&db).await;
+        let updated = FiatService::<MockFiatExchanger>::get_fiat_by_symbol(&db, "EUR")
+            .await
+            .unwrap();
+        assert_eq!(updated.id, eur.id);
+        assert_eq!(
+            FiatService::<MockFiatExchanger>::get_all_fiat(&db)
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+
     #[tokio::test]
     async fn test_get_fiat_by_id() {
         // Arrange
@@ -0,0 +1,164 @@
+use crate::fiat::iso4217;
+use crate::fiat_exchanger::{Currency, FiatExchanger, FiatSymbol, RangeRates, Rates};
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use tauri_plugin_http::reqwest;
+
+/// Days fetched at most by `get_range_rates`, the API has no series endpoint so each day is a request
+const MAX_RANGE_DAYS: i64 = 92;
+
+/// Currency API - https://github.com/fawazahmed0/exchange-api
+/// - daily rates of about 200 currencies, weekends included, from 2024-03-02
+pub struct CurrencyApiExchanger {
+    base_url: String,
+}
+
+impl Default for CurrencyApiExchanger {
+    fn default() -> Self {
+        Self {
+            base_url: "https://cdn.jsdelivr.net/npm/@fawazahmed0/currency-api".to_string(),
+        }
+    }
+}
+
+impl CurrencyApiExchanger {
+    async fn get(&self, version: &str, path: &str) -> Result<HashMap<String, Value>> {
+        let response = reqwest::get(format!("{}@{version}/v1/{path}", self.base_url)).await?;
+        if !response.status().is_success() {
+            bail!("request failed with status {}", response.status());
+        }
+        let response_text = response.text().await?;
+        Ok(serde_json::from_str(&response_text)?)
+    }
+
+    /// Rates keyed by the upper case symbol, only ISO 4217 currencies are kept, the obsolete ones
+    /// too for the dates they were in use
+    fn parse_rates(base: &str, response: &HashMap<String, Value>) -> Result<Rates> {
+        let date = response
+            .get("date")
+            .and_then(Value::as_str)
+            .context("missing date")?
+            .parse::<NaiveDate>()
+            .context("invalid date")?;
+        let rates: HashMap<FiatSymbol, Decimal> = response
+            .get(&base.to_lowercase())
+            .and_then(Value::as_object)
+            .with_context(|| format!("missing {base} rates"))?
+            .iter()
+            .filter_map(|(symbol, rate)| {
+                Some((
+                    symbol.to_uppercase(),
+                    <Decimal as Deserialize>::deserialize(rate).ok()?,
+                ))
+            })
+            .filter(|(symbol, _)| iso4217::lookup(symbol).is_some())
+            .collect();
+        Ok(Rates {
+            base: base.to_string(),
+            date,
+            rates,
+            provider: String::new(),
+        })
+    }
+}
+
+#[async_trait]
+impl FiatExchanger for CurrencyApiExchanger {
+    fn id(&self) -> &'static str {
+        "currency_api"
+    }
+
+    async fn get_available_currencies(&self) -> Result<Vec<Currency>> {
+        let response = self
+            .get("latest", "currencies.json")
+            .await
+            .context("Failed to fetch available currencies")?;
+
+        let mut currencies: Vec<Currency> = response
+            .iter()
+            .map(|(symbol, name)| (symbol.to_uppercase(), name))
+            // the API also lists crypto currencies
+            .filter(|(symbol, _)| {
+                iso4217::lookup(symbol).is_some_and(|currency| currency.is_active)
+            })
+            .map(|(symbol, name)| Currency {
+                name: name.as_str().unwrap_or(&symbol).to_string(),
+                symbol,
+            })
+            .collect();
+        currencies.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+        Ok(currencies)
+    }
+
+    #[allow(clippy::needless_lifetimes)]
+    async fn get_latest_rates<'a>(&self, base: &str, date: Option<&'a NaiveDate>) -> Result<Rates> {
+        let version = date.map_or_else(|| "latest".to_string(), |date| date.to_string());
+        let response = self
+            .get(
+                &version,
+                &format!("currencies/{}.json", base.to_lowercase()),
+            )
+            .await
+            .context("Failed to fetch latest rates")?;
+        let mut rates =
+            Self::parse_rates(base, &response).context("Failed to fetch latest rates")?;
+        rates.provider = self.id().to_string();
+        Ok(rates)
+    }
+
+    async fn get_range_rates(
+        &self,
+        base: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<RangeRates> {
+        if (end - start).num_days() >= MAX_RANGE_DAYS {
+            bail!("range from {start} to {end} is longer than {MAX_RANGE_DAYS} days");
+        }
+        let mut rates = BTreeMap::new();
+        for date in start.iter_days().take_while(|date| *date <= end) {
+            // a day not published yet is left out, like a non working day
+            if let Ok(day) = self.get_latest_rates(base, Some(&date)).await {
+                rates.insert(day.date, day.rates);
+            }
+        }
+        let (Some(start_date), Some(end_date)) = (
+            rates.keys().next().copied(),
+            rates.keys().next_back().copied(),
+        ) else {
+            bail!("no {base} rates from {start} to {end}");
+        };
+        Ok(RangeRates {
+            base: base.to_string(),
+            start_date,
+            end_date,
+            rates,
+            provider: self.id().to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rates() {
+        let response: HashMap<String, Value> = serde_json::from_str(
+            r#"{"date": "2024-03-06", "myr": {"ars": 183.2, "btc": 0.0000033, "sgd": 0.2834}}"#,
+        )
+        .unwrap();
+        let rates = CurrencyApiExchanger::parse_rates("MYR", &response).unwrap();
+
+        assert_eq!(rates.date, NaiveDate::from_ymd_opt(2024, 3, 6).unwrap());
+        assert_eq!(rates.rates.len(), 2);
+        assert_eq!(rates.rates["ARS"], Decimal::new(1832, 1));
+        assert!(!rates.rates.contains_key("BTC"));
+        assert!(CurrencyApiExchanger::parse_rates("EUR", &response).is_err());
+    }
+}
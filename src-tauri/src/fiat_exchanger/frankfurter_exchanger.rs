@@ -1,5 +1,6 @@
-use crate::fiat_exchanger::{Currency, FiatExchanger, Rates};
+use crate::fiat_exchanger::{Currency, FiatExchanger, RangeRates, Rates};
 use anyhow::{Context, Result};
+use async_trait::async_trait;
 use std::collections::HashMap;
 use tauri_plugin_http::reqwest;
 
@@ -15,7 +16,12 @@ impl Default for FrankfurterExchangerApi {
         }
     }
 }
+#[async_trait]
 impl FiatExchanger for FrankfurterExchangerApi {
+    fn id(&self) -> &'static str {
+        "frankfurter"
+    }
+
     async fn get_available_currencies(&self) -> Result<Vec<Currency>> {
         let response = reqwest::get(format!("{}/v1/currencies", self.base_url))
             .await
@@ -41,6 +47,7 @@ impl FiatExchanger for FrankfurterExchangerApi {
         Ok(currencies)
     }
 
+    #[allow(clippy::needless_lifetimes)]
     async fn get_latest_rates<'a>(
         &self,
         base: &str,
@@ -59,8 +66,29 @@ impl FiatExchanger for FrankfurterExchangerApi {
             .text()
             .await
             .context("Failed to fetch latest rates")?;
-        let response_json: Rates =
+        let mut response_json: Rates =
             serde_json::from_str(&response_text).context("Failed to fetch latest rates")?;
+        response_json.provider = self.id().to_string();
+        Ok(response_json)
+    }
+
+    async fn get_range_rates(
+        &self,
+        base: &str,
+        start: chrono::NaiveDate,
+        end: chrono::NaiveDate,
+    ) -> Result<RangeRates> {
+        let url = format!("{}/v1/{start}..{end}?base={base}", self.base_url);
+        let response = reqwest::get(url)
+            .await
+            .context("Failed to fetch range rates")?;
+        let response_text = response
+            .text()
+            .await
+            .context("Failed to fetch range rates")?;
+        let mut response_json: RangeRates =
+            serde_json::from_str(&response_text).context("Failed to fetch range rates")?;
+        response_json.provider = self.id().to_string();
         Ok(response_json)
     }
 }
@@ -108,4 +136,22 @@ mod tests {
         assert!(!rates.rates.is_empty());
         assert!(rates.rates.contains_key("SGD"));
     }
+
+    #[tokio::test]
+    async fn test_get_range_rates() {
+        let api = FrankfurterExchangerApi::default();
+        let rates = api
+            .get_range_rates(
+                "MYR",
+                chrono::NaiveDate::from_ymd_opt(2026, 1, 30).unwrap(),
+                chrono::NaiveDate::from_ymd_opt(2026, 2, 3).unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(rates.base, "MYR");
+        // friday, monday and tuesday, the weekend is left out
+        assert_eq!(rates.rates.len(), 3);
+        assert!(rates.rates.values().all(|day| day.contains_key("SGD")));
+    }
 }
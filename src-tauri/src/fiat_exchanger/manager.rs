@@ -0,0 +1,287 @@
+use super::currency_api_exchanger::CurrencyApiExchanger;
+use super::frankfurter_exchanger::FrankfurterExchangerApi;
+use super::{Currency, FiatExchanger, RangeRates, Rates};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use std::collections::BTreeMap;
+
+/// Fiat rate providers in priority order, a provider is asked when the ones before it failed
+pub struct FiatExchangerManager {
+    providers: Vec<Box<dyn FiatExchanger>>,
+}
+
+impl Default for FiatExchangerManager {
+    /// Frankfurter first for the ECB reference rates, then Currency API for the other currencies
+    fn default() -> Self {
+        Self::new(vec![
+            Box::new(FrankfurterExchangerApi::default()),
+            Box::new(CurrencyApiExchanger::default()),
+        ])
+    }
+}
+
+impl FiatExchangerManager {
+    pub fn new(providers: Vec<Box<dyn FiatExchanger>>) -> Self {
+        Self { providers }
+    }
+}
+
+#[async_trait]
+impl FiatExchanger for FiatExchangerManager {
+    fn id(&self) -> &'static str {
+        "manager"
+    }
+
+    /// The currencies of every provider, a currency keeps the name of the first provider listing it
+    /// - a failing provider is skipped, the call only fails if every provider failed
+    async fn get_available_currencies(&self) -> Result<Vec<Currency>> {
+        let mut last_error = anyhow!("No providers configured");
+        let mut any_success = false;
+        let mut currencies = BTreeMap::new();
+
+        for provider in &self.providers {
+            match provider.get_available_currencies().await {
+                Ok(provider_currencies) => {
+                    any_success = true;
+                    for currency in provider_currencies {
+                        currencies
+                            .entry(currency.symbol.clone())
+                            .or_insert(currency);
+                    }
+                }
+                Err(e) => {
+                    eprintln!(
+                        "Provider {} failed to get available currencies: {:#}",
+                        provider.id(),
+                        e
+                    );
+                    last_error = e;
+                    // Continue to next provider
+                }
+            }
+        }
+
+        if !any_success {
+            return Err(last_error);
+        }
+        Ok(currencies.into_values().collect())
+    }
+
+    #[allow(clippy::needless_lifetimes)]
+    async fn get_latest_rates<'a>(&self, base: &str, date: Option<&'a NaiveDate>) -> Result<Rates> {
+        let mut last_error = anyhow!("No providers configured");
+
+        for provider in &self.providers {
+            match provider.get_latest_rates(base, date).await {
+                Ok(rates) => return Ok(rates),
+                Err(e) => {
+                    eprintln!(
+                        "Provider {} failed to get {} rates: {:#}",
+                        provider.id(),
+                        base,
+                        e
+                    );
+                    last_error = e;
+                    // Continue to next provider
+                }
+            }
+        }
+        Err(last_error)
+    }
+
+    async fn get_range_rates(
+        &self,
+        base: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<RangeRates> {
+        let mut last_error = anyhow!("No providers configured");
+
+        for provider in &self.providers {
+            match provider.get_range_rates(base, start, end).await {
+                Ok(rates) => return Ok(rates),
+                Err(e) => {
+                    eprintln!(
+                        "Provider {} failed to get {} rates from {} to {}: {:#}",
+                        provider.id(),
+                        base,
+                        start,
+                        end,
+                        e
+                    );
+                    last_error = e;
+                    // Continue to next provider
+                }
+            }
+        }
+        Err(last_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Db;
+    use crate::fiat::FiatService;
+    use crate::fiat_exchanger::MockFiatExchanger;
+    use crate::fiat_rate;
+    use rust_decimal::Decimal;
+    use std::collections::HashMap;
+
+    fn currency(symbol: &str, name: &str) -> Currency {
+        Currency {
+            symbol: symbol.to_string(),
+            name: name.to_string(),
+        }
+    }
+
+    fn rates(base: &str, quotes: &[(&str, Decimal)], provider: &str) -> Rates {
+        Rates {
+            base: base.to_string(),
+            date: NaiveDate::from_ymd_opt(2024, 3, 6).unwrap(),
+            rates: quotes
+                .iter()
+                .map(|(symbol, rate)| (symbol.to_string(), *rate))
+                .collect(),
+            provider: provider.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_available_currencies_merges_providers() {
+        let mut mock_provider1 = MockFiatExchanger::new();
+        mock_provider1.expect_id().return_const("mock_provider_1");
+        mock_provider1
+            .expect_get_available_currencies()
+            .times(1)
+            .returning(|| {
+                Ok(vec![
+                    currency("EUR", "Euro"),
+                    currency("MYR", "Malaysian Ringgit"),
+                ])
+            });
+        let mut mock_provider2 = MockFiatExchanger::new();
+        mock_provider2.expect_id().return_const("mock_provider_2");
+        mock_provider2
+            .expect_get_available_currencies()
+            .times(1)
+            .returning(|| {
+                Ok(vec![
+                    currency("ARS", "Argentine Peso"),
+                    currency("MYR", "Ringgit"),
+                ])
+            });
+        let mut mock_provider3 = MockFiatExchanger::new();
+        mock_provider3.expect_id().return_const("mock_provider_3");
+        mock_provider3
+            .expect_get_available_currencies()
+            .times(1)
+            .returning(|| Err(anyhow!("Timeout")));
+
+        let manager = FiatExchangerManager::new(vec![
+            Box::new(mock_provider1),
+            Box::new(mock_provider2),
+            Box::new(mock_provider3),
+        ]);
+        let currencies = manager.get_available_currencies().await.unwrap();
+
+        let symbols: Vec<&str> = currencies.iter().map(|c| c.symbol.as_str()).collect();
+        assert_eq!(symbols, vec!["ARS", "EUR", "MYR"]);
+        assert_eq!(currencies[2].name, "Malaysian Ringgit");
+    }
+
+    #[tokio::test]
+    async fn test_get_latest_rates_fallback() {
+        let mut mock_provider1 = MockFiatExchanger::new();
+        mock_provider1.expect_id().return_const("mock_provider_1");
+        mock_provider1
+            .expect_get_latest_rates()
+            .times(1)
+            .returning(|_, _| Err(anyhow!("not found")));
+        let mut mock_provider2 = MockFiatExchanger::new();
+        mock_provider2.expect_id().return_const("mock_provider_2");
+        mock_provider2
+            .expect_get_latest_rates()
+            .times(1)
+            .returning(|base, _| {
+                Ok(rates(
+                    base,
+                    &[("MYR", Decimal::new(52, 4))],
+                    "mock_provider_2",
+                ))
+            });
+
+        let manager =
+            FiatExchangerManager::new(vec![Box::new(mock_provider1), Box::new(mock_provider2)]);
+        let result = manager.get_latest_rates("ARS", None).await.unwrap();
+        assert_eq!(result.provider, "mock_provider_2");
+        assert_eq!(result.rates["MYR"], Decimal::new(52, 4));
+
+        let manager = FiatExchangerManager::new(vec![]);
+        assert!(manager.get_latest_rates("ARS", None).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_stored_rate_records_provider() {
+        let db = Db::in_memory().await.unwrap();
+        let mut mock_currencies = MockFiatExchanger::new();
+        mock_currencies
+            .expect_get_available_currencies()
+            .returning(|| {
+                Ok(vec![
+                    currency("ARS", "Argentine Peso"),
+                    currency("MYR", "Malaysian Ringgit"),
+                ])
+            });
+        FiatService::new(mock_currencies)
+            .update_currencies(&db)
+            .await
+            .unwrap();
+
+        // the first provider has MYR rates without ARS, the second one has ARS rates
+        let mut mock_provider1 = MockFiatExchanger::new();
+        mock_provider1.expect_id().return_const("mock_provider_1");
+        mock_provider1
+            .expect_get_latest_rates()
+            .returning(|base, _| match base {
+                "MYR" => Ok(rates(
+                    "MYR",
+                    &[("SGD", Decimal::new(28, 2))],
+                    "mock_provider_1",
+                )),
+                _ => Err(anyhow!("not found")),
+            });
+        let mut mock_provider2 = MockFiatExchanger::new();
+        mock_provider2.expect_id().return_const("mock_provider_2");
+        mock_provider2
+            .expect_get_latest_rates()
+            .returning(|base, _| {
+                Ok(rates(
+                    base,
+                    &[("MYR", Decimal::new(5, 3))],
+                    "mock_provider_2",
+                ))
+            });
+        let manager =
+            FiatExchangerManager::new(vec![Box::new(mock_provider1), Box::new(mock_provider2)]);
+
+        let date = NaiveDate::from_ymd_opt(2024, 3, 6).unwrap();
+        let pair = fiat_rate::get_rate_for_pair(&db, &manager, "MYR", "ARS", &date, None)
+            .await
+            .unwrap();
+        assert_eq!(pair.rate, Decimal::new(200, 0));
+        assert_eq!(pair.provider.as_deref(), Some("mock_provider_2"));
+
+        let providers: HashMap<String, String> = sqlx::query_as(
+            "SELECT fiat.symbol, fiat_exchange_rate.provider FROM fiat_exchange_rate JOIN fiat ON fiat.id = base_fiat_id",
+        )
+        .fetch_all(&db.0)
+        .await
+        .unwrap()
+        .into_iter()
+        .collect();
+        assert_eq!(providers["MYR"], "mock_provider_1");
+        assert_eq!(providers["ARS"], "mock_provider_2");
+    }
+}
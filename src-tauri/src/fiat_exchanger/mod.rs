@@ -1,14 +1,32 @@
+pub mod currency_api_exchanger;
 pub mod frankfurter_exchanger;
-use std::collections::HashMap;
+pub mod manager;
+use std::collections::{BTreeMap, HashMap};
 
 use anyhow::Result;
+use async_trait::async_trait;
 use chrono::NaiveDate;
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
+pub use manager::FiatExchangerManager;
+
 #[cfg_attr(test, mockall::automock)]
-pub trait FiatExchanger {
+#[async_trait]
+pub trait FiatExchanger: Send + Sync {
     async fn get_available_currencies(&self) -> Result<Vec<Currency>>;
+    // explicit lifetime is required by mockall
+    #[allow(clippy::needless_lifetimes)]
     async fn get_latest_rates<'a>(&self, base: &str, date: Option<&'a NaiveDate>) -> Result<Rates>;
+    /// Rates of every working day from `start` to `end`, in one request
+    async fn get_range_rates(
+        &self,
+        base: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<RangeRates>;
+    /// Stored with each rate to record where it came from
+    fn id(&self) -> &'static str;
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -25,5 +43,21 @@ pub struct Rates {
     pub base: String,
     // the date which the rate was request / available in the server
     pub date: chrono::NaiveDate,
-    pub rates: HashMap<FiatSymbol, f64>,
+    pub rates: HashMap<FiatSymbol, Decimal>,
+    /// Id of the exchanger which supplied the rates
+    #[serde(default)]
+    pub provider: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RangeRates {
+    pub base: String,
+    // the first and last date with rates, not necessarily the requested ones
+    pub start_date: chrono::NaiveDate,
+    pub end_date: chrono::NaiveDate,
+    /// Rates of each working day, days without rates are left out
+    pub rates: BTreeMap<chrono::NaiveDate, HashMap<FiatSymbol, Decimal>>,
+    /// Id of the exchanger which supplied the rates
+    #[serde(default)]
+    pub provider: String,
 }
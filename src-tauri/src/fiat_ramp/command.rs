@@ -1,36 +1,53 @@
+use crate::asset::peg::StablecoinPegService;
+use crate::crypto_exchange::CryptoExchangeManager;
 use crate::db::Db;
 use crate::db::StringRowId;
-use crate::fiat_exchanger::frankfurter_exchanger::FrankfurterExchangerApi;
+use crate::fiat_exchanger::FiatExchangerManager;
+use crate::fiat_ramp::recurring::{
+    CreateRecurringRamp, RecurringRamp, RecurringRampProposal, RecurringRampService,
+    UpdateRecurringRamp,
+};
 use crate::fiat_ramp::CreateFiatRamp;
+use crate::fiat_ramp::FiatRamp;
 use crate::fiat_ramp::FiatRampPagination;
 use crate::fiat_ramp::FiatRampService;
 use crate::fiat_ramp::SortOptions;
+use crate::fiat_ramp::SummaryInterval;
 use crate::fiat_ramp::UpdateFiatRamp;
 use crate::fiat_rate;
 
+use chrono::{Local, NaiveDate};
 use tauri::{Emitter, State, Window};
 
+/// Occurrences given by the previews when no count is asked
+const DEFAULT_PREVIEW_COUNT: usize = 12;
+
 #[tauri::command]
 pub async fn create_fiat_ramp(
     create_fiat_ramp: CreateFiatRamp,
     db: State<'_, Db>,
+    fiat_exchanger: State<'_, FiatExchangerManager>,
+    crypto_exchange: State<'_, CryptoExchangeManager>,
 ) -> Result<StringRowId, String> {
-    let date = create_fiat_ramp.ramp_date;
-    let fiat_id = create_fiat_ramp.fiat_id;
-
     let result = FiatRampService::create(create_fiat_ramp, &db)
         .await
         .map_err(|e| format!("failed to create fiat ramp: {e}"))?;
 
     // Trigger rate fetch
-    let api = FrankfurterExchangerApi::default();
-    let _ = fiat_rate::get_rate(&db, &api, &date, Some(&result))
-        .await
-        .ok();
+    let api = fiat_exchanger.inner();
+    let _ = fiat_rate::get_rate_for_ramp(&db, api, &result).await.ok();
+    fetch_stablecoin_prices(&db, &crypto_exchange).await;
 
     Ok(result)
 }
 
+/// Fetch the market price of the stablecoin ramps priced by the market
+async fn fetch_stablecoin_prices(db: &Db, crypto_exchange: &CryptoExchangeManager) {
+    if let Err(e) = StablecoinPegService::fetch_missing_prices(db, crypto_exchange).await {
+        eprintln!("Failed to fetch stablecoin prices: {e:#}");
+    }
+}
+
 #[derive(Clone, serde::Serialize)]
 struct ProgressPayload {
     processed: usize,
@@ -43,6 +60,8 @@ struct ProgressPayload {
 pub async fn create_fiat_ramps_bulk(
     ramps: Vec<CreateFiatRamp>,
     db: State<'_, Db>,
+    fiat_exchanger: State<'_, FiatExchangerManager>,
+    crypto_exchange: State<'_, CryptoExchangeManager>,
     window: Window,
 ) -> Result<u64, String> {
     let total = ramps.len();
@@ -62,17 +81,20 @@ pub async fn create_fiat_ramps_bulk(
 
         // Trigger rate checks for this chunk using the real IDs
         // This ensures failures are queued correctly in `fiat_rate_missing`
-        let api = FrankfurterExchangerApi::default();
+        let api = fiat_exchanger.inner();
+        // Backfill the chunk dates with one range request per currency first
+        if let Err(e) = fiat_rate::backfill::backfill_rates(&db, api).await {
+            eprintln!("Failed to backfill rates: {e:#}");
+        }
 
-        // We can execute these in parallel since get_rate handles its own DB connections
+        // We can execute these in parallel since get_rate_for_ramp handles its own DB connections
         // Note: join_all awaits all futures concurrently
         let futures = all_new_fiat_ramps.iter().map(|fiat_ramp| {
-            let api_ref = &api;
+            let api_ref = api;
             let db_ref = &db;
             let id_ref = &fiat_ramp.id;
-            let date = fiat_ramp.ramp_date;
             async move {
-                fiat_rate::get_rate(db_ref, api_ref, &date, Some(id_ref))
+                fiat_rate::get_rate_for_ramp(db_ref, api_ref, id_ref)
                     .await
                     .ok()
             }
@@ -80,6 +102,8 @@ pub async fn create_fiat_ramps_bulk(
 
         futures::future::join_all(futures).await;
 
+        fetch_stablecoin_prices(&db, &crypto_exchange).await;
+
         processed += chunk.len();
         let _ = window.emit(
             "fiat-ramp-bulk-progress",
@@ -108,18 +132,31 @@ pub async fn get_fiat_ramps(
         .map_err(|e| format!("failed to get all fiat ramps: {e}"))
 }
 
-/// Get fiat ramp summary
+/// Get fiat ramp summary -- interval is optional and defaults to month
 #[tauri::command]
 pub async fn get_fiat_ramp_summary(
     db: State<'_, Db>,
     start_date: Option<chrono::NaiveDate>,
     end_date: Option<chrono::NaiveDate>,
+    interval: Option<SummaryInterval>,
 ) -> Result<crate::fiat_ramp::FiatRampSummary, String> {
-    FiatRampService::get_summary(start_date, end_date, &db)
+    FiatRampService::get_summary(start_date, end_date, interval.unwrap_or_default(), &db)
         .await
         .map_err(|e| format!("failed to get fiat ramp summary: {e}"))
 }
 
+/// Get the deposit, withdraw and net totals per exchange and per source currency
+#[tauri::command]
+pub async fn get_fiat_ramp_breakdown(
+    db: State<'_, Db>,
+    start_date: Option<chrono::NaiveDate>,
+    end_date: Option<chrono::NaiveDate>,
+) -> Result<crate::fiat_ramp::FiatRampBreakdown, String> {
+    FiatRampService::get_breakdown(start_date, end_date, &db)
+        .await
+        .map_err(|e| format!("failed to get fiat ramp breakdown: {e}"))
+}
+
 /// Get fiat ramp date range (min and max date)
 #[tauri::command]
 pub async fn get_fiat_ramp_date_range(
@@ -130,7 +167,11 @@ pub async fn get_fiat_ramp_date_range(
 
 /// Update a fiat ramp
 #[tauri::command]
-pub async fn update_fiat_ramp(fiat_ramp: UpdateFiatRamp, db: State<'_, Db>) -> Result<u64, String> {
+pub async fn update_fiat_ramp(
+    fiat_ramp: UpdateFiatRamp,
+    db: State<'_, Db>,
+    fiat_exchanger: State<'_, FiatExchangerManager>,
+) -> Result<u64, String> {
     let id = fiat_ramp.id.clone();
 
     // We need to fetch the updated values (or existing values if partial update) to trigger rate check.
@@ -141,31 +182,10 @@ pub async fn update_fiat_ramp(fiat_ramp: UpdateFiatRamp, db: State<'_, Db>) -> R
         .await
         .map_err(|e| format!("failed to update fiat ramp: {e}"))?;
 
-    // Now fetch the updated ramp to get the full state (fiat_id, ramp_date)
-    // We can't easily fetch just one ramp with existing service (get returns pagination).
-    // Let's implement a quick fetch or just use raw query here? Or add get_by_id to service.
-    // Given instructions, I'll use raw query to be safe and quick.
-
-    // Actually, we can just trigger get_rate IF fiat_id or ramp_date were updated.
-    // But we need BOTH values to call get_rate.
-    // So we must fetch the row.
-
-    let ramp_row = sqlx::query("SELECT fiat_id, ramp_date FROM fiat_ramp WHERE id = ?")
-        .bind(&id)
-        .fetch_optional(&db.0)
-        .await
-        .map_err(|e| format!("failed to fetch updated ramp: {e}"))?;
-
-    if let Some(row) = ramp_row {
-        use sqlx::Row;
-        let fiat_id: i64 = row.get("fiat_id");
-        let ramp_date: chrono::NaiveDate = row.get("ramp_date");
-        let api = FrankfurterExchangerApi::default();
-        // Trigger get_rate. This handles queue updates (removal/upsert) internally.
-        let _ = fiat_rate::get_rate(&db, &api, &ramp_date, Some(&id))
-            .await
-            .ok();
-    }
+    // The currency or the date may have changed, get_rate_for_ramp reads them from the updated row
+    // and handles queue updates (removal/upsert) internally.
+    let api = fiat_exchanger.inner();
+    let _ = fiat_rate::get_rate_for_ramp(&db, api, &id).await.ok();
 
     Ok(rows_affected)
 }
@@ -177,3 +197,109 @@ pub async fn delete_fiat_ramp(id: StringRowId, db: State<'_, Db>) -> Result<u64,
         .await
         .map_err(|e| format!("failed to delete fiat ramp: {e}"))
 }
+
+#[tauri::command]
+pub async fn create_recurring_ramp(
+    recurring_ramp: CreateRecurringRamp,
+    db: State<'_, Db>,
+) -> Result<RecurringRamp, String> {
+    RecurringRampService::create(recurring_ramp, &db)
+        .await
+        .map_err(|e| format!("failed to create recurring ramp: {e:#}"))
+}
+
+#[tauri::command]
+pub async fn get_recurring_ramps(db: State<'_, Db>) -> Result<Vec<RecurringRamp>, String> {
+    RecurringRampService::get_all(&db)
+        .await
+        .map_err(|e| format!("failed to get recurring ramps: {e:#}"))
+}
+
+/// Replace a recurring ramp, the ramps it created already are left as they are
+#[tauri::command]
+pub async fn update_recurring_ramp(
+    recurring_ramp: UpdateRecurringRamp,
+    db: State<'_, Db>,
+) -> Result<RecurringRamp, String> {
+    RecurringRampService::update(recurring_ramp, &db)
+        .await
+        .map_err(|e| format!("failed to update recurring ramp: {e:#}"))
+}
+
+/// Pause or resume a recurring ramp, the occurrences of the pause are skipped
+#[tauri::command]
+pub async fn set_recurring_ramp_paused(
+    id: StringRowId,
+    is_paused: bool,
+    db: State<'_, Db>,
+) -> Result<RecurringRamp, String> {
+    RecurringRampService::set_paused(&id, is_paused, Local::now().date_naive(), &db)
+        .await
+        .map_err(|e| format!("failed to pause recurring ramp: {e:#}"))
+}
+
+#[tauri::command]
+pub async fn delete_recurring_ramp(id: StringRowId, db: State<'_, Db>) -> Result<u64, String> {
+    RecurringRampService::delete(&id, &db)
+        .await
+        .map_err(|e| format!("failed to delete recurring ramp: {e:#}"))
+}
+
+/// Next occurrences of a recurring ramp -- count is optional and defaults to 12
+#[tauri::command]
+pub async fn preview_recurring_ramp(
+    id: StringRowId,
+    count: Option<usize>,
+    db: State<'_, Db>,
+) -> Result<Vec<NaiveDate>, String> {
+    RecurringRampService::preview(&id, count.unwrap_or(DEFAULT_PREVIEW_COUNT), &db)
+        .await
+        .map_err(|e| format!("failed to preview recurring ramp: {e:#}"))
+}
+
+/// First occurrences of a schedule before its recurring ramp is saved -- count is optional and
+/// defaults to 12
+#[tauri::command]
+pub async fn preview_recurring_schedule(
+    rrule: String,
+    start_date: NaiveDate,
+    count: Option<usize>,
+) -> Result<Vec<NaiveDate>, String> {
+    RecurringRampService::preview_rule(&rrule, start_date, count.unwrap_or(DEFAULT_PREVIEW_COUNT))
+        .map_err(|e| format!("failed to preview schedule: {e:#}"))
+}
+
+#[tauri::command]
+pub async fn get_recurring_ramp_proposals(
+    db: State<'_, Db>,
+) -> Result<Vec<RecurringRampProposal>, String> {
+    RecurringRampService::get_proposals(&db)
+        .await
+        .map_err(|e| format!("failed to get recurring ramp proposals: {e:#}"))
+}
+
+/// Create the ramp of a proposal and fetch its rate
+#[tauri::command]
+pub async fn confirm_recurring_ramp_proposal(
+    id: StringRowId,
+    db: State<'_, Db>,
+    fiat_exchanger: State<'_, FiatExchangerManager>,
+) -> Result<FiatRamp, String> {
+    let ramp = RecurringRampService::confirm_proposal(&id, &db)
+        .await
+        .map_err(|e| format!("failed to confirm recurring ramp proposal: {e:#}"))?;
+    let _ = fiat_rate::get_rate_for_ramp(&db, fiat_exchanger.inner(), &ramp.id)
+        .await
+        .ok();
+    Ok(ramp)
+}
+
+#[tauri::command]
+pub async fn dismiss_recurring_ramp_proposal(
+    id: StringRowId,
+    db: State<'_, Db>,
+) -> Result<u64, String> {
+    RecurringRampService::dismiss_proposal(&id, &db)
+        .await
+        .map_err(|e| format!("failed to dismiss recurring ramp proposal: {e:#}"))
+}
@@ -0,0 +1,165 @@
+use crate::db::{SqlDecimal, StringRowId};
+use crate::fiat_ramp::RampKind;
+use crate::utils::string_utils::normalize_name;
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use sqlx::prelude::FromRow;
+use sqlx::SqliteConnection;
+use uuid::Uuid;
+
+/// Namespace of the name based uuids used as fingerprints
+const FINGERPRINT_NAMESPACE: Uuid = Uuid::from_u128(0x0195_6b8e_5f3a_7c41_9d2e_a8b4_c6f0_1e37);
+
+/// Deterministic identity of a fiat ramp, the same ramp imported twice gets the same fingerprint
+/// - built from the date, the signed amount, the currency and the exchange name ignoring case,
+///   spaces and punctuation
+pub fn fingerprint(
+    ramp_date: NaiveDate,
+    kind: RampKind,
+    fiat_amount: Decimal,
+    fiat_symbol: &str,
+    exchange: &str,
+) -> String {
+    let sign = match kind {
+        RampKind::Deposit => "",
+        RampKind::Withdraw => "-",
+    };
+    let key = format!(
+        "{ramp_date}|{sign}{}|{}|{}",
+        // normalized so that 100.00 and 100 match, written like the floats stored before
+        fiat_amount.abs().normalize(),
+        fiat_symbol.trim().to_uppercase(),
+        normalize_name(exchange)
+    );
+    Uuid::new_v5(&FINGERPRINT_NAMESPACE, key.as_bytes()).to_string()
+}
+
+#[derive(FromRow)]
+struct Unfingerprinted {
+    id: StringRowId,
+    ramp_date: NaiveDate,
+    kind: RampKind,
+    #[sqlx(try_from = "SqlDecimal")]
+    fiat_amount: Decimal,
+    fiat_symbol: String,
+    via_exchange: String,
+}
+
+/// Fill in the fingerprint of the fiat ramps without one
+/// - new and edited ramps are left without a fingerprint until then
+/// - returns the number of ramps updated
+/// - runs on `conn`, an import classifies its rows inside its own transaction
+pub async fn refresh(conn: &mut SqliteConnection) -> Result<u64> {
+    let ramps = sqlx::query_as::<sqlx::Sqlite, Unfingerprinted>(
+        r#"
+        SELECT
+            fiat_ramp.id,
+            fiat_ramp.ramp_date,
+            fiat_ramp.kind,
+            fiat_ramp.fiat_amount,
+            COALESCE(asset.symbol, fiat.symbol) as fiat_symbol,
+            fiat_ramp.via_exchange
+        FROM fiat_ramp
+        JOIN fiat ON fiat.id = fiat_ramp.fiat_id
+        LEFT JOIN asset ON asset.id = fiat_ramp.asset_id
+        WHERE fiat_ramp.fingerprint IS NULL
+        "#,
+    )
+    .fetch_all(&mut *conn)
+    .await
+    .context("failed to get fiat ramps without fingerprint")?;
+
+    for ramp in &ramps {
+        sqlx::query("UPDATE fiat_ramp SET fingerprint = ? WHERE id = ?")
+            .bind(fingerprint(
+                ramp.ramp_date,
+                ramp.kind,
+                ramp.fiat_amount,
+                &ramp.fiat_symbol,
+                &ramp.via_exchange,
+            ))
+            .bind(&ramp.id)
+            .execute(&mut *conn)
+            .await
+            .context("failed to update fiat ramp fingerprint")?;
+    }
+    Ok(ramps.len() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint() {
+        let date = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+        let deposit = fingerprint(
+            date,
+            RampKind::Deposit,
+            Decimal::ONE_HUNDRED,
+            "EUR",
+            "Binance",
+        );
+        assert_eq!(
+            deposit,
+            fingerprint(
+                date,
+                RampKind::Deposit,
+                Decimal::ONE_HUNDRED,
+                "eur",
+                " binance "
+            )
+        );
+        assert_eq!(
+            deposit,
+            fingerprint(
+                date,
+                RampKind::Deposit,
+                Decimal::new(10_000, 2),
+                "EUR",
+                "Binance"
+            )
+        );
+        assert_ne!(
+            deposit,
+            fingerprint(
+                date,
+                RampKind::Withdraw,
+                Decimal::ONE_HUNDRED,
+                "EUR",
+                "Binance"
+            )
+        );
+        assert_ne!(
+            deposit,
+            fingerprint(
+                date,
+                RampKind::Deposit,
+                Decimal::new(1005, 1),
+                "EUR",
+                "Binance"
+            )
+        );
+        assert_ne!(
+            deposit,
+            fingerprint(
+                date,
+                RampKind::Deposit,
+                Decimal::ONE_HUNDRED,
+                "USD",
+                "Binance"
+            )
+        );
+        assert_ne!(
+            deposit,
+            fingerprint(
+                date,
+                RampKind::Deposit,
+                Decimal::ONE_HUNDRED,
+                "EUR",
+                "Kraken"
+            )
+        );
+    }
+}
@@ -1,12 +1,21 @@
 pub mod command;
-use crate::db::{Db, RowId, StringRowId};
-use chrono::{NaiveDate, Utc};
+pub mod fingerprint;
+pub mod recurring;
+pub mod schedule;
+use crate::asset::peg::StablecoinPegService;
+use crate::db::{Db, RowId, SqlDecimal, SqlOptionDecimal, StringRowId};
+use crate::exchange::{Exchange, ExchangeRef, ExchangeService};
+use crate::fiat::format::round_amount;
+use crate::fiat_rate::manual::ManualRateService;
+use crate::fiat_rate::RateSource;
+use chrono::{Datelike, Duration, NaiveDate};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
-use sqlx::{prelude::FromRow, sqlite::SqliteQueryResult};
-use std::collections::HashMap;
+use sqlx::{prelude::FromRow, sqlite::SqliteQueryResult, SqliteConnection};
+use std::collections::{BTreeMap, HashMap};
 use uuid::Uuid;
 
-#[derive(Debug, Serialize, Deserialize, sqlx::Type, Clone)]
+#[derive(Debug, Serialize, Deserialize, sqlx::Type, Clone, Copy, PartialEq)]
 #[serde(rename_all = "lowercase")]
 #[sqlx(rename_all = "lowercase")]
 pub enum RampKind {
@@ -18,29 +27,62 @@ pub enum RampKind {
 pub struct FiatRamp {
     pub id: StringRowId,
     pub fiat_id: RowId,
-    pub fiat_amount: f64,
+    /// stablecoin the ramp was made in, `fiat_id` is then the fiat of its peg
+    pub asset_id: Option<StringRowId>,
+    #[sqlx(try_from = "SqlDecimal")]
+    pub fiat_amount: Decimal,
     pub ramp_date: chrono::NaiveDate,
     pub kind: RampKind,
+    /// canonical name of the exchange
     pub via_exchange: String,
+    pub exchange_id: Option<StringRowId>,
+    pub external_ref: Option<String>,
+    /// fiat the fee was paid in, None means the fee was paid in the currency of the ramp
+    pub fee_fiat_id: Option<RowId>,
+    /// part of `fiat_amount`, the ramp nets `fiat_amount` less the fee
+    #[sqlx(try_from = "SqlOptionDecimal")]
+    pub fee_amount: Option<Decimal>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UpdateFiatRamp {
     pub id: StringRowId,
     pub fiat_id: Option<RowId>,
-    pub fiat_amount: Option<f64>,
+    pub fiat_amount: Option<Decimal>,
     pub ramp_date: Option<chrono::NaiveDate>,
     pub kind: Option<RampKind>,
-    pub via_exchange: Option<String>,
+    /// exchange id or name, see `ExchangeRef`
+    pub via_exchange: Option<ExchangeRef>,
+    /// stablecoin the ramp was made in, None for a ramp in `fiat_id`
+    #[serde(default)]
+    pub asset_id: Option<StringRowId>,
+    /// fiat the fee was paid in, None means the fee was paid in the currency of the ramp
+    #[serde(default)]
+    pub fee_fiat_id: Option<RowId>,
+    #[serde(default)]
+    pub fee_amount: Option<Decimal>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct CreateFiatRamp {
     pub fiat_id: RowId,
-    pub fiat_amount: f64,
+    pub fiat_amount: Decimal,
     pub ramp_date: chrono::NaiveDate,
-    pub via_exchange: String,
+    /// exchange id or name, see `ExchangeRef`
+    pub via_exchange: ExchangeRef,
     pub kind: RampKind,
+    /// reference of the ramp at the exchange, e.g. the transaction id of a statement
+    #[serde(default)]
+    pub external_ref: Option<String>,
+    /// stablecoin the ramp was made in, `fiat_id` is then replaced by the fiat of its peg
+    #[serde(default)]
+    pub asset_id: Option<StringRowId>,
+    /// fiat the fee was paid in, None means the fee was paid in the currency of the ramp
+    #[serde(default)]
+    pub fee_fiat_id: Option<RowId>,
+    /// part of `fiat_amount`, the ramp nets `fiat_amount` less the fee
+    #[serde(default)]
+    pub fee_amount: Option<Decimal>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -51,11 +93,112 @@ pub struct FiatRampPagination {
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct FiatRampSummary {
-    pub total_deposit: f64,
-    pub total_withdraw: f64,
+    /// gross deposits, fees included
+    pub total_deposit: Decimal,
+    /// gross withdrawals, fees included
+    pub total_withdraw: Decimal,
+    /// fees of the deposits
+    pub deposit_fee: Decimal,
+    /// fees of the withdrawals
+    pub withdraw_fee: Decimal,
+    /// fees of the deposits and the withdrawals
+    pub total_fee: Decimal,
+    /// deposits less their fees, what reached the exchanges
+    pub net_deposit: Decimal,
+    /// withdrawals less their fees, what reached the bank
+    pub net_withdraw: Decimal,
+    /// net invested before the start date, the cumulative series starts from it
+    pub opening_net_invested: Decimal,
+    /// net invested at the end of the range, gross deposits less gross withdrawals
+    pub net_invested: Decimal,
+    pub fiat_symbol: String,
+    pub fiat_name: String,
+    pub interval: SummaryInterval,
+    /// buckets in chronological order, periods without ramps are left out
+    pub data: Vec<FiatRampSummaryBucket>,
+}
+
+/// Size of the buckets of the summary series
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SummaryInterval {
+    Day,
+    /// weeks start on monday
+    Week,
+    #[default]
+    Month,
+    Year,
+}
+
+impl SummaryInterval {
+    /// First day of the bucket a ramp date falls in
+    fn period_start(&self, date: NaiveDate) -> NaiveDate {
+        match self {
+            SummaryInterval::Day => date,
+            SummaryInterval::Week => {
+                date - Duration::days(date.weekday().num_days_from_monday().into())
+            }
+            SummaryInterval::Month => date.with_day(1).unwrap_or(date),
+            SummaryInterval::Year => date.with_ordinal(1).unwrap_or(date),
+        }
+    }
+}
+
+/// Ramps of a single bucket converted to the default fiat
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct FiatRampSummaryBucket {
+    pub period_start: NaiveDate,
+    pub deposit: Decimal,
+    pub withdraw: Decimal,
+    /// fees of the deposits and the withdrawals of the bucket
+    pub fee: Decimal,
+    /// deposit - withdraw of the bucket
+    pub net: Decimal,
+    /// net invested up to and including the bucket
+    pub cumulative_net: Decimal,
+}
+
+/// Totals of a single exchange converted to the default fiat
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FiatRampExchangeTotal {
+    pub via_exchange: String,
+    pub deposit: Decimal,
+    pub withdraw: Decimal,
+    pub net: Decimal,
+    pub ramp_count: i64,
+    /// ramps without a conversion rate yet, they are not part of the totals
+    pub unconverted_count: i64,
+}
+
+/// Totals of a single source currency converted to the default fiat
+/// - the ramps in a stablecoin are apart from those in the fiat of its peg
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FiatRampCurrencyTotal {
+    pub fiat_id: RowId,
+    /// stablecoin of the ramps, the symbol and the name are then those of the stablecoin
+    pub asset_id: Option<StringRowId>,
+    pub fiat_symbol: String,
+    pub fiat_name: String,
+    pub deposit: Decimal,
+    pub withdraw: Decimal,
+    pub net: Decimal,
+    /// deposits in the source currency itself
+    pub source_deposit: Decimal,
+    /// withdrawals in the source currency itself
+    pub source_withdraw: Decimal,
+    pub ramp_count: i64,
+    /// ramps without a conversion rate yet, they are not part of the converted totals
+    pub unconverted_count: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FiatRampBreakdown {
     pub fiat_symbol: String,
     pub fiat_name: String,
-    pub data: HashMap<String, f64>,
+    /// ordered by net, largest first
+    pub by_exchange: Vec<FiatRampExchangeTotal>,
+    /// ordered by net, largest first
+    pub by_currency: Vec<FiatRampCurrencyTotal>,
 }
 
 /// Sorting direction for query results
@@ -83,18 +226,132 @@ pub struct FiatRampWithConversionView {
     pub to_fiat_id: RowId,
     pub to_fiat_symbol: String,
     pub to_fiat_name: String,
-    pub conversion_rate: Option<f64>,
+    /// Decimal places `converted_amount` and `converted_fee` are rounded to
+    pub to_fiat_minor_units: u32,
+    /// Rate picked by `fiat_ramp_view` as `to_rate / from_rate`, see `with_conversion`
+    #[sqlx(try_from = "SqlOptionDecimal")]
+    #[serde(skip)]
+    to_rate: Option<Decimal>,
+    #[sqlx(try_from = "SqlOptionDecimal")]
+    #[serde(skip)]
+    from_rate: Option<Decimal>,
+    /// stablecoin the ramp was made in, `from_fiat_*` is then the fiat of its peg
+    pub asset_id: Option<StringRowId>,
+    pub asset_symbol: Option<String>,
+    pub asset_name: Option<String>,
+    /// Amount of `from_fiat` for one coin of the stablecoin, 1 for a ramp in fiat
+    #[sqlx(try_from = "SqlOptionDecimal")]
+    #[serde(skip)]
+    asset_rate: Option<Decimal>,
+    /// fiat the fee was paid in, None means the fee was paid in the currency of the ramp
+    pub fee_fiat_id: Option<RowId>,
+    pub fee_fiat_symbol: Option<String>,
+    #[sqlx(try_from = "SqlOptionDecimal")]
+    pub fee_amount: Option<Decimal>,
+    /// Rate of a fee paid in `fee_fiat_id` as `fee_to_rate / fee_from_rate`
+    #[sqlx(try_from = "SqlOptionDecimal")]
+    #[serde(skip)]
+    fee_to_rate: Option<Decimal>,
+    #[sqlx(try_from = "SqlOptionDecimal")]
+    #[serde(skip)]
+    fee_from_rate: Option<Decimal>,
+    #[sqlx(skip)]
+    pub conversion_rate: Option<Decimal>,
     pub ramp_date: NaiveDate,
-    pub fiat_amount: f64,
+    #[sqlx(try_from = "SqlDecimal")]
+    pub fiat_amount: Decimal,
     pub kind: RampKind,
     pub via_exchange: String,
+    pub exchange_id: Option<StringRowId>,
     #[sqlx(default)]
     pub is_estimated: bool,
     #[sqlx(default)]
     pub is_non_working_day: bool,
     #[sqlx(default)]
     pub non_working_day_reason: Option<String>,
-    pub converted_amount: Option<f64>,
+    /// None for a ramp in the default currency or without a rate
+    #[sqlx(default)]
+    pub rate_source: Option<RateSource>,
+    /// Rounded to the minor units of the default fiat
+    #[sqlx(skip)]
+    pub converted_amount: Option<Decimal>,
+    /// None without a fee or without a rate for it, rounded like `converted_amount`
+    #[sqlx(skip)]
+    pub converted_fee: Option<Decimal>,
+}
+
+impl FiatRampWithConversionView {
+    /// Fill in the conversion rate and the converted amount from the rates picked by the view
+    /// - computed here in decimals, sqlite only has floats
+    /// - a ramp in a stablecoin goes through the fiat of its peg
+    /// - a fee in the currency of the ramp is converted at the rate of the ramp
+    pub fn with_conversion(mut self) -> Self {
+        if let (Some(to_rate), Some(from_rate), Some(asset_rate)) =
+            (self.to_rate, self.from_rate, self.asset_rate)
+        {
+            self.conversion_rate = asset_rate
+                .checked_mul(to_rate)
+                .and_then(|rate| rate.checked_div(from_rate));
+            self.converted_amount = self
+                .fiat_amount
+                .checked_mul(asset_rate)
+                .and_then(|amount| amount.checked_mul(to_rate))
+                .and_then(|amount| amount.checked_div(from_rate))
+                .map(|amount| round_amount(amount, self.to_fiat_minor_units));
+        }
+        let fee_rate = match self.fee_fiat_id {
+            Some(_) => self
+                .fee_to_rate
+                .zip(self.fee_from_rate)
+                .and_then(|(to_rate, from_rate)| to_rate.checked_div(from_rate)),
+            None => self.conversion_rate,
+        };
+        self.converted_fee = self
+            .fee_amount
+            .zip(fee_rate)
+            .and_then(|(fee, rate)| fee.checked_mul(rate))
+            .map(|fee| round_amount(fee, self.to_fiat_minor_units));
+        self
+    }
+
+    /// Converted amount, negative for a withdrawal
+    fn signed_converted_amount(&self) -> Option<Decimal> {
+        self.converted_amount.map(|amount| match self.kind {
+            RampKind::Deposit => amount,
+            RampKind::Withdraw => -amount,
+        })
+    }
+}
+
+/// Deposits and withdrawals of a group of ramps, see `get_breakdown`
+#[derive(Default)]
+struct RampTotals {
+    deposit: Decimal,
+    withdraw: Decimal,
+    source_deposit: Decimal,
+    source_withdraw: Decimal,
+    ramp_count: i64,
+    unconverted_count: i64,
+}
+
+impl RampTotals {
+    fn add(&mut self, ramp: &FiatRampWithConversionView) {
+        let converted_amount = ramp.converted_amount.unwrap_or_default();
+        match ramp.kind {
+            RampKind::Deposit => {
+                self.deposit += converted_amount;
+                self.source_deposit += ramp.fiat_amount;
+            }
+            RampKind::Withdraw => {
+                self.withdraw += converted_amount;
+                self.source_withdraw += ramp.fiat_amount;
+            }
+        }
+        self.ramp_count += 1;
+        if ramp.converted_amount.is_none() {
+            self.unconverted_count += 1;
+        }
+    }
 }
 
 pub struct FiatRampService {}
@@ -102,25 +359,44 @@ pub struct FiatRampService {}
 impl FiatRampService {
     /// Create a new fiat ramp
     pub async fn create(create_fiat_ramp: CreateFiatRamp, db: &Db) -> Result<StringRowId, String> {
+        check_fee(
+            create_fiat_ramp.fiat_id,
+            create_fiat_ramp.fiat_amount,
+            create_fiat_ramp.fee_fiat_id,
+            create_fiat_ramp.fee_amount,
+        )?;
         let id = Uuid::now_v7().to_string();
         let mut tx =
             db.0.begin()
                 .await
                 .map_err(|e| format!("failed to begin transaction: {e}"))?;
+        let exchange = resolve_exchange(&create_fiat_ramp.via_exchange, &mut tx).await?;
+        let fiat_id = resolve_fiat_id(
+            create_fiat_ramp.fiat_id,
+            create_fiat_ramp.asset_id.as_deref(),
+            &mut tx,
+        )
+        .await?;
 
         sqlx::query(
             r#"
             INSERT INTO fiat_ramp
-            (id, fiat_id, fiat_amount, ramp_date, via_exchange, kind)
-            VALUES (?, ?, ?, ?, ?, ?)
+            (id, fiat_id, fiat_amount, ramp_date, via_exchange, exchange_id, kind, external_ref, asset_id,
+            fee_fiat_id, fee_amount)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         "#,
         )
         .bind(&id)
-        .bind(create_fiat_ramp.fiat_id)
-        .bind(create_fiat_ramp.fiat_amount)
+        .bind(fiat_id)
+        .bind(create_fiat_ramp.fiat_amount.to_string())
         .bind(create_fiat_ramp.ramp_date)
-        .bind(create_fiat_ramp.via_exchange)
+        .bind(exchange.name)
+        .bind(exchange.id)
         .bind(create_fiat_ramp.kind)
+        .bind(create_fiat_ramp.external_ref)
+        .bind(create_fiat_ramp.asset_id)
+        .bind(create_fiat_ramp.fee_fiat_id)
+        .bind(create_fiat_ramp.fee_amount.map(|fee| fee.to_string()))
         .execute(&mut *tx)
         .await
         .map_err(|e| format!("failed to insert into fiat_ramp table: {e}"))?;
@@ -131,47 +407,95 @@ impl FiatRampService {
         Ok(id)
     }
 
-    /// Create multiple fiat ramps and return all IDs
+    /// Create multiple fiat ramps and return them
     pub async fn create_bulk(ramps: Vec<CreateFiatRamp>, db: &Db) -> Result<Vec<FiatRamp>, String> {
         let mut tx =
             db.0.begin()
                 .await
                 .map_err(|e| format!("failed to begin transaction: {e}"))?;
+        let fiat_ramps = Self::create_bulk_in(ramps, &mut tx).await?;
+        tx.commit()
+            .await
+            .map_err(|e| format!("failed to commit transaction: {e}"))?;
+        Ok(fiat_ramps)
+    }
+
+    /// `create_bulk` on `conn`, e.g. inside the transaction of an import
+    pub(crate) async fn create_bulk_in(
+        ramps: Vec<CreateFiatRamp>,
+        conn: &mut SqliteConnection,
+    ) -> Result<Vec<FiatRamp>, String> {
+        for ramp in &ramps {
+            check_fee(
+                ramp.fiat_id,
+                ramp.fiat_amount,
+                ramp.fee_fiat_id,
+                ramp.fee_amount,
+            )?;
+        }
+        let mut exchanges: HashMap<ExchangeRef, Exchange> = HashMap::new();
+        for ramp in &ramps {
+            if !exchanges.contains_key(&ramp.via_exchange) {
+                let exchange = resolve_exchange(&ramp.via_exchange, conn).await?;
+                exchanges.insert(ramp.via_exchange.clone(), exchange);
+            }
+        }
+        // the fiat of the peg of each stablecoin
+        let mut peg_fiat_ids: HashMap<StringRowId, RowId> = HashMap::new();
+        for ramp in &ramps {
+            if let Some(asset_id) = &ramp.asset_id {
+                if !peg_fiat_ids.contains_key(asset_id) {
+                    let fiat_id = resolve_fiat_id(ramp.fiat_id, Some(asset_id), conn).await?;
+                    peg_fiat_ids.insert(asset_id.clone(), fiat_id);
+                }
+            }
+        }
 
         let mut fiat_ramps: Vec<FiatRamp> = Vec::with_capacity(ramps.len());
         for ramp in ramps {
             let id = Uuid::now_v7().to_string();
+            let exchange = &exchanges[&ramp.via_exchange];
+            let fiat_id = match &ramp.asset_id {
+                Some(asset_id) => peg_fiat_ids[asset_id],
+                None => ramp.fiat_id,
+            };
             sqlx::query(
                 r#"
                 INSERT INTO fiat_ramp
-                (id, fiat_id, fiat_amount, ramp_date, via_exchange, kind)
-                VALUES (?, ?, ?, ?, ?, ?)
+                (id, fiat_id, fiat_amount, ramp_date, via_exchange, exchange_id, kind, external_ref,
+                asset_id, fee_fiat_id, fee_amount)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
             )
             .bind(&id)
-            .bind(ramp.fiat_id)
-            .bind(ramp.fiat_amount)
+            .bind(fiat_id)
+            .bind(ramp.fiat_amount.to_string())
             .bind(ramp.ramp_date)
-            .bind(&ramp.via_exchange)
-            .bind(&ramp.kind)
-            .execute(&mut *tx)
+            .bind(&exchange.name)
+            .bind(&exchange.id)
+            .bind(ramp.kind)
+            .bind(&ramp.external_ref)
+            .bind(&ramp.asset_id)
+            .bind(ramp.fee_fiat_id)
+            .bind(ramp.fee_amount.map(|fee| fee.to_string()))
+            .execute(&mut *conn)
             .await
             .map_err(|e| format!("failed to insert fiat ramp: {e}"))?;
 
             fiat_ramps.push(FiatRamp {
                 id,
-                fiat_id: ramp.fiat_id,
+                fiat_id,
+                asset_id: ramp.asset_id,
                 fiat_amount: ramp.fiat_amount,
                 ramp_date: ramp.ramp_date,
-                via_exchange: ramp.via_exchange,
+                via_exchange: exchange.name.clone(),
+                exchange_id: Some(exchange.id.clone()),
                 kind: ramp.kind,
+                external_ref: ramp.external_ref,
+                fee_fiat_id: ramp.fee_fiat_id,
+                fee_amount: ramp.fee_amount,
             });
         }
-
-        tx.commit()
-            .await
-            .map_err(|e| format!("failed to commit transaction: {e}"))?;
-
         Ok(fiat_ramps)
     }
 
@@ -194,7 +518,8 @@ impl FiatRampService {
             OR kind LIKE ?
             OR CAST(fiat_amount AS TEXT) LIKE ?
             OR from_fiat_symbol LIKE ?
-            OR from_fiat_name LIKE ?)
+            OR from_fiat_name LIKE ?
+            OR asset_symbol LIKE ?)
             AND (ramp_date >= ? OR ? IS NULL)
             AND (ramp_date <= ? OR ? IS NULL)
         "#,
@@ -204,6 +529,7 @@ impl FiatRampService {
         .bind(format!("%{}%", query_filter))
         .bind(format!("%{}%", query_filter))
         .bind(format!("%{}%", query_filter))
+        .bind(format!("%{}%", query_filter))
         .bind(start_date)
         .bind(start_date)
         .bind(end_date)
@@ -221,8 +547,12 @@ impl FiatRampService {
                 // Whitelist allowed columns to prevent SQL injection
                 let valid_column = match col.as_str() {
                     "ramp_date" => "ramp_date",
-                    "fiat_amount" => "fiat_amount",
-                    "converted_amount" => "converted_amount",
+                    // amounts are stored as text, they are ordered by their value
+                    "fiat_amount" => "CAST(fiat_amount AS REAL)",
+                    "converted_amount" => {
+                        "CAST(fiat_amount AS REAL) * asset_rate * to_rate / from_rate"
+                    }
+                    "fee_amount" => "CAST(fee_amount AS REAL)",
                     "via_exchange" => "via_exchange",
                     "kind" => "kind",
                     _ => "ramp_date", // Default fallback
@@ -243,7 +573,8 @@ impl FiatRampService {
                 OR kind LIKE ?
                 OR CAST(fiat_amount AS TEXT) LIKE ?
                 OR from_fiat_symbol LIKE ?
-                OR from_fiat_name LIKE ?)
+                OR from_fiat_name LIKE ?
+                OR asset_symbol LIKE ?)
                 AND (ramp_date >= ? OR ? IS NULL)
                 AND (ramp_date <= ? OR ? IS NULL)
                 {}
@@ -259,6 +590,7 @@ impl FiatRampService {
             .bind(format!("%{}%", query_filter))
             .bind(format!("%{}%", query_filter))
             .bind(format!("%{}%", query_filter))
+            .bind(format!("%{}%", query_filter))
             .bind(start_date)
             .bind(start_date)
             .bind(end_date)
@@ -270,32 +602,71 @@ impl FiatRampService {
             .map_err(|e| format!("failed to select from fiat_ramp_view: {e}"))?;
         Ok(FiatRampPagination {
             total_count,
-            fiat_ramps: result,
+            fiat_ramps: result
+                .into_iter()
+                .map(FiatRampWithConversionView::with_conversion)
+                .collect(),
         })
     }
 
+    /// Get the totals and the series of deposits and withdrawals converted to the default fiat
+    /// - gross amounts include the fees, the net ones are less the fees
+    /// - ramps and fees without a conversion rate yet are left out
     pub async fn get_summary(
         start_date: Option<NaiveDate>,
         end_date: Option<NaiveDate>,
+        interval: SummaryInterval,
         db: &Db,
     ) -> Result<FiatRampSummary, String> {
-        let (total_deposit, total_withdraw): (Option<f64>, Option<f64>) = sqlx::query_as(
-            r#"
-            SELECT
-                SUM(CASE WHEN kind = 'deposit' THEN converted_amount ELSE 0.0 END) as total_deposit,
-                SUM(CASE WHEN kind = 'withdraw' THEN converted_amount ELSE 0.0 END) as total_withdraw
-            FROM fiat_ramp_view
-            WHERE (ramp_date >= ? OR ? IS NULL)
-            AND (ramp_date <= ? OR ? IS NULL)
-            "#,
-        )
-        .bind(start_date)
-        .bind(start_date)
-        .bind(end_date)
-        .bind(end_date)
-        .fetch_one(&db.0)
-        .await
-        .map_err(|e| format!("failed to get summary: {e}"))?;
+        let ramps = Self::get_converted(None, end_date, db).await?;
+        let (opening, ramps): (Vec<_>, Vec<_>) = ramps
+            .into_iter()
+            .partition(|ramp| start_date.is_some_and(|start_date| ramp.ramp_date < start_date));
+        let opening_net_invested: Decimal = opening
+            .iter()
+            .filter_map(FiatRampWithConversionView::signed_converted_amount)
+            .sum();
+
+        // periods with only unconverted ramps are kept, with zero totals
+        let mut buckets: BTreeMap<NaiveDate, (Decimal, Decimal, Decimal)> = BTreeMap::new();
+        let (mut deposit_fee, mut withdraw_fee) = (Decimal::ZERO, Decimal::ZERO);
+        for ramp in &ramps {
+            let (deposit, withdraw, fee) = buckets
+                .entry(interval.period_start(ramp.ramp_date))
+                .or_default();
+            let amount = ramp.converted_amount.unwrap_or_default();
+            let converted_fee = ramp.converted_fee.unwrap_or_default();
+            *fee += converted_fee;
+            match ramp.kind {
+                RampKind::Deposit => {
+                    *deposit += amount;
+                    deposit_fee += converted_fee;
+                }
+                RampKind::Withdraw => {
+                    *withdraw += amount;
+                    withdraw_fee += converted_fee;
+                }
+            }
+        }
+        let total_deposit: Decimal = buckets.values().map(|(deposit, _, _)| *deposit).sum();
+        let total_withdraw: Decimal = buckets.values().map(|(_, withdraw, _)| *withdraw).sum();
+
+        let mut cumulative_net = opening_net_invested;
+        let data = buckets
+            .into_iter()
+            .map(|(period_start, (deposit, withdraw, fee))| {
+                let net = deposit - withdraw;
+                cumulative_net += net;
+                FiatRampSummaryBucket {
+                    period_start,
+                    deposit,
+                    withdraw,
+                    fee,
+                    net,
+                    cumulative_net,
+                }
+            })
+            .collect();
 
         let target_fiat_info = sqlx::query(
             r#"
@@ -318,14 +689,137 @@ impl FiatRampService {
         };
 
         Ok(FiatRampSummary {
-            total_deposit: total_deposit.unwrap_or(0.0),
-            total_withdraw: total_withdraw.unwrap_or(0.0),
+            total_deposit,
+            total_withdraw,
+            deposit_fee,
+            withdraw_fee,
+            total_fee: deposit_fee + withdraw_fee,
+            net_deposit: total_deposit - deposit_fee,
+            net_withdraw: total_withdraw - withdraw_fee,
+            opening_net_invested,
+            net_invested: opening_net_invested + total_deposit - total_withdraw,
             fiat_symbol,
             fiat_name,
-            data: HashMap::new(),
+            interval,
+            data,
         })
     }
 
+    /// Get the deposit, withdraw and net totals per exchange and per source currency
+    pub async fn get_breakdown(
+        start_date: Option<NaiveDate>,
+        end_date: Option<NaiveDate>,
+        db: &Db,
+    ) -> Result<FiatRampBreakdown, String> {
+        let ramps = Self::get_converted(start_date, end_date, db).await?;
+        let mut exchanges: BTreeMap<&str, RampTotals> = BTreeMap::new();
+        let mut currencies: BTreeMap<
+            (RowId, Option<&StringRowId>),
+            (&FiatRampWithConversionView, RampTotals),
+        > = BTreeMap::new();
+        for ramp in &ramps {
+            exchanges.entry(&ramp.via_exchange).or_default().add(ramp);
+            currencies
+                .entry((ramp.from_fiat_id, ramp.asset_id.as_ref()))
+                .or_insert_with(|| (ramp, RampTotals::default()))
+                .1
+                .add(ramp);
+        }
+
+        let mut by_exchange: Vec<FiatRampExchangeTotal> = exchanges
+            .into_iter()
+            .map(|(via_exchange, totals)| FiatRampExchangeTotal {
+                via_exchange: via_exchange.to_string(),
+                deposit: totals.deposit,
+                withdraw: totals.withdraw,
+                net: totals.deposit - totals.withdraw,
+                ramp_count: totals.ramp_count,
+                unconverted_count: totals.unconverted_count,
+            })
+            .collect();
+        by_exchange.sort_by(|a, b| {
+            b.net
+                .cmp(&a.net)
+                .then_with(|| a.via_exchange.cmp(&b.via_exchange))
+        });
+
+        let mut by_currency: Vec<FiatRampCurrencyTotal> = currencies
+            .into_values()
+            .map(|(ramp, totals)| FiatRampCurrencyTotal {
+                fiat_id: ramp.from_fiat_id,
+                asset_id: ramp.asset_id.clone(),
+                fiat_symbol: ramp
+                    .asset_symbol
+                    .clone()
+                    .unwrap_or_else(|| ramp.from_fiat_symbol.clone()),
+                fiat_name: ramp
+                    .asset_name
+                    .clone()
+                    .unwrap_or_else(|| ramp.from_fiat_name.clone()),
+                deposit: totals.deposit,
+                withdraw: totals.withdraw,
+                net: totals.deposit - totals.withdraw,
+                source_deposit: totals.source_deposit,
+                source_withdraw: totals.source_withdraw,
+                ramp_count: totals.ramp_count,
+                unconverted_count: totals.unconverted_count,
+            })
+            .collect();
+        by_currency.sort_by(|a, b| {
+            b.net
+                .cmp(&a.net)
+                .then_with(|| a.fiat_symbol.cmp(&b.fiat_symbol))
+        });
+
+        let (fiat_symbol, fiat_name): (String, String) = sqlx::query_as(
+            r#"
+            SELECT symbol, name
+            FROM fiat
+            JOIN user_settings ON user_settings.default_fiat_id = fiat.id
+            WHERE user_settings.id = 1
+            "#,
+        )
+        .fetch_optional(&db.0)
+        .await
+        .map_err(|e| format!("failed to get target fiat info: {e}"))?
+        .unwrap_or_else(|| ("?".to_string(), "Unknown".to_string()));
+
+        Ok(FiatRampBreakdown {
+            fiat_symbol,
+            fiat_name,
+            by_exchange,
+            by_currency,
+        })
+    }
+
+    /// Get the ramps of a date range converted to the default fiat, oldest first
+    /// - the totals are summed here rather than in sqlite, so that they stay exact
+    async fn get_converted(
+        start_date: Option<NaiveDate>,
+        end_date: Option<NaiveDate>,
+        db: &Db,
+    ) -> Result<Vec<FiatRampWithConversionView>, String> {
+        let ramps = sqlx::query_as::<sqlx::Sqlite, FiatRampWithConversionView>(
+            r#"
+            SELECT * FROM fiat_ramp_view
+            WHERE (ramp_date >= ? OR ? IS NULL)
+            AND (ramp_date <= ? OR ? IS NULL)
+            ORDER BY ramp_date ASC
+            "#,
+        )
+        .bind(start_date)
+        .bind(start_date)
+        .bind(end_date)
+        .bind(end_date)
+        .fetch_all(&db.0)
+        .await
+        .map_err(|e| format!("failed to select from fiat_ramp_view: {e}"))?;
+        Ok(ramps
+            .into_iter()
+            .map(FiatRampWithConversionView::with_conversion)
+            .collect())
+    }
+
     /// Get the min and max date of all fiat ramps
     pub async fn get_date_range(db: &Db) -> Result<(Option<NaiveDate>, Option<NaiveDate>), String> {
         let row = sqlx::query(
@@ -346,39 +840,87 @@ impl FiatRampService {
     }
 
     /// Update the fiat ramp
+    /// - a ramp updated without `asset_id` is a ramp in `fiat_id`
+    /// - the fiat, amount, date, kind and exchange left as None are kept, the fee is checked
+    ///   against the merged ramp
+    /// - a manual rate set for the previous currency is deleted, the ramp is queued again when
+    ///   left without a rate
     /// - returns the number of rows affected
     pub async fn update(update_ramp: UpdateFiatRamp, db: &Db) -> Result<u64, String> {
         let mut tx =
             db.0.begin()
                 .await
                 .map_err(|e| format!("failed to begin transaction: {e}"))?;
+        let rows_affected = Self::update_in(update_ramp, &mut tx).await?;
+        tx.commit()
+            .await
+            .map_err(|e| format!("failed to commit transaction: {e}"))?;
+        Ok(rows_affected)
+    }
+
+    /// `update` on `conn`, e.g. inside the transaction of an import
+    pub(crate) async fn update_in(
+        update_ramp: UpdateFiatRamp,
+        conn: &mut SqliteConnection,
+    ) -> Result<u64, String> {
+        let exchange = match &update_ramp.via_exchange {
+            Some(exchange_ref) => Some(resolve_exchange(exchange_ref, conn).await?),
+            None => None,
+        };
+        let stored: Option<(RowId, SqlDecimal)> =
+            sqlx::query_as("SELECT fiat_id, fiat_amount FROM fiat_ramp WHERE id = ?")
+                .bind(&update_ramp.id)
+                .fetch_optional(&mut *conn)
+                .await
+                .map_err(|e| format!("failed to get fiat ramp: {e}"))?;
+        let Some((stored_fiat_id, stored_amount)) = stored else {
+            return Ok(0);
+        };
+        let fiat_id = match &update_ramp.asset_id {
+            Some(asset_id) => resolve_fiat_id(0, Some(asset_id), conn).await?,
+            None => update_ramp.fiat_id.unwrap_or(stored_fiat_id),
+        };
+        check_fee(
+            fiat_id,
+            update_ramp.fiat_amount.unwrap_or(stored_amount.0),
+            update_ramp.fee_fiat_id,
+            update_ramp.fee_amount,
+        )?;
 
         let result: SqliteQueryResult = sqlx::query(
             r#"
             UPDATE fiat_ramp
             SET
             fiat_id = ?,
-            fiat_amount = ?,
-            ramp_date = ?,
-            via_exchange = ?,
-            kind = ?,
+            fiat_amount = COALESCE(?, fiat_amount),
+            ramp_date = COALESCE(?, ramp_date),
+            via_exchange = COALESCE(?, via_exchange),
+            exchange_id = COALESCE(?, exchange_id),
+            kind = COALESCE(?, kind),
+            asset_id = ?,
+            fee_fiat_id = ?,
+            fee_amount = ?,
+            fingerprint = NULL,
             updated_at = CURRENT_TIMESTAMP
             WHERE id = ?
         "#,
         )
-        .bind(update_ramp.fiat_id)
-        .bind(update_ramp.fiat_amount)
+        .bind(fiat_id)
+        .bind(update_ramp.fiat_amount.map(|amount| amount.to_string()))
         .bind(update_ramp.ramp_date)
-        .bind(update_ramp.via_exchange)
+        .bind(exchange.as_ref().map(|e| e.name.clone()))
+        .bind(exchange.map(|e| e.id))
         .bind(update_ramp.kind)
-        .bind(update_ramp.id)
-        .execute(&mut *tx)
+        .bind(update_ramp.asset_id)
+        .bind(update_ramp.fee_fiat_id)
+        .bind(update_ramp.fee_amount.map(|fee| fee.to_string()))
+        .bind(&update_ramp.id)
+        .execute(&mut *conn)
         .await
         .map_err(|e| format!("failed to update fiat_ramp table: {e}"))?;
-
-        tx.commit()
+        ManualRateService::reset_ramps_in(&[update_ramp.id], conn)
             .await
-            .map_err(|e| format!("failed to commit transaction: {e}"))?;
+            .map_err(|e| format!("failed to reset rate of fiat ramp: {e:#}"))?;
         Ok(result.rows_affected())
     }
 
@@ -403,6 +945,51 @@ impl FiatRampService {
     }
 }
 
+async fn resolve_exchange(
+    exchange_ref: &ExchangeRef,
+    conn: &mut SqliteConnection,
+) -> Result<Exchange, String> {
+    ExchangeService::resolve_in(exchange_ref, conn)
+        .await
+        .map_err(|e| format!("failed to resolve exchange: {e:#}"))
+}
+
+/// Check the fee of a ramp of `fiat_amount` in `fiat_id`
+/// - a fee in the currency of the ramp is part of the amount, it cannot be larger
+fn check_fee(
+    fiat_id: RowId,
+    fiat_amount: Decimal,
+    fee_fiat_id: Option<RowId>,
+    fee_amount: Option<Decimal>,
+) -> Result<(), String> {
+    if fee_amount.is_some_and(|fee| fee < Decimal::ZERO) {
+        return Err("fee amount cannot be negative".to_string());
+    }
+    if fee_fiat_id.is_some() && fee_amount.is_none() {
+        return Err("fee fiat is set without a fee amount".to_string());
+    }
+    let same_fiat = fee_fiat_id.is_none_or(|fee_fiat_id| fee_fiat_id == fiat_id);
+    if same_fiat && fee_amount.is_some_and(|fee| fee > fiat_amount) {
+        return Err("fee amount cannot be larger than the amount".to_string());
+    }
+    Ok(())
+}
+
+/// The fiat of a ramp, the fiat of the peg for a ramp in a stablecoin
+async fn resolve_fiat_id(
+    fiat_id: RowId,
+    asset_id: Option<&str>,
+    conn: &mut SqliteConnection,
+) -> Result<RowId, String> {
+    match asset_id {
+        Some(asset_id) => StablecoinPegService::get_in(asset_id, conn)
+            .await
+            .map(|peg| peg.fiat_id)
+            .map_err(|e| format!("failed to get stablecoin peg: {e:#}")),
+        None => Ok(fiat_id),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::str::FromStr;
@@ -462,10 +1049,14 @@ mod tests {
         let db = init_db().await;
         let create_fiat_ramp = CreateFiatRamp {
             fiat_id: 1,
-            fiat_amount: 100.0,
+            fiat_amount: Decimal::ONE_HUNDRED,
             ramp_date: chrono::NaiveDate::from_ymd_opt(2022, 1, 1).unwrap(),
-            via_exchange: "coinbase".to_string(),
+            via_exchange: ExchangeRef::Name("coinbase".to_string()),
             kind: RampKind::Deposit,
+            external_ref: None,
+            asset_id: None,
+            fee_fiat_id: None,
+            fee_amount: None,
         };
         let result = FiatRampService::create(create_fiat_ramp, &db).await;
         assert!(result.is_ok());
@@ -480,10 +1071,14 @@ mod tests {
             let fiat_id: i64 = rng.random_range(1..=2);
             let create_fiat_ramp = CreateFiatRamp {
                 fiat_id,
-                fiat_amount: 100.0,
+                fiat_amount: Decimal::ONE_HUNDRED,
                 ramp_date: chrono::NaiveDate::from_ymd_opt(2022, 1, 1).unwrap(),
-                via_exchange: "coinbase".to_string(),
+                via_exchange: ExchangeRef::Name("coinbase".to_string()),
                 kind: RampKind::Deposit,
+                external_ref: None,
+                asset_id: None,
+                fee_fiat_id: None,
+                fee_amount: None,
             };
             let _ = FiatRampService::create(create_fiat_ramp, &db).await;
         }
@@ -527,10 +1122,14 @@ mod tests {
         // create a fiat ramp
         let create_fiat_ramp = CreateFiatRamp {
             fiat_id: 1, // Depending on init_db order, this is either MYR or SGD
-            fiat_amount: 100.0,
+            fiat_amount: Decimal::ONE_HUNDRED,
             ramp_date: chrono::NaiveDate::from_ymd_opt(2022, 1, 1).unwrap(),
-            via_exchange: "coinbase".to_string(),
+            via_exchange: ExchangeRef::Name("coinbase".to_string()),
             kind: RampKind::Deposit,
+            external_ref: None,
+            asset_id: None,
+            fee_fiat_id: None,
+            fee_amount: None,
         };
         let _ = FiatRampService::create(create_fiat_ramp, &db).await;
 
@@ -571,10 +1170,14 @@ mod tests {
         let db = init_db().await;
         let create_fiat_ramp = CreateFiatRamp {
             fiat_id: 1,
-            fiat_amount: 100.0,
+            fiat_amount: Decimal::ONE_HUNDRED,
             ramp_date: chrono::NaiveDate::from_ymd_opt(2022, 1, 1).unwrap(),
-            via_exchange: "coinbase".to_string(),
+            via_exchange: ExchangeRef::Name("coinbase".to_string()),
             kind: RampKind::Deposit,
+            external_ref: None,
+            asset_id: None,
+            fee_fiat_id: None,
+            fee_amount: None,
         };
         let id = FiatRampService::create(create_fiat_ramp, &db)
             .await
@@ -585,10 +1188,13 @@ mod tests {
         let update_ramp = UpdateFiatRamp {
             id,
             fiat_id: Some(1),
-            fiat_amount: Some(200.0),
+            fiat_amount: Some(Decimal::new(200, 0)),
             ramp_date: Some(chrono::NaiveDate::from_ymd_opt(2022, 1, 1).unwrap()),
-            via_exchange: Some("coinbase".to_string()),
+            via_exchange: Some(ExchangeRef::Name("coinbase".to_string())),
             kind: Some(RampKind::Deposit),
+            asset_id: None,
+            fee_fiat_id: None,
+            fee_amount: None,
         };
         let result = FiatRampService::update(update_ramp, &db).await;
         assert!(result.unwrap() == 1);
@@ -598,7 +1204,7 @@ mod tests {
         assert!(result.is_ok());
         let result = result.unwrap();
         assert!(result.fiat_ramps.len() == 1);
-        assert!(result.fiat_ramps[0].fiat_amount == 200.0);
+        assert!(result.fiat_ramps[0].fiat_amount == Decimal::new(200, 0));
     }
 
     #[tokio::test]
@@ -606,10 +1212,14 @@ mod tests {
         let db = init_db().await;
         let create_fiat_ramp = CreateFiatRamp {
             fiat_id: 1,
-            fiat_amount: 100.0,
+            fiat_amount: Decimal::ONE_HUNDRED,
             ramp_date: chrono::NaiveDate::from_ymd_opt(2022, 1, 1).unwrap(),
-            via_exchange: "coinbase".to_string(),
+            via_exchange: ExchangeRef::Name("coinbase".to_string()),
             kind: RampKind::Deposit,
+            external_ref: None,
+            asset_id: None,
+            fee_fiat_id: None,
+            fee_amount: None,
         };
         let id = FiatRampService::create(create_fiat_ramp, &db)
             .await
@@ -621,6 +1231,279 @@ mod tests {
         assert!(result.unwrap() == 1);
     }
 
+    #[tokio::test]
+    async fn test_get_summary_series() {
+        let db = init_db().await;
+        let fiat_id = sqlx::query_scalar::<_, i64>("SELECT default_fiat_id FROM user_settings")
+            .fetch_one(&db.0)
+            .await
+            .unwrap();
+        let ramps = [
+            ("2024-01-05", 100, RampKind::Deposit),
+            ("2024-01-20", 50, RampKind::Deposit),
+            ("2024-02-03", 30, RampKind::Withdraw),
+            ("2024-03-01", 10, RampKind::Deposit),
+        ];
+        for (date, amount, kind) in ramps {
+            let create_ramp = CreateFiatRamp {
+                fiat_id,
+                fiat_amount: Decimal::new(amount, 0),
+                ramp_date: NaiveDate::from_str(date).unwrap(),
+                via_exchange: ExchangeRef::Name("test".to_string()),
+                kind,
+                external_ref: None,
+                asset_id: None,
+                fee_fiat_id: None,
+                fee_amount: None,
+            };
+            FiatRampService::create(create_ramp, &db).await.unwrap();
+        }
+
+        let start_date = NaiveDate::from_ymd_opt(2024, 1, 10);
+        let summary = FiatRampService::get_summary(start_date, None, SummaryInterval::Month, &db)
+            .await
+            .unwrap();
+        assert_eq!(summary.opening_net_invested, Decimal::new(100, 0));
+        assert_eq!(summary.net_invested, Decimal::new(130, 0));
+        let cumulative: Vec<(NaiveDate, Decimal)> = summary
+            .data
+            .iter()
+            .map(|bucket| (bucket.period_start, bucket.cumulative_net))
+            .collect();
+        assert_eq!(
+            cumulative,
+            vec![
+                (
+                    NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+                    Decimal::new(150, 0)
+                ),
+                (
+                    NaiveDate::from_ymd_opt(2024, 2, 1).unwrap(),
+                    Decimal::new(120, 0)
+                ),
+                (
+                    NaiveDate::from_ymd_opt(2024, 3, 1).unwrap(),
+                    Decimal::new(130, 0)
+                ),
+            ]
+        );
+        assert_eq!(summary.data[1].withdraw, Decimal::new(30, 0));
+        assert_eq!(summary.data[1].net, Decimal::new(-30, 0));
+
+        // 2024-01-20 is a saturday
+        let summary = FiatRampService::get_summary(start_date, None, SummaryInterval::Week, &db)
+            .await
+            .unwrap();
+        assert_eq!(
+            summary.data[0].period_start,
+            NaiveDate::from_ymd_opt(2024, 1, 15).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fiat_ramp_fees() {
+        let db = init_db().await;
+        let ids: Vec<i64> = sqlx::query_scalar(
+            "INSERT INTO fiat (symbol, name) VALUES ('USD', 'US Dollar'), ('EUR', 'Euro') RETURNING id",
+        )
+        .fetch_all(&db.0)
+        .await
+        .unwrap();
+        let (usd_id, eur_id) = (ids[0], ids[1]);
+        let myr_id: i64 = sqlx::query_scalar("SELECT id FROM fiat WHERE symbol = 'MYR'")
+            .fetch_one(&db.0)
+            .await
+            .unwrap();
+        sqlx::query("INSERT OR REPLACE INTO user_settings (id, locale, default_fiat_id) VALUES (1, 'en', ?)")
+            .bind(myr_id)
+            .execute(&db.0)
+            .await
+            .unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 3, 4).unwrap();
+        for (base_fiat_id, rates) in [
+            (usd_id, r#"{"USD": "1", "EUR": "0.8", "MYR": "4"}"#),
+            (eur_id, r#"{"EUR": "1", "USD": "1.25", "MYR": "5"}"#),
+        ] {
+            sqlx::query(
+                "INSERT INTO fiat_exchange_rate (base_fiat_id, date, rates) VALUES (?, ?, ?)",
+            )
+            .bind(base_fiat_id)
+            .bind(date)
+            .bind(rates)
+            .execute(&db.0)
+            .await
+            .unwrap();
+        }
+
+        let ramp = |fiat_id, amount, kind, fee_fiat_id, fee_amount| CreateFiatRamp {
+            fiat_id,
+            fiat_amount: Decimal::new(amount, 0),
+            ramp_date: date,
+            via_exchange: ExchangeRef::Name("fee_test".to_string()),
+            kind,
+            external_ref: None,
+            asset_id: None,
+            fee_fiat_id,
+            fee_amount,
+        };
+        let negative = ramp(
+            eur_id,
+            100,
+            RampKind::Deposit,
+            None,
+            Some(Decimal::NEGATIVE_ONE),
+        );
+        assert!(FiatRampService::create(negative, &db).await.is_err());
+        let without_amount = ramp(eur_id, 100, RampKind::Deposit, Some(usd_id), None);
+        assert!(FiatRampService::create(without_amount, &db).await.is_err());
+        // a fee in the currency of the ramp is part of its amount
+        let larger_fee = ramp(eur_id, 1, RampKind::Deposit, None, Some(Decimal::TWO));
+        assert!(FiatRampService::create(larger_fee, &db).await.is_err());
+        let larger_fee = ramp(
+            eur_id,
+            1,
+            RampKind::Deposit,
+            Some(eur_id),
+            Some(Decimal::TWO),
+        );
+        assert!(FiatRampService::create_bulk(vec![larger_fee], &db)
+            .await
+            .is_err());
+
+        // a fee in the currency of the ramp is converted at the rate of the ramp
+        let deposit = ramp(eur_id, 100, RampKind::Deposit, None, Some(Decimal::TWO));
+        let deposit_id = FiatRampService::create(deposit, &db).await.unwrap();
+        let larger_fee = UpdateFiatRamp {
+            id: deposit_id.clone(),
+            fiat_id: Some(eur_id),
+            fiat_amount: Some(Decimal::ONE_HUNDRED),
+            ramp_date: Some(date),
+            kind: Some(RampKind::Deposit),
+            via_exchange: None,
+            asset_id: None,
+            fee_fiat_id: None,
+            fee_amount: Some(Decimal::new(101, 0)),
+        };
+        assert!(FiatRampService::update(larger_fee, &db).await.is_err());
+        // the stored amount and fiat are used when only the fee is updated
+        let larger_fee = UpdateFiatRamp {
+            id: deposit_id.clone(),
+            fiat_id: None,
+            fiat_amount: None,
+            ramp_date: None,
+            kind: None,
+            via_exchange: None,
+            asset_id: None,
+            fee_fiat_id: None,
+            fee_amount: Some(Decimal::new(101, 0)),
+        };
+        let error = FiatRampService::update(larger_fee, &db).await.unwrap_err();
+        assert_eq!(error, "fee amount cannot be larger than the amount");
+        // a fee in another currency through the rates of its own currency
+        let withdraw = ramp(
+            myr_id,
+            50,
+            RampKind::Withdraw,
+            Some(usd_id),
+            Some(Decimal::ONE),
+        );
+        FiatRampService::create(withdraw, &db).await.unwrap();
+
+        let ramps = FiatRampService::get(10, 0, None, None, None, None, &db)
+            .await
+            .unwrap()
+            .fiat_ramps;
+        let deposit = ramps
+            .iter()
+            .find(|ramp| ramp.fiat_ramp_id == deposit_id)
+            .unwrap();
+        assert_eq!(deposit.converted_amount, Some(Decimal::new(500, 0)));
+        assert_eq!(deposit.converted_fee, Some(Decimal::new(10, 0)));
+        let withdraw = ramps
+            .iter()
+            .find(|ramp| ramp.fiat_ramp_id != deposit_id)
+            .unwrap();
+        assert_eq!(withdraw.fee_fiat_symbol.as_deref(), Some("USD"));
+        assert_eq!(withdraw.converted_amount, Some(Decimal::new(50, 0)));
+        assert_eq!(withdraw.converted_fee, Some(Decimal::new(4, 0)));
+
+        let summary = FiatRampService::get_summary(None, None, SummaryInterval::Month, &db)
+            .await
+            .unwrap();
+        assert_eq!(summary.total_deposit, Decimal::new(500, 0));
+        assert_eq!(summary.deposit_fee, Decimal::new(10, 0));
+        assert_eq!(summary.net_deposit, Decimal::new(490, 0));
+        assert_eq!(summary.total_withdraw, Decimal::new(50, 0));
+        assert_eq!(summary.withdraw_fee, Decimal::new(4, 0));
+        assert_eq!(summary.net_withdraw, Decimal::new(46, 0));
+        assert_eq!(summary.total_fee, Decimal::new(14, 0));
+        assert_eq!(summary.data[0].fee, Decimal::new(14, 0));
+        assert_eq!(summary.net_invested, Decimal::new(450, 0));
+    }
+
+    #[tokio::test]
+    async fn test_get_breakdown() {
+        let db = init_db().await;
+        let (myr_id, sgd_id): (i64, i64) = sqlx::query_as(
+            "SELECT (SELECT id FROM fiat WHERE symbol = 'MYR'), (SELECT id FROM fiat WHERE symbol = 'SGD')",
+        )
+        .fetch_one(&db.0)
+        .await
+        .unwrap();
+        sqlx::query("UPDATE user_settings SET default_fiat_id = ? WHERE id = 1")
+            .bind(myr_id)
+            .execute(&db.0)
+            .await
+            .unwrap();
+
+        let date = NaiveDate::from_ymd_opt(2024, 5, 2).unwrap();
+        let ramps = [
+            (myr_id, 1000, "Binance", RampKind::Deposit),
+            (myr_id, 200, "Binance", RampKind::Withdraw),
+            (myr_id, 300, "Luno", RampKind::Deposit),
+            // no SGD rate on that date
+            (sgd_id, 100, "Luno", RampKind::Deposit),
+        ];
+        for (fiat_id, amount, exchange, kind) in ramps {
+            let create_ramp = CreateFiatRamp {
+                fiat_id,
+                fiat_amount: Decimal::new(amount, 0),
+                ramp_date: date,
+                via_exchange: ExchangeRef::Name(exchange.to_string()),
+                kind,
+                external_ref: None,
+                asset_id: None,
+                fee_fiat_id: None,
+                fee_amount: None,
+            };
+            FiatRampService::create(create_ramp, &db).await.unwrap();
+        }
+
+        let breakdown = FiatRampService::get_breakdown(None, None, &db)
+            .await
+            .unwrap();
+        assert_eq!(breakdown.fiat_symbol, "MYR");
+
+        assert_eq!(breakdown.by_exchange.len(), 2);
+        let binance = &breakdown.by_exchange[0];
+        assert_eq!(binance.via_exchange, "Binance");
+        assert_eq!(binance.deposit, Decimal::new(1000, 0));
+        assert_eq!(binance.withdraw, Decimal::new(200, 0));
+        assert_eq!(binance.net, Decimal::new(800, 0));
+        let luno = &breakdown.by_exchange[1];
+        assert_eq!(luno.net, Decimal::new(300, 0));
+        assert_eq!(luno.ramp_count, 2);
+        assert_eq!(luno.unconverted_count, 1);
+
+        assert_eq!(breakdown.by_currency.len(), 2);
+        let myr = &breakdown.by_currency[0];
+        assert_eq!(myr.fiat_symbol, "MYR");
+        assert_eq!(myr.net, Decimal::new(1100, 0));
+        let sgd = &breakdown.by_currency[1];
+        assert_eq!(sgd.source_deposit, Decimal::ONE_HUNDRED);
+        assert_eq!(sgd.deposit, Decimal::ZERO);
+    }
+
     #[tokio::test]
     async fn test_fiat_ramp_view() {
         let db = init_db().await;
@@ -668,10 +1551,14 @@ mod tests {
         // Create Ramp in EUR
         let create_ramp = CreateFiatRamp {
             fiat_id: eur_id,
-            fiat_amount: 100.0,
+            fiat_amount: Decimal::ONE_HUNDRED,
             ramp_date: date,
-            via_exchange: "test".to_string(),
+            via_exchange: ExchangeRef::Name("test".to_string()),
             kind: RampKind::Deposit,
+            external_ref: None,
+            asset_id: None,
+            fee_fiat_id: None,
+            fee_amount: None,
         };
         FiatRampService::create(create_ramp, &db).await.unwrap();
 
@@ -697,19 +1584,25 @@ mod tests {
         )
         .fetch_one(&db.0)
         .await
-        .unwrap();
+        .unwrap()
+        .with_conversion();
 
         assert_eq!(view_result.to_fiat_symbol, "USD");
+        assert_eq!(view_result.converted_amount, Some(Decimal::new(105, 0)));
 
         // 5. Test Same Currency Conversion (Identity Case)
         // Create Ramp in USD (Default is USD)
         // No rate entry needed for this date/pair ideally if our view logic is correct.
         let create_ramp_usd = CreateFiatRamp {
             fiat_id: usd_id,
-            fiat_amount: 50.0,
+            fiat_amount: Decimal::new(50, 0),
             ramp_date: date,
-            via_exchange: "withdraw_test".to_string(),
+            via_exchange: ExchangeRef::Name("withdraw_test".to_string()),
             kind: RampKind::Withdraw,
+            external_ref: None,
+            asset_id: None,
+            fee_fiat_id: None,
+            fee_amount: None,
         };
         FiatRampService::create(create_ramp_usd, &db).await.unwrap();
 
@@ -718,14 +1611,15 @@ mod tests {
         )
         .fetch_one(&db.0)
         .await
-        .unwrap();
+        .unwrap()
+        .with_conversion();
 
         assert_eq!(view_result_usd.to_fiat_symbol, "USD");
         assert_eq!(view_result_usd.from_fiat_symbol, "USD");
-        // Conversion rate should be 1.0
-        assert_eq!(view_result_usd.conversion_rate, Some(1.0));
+        // Conversion rate should be 1
+        assert_eq!(view_result_usd.conversion_rate, Some(Decimal::ONE));
         // Amount should be same
-        assert_eq!(view_result_usd.converted_amount, Some(50.0));
+        assert_eq!(view_result_usd.converted_amount, Some(Decimal::new(50, 0)));
     }
 
     #[tokio::test]
@@ -754,10 +1648,14 @@ mod tests {
         // 2. Create Ramp
         let create_ramp = CreateFiatRamp {
             fiat_id: eur_id,
-            fiat_amount: 100.0,
+            fiat_amount: Decimal::ONE_HUNDRED,
             ramp_date: date,
-            via_exchange: "est_test".to_string(),
+            via_exchange: ExchangeRef::Name("est_test".to_string()),
             kind: RampKind::Deposit,
+            external_ref: None,
+            asset_id: None,
+            fee_fiat_id: None,
+            fee_amount: None,
         };
         FiatRampService::create(create_ramp, &db).await.unwrap();
 
@@ -778,6 +1676,102 @@ mod tests {
         .await
         .unwrap();
 
-        assert_eq!(view_result.is_estimated, true);
+        assert!(view_result.is_estimated);
+    }
+
+    #[tokio::test]
+    async fn test_fiat_ramp_view_pair_rates() {
+        let db = init_db().await;
+        let ids: Vec<i64> = sqlx::query_scalar(
+            "INSERT INTO fiat (symbol, name) VALUES ('USD', 'US Dollar'), ('EUR', 'Euro') RETURNING id",
+        )
+        .fetch_all(&db.0)
+        .await
+        .unwrap();
+        let (usd_id, eur_id) = (ids[0], ids[1]);
+        let myr_id: i64 = sqlx::query_scalar("SELECT id FROM fiat WHERE symbol = 'MYR'")
+            .fetch_one(&db.0)
+            .await
+            .unwrap();
+        sqlx::query("INSERT OR REPLACE INTO user_settings (id, locale, default_fiat_id) VALUES (1, 'en', ?)")
+            .bind(myr_id)
+            .execute(&db.0)
+            .await
+            .unwrap();
+
+        let date = chrono::NaiveDate::from_ymd_opt(2023, 10, 25).unwrap();
+        FiatRampService::create(
+            CreateFiatRamp {
+                fiat_id: eur_id,
+                fiat_amount: Decimal::ONE_HUNDRED,
+                ramp_date: date,
+                via_exchange: ExchangeRef::Name("pair_test".to_string()),
+                kind: RampKind::Deposit,
+                external_ref: None,
+                asset_id: None,
+                fee_fiat_id: None,
+                fee_amount: None,
+            },
+            &db,
+        )
+        .await
+        .unwrap();
+
+        let insert_rate = |base_fiat_id: i64, rates: serde_json::Value, is_estimated: bool| {
+            let db = &db;
+            async move {
+                sqlx::query("INSERT INTO fiat_exchange_rate (base_fiat_id, date, rates, is_estimated) VALUES (?, ?, ?, ?)")
+                    .bind(base_fiat_id)
+                    .bind(date)
+                    .bind(rates.to_string())
+                    .bind(is_estimated)
+                    .execute(&db.0)
+                    .await
+                    .unwrap();
+            }
+        };
+        let view = || async {
+            sqlx::query_as::<sqlx::Sqlite, FiatRampWithConversionView>(
+                "SELECT * FROM fiat_ramp_view WHERE via_exchange = 'pair_test'",
+            )
+            .fetch_all(&db.0)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(FiatRampWithConversionView::with_conversion)
+            .collect::<Vec<_>>()
+        };
+
+        // not crossed through USD when it is the only rate of the date
+        insert_rate(
+            usd_id,
+            serde_json::json!({ "USD": 1.0, "EUR": 0.8, "MYR": 4.0 }),
+            true,
+        )
+        .await;
+        let rows = view().await;
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].conversion_rate, None);
+        assert_eq!(rows[0].rate_source, None);
+        assert!(!rows[0].is_estimated);
+
+        // the inverse of a rate based on the default currency
+        insert_rate(
+            myr_id,
+            serde_json::json!({ "MYR": 1.0, "EUR": 0.25 }),
+            false,
+        )
+        .await;
+        let rows = view().await;
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].conversion_rate, Some(Decimal::new(4, 0)));
+        assert!(!rows[0].is_estimated);
+
+        // a rate based on the ramp currency comes first
+        insert_rate(eur_id, serde_json::json!({ "EUR": 1.0, "MYR": 4.5 }), false).await;
+        let rows = view().await;
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].conversion_rate, Some(Decimal::new(45, 1)));
+        assert_eq!(rows[0].converted_amount, Some(Decimal::new(450, 0)));
     }
 }
@@ -0,0 +1,676 @@
+use super::schedule::Schedule;
+use super::{check_fee, CreateFiatRamp, FiatRamp, FiatRampService, RampKind};
+use crate::db::{Db, RowId, SqlDecimal, SqlOptionDecimal, StringRowId};
+use crate::exchange::{ExchangeRef, ExchangeService};
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{Duration, NaiveDate};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use sqlx::SqliteConnection;
+use uuid::Uuid;
+
+/// Template of a ramp repeated on a schedule, e.g. a monthly deposit (DCA plan)
+#[derive(Debug, FromRow, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RecurringRamp {
+    pub id: StringRowId,
+    pub name: String,
+    pub fiat_id: RowId,
+    pub fiat_symbol: String,
+    /// stablecoin of the ramps, see `CreateFiatRamp`
+    pub asset_id: Option<StringRowId>,
+    #[sqlx(try_from = "SqlDecimal")]
+    pub fiat_amount: Decimal,
+    pub fee_fiat_id: Option<RowId>,
+    #[sqlx(try_from = "SqlOptionDecimal")]
+    pub fee_amount: Option<Decimal>,
+    /// canonical name of the exchange
+    pub via_exchange: String,
+    pub exchange_id: Option<StringRowId>,
+    pub kind: RampKind,
+    /// RRULE without DTSTART, see `Schedule`
+    pub rrule: String,
+    /// first date the schedule may fall on
+    pub start_date: NaiveDate,
+    /// the occurrences are proposed rather than created
+    pub needs_confirmation: bool,
+    pub is_paused: bool,
+    /// the occurrences up to this date are created or proposed already
+    pub materialized_until: Option<NaiveDate>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CreateRecurringRamp {
+    pub name: String,
+    pub fiat_id: RowId,
+    #[serde(default)]
+    pub asset_id: Option<StringRowId>,
+    pub fiat_amount: Decimal,
+    #[serde(default)]
+    pub fee_fiat_id: Option<RowId>,
+    #[serde(default)]
+    pub fee_amount: Option<Decimal>,
+    /// exchange id or name, see `ExchangeRef`
+    pub via_exchange: ExchangeRef,
+    pub kind: RampKind,
+    pub rrule: String,
+    pub start_date: NaiveDate,
+    #[serde(default)]
+    pub needs_confirmation: bool,
+}
+
+/// Replaces the template, the occurrences created or proposed already are kept
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UpdateRecurringRamp {
+    pub id: StringRowId,
+    pub name: String,
+    pub fiat_id: RowId,
+    #[serde(default)]
+    pub asset_id: Option<StringRowId>,
+    pub fiat_amount: Decimal,
+    #[serde(default)]
+    pub fee_fiat_id: Option<RowId>,
+    #[serde(default)]
+    pub fee_amount: Option<Decimal>,
+    /// exchange id or name, see `ExchangeRef`
+    pub via_exchange: ExchangeRef,
+    pub kind: RampKind,
+    pub rrule: String,
+    pub start_date: NaiveDate,
+    #[serde(default)]
+    pub needs_confirmation: bool,
+}
+
+/// Occurrence of a recurring ramp waiting to be confirmed or dismissed
+#[derive(Debug, FromRow, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RecurringRampProposal {
+    pub id: StringRowId,
+    pub recurring_ramp_id: StringRowId,
+    pub name: String,
+    pub ramp_date: NaiveDate,
+    pub fiat_symbol: String,
+    #[sqlx(try_from = "SqlDecimal")]
+    pub fiat_amount: Decimal,
+    pub kind: RampKind,
+    pub via_exchange: String,
+}
+
+const SELECT_RECURRING_RAMP: &str = r#"
+    SELECT recurring_ramp.*, fiat.symbol as fiat_symbol
+    FROM recurring_ramp
+    JOIN fiat ON fiat.id = recurring_ramp.fiat_id
+"#;
+
+impl RecurringRamp {
+    fn schedule(&self) -> Result<Schedule> {
+        self.rrule
+            .parse()
+            .with_context(|| format!("invalid schedule of recurring ramp {}", self.name))
+    }
+
+    /// Occurrences not created or proposed yet, in order
+    fn pending_occurrences<'a>(
+        &self,
+        schedule: &'a Schedule,
+    ) -> impl Iterator<Item = NaiveDate> + 'a {
+        let materialized_until = self.materialized_until;
+        schedule
+            .occurrences(self.start_date)
+            .skip_while(move |date| materialized_until.is_some_and(|until| *date <= until))
+    }
+
+    /// The ramp of an occurrence
+    fn ramp_on(&self, ramp_date: NaiveDate) -> CreateFiatRamp {
+        CreateFiatRamp {
+            fiat_id: self.fiat_id,
+            fiat_amount: self.fiat_amount,
+            ramp_date,
+            via_exchange: match &self.exchange_id {
+                Some(id) => ExchangeRef::Id { id: id.clone() },
+                None => ExchangeRef::Name(self.via_exchange.clone()),
+            },
+            kind: self.kind,
+            external_ref: None,
+            asset_id: self.asset_id.clone(),
+            fee_fiat_id: self.fee_fiat_id,
+            fee_amount: self.fee_amount,
+        }
+    }
+}
+
+pub struct RecurringRampService {}
+
+impl RecurringRampService {
+    /// Create a recurring ramp, its past occurrences are created on the next materialisation
+    pub async fn create(create: CreateRecurringRamp, db: &Db) -> Result<RecurringRamp> {
+        let rrule = check_template(
+            &create.name,
+            create.fiat_id,
+            create.fiat_amount,
+            create.fee_fiat_id,
+            create.fee_amount,
+            &create.rrule,
+        )?;
+        let exchange = ExchangeService::resolve(&create.via_exchange, db).await?;
+        let id = Uuid::now_v7().to_string();
+        sqlx::query(
+            r#"
+            INSERT INTO recurring_ramp
+            (id, name, fiat_id, asset_id, fiat_amount, fee_fiat_id, fee_amount, via_exchange,
+            exchange_id, kind, rrule, start_date, needs_confirmation)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&id)
+        .bind(create.name.trim())
+        .bind(create.fiat_id)
+        .bind(create.asset_id)
+        .bind(create.fiat_amount.to_string())
+        .bind(create.fee_fiat_id)
+        .bind(create.fee_amount.map(|fee| fee.to_string()))
+        .bind(exchange.name)
+        .bind(exchange.id)
+        .bind(create.kind)
+        .bind(rrule)
+        .bind(create.start_date)
+        .bind(create.needs_confirmation)
+        .execute(&db.0)
+        .await
+        .context("failed to insert into recurring_ramp table")?;
+        Self::get(&id, db).await
+    }
+
+    pub async fn get(id: &str, db: &Db) -> Result<RecurringRamp> {
+        let mut conn =
+            db.0.acquire()
+                .await
+                .context("failed to acquire connection")?;
+        Self::get_in(id, &mut conn).await
+    }
+
+    /// `get` on `conn`, e.g. inside the transaction of a materialisation
+    async fn get_in(id: &str, conn: &mut SqliteConnection) -> Result<RecurringRamp> {
+        sqlx::query_as::<sqlx::Sqlite, RecurringRamp>(&format!(
+            "{SELECT_RECURRING_RAMP} WHERE recurring_ramp.id = ?"
+        ))
+        .bind(id)
+        .fetch_optional(&mut *conn)
+        .await
+        .context("failed to get recurring ramp")?
+        .with_context(|| format!("recurring ramp {id} not found"))
+    }
+
+    /// Get all recurring ramps ordered by name
+    pub async fn get_all(db: &Db) -> Result<Vec<RecurringRamp>> {
+        sqlx::query_as::<sqlx::Sqlite, RecurringRamp>(&format!(
+            "{SELECT_RECURRING_RAMP} ORDER BY recurring_ramp.name, recurring_ramp.id"
+        ))
+        .fetch_all(&db.0)
+        .await
+        .context("failed to get recurring ramps")
+    }
+
+    /// Replace a recurring ramp, the occurrences created or proposed already are left as they are
+    pub async fn update(update: UpdateRecurringRamp, db: &Db) -> Result<RecurringRamp> {
+        let rrule = check_template(
+            &update.name,
+            update.fiat_id,
+            update.fiat_amount,
+            update.fee_fiat_id,
+            update.fee_amount,
+            &update.rrule,
+        )?;
+        let exchange = ExchangeService::resolve(&update.via_exchange, db).await?;
+        let result = sqlx::query(
+            r#"
+            UPDATE recurring_ramp
+            SET
+            name = ?,
+            fiat_id = ?,
+            asset_id = ?,
+            fiat_amount = ?,
+            fee_fiat_id = ?,
+            fee_amount = ?,
+            via_exchange = ?,
+            exchange_id = ?,
+            kind = ?,
+            rrule = ?,
+            start_date = ?,
+            needs_confirmation = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(update.name.trim())
+        .bind(update.fiat_id)
+        .bind(update.asset_id)
+        .bind(update.fiat_amount.to_string())
+        .bind(update.fee_fiat_id)
+        .bind(update.fee_amount.map(|fee| fee.to_string()))
+        .bind(exchange.name)
+        .bind(exchange.id)
+        .bind(update.kind)
+        .bind(rrule)
+        .bind(update.start_date)
+        .bind(update.needs_confirmation)
+        .bind(&update.id)
+        .execute(&db.0)
+        .await
+        .context("failed to update recurring_ramp table")?;
+        if result.rows_affected() == 0 {
+            bail!("recurring ramp {} not found", update.id);
+        }
+        Self::get(&update.id, db).await
+    }
+
+    /// Pause or resume a recurring ramp
+    /// - the occurrences of the pause are skipped, a resumed ramp starts again after `today`
+    pub async fn set_paused(
+        id: &str,
+        is_paused: bool,
+        today: NaiveDate,
+        db: &Db,
+    ) -> Result<RecurringRamp> {
+        let recurring_ramp = Self::get(id, db).await?;
+        let materialized_until = match (recurring_ramp.is_paused, is_paused) {
+            (true, false) => {
+                let yesterday = today - Duration::days(1);
+                recurring_ramp
+                    .materialized_until
+                    .max(Some(yesterday))
+                    .filter(|until| *until >= recurring_ramp.start_date)
+            }
+            _ => recurring_ramp.materialized_until,
+        };
+        sqlx::query("UPDATE recurring_ramp SET is_paused = ?, materialized_until = ? WHERE id = ?")
+            .bind(is_paused)
+            .bind(materialized_until)
+            .bind(id)
+            .execute(&db.0)
+            .await
+            .context("failed to update recurring_ramp table")?;
+        Self::get(id, db).await
+    }
+
+    /// Delete a recurring ramp and its proposals, the ramps it created are kept
+    /// - returns the number of rows affected
+    pub async fn delete(id: &str, db: &Db) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM recurring_ramp WHERE id = ?")
+            .bind(id)
+            .execute(&db.0)
+            .await
+            .context("failed to delete from recurring_ramp table")?;
+        Ok(result.rows_affected())
+    }
+
+    /// Next `count` occurrences of a recurring ramp not created or proposed yet
+    pub async fn preview(id: &str, count: usize, db: &Db) -> Result<Vec<NaiveDate>> {
+        let recurring_ramp = Self::get(id, db).await?;
+        let schedule = recurring_ramp.schedule()?;
+        Ok(recurring_ramp
+            .pending_occurrences(&schedule)
+            .take(count)
+            .collect())
+    }
+
+    /// First `count` occurrences of a rule from a start date, before the ramp is saved
+    pub fn preview_rule(
+        rrule: &str,
+        start_date: NaiveDate,
+        count: usize,
+    ) -> Result<Vec<NaiveDate>> {
+        let schedule: Schedule = rrule.parse()?;
+        Ok(schedule.occurrences(start_date).take(count).collect())
+    }
+
+    /// Create the ramps of the occurrences due up to `today`, or propose them for confirmation
+    /// - a paused recurring ramp is skipped
+    /// - a recurring ramp failing to create its ramps is skipped and tried again next time
+    /// - returns the ramps created, their rates are left to the caller
+    pub async fn materialize(today: NaiveDate, db: &Db) -> Result<Vec<FiatRamp>> {
+        let recurring_ramps = Self::get_all(db).await?;
+        let mut created = Vec::new();
+        for recurring_ramp in recurring_ramps.iter().filter(|ramp| !ramp.is_paused) {
+            match Self::materialize_one(recurring_ramp, today, db).await {
+                Ok(ramps) => created.extend(ramps),
+                Err(e) => eprintln!(
+                    "Failed to materialize recurring ramp {}: {e:#}",
+                    recurring_ramp.name
+                ),
+            }
+        }
+        Ok(created)
+    }
+
+    /// Create or propose the due occurrences of a recurring ramp and move its
+    /// `materialized_until` in one transaction
+    /// - the recurring ramp is read again inside the transaction, a materialisation running at
+    ///   the same time does not create the same occurrences twice
+    async fn materialize_one(
+        recurring_ramp: &RecurringRamp,
+        today: NaiveDate,
+        db: &Db,
+    ) -> Result<Vec<FiatRamp>> {
+        let mut tx =
+            db.0.begin_with("BEGIN IMMEDIATE")
+                .await
+                .context("failed to begin transaction")?;
+        let recurring_ramp = Self::get_in(&recurring_ramp.id, &mut tx).await?;
+        let schedule = recurring_ramp.schedule()?;
+        let due: Vec<NaiveDate> = recurring_ramp
+            .pending_occurrences(&schedule)
+            .take_while(|date| *date <= today)
+            .collect();
+        if recurring_ramp.is_paused || due.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut created = Vec::new();
+        if recurring_ramp.needs_confirmation {
+            for ramp_date in &due {
+                sqlx::query(
+                    r#"
+                    INSERT OR IGNORE INTO recurring_ramp_proposal (id, recurring_ramp_id, ramp_date)
+                    VALUES (?, ?, ?)
+                    "#,
+                )
+                .bind(Uuid::now_v7().to_string())
+                .bind(&recurring_ramp.id)
+                .bind(ramp_date)
+                .execute(&mut *tx)
+                .await
+                .context("failed to insert into recurring_ramp_proposal table")?;
+            }
+        } else {
+            let ramps = due
+                .iter()
+                .map(|ramp_date| recurring_ramp.ramp_on(*ramp_date))
+                .collect();
+            created = FiatRampService::create_bulk_in(ramps, &mut tx)
+                .await
+                .map_err(|e| anyhow!(e))?;
+            link_ramps(&recurring_ramp.id, &created, &mut tx).await?;
+        }
+
+        sqlx::query("UPDATE recurring_ramp SET materialized_until = ? WHERE id = ?")
+            .bind(today)
+            .bind(&recurring_ramp.id)
+            .execute(&mut *tx)
+            .await
+            .context("failed to update recurring_ramp table")?;
+        tx.commit().await.context("failed to commit transaction")?;
+        Ok(created)
+    }
+
+    /// Get the proposals waiting for confirmation, oldest first
+    pub async fn get_proposals(db: &Db) -> Result<Vec<RecurringRampProposal>> {
+        sqlx::query_as::<sqlx::Sqlite, RecurringRampProposal>(
+            r#"
+            SELECT
+                recurring_ramp_proposal.id,
+                recurring_ramp_proposal.recurring_ramp_id,
+                recurring_ramp.name,
+                recurring_ramp_proposal.ramp_date,
+                COALESCE(asset.symbol, fiat.symbol) as fiat_symbol,
+                recurring_ramp.fiat_amount,
+                recurring_ramp.kind,
+                recurring_ramp.via_exchange
+            FROM recurring_ramp_proposal
+            JOIN recurring_ramp ON recurring_ramp.id = recurring_ramp_proposal.recurring_ramp_id
+            JOIN fiat ON fiat.id = recurring_ramp.fiat_id
+            LEFT JOIN asset ON asset.id = recurring_ramp.asset_id
+            ORDER BY recurring_ramp_proposal.ramp_date, recurring_ramp.name
+            "#,
+        )
+        .fetch_all(&db.0)
+        .await
+        .context("failed to get recurring ramp proposals")
+    }
+
+    /// Create the ramp of a proposal from its recurring ramp as it is now
+    /// - the proposal is claimed and its ramp created in one transaction, a proposal confirmed
+    ///   twice creates one ramp and the second confirmation fails
+    pub async fn confirm_proposal(id: &str, db: &Db) -> Result<FiatRamp> {
+        let mut tx =
+            db.0.begin_with("BEGIN IMMEDIATE")
+                .await
+                .context("failed to begin transaction")?;
+        let (recurring_ramp_id, ramp_date): (StringRowId, NaiveDate) = sqlx::query_as(
+            "DELETE FROM recurring_ramp_proposal WHERE id = ? RETURNING recurring_ramp_id, ramp_date",
+        )
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await
+        .context("failed to claim recurring ramp proposal")?
+        .with_context(|| format!("recurring ramp proposal {id} not found"))?;
+        let recurring_ramp = Self::get_in(&recurring_ramp_id, &mut tx).await?;
+
+        let ramps =
+            FiatRampService::create_bulk_in(vec![recurring_ramp.ramp_on(ramp_date)], &mut tx)
+                .await
+                .map_err(|e| anyhow!(e))?;
+        link_ramps(&recurring_ramp.id, &ramps, &mut tx).await?;
+        tx.commit().await.context("failed to commit transaction")?;
+        ramps.into_iter().next().context("no fiat ramp created")
+    }
+
+    /// Drop a proposal without creating its ramp
+    /// - returns the number of rows affected
+    pub async fn dismiss_proposal(id: &str, db: &Db) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM recurring_ramp_proposal WHERE id = ?")
+            .bind(id)
+            .execute(&db.0)
+            .await
+            .context("failed to delete from recurring_ramp_proposal table")?;
+        Ok(result.rows_affected())
+    }
+}
+
+/// Record the recurring ramp the ramps were created from
+/// - fails for a date the recurring ramp has a ramp on already, see migration 37
+async fn link_ramps(
+    recurring_ramp_id: &str,
+    ramps: &[FiatRamp],
+    conn: &mut SqliteConnection,
+) -> Result<()> {
+    for ramp in ramps {
+        sqlx::query("UPDATE fiat_ramp SET recurring_ramp_id = ? WHERE id = ?")
+            .bind(recurring_ramp_id)
+            .bind(&ramp.id)
+            .execute(&mut *conn)
+            .await
+            .with_context(|| {
+                format!(
+                    "failed to link the ramp of {} to its recurring ramp",
+                    ramp.ramp_date
+                )
+            })?;
+    }
+    Ok(())
+}
+
+/// Check the values of a template, returns its rule written the way it is stored
+fn check_template(
+    name: &str,
+    fiat_id: RowId,
+    fiat_amount: Decimal,
+    fee_fiat_id: Option<RowId>,
+    fee_amount: Option<Decimal>,
+    rrule: &str,
+) -> Result<String> {
+    if name.trim().is_empty() {
+        bail!("name must not be empty");
+    }
+    if fiat_amount <= Decimal::ZERO {
+        bail!("amount must be greater than zero");
+    }
+    check_fee(fiat_id, fiat_amount, fee_fiat_id, fee_amount).map_err(|e| anyhow!(e))?;
+    let schedule: Schedule = rrule.parse()?;
+    Ok(schedule.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::from_str(value).unwrap()
+    }
+
+    async fn ramp_dates(db: &Db) -> Vec<NaiveDate> {
+        sqlx::query_scalar("SELECT ramp_date FROM fiat_ramp ORDER BY ramp_date")
+            .fetch_all(&db.0)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_recurring_ramp() {
+        let db = Db::in_memory().await.unwrap();
+        let eur_id: RowId = sqlx::query_scalar(
+            "INSERT INTO fiat (symbol, name) VALUES ('EUR', 'Euro') RETURNING id",
+        )
+        .fetch_one(&db.0)
+        .await
+        .unwrap();
+        let create = CreateRecurringRamp {
+            name: "Monthly deposit".to_string(),
+            fiat_id: eur_id,
+            asset_id: None,
+            fiat_amount: Decimal::ONE_HUNDRED,
+            fee_fiat_id: None,
+            fee_amount: Some(Decimal::ONE),
+            via_exchange: ExchangeRef::Name("Kraken".to_string()),
+            kind: RampKind::Deposit,
+            rrule: "freq=monthly;bymonthday=31".to_string(),
+            start_date: date("2024-01-15"),
+            needs_confirmation: false,
+        };
+        let invalid = CreateRecurringRamp {
+            rrule: "FREQ=HOURLY".to_string(),
+            ..create.clone()
+        };
+        assert!(RecurringRampService::create(invalid, &db).await.is_err());
+        let recurring_ramp = RecurringRampService::create(create.clone(), &db)
+            .await
+            .unwrap();
+        assert_eq!(recurring_ramp.rrule, "FREQ=MONTHLY;BYMONTHDAY=31");
+        assert_eq!(recurring_ramp.via_exchange, "Kraken");
+
+        assert_eq!(
+            RecurringRampService::preview(&recurring_ramp.id, 2, &db)
+                .await
+                .unwrap(),
+            [date("2024-01-31"), date("2024-02-29")]
+        );
+
+        // the due occurrences are created once
+        let created = RecurringRampService::materialize(date("2024-03-15"), &db)
+            .await
+            .unwrap();
+        assert_eq!(created.len(), 2);
+        assert_eq!(created[0].fee_amount, Some(Decimal::ONE));
+        assert!(RecurringRampService::materialize(date("2024-03-15"), &db)
+            .await
+            .unwrap()
+            .is_empty());
+        // a copy read before the ramps were created does not create them again
+        assert!(
+            RecurringRampService::materialize_one(&recurring_ramp, date("2024-03-15"), &db)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        // one ramp per date of a recurring ramp
+        assert!(sqlx::query("INSERT INTO fiat_ramp (id, fiat_id, fiat_amount, ramp_date, via_exchange, kind, recurring_ramp_id) VALUES ('copy', ?, 100, '2024-01-31', 'Kraken', 'deposit', ?)")
+            .bind(eur_id)
+            .bind(&recurring_ramp.id)
+            .execute(&db.0)
+            .await
+            .is_err());
+        assert_eq!(
+            RecurringRampService::preview(&recurring_ramp.id, 1, &db)
+                .await
+                .unwrap(),
+            [date("2024-03-31")]
+        );
+
+        // the occurrences of a pause are skipped
+        RecurringRampService::set_paused(&recurring_ramp.id, true, date("2024-03-20"), &db)
+            .await
+            .unwrap();
+        assert!(RecurringRampService::materialize(date("2024-05-10"), &db)
+            .await
+            .unwrap()
+            .is_empty());
+        let resumed =
+            RecurringRampService::set_paused(&recurring_ramp.id, false, date("2024-05-10"), &db)
+                .await
+                .unwrap();
+        assert_eq!(resumed.materialized_until, Some(date("2024-05-09")));
+        let created = RecurringRampService::materialize(date("2024-06-01"), &db)
+            .await
+            .unwrap();
+        assert_eq!(created.len(), 1);
+        assert_eq!(created[0].ramp_date, date("2024-05-31"));
+
+        // proposed occurrences wait for a confirmation
+        let update = UpdateRecurringRamp {
+            id: recurring_ramp.id.clone(),
+            name: create.name.clone(),
+            fiat_id: eur_id,
+            asset_id: None,
+            fiat_amount: Decimal::new(250, 0),
+            fee_fiat_id: None,
+            fee_amount: None,
+            via_exchange: ExchangeRef::Name("kraken".to_string()),
+            kind: RampKind::Deposit,
+            rrule: create.rrule.clone(),
+            start_date: create.start_date,
+            needs_confirmation: true,
+        };
+        RecurringRampService::update(update, &db).await.unwrap();
+        assert!(RecurringRampService::materialize(date("2024-07-31"), &db)
+            .await
+            .unwrap()
+            .is_empty());
+        let proposals = RecurringRampService::get_proposals(&db).await.unwrap();
+        assert_eq!(proposals.len(), 2);
+        assert_eq!(proposals[0].ramp_date, date("2024-06-30"));
+        assert_eq!(proposals[0].fiat_amount, Decimal::new(250, 0));
+
+        let ramp = RecurringRampService::confirm_proposal(&proposals[0].id, &db)
+            .await
+            .unwrap();
+        assert_eq!(ramp.fiat_amount, Decimal::new(250, 0));
+        assert_eq!(ramp.exchange_id, recurring_ramp.exchange_id);
+        // confirmed once
+        assert!(
+            RecurringRampService::confirm_proposal(&proposals[0].id, &db)
+                .await
+                .is_err()
+        );
+        RecurringRampService::dismiss_proposal(&proposals[1].id, &db)
+            .await
+            .unwrap();
+        assert!(RecurringRampService::get_proposals(&db)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            ramp_dates(&db).await,
+            [
+                date("2024-01-31"),
+                date("2024-02-29"),
+                date("2024-05-31"),
+                date("2024-06-30")
+            ]
+        );
+
+        // the ramps outlive their recurring ramp
+        RecurringRampService::delete(&recurring_ramp.id, &db)
+            .await
+            .unwrap();
+        assert_eq!(ramp_dates(&db).await.len(), 4);
+    }
+}
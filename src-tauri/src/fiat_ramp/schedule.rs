@@ -0,0 +1,302 @@
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{Datelike, Duration, Months, NaiveDate, Weekday};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// Schedule of a recurring ramp, a subset of the RRULE of RFC 5545
+/// - e.g. `FREQ=MONTHLY;BYMONTHDAY=1` or `FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,TH;COUNT=10`
+/// - `BYDAY` is only taken with `FREQ=WEEKLY` and `BYMONTHDAY` only with `FREQ=MONTHLY`
+/// - a month day past the end of a month falls on its last day, where RFC 5545 skips the month,
+///   so that a plan on the 31st still deposits every month
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schedule {
+    pub frequency: Frequency,
+    /// every `interval` days, weeks, months or years
+    pub interval: u32,
+    /// days of the week, monday first
+    pub by_day: Vec<Weekday>,
+    /// day of the month, negative from the end, e.g. -1 for the last day
+    pub by_month_day: Option<i32>,
+    /// occurrences at most, counted from the start date
+    pub count: Option<u32>,
+    /// last date an occurrence may fall on
+    pub until: Option<NaiveDate>,
+}
+
+const WEEKDAYS: [(&str, Weekday); 7] = [
+    ("MO", Weekday::Mon),
+    ("TU", Weekday::Tue),
+    ("WE", Weekday::Wed),
+    ("TH", Weekday::Thu),
+    ("FR", Weekday::Fri),
+    ("SA", Weekday::Sat),
+    ("SU", Weekday::Sun),
+];
+
+impl FromStr for Schedule {
+    type Err = anyhow::Error;
+
+    fn from_str(rule: &str) -> Result<Self> {
+        let rule = rule.trim().to_uppercase();
+        let rule = rule.strip_prefix("RRULE:").unwrap_or(&rule);
+        let mut frequency = None;
+        let mut schedule = Schedule {
+            frequency: Frequency::Daily,
+            interval: 1,
+            by_day: Vec::new(),
+            by_month_day: None,
+            count: None,
+            until: None,
+        };
+        for part in rule.split(';').filter(|part| !part.is_empty()) {
+            let (name, value) = part
+                .split_once('=')
+                .ok_or_else(|| anyhow!("invalid rule part '{part}'"))?;
+            match name {
+                "FREQ" => {
+                    frequency = Some(match value {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        _ => bail!("unsupported frequency '{value}'"),
+                    })
+                }
+                "INTERVAL" => {
+                    schedule.interval = value
+                        .parse()
+                        .ok()
+                        .filter(|interval| *interval > 0)
+                        .ok_or_else(|| anyhow!("invalid interval '{value}'"))?
+                }
+                "BYDAY" => {
+                    for day in value.split(',') {
+                        let weekday = WEEKDAYS
+                            .iter()
+                            .find(|(code, _)| *code == day)
+                            .map(|(_, weekday)| *weekday)
+                            .ok_or_else(|| anyhow!("invalid week day '{day}'"))?;
+                        if !schedule.by_day.contains(&weekday) {
+                            schedule.by_day.push(weekday);
+                        }
+                    }
+                    schedule.by_day.sort_by_key(Weekday::num_days_from_monday);
+                }
+                "BYMONTHDAY" => {
+                    schedule.by_month_day = Some(
+                        value
+                            .parse()
+                            .ok()
+                            .filter(|day: &i32| *day != 0 && day.abs() <= 31)
+                            .ok_or_else(|| anyhow!("invalid month day '{value}'"))?,
+                    )
+                }
+                "COUNT" => {
+                    schedule.count = Some(
+                        value
+                            .parse()
+                            .ok()
+                            .filter(|count| *count > 0)
+                            .ok_or_else(|| anyhow!("invalid count '{value}'"))?,
+                    )
+                }
+                "UNTIL" => {
+                    // a date-time is cut to its date
+                    let date = value.get(..8).unwrap_or(value);
+                    schedule.until = Some(
+                        NaiveDate::parse_from_str(date, "%Y%m%d")
+                            .with_context(|| format!("invalid until date '{value}'"))?,
+                    )
+                }
+                _ => bail!("unsupported rule part '{name}'"),
+            }
+        }
+        schedule.frequency = frequency.context("the rule has no FREQ")?;
+        if !schedule.by_day.is_empty() && schedule.frequency != Frequency::Weekly {
+            bail!("BYDAY is only supported with FREQ=WEEKLY");
+        }
+        if schedule.by_month_day.is_some() && schedule.frequency != Frequency::Monthly {
+            bail!("BYMONTHDAY is only supported with FREQ=MONTHLY");
+        }
+        if schedule.count.is_some() && schedule.until.is_some() {
+            bail!("COUNT and UNTIL cannot both be set");
+        }
+        Ok(schedule)
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let frequency = match self.frequency {
+            Frequency::Daily => "DAILY",
+            Frequency::Weekly => "WEEKLY",
+            Frequency::Monthly => "MONTHLY",
+            Frequency::Yearly => "YEARLY",
+        };
+        write!(f, "FREQ={frequency}")?;
+        if self.interval != 1 {
+            write!(f, ";INTERVAL={}", self.interval)?;
+        }
+        if !self.by_day.is_empty() {
+            let days: Vec<&str> = self
+                .by_day
+                .iter()
+                .filter_map(|weekday| {
+                    WEEKDAYS
+                        .iter()
+                        .find(|(_, day)| day == weekday)
+                        .map(|(code, _)| *code)
+                })
+                .collect();
+            write!(f, ";BYDAY={}", days.join(","))?;
+        }
+        if let Some(day) = self.by_month_day {
+            write!(f, ";BYMONTHDAY={day}")?;
+        }
+        if let Some(count) = self.count {
+            write!(f, ";COUNT={count}")?;
+        }
+        if let Some(until) = self.until {
+            write!(f, ";UNTIL={}", until.format("%Y%m%d"))?;
+        }
+        Ok(())
+    }
+}
+
+impl Schedule {
+    /// Dates of the schedule from `start`, in order
+    /// - without `COUNT` or `UNTIL` the dates never end, the caller bounds them
+    pub fn occurrences(&self, start: NaiveDate) -> impl Iterator<Item = NaiveDate> + '_ {
+        let until = self.until;
+        let dates = (0u32..)
+            .map_while(move |period| self.period_dates(start, period))
+            .flatten()
+            .filter(move |date| *date >= start)
+            .take_while(move |date| until.is_none_or(|until| *date <= until));
+        dates.take(self.count.map_or(usize::MAX, |count| count as usize))
+    }
+
+    /// Dates of the nth period from `start`, None past the dates chrono can hold
+    fn period_dates(&self, start: NaiveDate, period: u32) -> Option<Vec<NaiveDate>> {
+        let step = period.checked_mul(self.interval)?;
+        match self.frequency {
+            Frequency::Daily => Some(vec![start.checked_add_signed(Duration::days(step.into()))?]),
+            Frequency::Weekly => {
+                let date = start.checked_add_signed(Duration::weeks(step.into()))?;
+                if self.by_day.is_empty() {
+                    return Some(vec![date]);
+                }
+                let monday = date.week(Weekday::Mon).first_day();
+                Some(
+                    self.by_day
+                        .iter()
+                        .filter_map(|weekday| {
+                            monday.checked_add_signed(Duration::days(
+                                weekday.num_days_from_monday().into(),
+                            ))
+                        })
+                        .collect(),
+                )
+            }
+            Frequency::Monthly => {
+                let month = start.with_day(1)?.checked_add_months(Months::new(step))?;
+                let day = self.by_month_day.unwrap_or(start.day() as i32);
+                Some(vec![month_day(month, day)?])
+            }
+            Frequency::Yearly => {
+                let year = start
+                    .with_day(1)?
+                    .checked_add_months(Months::new(step.checked_mul(12)?))?;
+                Some(vec![month_day(year, start.day() as i32)?])
+            }
+        }
+    }
+}
+
+/// Day of the month of `first`, negative from the end, clamped to the days of the month
+fn month_day(first: NaiveDate, day: i32) -> Option<NaiveDate> {
+    let last = first.checked_add_months(Months::new(1))?.pred_opt()?.day() as i32;
+    let day = if day < 0 { last + 1 + day } else { day };
+    first.with_day(day.clamp(1, last) as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::from_str(value).unwrap()
+    }
+
+    fn dates(rule: &str, start: &str, count: usize) -> Vec<String> {
+        let schedule: Schedule = rule.parse().unwrap();
+        schedule
+            .occurrences(date(start))
+            .take(count)
+            .map(|date| date.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_schedule() {
+        let schedule: Schedule = "rrule:freq=weekly;interval=2;byday=th,mo,mo"
+            .parse()
+            .unwrap();
+        assert_eq!(schedule.frequency, Frequency::Weekly);
+        assert_eq!(schedule.by_day, vec![Weekday::Mon, Weekday::Thu]);
+        assert_eq!(schedule.to_string(), "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,TH");
+        let schedule: Schedule = "FREQ=MONTHLY;UNTIL=20241231T000000Z".parse().unwrap();
+        assert_eq!(schedule.until, Some(date("2024-12-31")));
+
+        assert!("INTERVAL=2".parse::<Schedule>().is_err());
+        assert!("FREQ=HOURLY".parse::<Schedule>().is_err());
+        assert!("FREQ=DAILY;INTERVAL=0".parse::<Schedule>().is_err());
+        assert!("FREQ=MONTHLY;BYDAY=MO".parse::<Schedule>().is_err());
+        assert!("FREQ=MONTHLY;BYMONTHDAY=32".parse::<Schedule>().is_err());
+        assert!("FREQ=DAILY;COUNT=2;UNTIL=20240101"
+            .parse::<Schedule>()
+            .is_err());
+        assert!("FREQ=DAILY;BYHOUR=9".parse::<Schedule>().is_err());
+    }
+
+    #[test]
+    fn test_occurrences() {
+        assert_eq!(
+            dates("FREQ=DAILY;INTERVAL=3", "2024-02-27", 3),
+            ["2024-02-27", "2024-03-01", "2024-03-04"]
+        );
+        // 2024-01-03 is a wednesday, the monday of its week is before the start
+        assert_eq!(
+            dates("FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE", "2024-01-03", 3),
+            ["2024-01-03", "2024-01-15", "2024-01-17"]
+        );
+        assert_eq!(
+            dates("FREQ=MONTHLY", "2024-01-31", 3),
+            ["2024-01-31", "2024-02-29", "2024-03-31"]
+        );
+        assert_eq!(
+            dates("FREQ=MONTHLY;BYMONTHDAY=-1", "2024-01-15", 2),
+            ["2024-01-31", "2024-02-29"]
+        );
+        assert_eq!(
+            dates("FREQ=YEARLY", "2024-02-29", 2),
+            ["2024-02-29", "2025-02-28"]
+        );
+        assert_eq!(
+            dates("FREQ=MONTHLY;BYMONTHDAY=1;COUNT=2", "2024-01-01", 5),
+            ["2024-01-01", "2024-02-01"]
+        );
+        assert_eq!(
+            dates("FREQ=WEEKLY;UNTIL=20240115", "2024-01-01", 5),
+            ["2024-01-01", "2024-01-08", "2024-01-15"]
+        );
+    }
+}
@@ -0,0 +1,363 @@
+use super::{process_and_insert_rate, remove_from_missing_queue_by_rate};
+use crate::db::Db;
+use crate::fiat_exchanger::{FiatExchanger, Rates};
+use anyhow::{Context, Result};
+use chrono::{Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Days requested before the first date, so that a date after a long holiday still finds the
+/// last working day before it
+const LOOKBACK_DAYS: i64 = 7;
+
+/// Days a range request spans at most, lookback included, dates further apart are requested in
+/// several ranges
+const MAX_RANGE_DAYS: i64 = 366;
+
+#[derive(Debug, Serialize, Deserialize, Default, PartialEq)]
+pub struct BackfillReport {
+    /// Ramp dates with a rate, exact or of the last working day
+    pub filled: usize,
+    /// Ramp dates left to the per date fetch, see `process_missing_rates`
+    pub unresolved: usize,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct MissingRampDate {
+    base_fiat_id: i64,
+    base_symbol: String,
+    quote_fiat_id: i64,
+    quote_symbol: String,
+    date: NaiveDate,
+}
+
+/// Fetch the rates of every ramp date without one, with one range request per ramp currency
+/// - the currencies the fees were paid in count as ramp currencies
+/// - each date gets the rates of its last working day, marked as a weekend or holiday the same way
+///   as `get_rate`
+/// - rates estimated from the day before are refetched, the day may have been published since
+/// - a date whose rates lack the default currency is recorded in `fiat_rate_attempt` and left to
+///   the per date fetch, it is not requested again
+/// - the dates of a currency are requested in ranges of `MAX_RANGE_DAYS` at most
+pub async fn backfill_rates<A: FiatExchanger>(db: &Db, exchange_api: &A) -> Result<BackfillReport> {
+    let missing = sqlx::query_as::<sqlx::Sqlite, MissingRampDate>(
+        r#"
+        WITH ramp_fiat AS (
+            SELECT fiat_id, ramp_date FROM fiat_ramp
+            UNION
+            SELECT fee_fiat_id, ramp_date FROM fiat_ramp WHERE fee_fiat_id IS NOT NULL
+        )
+        SELECT DISTINCT
+        fiat.id as base_fiat_id,
+        fiat.symbol as base_symbol,
+        default_fiat.id as quote_fiat_id,
+        default_fiat.symbol as quote_symbol,
+        ramp_fiat.ramp_date as date
+        FROM ramp_fiat
+        JOIN fiat ON fiat.id = ramp_fiat.fiat_id
+        JOIN user_settings ON user_settings.id = 1
+        JOIN fiat AS default_fiat ON default_fiat.id = user_settings.default_fiat_id
+        WHERE ramp_fiat.fiat_id != user_settings.default_fiat_id
+        AND NOT EXISTS (
+            SELECT 1 FROM fiat_rate_attempt
+            WHERE fiat_rate_attempt.base_fiat_id = ramp_fiat.fiat_id
+            AND fiat_rate_attempt.date = ramp_fiat.ramp_date
+            AND fiat_rate_attempt.quote_fiat_id = default_fiat.id
+        )
+        AND NOT EXISTS (
+            SELECT 1 FROM fiat_exchange_rate
+            WHERE fiat_exchange_rate.date = ramp_fiat.ramp_date
+            AND (
+                (
+                    fiat_exchange_rate.base_fiat_id = ramp_fiat.fiat_id
+                    AND json_extract(fiat_exchange_rate.rates, '$.' || default_fiat.symbol) IS NOT NULL
+                )
+                OR (
+                    fiat_exchange_rate.base_fiat_id = default_fiat.id
+                    AND json_extract(fiat_exchange_rate.rates, '$.' || fiat.symbol) IS NOT NULL
+                )
+            )
+            AND (
+                COALESCE(fiat_exchange_rate.is_estimated, 0) = 0
+                OR COALESCE(fiat_exchange_rate.is_non_working_day, 0) = 1
+            )
+        )
+        ORDER BY fiat.id, ramp_fiat.ramp_date
+        "#,
+    )
+    .fetch_all(&db.0)
+    .await
+    .context("failed to get ramp dates without rates")?;
+
+    let mut by_base: BTreeMap<(i64, String), Vec<MissingRampDate>> = BTreeMap::new();
+    for item in missing {
+        by_base
+            .entry((item.base_fiat_id, item.base_symbol.clone()))
+            .or_default()
+            .push(item);
+    }
+
+    let mut report = BackfillReport::default();
+    for ((base_fiat_id, base_symbol), items) in by_base {
+        // dates are sorted by the query
+        for chunk in ranges(&items) {
+            let start = chunk[0].date - Duration::days(LOOKBACK_DAYS);
+            let end = chunk[chunk.len() - 1].date;
+            let series = match exchange_api.get_range_rates(&base_symbol, start, end).await {
+                Ok(series) => series,
+                Err(e) => {
+                    eprintln!("Failed to fetch {base_symbol} rates from {start} to {end}: {e:#}");
+                    report.unresolved += chunk.len();
+                    continue;
+                }
+            };
+
+            for item in chunk {
+                let date = item.date;
+                // the last working day on or before the date
+                let Some((rate_date, rates)) = series.rates.range(..=date).next_back() else {
+                    record_attempt(db, item).await?;
+                    report.unresolved += 1;
+                    continue;
+                };
+                let has_quote = rates.contains_key(&item.quote_symbol);
+                let api_rates = Rates {
+                    base: base_symbol.clone(),
+                    date: *rate_date,
+                    rates: rates.clone(),
+                    provider: series.provider.clone(),
+                };
+                let (_, should_retry) =
+                    process_and_insert_rate(db, base_fiat_id, &date, api_rates, &base_symbol)
+                        .await?;
+                if should_retry {
+                    report.unresolved += 1;
+                } else if !has_quote {
+                    record_attempt(db, item).await?;
+                    report.unresolved += 1;
+                } else {
+                    remove_from_missing_queue_by_rate(db, base_fiat_id, &date)
+                        .await
+                        .ok();
+                    report.filled += 1;
+                }
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// Split the sorted dates of a currency into runs requested by one range of `MAX_RANGE_DAYS` at
+/// most
+fn ranges(items: &[MissingRampDate]) -> Vec<&[MissingRampDate]> {
+    let mut ranges = Vec::new();
+    let mut first = 0;
+    for (i, item) in items.iter().enumerate() {
+        let span = (item.date - items[first].date).num_days() + LOOKBACK_DAYS;
+        if span > MAX_RANGE_DAYS {
+            ranges.push(&items[first..i]);
+            first = i;
+        }
+    }
+    if first < items.len() {
+        ranges.push(&items[first..]);
+    }
+    ranges
+}
+
+/// Record that the rates of the date were requested for the default currency
+async fn record_attempt(db: &Db, item: &MissingRampDate) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO fiat_rate_attempt (base_fiat_id, date, quote_fiat_id) VALUES (?, ?, ?)
+        ON CONFLICT (base_fiat_id, date, quote_fiat_id) DO UPDATE SET
+            attempted_at = CURRENT_TIMESTAMP
+        "#,
+    )
+    .bind(item.base_fiat_id)
+    .bind(item.date)
+    .bind(item.quote_fiat_id)
+    .execute(&db.0)
+    .await
+    .context("failed to record rate attempt")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fiat::FiatService;
+    use crate::fiat_exchanger::{Currency, MockFiatExchanger, RangeRates};
+    use crate::fiat_rate::FiatExchangeRate;
+    use rust_decimal::Decimal;
+    use std::collections::HashMap;
+
+    async fn setup() -> Db {
+        let db = Db::in_memory().await.unwrap();
+        let mut mock_api = MockFiatExchanger::new();
+        mock_api.expect_get_available_currencies().returning(|| {
+            Ok(["USD", "EUR", "MYR"]
+                .into_iter()
+                .map(|symbol| Currency {
+                    name: symbol.to_string(),
+                    symbol: symbol.to_string(),
+                })
+                .collect())
+        });
+        FiatService::<MockFiatExchanger>::new(mock_api)
+            .update_currencies(&db)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO user_settings (id, locale, default_fiat_id) SELECT 1, 'en', id FROM fiat WHERE symbol = 'USD'")
+            .execute(&db.0)
+            .await
+            .unwrap();
+        db
+    }
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 2, day).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_backfill_rates() {
+        let db = setup().await;
+        // 2026-02-06 is a friday, 2026-02-16 a holiday
+        for (fiat, day) in [("EUR", 6), ("EUR", 7), ("EUR", 16), ("MYR", 6), ("USD", 6)] {
+            sqlx::query("INSERT INTO fiat_ramp (id, fiat_id, fiat_amount, ramp_date, via_exchange, kind) SELECT ?, id, 100, ?, 'test', 'deposit' FROM fiat WHERE symbol = ?")
+                .bind(format!("{fiat}-{day}"))
+                .bind(date(day))
+                .bind(fiat)
+                .execute(&db.0)
+                .await
+                .unwrap();
+        }
+
+        let mut mock_api = MockFiatExchanger::new();
+        mock_api
+            .expect_get_range_rates()
+            .withf(|base, start, end| {
+                base == "EUR"
+                    && *start == date(6) - Duration::days(LOOKBACK_DAYS)
+                    && *end == date(16)
+            })
+            .times(1)
+            .returning(|_, start, end| {
+                Ok(RangeRates {
+                    base: "EUR".to_string(),
+                    start_date: start,
+                    end_date: end,
+                    rates: BTreeMap::from([
+                        (
+                            date(5),
+                            HashMap::from([("USD".to_string(), Decimal::new(110, 2))]),
+                        ),
+                        (
+                            date(6),
+                            HashMap::from([("USD".to_string(), Decimal::new(120, 2))]),
+                        ),
+                        (
+                            date(13),
+                            HashMap::from([("USD".to_string(), Decimal::new(130, 2))]),
+                        ),
+                    ]),
+                    provider: "mock".to_string(),
+                })
+            });
+        mock_api
+            .expect_get_range_rates()
+            .withf(|base, _, _| base == "MYR")
+            .times(1)
+            .returning(|_, _, _| Err(anyhow::anyhow!("service unavailable")));
+
+        let report = backfill_rates(&db, &mock_api).await.unwrap();
+        assert_eq!(
+            report,
+            BackfillReport {
+                filled: 3,
+                unresolved: 1
+            }
+        );
+
+        let rates = sqlx::query_as::<sqlx::Sqlite, FiatExchangeRate>(
+            "SELECT fiat_exchange_rate.* FROM fiat_exchange_rate JOIN fiat ON fiat.id = base_fiat_id WHERE fiat.symbol = 'EUR' ORDER BY date",
+        )
+        .fetch_all(&db.0)
+        .await
+        .unwrap();
+        assert_eq!(rates.len(), 3);
+        assert_eq!(rates[0].date, date(6));
+        assert_eq!(rates[0].rates["USD"], Decimal::new(120, 2));
+        assert!(!rates[0].is_estimated);
+        assert_eq!(rates[1].date, date(7));
+        assert_eq!(rates[1].rates["USD"], Decimal::new(120, 2));
+        assert_eq!(rates[1].non_working_day_reason.as_deref(), Some("weekend"));
+        assert_eq!(rates[2].date, date(16));
+        assert_eq!(rates[2].rates["USD"], Decimal::new(130, 2));
+        assert_eq!(
+            rates[2].non_working_day_reason.as_deref(),
+            Some("public_holiday")
+        );
+
+        // nothing left to fetch for EUR
+        let mut mock_api = MockFiatExchanger::new();
+        mock_api
+            .expect_get_range_rates()
+            .withf(|base, _, _| base == "MYR")
+            .times(1)
+            .returning(|_, _, _| Err(anyhow::anyhow!("service unavailable")));
+        let report = backfill_rates(&db, &mock_api).await.unwrap();
+        assert_eq!(report.filled, 0);
+        assert_eq!(report.unresolved, 1);
+    }
+
+    #[tokio::test]
+    async fn test_backfill_rates_in_ranges() {
+        let db = setup().await;
+        let far = date(6) - Duration::days(MAX_RANGE_DAYS);
+        for (id, day) in [("far", far), ("near", date(6))] {
+            sqlx::query("INSERT INTO fiat_ramp (id, fiat_id, fiat_amount, ramp_date, via_exchange, kind) SELECT ?, id, 100, ?, 'test', 'deposit' FROM fiat WHERE symbol = 'MYR'")
+                .bind(id)
+                .bind(day)
+                .execute(&db.0)
+                .await
+                .unwrap();
+        }
+
+        // one range per date as they are too far apart, the rates lack the default currency
+        let mut mock_api = MockFiatExchanger::new();
+        for day in [far, date(6)] {
+            mock_api
+                .expect_get_range_rates()
+                .withf(move |base, start, end| {
+                    base == "MYR" && *start == day - Duration::days(LOOKBACK_DAYS) && *end == day
+                })
+                .times(1)
+                .returning(move |_, start, end| {
+                    Ok(RangeRates {
+                        base: "MYR".to_string(),
+                        start_date: start,
+                        end_date: end,
+                        rates: BTreeMap::from([(
+                            day,
+                            HashMap::from([("EUR".to_string(), Decimal::new(21, 2))]),
+                        )]),
+                        provider: "mock".to_string(),
+                    })
+                });
+        }
+        let report = backfill_rates(&db, &mock_api).await.unwrap();
+        assert_eq!(
+            report,
+            BackfillReport {
+                filled: 0,
+                unresolved: 2
+            }
+        );
+
+        // recorded as attempted, not requested again
+        let mock_api = MockFiatExchanger::new();
+        let report = backfill_rates(&db, &mock_api).await.unwrap();
+        assert_eq!(report, BackfillReport::default());
+    }
+}
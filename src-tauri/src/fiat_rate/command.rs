@@ -1,14 +1,99 @@
 use crate::db::Db;
-use crate::fiat_exchanger::frankfurter_exchanger::FrankfurterExchangerApi;
-use crate::fiat_rate::{get_rate, FiatExchangeRate};
-use anyhow::Result;
+use crate::fiat_exchanger::FiatExchangerManager;
+use crate::fiat_rate::backfill::{backfill_rates, BackfillReport};
+use crate::fiat_rate::manual::{ManualRate, ManualRateService};
+use crate::fiat_rate::{get_rate_for_base, get_rate_for_pair, FiatExchangeRate, PairRate};
 use chrono::NaiveDate;
+use rust_decimal::Decimal;
 
+/// Get the rates of a date based on `base` -- base is optional and defaults to USD
 #[tauri::command]
-async fn get_fiat_rate(db: tauri::State<'_, Db>, date: &NaiveDate) -> Result<FiatExchangeRate> {
-    let exchange_api = FrankfurterExchangerApi::default();
+pub async fn get_fiat_rate(
+    db: tauri::State<'_, Db>,
+    fiat_exchanger: tauri::State<'_, FiatExchangerManager>,
+    date: NaiveDate,
+    base: Option<String>,
+) -> Result<FiatExchangeRate, String> {
+    let exchange_api = fiat_exchanger.inner();
 
-    let rate = get_rate(&db, &exchange_api, date, None).await?;
+    get_rate_for_base(
+        &db,
+        exchange_api,
+        base.as_deref().unwrap_or("USD"),
+        &date,
+        None,
+    )
+    .await
+    .map_err(|e| format!("failed to get fiat rate: {e}"))
+}
+
+/// Get the rate of `from` in `to` on a date
+#[tauri::command]
+pub async fn get_fiat_pair_rate(
+    db: tauri::State<'_, Db>,
+    fiat_exchanger: tauri::State<'_, FiatExchangerManager>,
+    from: String,
+    to: String,
+    date: NaiveDate,
+) -> Result<PairRate, String> {
+    let exchange_api = fiat_exchanger.inner();
+
+    get_rate_for_pair(&db, exchange_api, &from, &to, &date, None)
+        .await
+        .map_err(|e| format!("failed to get fiat pair rate: {e:#}"))
+}
 
-    Ok(rate)
+/// Fetch the rates of every fiat ramp date without one
+#[tauri::command]
+pub async fn backfill_fiat_rates(
+    db: tauri::State<'_, Db>,
+    fiat_exchanger: tauri::State<'_, FiatExchangerManager>,
+) -> Result<BackfillReport, String> {
+    let exchange_api = fiat_exchanger.inner();
+
+    backfill_rates(&db, exchange_api)
+        .await
+        .map_err(|e| format!("failed to backfill fiat rates: {e:#}"))
+}
+
+/// Set the rate of a fiat ramp in the default currency, it comes before the provider rates
+#[tauri::command]
+pub async fn set_fiat_ramp_manual_rate(
+    db: tauri::State<'_, Db>,
+    fiat_ramp_id: String,
+    rate: Decimal,
+    note: Option<String>,
+) -> Result<ManualRate, String> {
+    ManualRateService::set_for_ramp(&fiat_ramp_id, rate, note, &db)
+        .await
+        .map_err(|e| format!("failed to set manual rate: {e:#}"))
+}
+
+/// Set the rate of `from` in `to` on a date, it comes before the provider rates
+#[tauri::command]
+pub async fn set_manual_fiat_rate(
+    db: tauri::State<'_, Db>,
+    from: String,
+    to: String,
+    date: NaiveDate,
+    rate: Decimal,
+    note: Option<String>,
+) -> Result<ManualRate, String> {
+    ManualRateService::set_for_pair(&from, &to, date, rate, note, &db)
+        .await
+        .map_err(|e| format!("failed to set manual rate: {e:#}"))
+}
+
+#[tauri::command]
+pub async fn get_manual_fiat_rates(db: tauri::State<'_, Db>) -> Result<Vec<ManualRate>, String> {
+    ManualRateService::get(&db)
+        .await
+        .map_err(|e| format!("failed to get manual rates: {e:#}"))
+}
+
+#[tauri::command]
+pub async fn delete_manual_fiat_rate(db: tauri::State<'_, Db>, id: String) -> Result<u64, String> {
+    ManualRateService::delete(&id, &db)
+        .await
+        .map_err(|e| format!("failed to delete manual rate: {e:#}"))
 }
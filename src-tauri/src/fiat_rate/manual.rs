@@ -0,0 +1,449 @@
+use super::RateSource;
+use crate::db::{Db, RowId, SqlDecimal, StringRowId};
+use anyhow::{bail, Context, Result};
+use chrono::{NaiveDate, NaiveDateTime};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::SqliteConnection;
+use uuid::Uuid;
+
+#[derive(Debug, sqlx::FromRow, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ManualRate {
+    pub id: StringRowId,
+    pub from_fiat_id: RowId,
+    pub from_fiat_symbol: String,
+    pub to_fiat_id: RowId,
+    pub to_fiat_symbol: String,
+    /// Set for the rate of a pair on a date
+    pub date: Option<NaiveDate>,
+    /// Set for the rate of one ramp
+    pub fiat_ramp_id: Option<StringRowId>,
+    /// Amount of the to currency for one from currency
+    #[sqlx(try_from = "SqlDecimal")]
+    pub rate: Decimal,
+    pub source: RateSource,
+    pub note: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+const SELECT_MANUAL_RATE: &str = r#"
+    SELECT
+    fiat_manual_rate.*,
+    from_fiat.symbol as from_fiat_symbol,
+    to_fiat.symbol as to_fiat_symbol
+    FROM fiat_manual_rate
+    JOIN fiat AS from_fiat ON from_fiat.id = fiat_manual_rate.from_fiat_id
+    JOIN fiat AS to_fiat ON to_fiat.id = fiat_manual_rate.to_fiat_id
+"#;
+
+pub struct ManualRateService {}
+
+impl ManualRateService {
+    /// Set the rate of a ramp in the default currency, replacing the previous one
+    /// - the ramp stops waiting for a provider rate in `fiat_rate_missing`
+    pub async fn set_for_ramp(
+        fiat_ramp_id: &str,
+        rate: Decimal,
+        note: Option<String>,
+        db: &Db,
+    ) -> Result<ManualRate> {
+        check_rate(rate)?;
+        let (from_fiat_id, to_fiat_id) = sqlx::query_as::<_, (RowId, RowId)>(
+            r#"
+            SELECT fiat_ramp.fiat_id, user_settings.default_fiat_id
+            FROM fiat_ramp
+            JOIN user_settings ON user_settings.id = 1
+            WHERE fiat_ramp.id = ?
+            "#,
+        )
+        .bind(fiat_ramp_id)
+        .fetch_optional(&db.0)
+        .await
+        .context("failed to get fiat ramp")?
+        .with_context(|| format!("fiat ramp {fiat_ramp_id} not found"))?;
+        if from_fiat_id == to_fiat_id {
+            bail!("the fiat ramp is in the default currency");
+        }
+
+        let id: StringRowId = sqlx::query_scalar(
+            r#"
+            INSERT INTO fiat_manual_rate (id, from_fiat_id, to_fiat_id, fiat_ramp_id, rate, note)
+            VALUES (?, ?, ?, ?, ?, ?)
+            ON CONFLICT (fiat_ramp_id) WHERE fiat_ramp_id IS NOT NULL DO UPDATE SET
+                from_fiat_id = excluded.from_fiat_id,
+                to_fiat_id = excluded.to_fiat_id,
+                rate = excluded.rate,
+                note = excluded.note
+            RETURNING id
+            "#,
+        )
+        .bind(Uuid::now_v7().to_string())
+        .bind(from_fiat_id)
+        .bind(to_fiat_id)
+        .bind(fiat_ramp_id)
+        .bind(rate.to_string())
+        .bind(note)
+        .fetch_one(&db.0)
+        .await
+        .context("failed to set manual rate")?;
+
+        Self::clear_missing(db).await?;
+        Self::get_by_id(&id, db).await
+    }
+
+    /// Set the rate of a pair on a date, replacing the previous one
+    /// - used by every ramp of the date in either currency of the pair, converted to the other one
+    pub async fn set_for_pair(
+        from_symbol: &str,
+        to_symbol: &str,
+        date: NaiveDate,
+        rate: Decimal,
+        note: Option<String>,
+        db: &Db,
+    ) -> Result<ManualRate> {
+        check_rate(rate)?;
+        if from_symbol == to_symbol {
+            bail!("the pair needs two currencies");
+        }
+
+        let id: StringRowId = sqlx::query_scalar(
+            r#"
+            INSERT INTO fiat_manual_rate (id, from_fiat_id, to_fiat_id, date, rate, note)
+            SELECT ?, from_fiat.id, to_fiat.id, ?, ?, ?
+            FROM fiat AS from_fiat, fiat AS to_fiat
+            WHERE from_fiat.symbol = ? AND to_fiat.symbol = ?
+            ON CONFLICT (from_fiat_id, to_fiat_id, date) WHERE date IS NOT NULL DO UPDATE SET
+                rate = excluded.rate,
+                note = excluded.note
+            RETURNING id
+            "#,
+        )
+        .bind(Uuid::now_v7().to_string())
+        .bind(date)
+        .bind(rate.to_string())
+        .bind(note)
+        .bind(from_symbol)
+        .bind(to_symbol)
+        .fetch_optional(&db.0)
+        .await
+        .context("failed to set manual rate")?
+        .with_context(|| format!("unknown currency in {from_symbol}/{to_symbol}"))?;
+
+        Self::clear_missing(db).await?;
+        Self::get_by_id(&id, db).await
+    }
+
+    /// Get every manual rate, the latest dates first and the rates of ramps last
+    pub async fn get(db: &Db) -> Result<Vec<ManualRate>> {
+        let sql = format!(
+            "{SELECT_MANUAL_RATE} ORDER BY fiat_manual_rate.date IS NULL, fiat_manual_rate.date DESC, fiat_manual_rate.created_at DESC"
+        );
+        sqlx::query_as::<_, ManualRate>(&sql)
+            .fetch_all(&db.0)
+            .await
+            .context("failed to get manual rates")
+    }
+
+    pub async fn get_by_id(id: &str, db: &Db) -> Result<ManualRate> {
+        let sql = format!("{SELECT_MANUAL_RATE} WHERE fiat_manual_rate.id = ?");
+        sqlx::query_as::<_, ManualRate>(&sql)
+            .bind(id)
+            .fetch_one(&db.0)
+            .await
+            .context("failed to get manual rate")
+    }
+
+    /// Delete a manual rate, the ramps using it go back to the provider rates
+    pub async fn delete(id: &str, db: &Db) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM fiat_manual_rate WHERE id = ?")
+            .bind(id)
+            .execute(&db.0)
+            .await
+            .context("failed to delete manual rate")?;
+        Ok(result.rows_affected())
+    }
+
+    /// The manual rate of a pair on a date, or the inverse of the rate of the reversed pair
+    pub async fn get_for_pair(
+        from_fiat_id: RowId,
+        to_fiat_id: RowId,
+        date: &NaiveDate,
+        db: &Db,
+    ) -> Result<Option<Decimal>> {
+        let row = sqlx::query_as::<_, (RowId, SqlDecimal)>(
+            r#"
+            SELECT from_fiat_id, rate
+            FROM fiat_manual_rate
+            WHERE date = ?
+            AND ((from_fiat_id = ? AND to_fiat_id = ?) OR (from_fiat_id = ? AND to_fiat_id = ?))
+            ORDER BY from_fiat_id = ? DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(date)
+        .bind(from_fiat_id)
+        .bind(to_fiat_id)
+        .bind(to_fiat_id)
+        .bind(from_fiat_id)
+        .bind(from_fiat_id)
+        .fetch_optional(&db.0)
+        .await
+        .context("failed to get manual rate of pair")?;
+        Ok(row.map(|(rate_from_fiat_id, SqlDecimal(rate))| {
+            if rate_from_fiat_id == from_fiat_id {
+                rate
+            } else {
+                Decimal::ONE / rate
+            }
+        }))
+    }
+
+    /// The manual rate of a ramp in the default currency, see the `fiat_ramp_manual_rate` view
+    pub async fn get_for_ramp(fiat_ramp_id: &str, db: &Db) -> Result<Option<Decimal>> {
+        let row = sqlx::query_as::<_, (SqlDecimal, SqlDecimal)>(
+            "SELECT to_rate, from_rate FROM fiat_ramp_manual_rate WHERE fiat_ramp_id = ?",
+        )
+        .bind(fiat_ramp_id)
+        .fetch_optional(&db.0)
+        .await
+        .context("failed to get manual rate of ramp")?;
+        Ok(row.map(|(SqlDecimal(to_rate), SqlDecimal(from_rate))| to_rate / from_rate))
+    }
+
+    /// Bring the rates of ramps moved to another currency or date back in line, on `conn` inside
+    /// the transaction of the move
+    /// - a manual rate of a ramp set for another currency than the one of the ramp is deleted
+    /// - the queue entry of the previous currency or date is dropped, a ramp left without a rate
+    ///   is queued again in `fiat_rate_missing`
+    pub(crate) async fn reset_ramps_in(
+        fiat_ramp_ids: &[StringRowId],
+        conn: &mut SqliteConnection,
+    ) -> Result<()> {
+        for fiat_ramp_id in fiat_ramp_ids {
+            sqlx::query(
+                r#"
+                DELETE FROM fiat_manual_rate
+                WHERE fiat_ramp_id = ?1
+                AND from_fiat_id != (SELECT fiat_id FROM fiat_ramp WHERE id = ?1)
+                "#,
+            )
+            .bind(fiat_ramp_id)
+            .execute(&mut *conn)
+            .await
+            .context("failed to delete manual rate of ramp")?;
+            sqlx::query("DELETE FROM fiat_rate_missing WHERE fiat_ramp_id = ?")
+                .bind(fiat_ramp_id)
+                .execute(&mut *conn)
+                .await
+                .context("failed to delete missing rate of ramp")?;
+            // a ramp of the same currency and date already queued fetches the rate for both
+            sqlx::query(
+                r#"
+                INSERT INTO fiat_rate_missing (fiat_ramp_id, base_fiat_id, date, last_error_msg)
+                SELECT fiat_ramp_id, from_fiat_id, ramp_date, 'No rate for the pair'
+                FROM fiat_ramp_view
+                WHERE fiat_ramp_id = ? AND to_rate IS NULL
+                ON CONFLICT DO NOTHING
+                "#,
+            )
+            .bind(fiat_ramp_id)
+            .execute(&mut *conn)
+            .await
+            .context("failed to queue missing rate of ramp")?;
+        }
+        Ok(())
+    }
+
+    /// Stop retrying the ramps which have a manual rate
+    pub async fn clear_missing(db: &Db) -> Result<u64> {
+        let result = sqlx::query(
+            "DELETE FROM fiat_rate_missing WHERE fiat_ramp_id IN (SELECT fiat_ramp_id FROM fiat_ramp_manual_rate)",
+        )
+        .execute(&db.0)
+        .await
+        .context("failed to clear missing rates with a manual rate")?;
+        Ok(result.rows_affected())
+    }
+}
+
+fn check_rate(rate: Decimal) -> Result<()> {
+    if rate <= Decimal::ZERO {
+        bail!("the rate must be greater than 0");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fiat_exchanger::MockFiatExchanger;
+    use crate::fiat_ramp::{FiatRampService, FiatRampWithConversionView, RampKind, UpdateFiatRamp};
+    use crate::fiat_rate::get_rate_for_ramp;
+
+    async fn setup() -> Db {
+        let db = Db::in_memory().await.unwrap();
+        sqlx::query(
+            "INSERT INTO fiat (symbol, name) VALUES ('EUR', 'Euro'), ('MYR', 'Malaysian Ringgit')",
+        )
+        .execute(&db.0)
+        .await
+        .unwrap();
+        sqlx::query("INSERT INTO user_settings (id, locale, default_fiat_id) SELECT 1, 'en', id FROM fiat WHERE symbol = 'MYR'")
+            .execute(&db.0)
+            .await
+            .unwrap();
+        db
+    }
+
+    async fn view(db: &Db) -> Vec<FiatRampWithConversionView> {
+        sqlx::query_as("SELECT * FROM fiat_ramp_view ORDER BY fiat_ramp_id")
+            .fetch_all(&db.0)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(FiatRampWithConversionView::with_conversion)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_manual_rates() {
+        let db = setup().await;
+        let date = NaiveDate::from_ymd_opt(2026, 2, 2).unwrap();
+        for id in ["ramp-1", "ramp-2"] {
+            sqlx::query("INSERT INTO fiat_ramp (id, fiat_id, fiat_amount, ramp_date, via_exchange, kind) SELECT ?, id, 100, ?, 'bank', 'deposit' FROM fiat WHERE symbol = 'EUR'")
+                .bind(id)
+                .bind(date)
+                .execute(&db.0)
+                .await
+                .unwrap();
+        }
+        sqlx::query("INSERT INTO fiat_exchange_rate (base_fiat_id, date, rates) SELECT id, ?, '{\"EUR\": 1.0, \"MYR\": 4.5}' FROM fiat WHERE symbol = 'EUR'")
+            .bind(date)
+            .execute(&db.0)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO fiat_rate_missing (fiat_ramp_id, base_fiat_id, date, error_count) SELECT 'ramp-1', id, ?, 5 FROM fiat WHERE symbol = 'EUR'")
+            .bind(date)
+            .execute(&db.0)
+            .await
+            .unwrap();
+
+        let rows = view(&db).await;
+        assert_eq!(rows[0].conversion_rate, Some(Decimal::new(45, 1)));
+        assert_eq!(rows[0].rate_source, Some(RateSource::Provider));
+
+        // the rate of the reversed pair is inverted
+        let pair_rate =
+            ManualRateService::set_for_pair("MYR", "EUR", date, Decimal::new(25, 2), None, &db)
+                .await
+                .unwrap();
+        assert_eq!(pair_rate.source, RateSource::Manual);
+        let rows = view(&db).await;
+        assert!(rows
+            .iter()
+            .all(|row| row.conversion_rate == Some(Decimal::new(4, 0))
+                && row.rate_source == Some(RateSource::Manual)));
+        let missing: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM fiat_rate_missing")
+            .fetch_one(&db.0)
+            .await
+            .unwrap();
+        assert_eq!(missing, 0);
+
+        // the rate of the ramp comes before the rate of the pair
+        let ramp_rate = ManualRateService::set_for_ramp(
+            "ramp-1",
+            Decimal::new(42, 1),
+            Some("bank rate".to_string()),
+            &db,
+        )
+        .await
+        .unwrap();
+        let ramp_rate = ManualRateService::set_for_ramp(
+            "ramp-1",
+            Decimal::new(43, 1),
+            ramp_rate.note.clone(),
+            &db,
+        )
+        .await
+        .unwrap();
+        assert_eq!(ramp_rate.from_fiat_symbol, "EUR");
+        assert_eq!(ramp_rate.to_fiat_symbol, "MYR");
+        let rows = view(&db).await;
+        assert_eq!(rows[0].conversion_rate, Some(Decimal::new(43, 1)));
+        assert_eq!(rows[0].converted_amount, Some(Decimal::new(430, 0)));
+        assert_eq!(rows[1].conversion_rate, Some(Decimal::new(4, 0)));
+
+        // no provider is asked for a ramp with a manual rate
+        let mock_api = MockFiatExchanger::new();
+        let rate = get_rate_for_ramp(&db, &mock_api, &"ramp-1".to_string())
+            .await
+            .unwrap();
+        assert_eq!(rate.rate, Decimal::new(43, 1));
+        assert_eq!(rate.source, RateSource::Manual);
+
+        ManualRateService::delete(&pair_rate.id, &db).await.unwrap();
+        let rows = view(&db).await;
+        assert_eq!(rows[1].conversion_rate, Some(Decimal::new(45, 1)));
+        assert_eq!(ManualRateService::get(&db).await.unwrap(), vec![ramp_rate]);
+
+        assert!(
+            ManualRateService::set_for_ramp("ramp-2", Decimal::ZERO, None, &db)
+                .await
+                .is_err()
+        );
+        assert!(
+            ManualRateService::set_for_pair("EUR", "XXX", date, Decimal::ONE, None, &db)
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ramp_moved_to_another_currency() {
+        let db = setup().await;
+        let date = NaiveDate::from_ymd_opt(2026, 2, 2).unwrap();
+        let usd_id: RowId = sqlx::query_scalar(
+            "INSERT INTO fiat (symbol, name) VALUES ('USD', 'US Dollar') RETURNING id",
+        )
+        .fetch_one(&db.0)
+        .await
+        .unwrap();
+        sqlx::query("INSERT INTO fiat_ramp (id, fiat_id, fiat_amount, ramp_date, via_exchange, kind) SELECT 'ramp-1', id, 100, ?, 'bank', 'deposit' FROM fiat WHERE symbol = 'EUR'")
+            .bind(date)
+            .execute(&db.0)
+            .await
+            .unwrap();
+        ManualRateService::set_for_ramp("ramp-1", Decimal::new(43, 1), None, &db)
+            .await
+            .unwrap();
+
+        // the rate of the euro ramp does not apply to dollars
+        FiatRampService::update(
+            UpdateFiatRamp {
+                id: "ramp-1".to_string(),
+                fiat_id: Some(usd_id),
+                fiat_amount: Some(Decimal::ONE_HUNDRED),
+                ramp_date: Some(date),
+                kind: Some(RampKind::Deposit),
+                via_exchange: None,
+                asset_id: None,
+                fee_fiat_id: None,
+                fee_amount: None,
+            },
+            &db,
+        )
+        .await
+        .unwrap();
+        assert!(ManualRateService::get(&db).await.unwrap().is_empty());
+        let rows = view(&db).await;
+        assert_eq!(rows[0].conversion_rate, None);
+        let queued: (RowId, NaiveDate) = sqlx::query_as(
+            "SELECT base_fiat_id, date FROM fiat_rate_missing WHERE fiat_ramp_id = 'ramp-1'",
+        )
+        .fetch_one(&db.0)
+        .await
+        .unwrap();
+        assert_eq!(queued, (usd_id, date));
+    }
+}
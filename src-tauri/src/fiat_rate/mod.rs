@@ -1,11 +1,15 @@
+pub mod backfill;
 pub mod command;
+pub mod manual;
 use crate::db::StringRowId;
 use crate::fiat::FiatService;
 use crate::fiat_exchanger::frankfurter_exchanger::FrankfurterExchangerApi;
 use crate::fiat_exchanger::Rates;
+use crate::fiat_rate::manual::ManualRateService;
 use crate::{db::Db, fiat_exchanger::FiatExchanger};
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
 use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -24,8 +28,29 @@ pub struct FiatExchangeRate {
     pub is_non_working_day: bool,
     #[sqlx(default)]
     pub non_working_day_reason: Option<String>,
+    /// Id of the exchanger which supplied the rates
+    #[sqlx(default)]
+    pub provider: Option<String>,
     #[sqlx(json)]
-    pub rates: HashMap<String, f64>,
+    pub rates: HashMap<String, Decimal>,
+}
+
+impl FiatExchangeRate {
+    /// Convert an amount from one fiat to another with the rates of this day
+    pub fn convert(&self, amount: Decimal, from_symbol: &str, to_symbol: &str) -> Result<Decimal> {
+        if from_symbol == to_symbol {
+            return Ok(amount);
+        }
+        Ok(amount * self.rate_of(to_symbol)? / self.rate_of(from_symbol)?)
+    }
+
+    fn rate_of(&self, symbol: &str) -> Result<Decimal> {
+        self.rates
+            .get(symbol)
+            .copied()
+            .filter(|rate| !rate.is_zero())
+            .with_context(|| format!("no {symbol} rate on {}", self.date))
+    }
 }
 
 #[derive(Debug, sqlx::FromRow)]
@@ -48,84 +73,137 @@ fn previous_business_day(date: &NaiveDate) -> NaiveDate {
     candidate
 }
 
-// Get the rate for a specific fiat and date
-// In FiatExchangeRate Table the base_fiat_id is the id of the USD dollar
+/// Where a rate comes from, a provider or the user
+#[derive(Debug, Serialize, Deserialize, sqlx::Type, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum RateSource {
+    #[default]
+    Provider,
+    Manual,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PairRate {
+    pub from_symbol: String,
+    pub to_symbol: String,
+    pub date: NaiveDate,
+    /// Amount of `to_symbol` for one `from_symbol`
+    pub rate: Decimal,
+    pub is_estimated: bool,
+    pub is_non_working_day: bool,
+    pub non_working_day_reason: Option<String>,
+    pub source: RateSource,
+    /// Id of the exchanger which supplied the rate, None for a manual rate or a currency in itself
+    pub provider: Option<String>,
+}
+
+impl PairRate {
+    fn identity(symbol: &str, date: &NaiveDate) -> Self {
+        Self::manual(symbol, symbol, date, Decimal::ONE)
+    }
+
+    fn manual(from_symbol: &str, to_symbol: &str, date: &NaiveDate, rate: Decimal) -> Self {
+        Self {
+            from_symbol: from_symbol.to_string(),
+            to_symbol: to_symbol.to_string(),
+            date: *date,
+            rate,
+            is_estimated: false,
+            is_non_working_day: false,
+            non_working_day_reason: None,
+            source: if from_symbol == to_symbol {
+                RateSource::Provider
+            } else {
+                RateSource::Manual
+            },
+            provider: None,
+        }
+    }
+
+    /// The rate of the pair from a rate row based on `from_symbol`, or on `to_symbol` when `inverse`
+    fn from_row(
+        row: &FiatExchangeRate,
+        from_symbol: &str,
+        to_symbol: &str,
+        inverse: bool,
+    ) -> Option<Self> {
+        let quote = if inverse { from_symbol } else { to_symbol };
+        let rate = row
+            .rates
+            .get(quote)
+            .copied()
+            .filter(|rate| rate.is_sign_positive() && !rate.is_zero())?;
+        Some(Self {
+            from_symbol: from_symbol.to_string(),
+            to_symbol: to_symbol.to_string(),
+            date: row.date,
+            rate: if inverse { Decimal::ONE / rate } else { rate },
+            is_estimated: row.is_estimated,
+            is_non_working_day: row.is_non_working_day,
+            non_working_day_reason: row.non_working_day_reason.clone(),
+            source: RateSource::Provider,
+            provider: row.provider.clone(),
+        })
+    }
+}
+
+// Get the USD based rates of a date
+// - kept for the portfolio and cost basis, which convert through any pair of the same day
 pub async fn get_rate<A: FiatExchanger>(
     db: &Db,
     exchange_api: &A,
     date: &NaiveDate,
     fiat_ramp_id: Option<&StringRowId>,
 ) -> Result<FiatExchangeRate> {
-    let usd_fiat = FiatService::<FrankfurterExchangerApi>::get_fiat_by_symbol(db, "USD").await?;
-    // 1. Check DB first
-    let result = sqlx::query_as::<sqlx::Sqlite, FiatExchangeRate>(
-        r#"
-        SELECT
-        fiat_exchange_rate.*,
-        fiat.symbol,
-        fiat.name
-        FROM fiat_exchange_rate
-        JOIN fiat ON fiat_exchange_rate.base_fiat_id = fiat.id
-        WHERE fiat_exchange_rate.base_fiat_id = ?
-        AND fiat_exchange_rate.date = ?
-        "#,
-    )
-    .bind(&usd_fiat.id)
-    .bind(date)
-    .fetch_one(&db.0)
-    .await;
-
-    if let Ok(rate) = result {
-        // Rate Exists
-        // If exact match (not estimated), and we have a ramp_id, ensure we are NOT in the queue.
-        // Actually, even if estimated, if we found it in DB, we use it.
-        // But the user might be calling this to trigger a "re-check" or "ensure consistent".
+    get_rate_for_base(db, exchange_api, "USD", date, fiat_ramp_id).await
+}
 
-        // If we have a ramp_id, update the queue only when it's a true retry case.
-        // Estimated rates caused by non-working days (weekends/holidays) should NOT be queued.
+/// Get the rates based on `base_symbol` of a date, fetched from the exchanger when not stored yet
+pub async fn get_rate_for_base<A: FiatExchanger>(
+    db: &Db,
+    exchange_api: &A,
+    base_symbol: &str,
+    date: &NaiveDate,
+    fiat_ramp_id: Option<&StringRowId>,
+) -> Result<FiatExchangeRate> {
+    let base_fiat =
+        FiatService::<FrankfurterExchangerApi>::get_fiat_by_symbol(db, base_symbol).await?;
+    // 1. Check DB first
+    if let Some(rate) = get_stored_rate(db, base_fiat.id, date).await? {
         if let Some(id) = fiat_ramp_id {
-            if !rate.is_estimated || rate.is_non_working_day {
-                remove_from_missing_queue_by_ramp_id(db, id).await.ok();
-            } else {
-                add_to_missing_queue(db, id, usd_fiat.id, date, Some("Using estimated rate"))
-                    .await
-                    .ok();
-            }
+            sync_missing_queue(db, &rate, id).await;
         }
-
         return Ok(rate);
     }
 
     // 2. Not found in DB, fetch from API
-    let usd_fiat = FiatService::<A>::get_fiat_by_id(db, usd_fiat.id)
-        .await
-        .context("failed to get fiat by id")?;
-    let base_symbol = usd_fiat.symbol;
-    let base_name = usd_fiat.name;
+    let base_symbol = base_fiat.symbol;
+    let base_name = base_fiat.name;
 
     // Call API
     let api_result = exchange_api
-        .get_latest_rates(&base_symbol.as_str(), Some(date))
+        .get_latest_rates(base_symbol.as_str(), Some(date))
         .await;
 
     match api_result {
         Ok(api_rates) => {
             let (mut fiat_rate, should_retry) =
-                process_and_insert_rate(db, usd_fiat.id, date, api_rates, &base_symbol).await?;
+                process_and_insert_rate(db, base_fiat.id, date, api_rates, &base_symbol).await?;
 
             if should_retry {
                 if let Some(id) = fiat_ramp_id {
                     add_to_missing_queue(
                         db,
                         id,
-                        usd_fiat.id,
+                        base_fiat.id,
                         date,
                         Some("Fallback: 1 day difference"),
                     )
                     .await?;
                 }
             } else {
-                remove_from_missing_queue_by_rate(db, usd_fiat.id, date)
+                remove_from_missing_queue_by_rate(db, base_fiat.id, date)
                     .await
                     .ok();
                 if let Some(id) = fiat_ramp_id {
@@ -140,14 +218,172 @@ pub async fn get_rate<A: FiatExchanger>(
         Err(e) => {
             // API Failure
             if let Some(id) = fiat_ramp_id {
-                add_to_missing_queue(db, id, usd_fiat.id, date, Some(&e.to_string())).await?;
+                add_to_missing_queue(db, id, base_fiat.id, date, Some(&e.to_string())).await?;
             }
-            Err(e.into())
+            Err(e)
+        }
+    }
+}
+
+/// Get the rate of one currency in another on a date
+/// - a manual rate of the pair comes first
+/// - then a stored rate based on `from_symbol` is used first, then the inverse of one based on
+///   `to_symbol`, otherwise the rates based on `from_symbol` are fetched, and the ones based on
+///   `to_symbol` when the first lack the pair, as the providers do not all quote every currency
+/// - never crossed through a third currency, so the pair does not depend on both currencies being
+///   quoted against it, a ramp whose pair is quoted by neither is queued in `fiat_rate_missing`
+pub async fn get_rate_for_pair<A: FiatExchanger>(
+    db: &Db,
+    exchange_api: &A,
+    from_symbol: &str,
+    to_symbol: &str,
+    date: &NaiveDate,
+    fiat_ramp_id: Option<&StringRowId>,
+) -> Result<PairRate> {
+    if from_symbol == to_symbol {
+        if let Some(id) = fiat_ramp_id {
+            remove_from_missing_queue_by_ramp_id(db, id).await.ok();
+        }
+        return Ok(PairRate::identity(from_symbol, date));
+    }
+
+    let from_fiat =
+        FiatService::<FrankfurterExchangerApi>::get_fiat_by_symbol(db, from_symbol).await?;
+    let to_fiat = FiatService::<FrankfurterExchangerApi>::get_fiat_by_symbol(db, to_symbol).await?;
+    if let Some(rate) = ManualRateService::get_for_pair(from_fiat.id, to_fiat.id, date, db).await? {
+        if let Some(id) = fiat_ramp_id {
+            remove_from_missing_queue_by_ramp_id(db, id).await.ok();
         }
+        return Ok(PairRate::manual(from_symbol, to_symbol, date, rate));
+    }
+    let stored = [(from_fiat.id, false), (to_fiat.id, true)];
+    for (base_fiat_id, inverse) in stored {
+        let Some(row) = get_stored_rate(db, base_fiat_id, date).await? else {
+            continue;
+        };
+        if let Some(pair) = PairRate::from_row(&row, from_symbol, to_symbol, inverse) {
+            if let Some(id) = fiat_ramp_id {
+                sync_missing_queue(db, &row, id).await;
+            }
+            return Ok(pair);
+        }
+    }
+
+    if let Ok(row) = get_rate_for_base(db, exchange_api, from_symbol, date, fiat_ramp_id).await {
+        if let Some(pair) = PairRate::from_row(&row, from_symbol, to_symbol, false) {
+            return Ok(pair);
+        }
+    }
+    let row = get_rate_for_base(db, exchange_api, to_symbol, date, fiat_ramp_id).await?;
+    if let Some(pair) = PairRate::from_row(&row, from_symbol, to_symbol, true) {
+        return Ok(pair);
+    }
+    // neither currency quotes the other, the ramp has no rate and is retried by the queue
+    let error = format!("no {from_symbol}/{to_symbol} rate on {date}");
+    if let Some(id) = fiat_ramp_id {
+        add_to_missing_queue(db, id, from_fiat.id, date, Some(&error)).await?;
+    }
+    Err(anyhow!(error))
+}
+
+/// Get the rate of a fiat ramp in the default currency, failures are queued in `fiat_rate_missing`
+/// - the manual rate of the ramp comes first
+/// - the rate of a fee paid in another currency is fetched too, it is not queued
+pub async fn get_rate_for_ramp<A: FiatExchanger>(
+    db: &Db,
+    exchange_api: &A,
+    fiat_ramp_id: &StringRowId,
+) -> Result<PairRate> {
+    let (from_symbol, to_symbol, date, fee_symbol) =
+        sqlx::query_as::<_, (String, String, NaiveDate, Option<String>)>(
+            r#"
+            SELECT fiat.symbol, default_fiat.symbol, fiat_ramp.ramp_date, fee_fiat.symbol
+            FROM fiat_ramp
+            JOIN fiat ON fiat.id = fiat_ramp.fiat_id
+            JOIN user_settings ON user_settings.id = 1
+            JOIN fiat AS default_fiat ON default_fiat.id = user_settings.default_fiat_id
+            LEFT JOIN fiat AS fee_fiat ON fee_fiat.id = fiat_ramp.fee_fiat_id
+            WHERE fiat_ramp.id = ?
+            "#,
+        )
+        .bind(fiat_ramp_id)
+        .fetch_one(&db.0)
+        .await
+        .context("failed to get fiat ramp currencies")?;
+
+    if let Some(fee_symbol) = fee_symbol.filter(|symbol| *symbol != from_symbol) {
+        if let Err(e) =
+            get_rate_for_pair(db, exchange_api, &fee_symbol, &to_symbol, &date, None).await
+        {
+            eprintln!("Failed to get {fee_symbol}/{to_symbol} fee rate on {date}: {e:#}");
+        }
+    }
+
+    if let Some(rate) = ManualRateService::get_for_ramp(fiat_ramp_id, db).await? {
+        remove_from_missing_queue_by_ramp_id(db, fiat_ramp_id)
+            .await
+            .ok();
+        return Ok(PairRate::manual(&from_symbol, &to_symbol, &date, rate));
+    }
+
+    get_rate_for_pair(
+        db,
+        exchange_api,
+        &from_symbol,
+        &to_symbol,
+        &date,
+        Some(fiat_ramp_id),
+    )
+    .await
+}
+
+async fn get_stored_rate(
+    db: &Db,
+    base_fiat_id: i64,
+    date: &NaiveDate,
+) -> Result<Option<FiatExchangeRate>> {
+    sqlx::query_as::<sqlx::Sqlite, FiatExchangeRate>(
+        r#"
+        SELECT
+        fiat_exchange_rate.*,
+        fiat.symbol,
+        fiat.name
+        FROM fiat_exchange_rate
+        JOIN fiat ON fiat_exchange_rate.base_fiat_id = fiat.id
+        WHERE fiat_exchange_rate.base_fiat_id = ?
+        AND fiat_exchange_rate.date = ?
+        "#,
+    )
+    .bind(base_fiat_id)
+    .bind(date)
+    .fetch_optional(&db.0)
+    .await
+    .context("failed to get stored rate")
+}
+
+// Helper: Keep the queue of a ramp in line with the stored rate it uses
+// Estimated rates caused by non-working days (weekends/holidays) should NOT be queued.
+async fn sync_missing_queue(db: &Db, rate: &FiatExchangeRate, fiat_ramp_id: &str) {
+    if !rate.is_estimated || rate.is_non_working_day {
+        remove_from_missing_queue_by_ramp_id(db, fiat_ramp_id)
+            .await
+            .ok();
+    } else {
+        add_to_missing_queue(
+            db,
+            fiat_ramp_id,
+            rate.base_fiat_id,
+            &rate.date,
+            Some("Using estimated rate"),
+        )
+        .await
+        .ok();
     }
 }
 
 pub async fn process_missing_rates<A: FiatExchanger>(db: &Db, exchange_api: &A) -> Result<()> {
+    // Ramps with a manual rate are not retried
+    ManualRateService::clear_missing(db).await?;
     // 1. Fetch unique missing rates (grouped by fiat/date)
     // We only care about distinct rates that are missing.
     let missing_items = sqlx::query_as::<sqlx::Sqlite, MissingRate>(
@@ -235,8 +471,10 @@ async fn process_and_insert_rate(
 ) -> Result<(FiatExchangeRate, bool)> {
     let diff = (*target_date - api_rates.date).num_days();
 
-    // Insert base rate = 1.0 (Frankfurter API logic)
-    api_rates.rates.insert(base_symbol.to_string(), 1.0);
+    // Insert base rate = 1 (Frankfurter API logic)
+    api_rates
+        .rates
+        .insert(base_symbol.to_string(), Decimal::ONE);
 
     let is_non_working_day = diff > 0 && api_rates.date == previous_business_day(target_date);
     let non_working_day_reason = if is_non_working_day {
@@ -252,6 +490,7 @@ async fn process_and_insert_rate(
     let (is_estimated, is_nwd, final_reason, should_retry) =
         determine_rate_status(diff, is_non_working_day, non_working_day_reason);
 
+    let provider = Some(api_rates.provider.as_str()).filter(|provider| !provider.is_empty());
     let fiat_rate = insert_rate(
         db,
         base_fiat_id,
@@ -260,6 +499,7 @@ async fn process_and_insert_rate(
         is_estimated,
         is_nwd,
         final_reason,
+        provider,
     )
     .await?;
 
@@ -291,21 +531,23 @@ fn determine_rate_status(
 }
 
 // Helper: Insert (or Replace) Rate
+#[allow(clippy::too_many_arguments)]
 async fn insert_rate(
     db: &Db,
     base_fiat_id: i64,
     date: &NaiveDate,
-    rates: &HashMap<String, f64>,
+    rates: &HashMap<String, Decimal>,
     is_estimated: bool,
     is_non_working_day: bool,
     non_working_day_reason: Option<&str>,
+    provider: Option<&str>,
 ) -> Result<FiatExchangeRate> {
     // We use INSERT OR REPLACE to handle cases where we are upgrading an estimated rate to a real one
     let sql = r#"
         INSERT OR REPLACE
         INTO fiat_exchange_rate
-        (base_fiat_id, date, rates, is_estimated, is_non_working_day, non_working_day_reason)
-        VALUES (?, ?, ?, ?, ?, ?)
+        (base_fiat_id, date, rates, is_estimated, is_non_working_day, non_working_day_reason, provider)
+        VALUES (?, ?, ?, ?, ?, ?, ?)
         RETURNING *
     "#
     .split_whitespace()
@@ -319,6 +561,7 @@ async fn insert_rate(
         .bind(is_estimated)
         .bind(is_non_working_day)
         .bind(non_working_day_reason)
+        .bind(provider)
         .fetch_one(&db.0)
         .await
         .context("Failed to insert/replace rate")?;
@@ -474,7 +717,11 @@ mod tests {
             .times(1)
             .returning(|_, _| {
                 Ok(Rates {
-                    rates: HashMap::from([("EUR".to_string(), 0.85), ("MYR".to_string(), 4.7424)]),
+                    provider: "mock".to_string(),
+                    rates: HashMap::from([
+                        ("EUR".to_string(), Decimal::new(85, 2)),
+                        ("MYR".to_string(), Decimal::new(47424, 4)),
+                    ]),
                     base: "USD".to_string(),
                     date: NaiveDate::from_ymd_opt(2026, 2, 1).unwrap(),
                 })
@@ -489,10 +736,10 @@ mod tests {
         assert!(rate.is_ok());
         let rate = rate.unwrap();
         //ensure base fiat rate is 1
-        assert_eq!(rate.rates.get("USD").unwrap(), &1.0);
+        assert_eq!(rate.rates.get("USD").unwrap(), &Decimal::ONE);
         //ensure other fiat rates are correct
-        assert_eq!(rate.rates.get("EUR").unwrap(), &0.85);
-        assert_eq!(rate.rates.get("MYR").unwrap(), &4.7424);
+        assert_eq!(rate.rates.get("EUR").unwrap(), &Decimal::new(85, 2));
+        assert_eq!(rate.rates.get("MYR").unwrap(), &Decimal::new(47424, 4));
         assert!(!rate.is_estimated);
     }
 
@@ -508,7 +755,8 @@ mod tests {
             .times(1)
             .returning(|_, _| {
                 Ok(Rates {
-                    rates: HashMap::from([("EUR".to_string(), 0.85)]),
+                    provider: "mock".to_string(),
+                    rates: HashMap::from([("EUR".to_string(), Decimal::new(85, 2))]),
                     base: "USD".to_string(),
                     date: NaiveDate::from_ymd_opt(2026, 2, 6).unwrap(),
                 })
@@ -562,7 +810,8 @@ mod tests {
             .times(1)
             .returning(|_, _| {
                 Ok(Rates {
-                    rates: HashMap::from([("EUR".to_string(), 0.85)]),
+                    provider: "mock".to_string(),
+                    rates: HashMap::from([("EUR".to_string(), Decimal::new(85, 2))]),
                     base: "USD".to_string(),
                     date: NaiveDate::from_ymd_opt(2026, 2, 1).unwrap(),
                 })
@@ -635,7 +884,8 @@ mod tests {
             .times(1)
             .returning(move |_, _| {
                 Ok(Rates {
-                    rates: HashMap::from([("USD".to_string(), 1.10)]),
+                    provider: "mock".to_string(),
+                    rates: HashMap::from([("USD".to_string(), Decimal::new(110, 2))]),
                     base: "EUR".to_string(),
                     date: exact_date,
                 })
@@ -663,4 +913,106 @@ mod tests {
 
         assert!(!rate.is_estimated);
     }
+
+    #[tokio::test]
+    async fn test_get_rate_for_pair() {
+        let db = setup().await;
+        sqlx::query("INSERT INTO fiat (symbol, name) VALUES ('MYR', 'Malaysian Ringgit'), ('SGD', 'Singapore Dollar')")
+            .execute(&db.0)
+            .await
+            .unwrap();
+        let date = NaiveDate::from_ymd_opt(2026, 2, 2).unwrap();
+
+        // a USD based rate quoting both currencies is not used for the pair
+        let mut mock_api = MockFiatExchanger::new();
+        mock_api
+            .expect_get_latest_rates()
+            .times(1)
+            .returning(|_, _| {
+                Ok(Rates {
+                    provider: "mock".to_string(),
+                    rates: HashMap::from([
+                        ("MYR".to_string(), Decimal::new(4, 0)),
+                        ("SGD".to_string(), Decimal::new(12, 1)),
+                    ]),
+                    base: "USD".to_string(),
+                    date: NaiveDate::from_ymd_opt(2026, 2, 2).unwrap(),
+                })
+            });
+        get_rate(&db, &mock_api, &date, None).await.unwrap();
+
+        let mut mock_api = MockFiatExchanger::new();
+        mock_api
+            .expect_get_latest_rates()
+            .withf(|base, _| base == "MYR")
+            .times(1)
+            .returning(|_, _| {
+                Ok(Rates {
+                    provider: "mock".to_string(),
+                    rates: HashMap::from([("SGD".to_string(), Decimal::new(31, 2))]),
+                    base: "MYR".to_string(),
+                    date: NaiveDate::from_ymd_opt(2026, 2, 2).unwrap(),
+                })
+            });
+        let pair = get_rate_for_pair(&db, &mock_api, "MYR", "SGD", &date, None)
+            .await
+            .unwrap();
+        assert_eq!(pair.rate, Decimal::new(31, 2));
+        assert!(!pair.is_estimated);
+
+        // stored now, both ways
+        let mock_api = MockFiatExchanger::new();
+        let pair = get_rate_for_pair(&db, &mock_api, "MYR", "SGD", &date, None)
+            .await
+            .unwrap();
+        assert_eq!(pair.rate, Decimal::new(31, 2));
+        let pair = get_rate_for_pair(&db, &mock_api, "SGD", "MYR", &date, None)
+            .await
+            .unwrap();
+        assert_eq!(pair.from_symbol, "SGD");
+        assert_eq!(pair.rate, Decimal::ONE / Decimal::new(31, 2));
+
+        let pair = get_rate_for_pair(&db, &mock_api, "SGD", "SGD", &date, None)
+            .await
+            .unwrap();
+        assert_eq!(pair.rate, Decimal::ONE);
+
+        // a ramp whose pair neither currency quotes is queued
+        let date = NaiveDate::from_ymd_opt(2026, 2, 3).unwrap();
+        let myr_id: i64 = sqlx::query_scalar("SELECT id FROM fiat WHERE symbol = 'MYR'")
+            .fetch_one(&db.0)
+            .await
+            .unwrap();
+        let ramp_id = "test-ramp-unquoted".to_string();
+        sqlx::query("INSERT INTO fiat_ramp (id, fiat_id, fiat_amount, ramp_date, via_exchange, kind) VALUES (?, ?, 100, ?, 'test', 'deposit')")
+            .bind(&ramp_id)
+            .bind(myr_id)
+            .bind(date)
+            .execute(&db.0).await.unwrap();
+        let mut mock_api = MockFiatExchanger::new();
+        mock_api
+            .expect_get_latest_rates()
+            .times(2)
+            .returning(|base, _| {
+                Ok(Rates {
+                    provider: "mock".to_string(),
+                    rates: HashMap::from([("USD".to_string(), Decimal::new(25, 2))]),
+                    base: base.to_string(),
+                    date: NaiveDate::from_ymd_opt(2026, 2, 3).unwrap(),
+                })
+            });
+        assert!(
+            get_rate_for_pair(&db, &mock_api, "MYR", "SGD", &date, Some(&ramp_id))
+                .await
+                .is_err()
+        );
+        let queued: (i64, NaiveDate) = sqlx::query_as(
+            "SELECT base_fiat_id, date FROM fiat_rate_missing WHERE fiat_ramp_id = ?",
+        )
+        .bind(&ramp_id)
+        .fetch_one(&db.0)
+        .await
+        .unwrap();
+        assert_eq!(queued, (myr_id, date));
+    }
 }
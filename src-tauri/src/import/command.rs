@@ -0,0 +1,139 @@
+use crate::db::Db;
+use crate::fiat_exchanger::FiatExchangerManager;
+use crate::fiat_ramp::FiatRamp;
+use crate::fiat_rate::{self, backfill};
+use crate::import::duplicate::DuplicatePolicy;
+use crate::import::statement::{
+    StatementImportReport, StatementImportService, StatementParserInfo,
+};
+use crate::import::{ColumnMapping, FiatRampImportReport, ImportMapping, ImportService};
+use std::path::PathBuf;
+use tauri::State;
+
+/// Headers of the file at `file_path`, to build a column mapping from
+#[tauri::command]
+pub async fn get_import_file_headers(file_path: PathBuf) -> Result<Vec<String>, String> {
+    ImportService::get_headers(&file_path).map_err(|e| format!("failed to read file: {e:#}"))
+}
+
+/// Validate every row of the file, nothing is written
+#[tauri::command]
+pub async fn preview_fiat_ramp_import(
+    file_path: PathBuf,
+    mapping: ColumnMapping,
+    db: State<'_, Db>,
+) -> Result<FiatRampImportReport, String> {
+    ImportService::preview(&file_path, &mapping, &db)
+        .await
+        .map_err(|e| format!("failed to preview import: {e:#}"))
+}
+
+/// Create the new rows of the file as fiat ramps and fetch their rates
+/// - rows already recorded are skipped unless `duplicate_policy` is merge
+#[tauri::command]
+pub async fn import_fiat_ramps(
+    file_path: PathBuf,
+    mapping: ColumnMapping,
+    duplicate_policy: Option<DuplicatePolicy>,
+    db: State<'_, Db>,
+    fiat_exchanger: State<'_, FiatExchangerManager>,
+) -> Result<FiatRampImportReport, String> {
+    let policy = duplicate_policy.unwrap_or_default();
+    let (mut report, created) = ImportService::commit(&file_path, &mapping, policy, &db)
+        .await
+        .map_err(|e| format!("failed to import fiat ramps: {e:#}"))?;
+
+    report.rate_errors = fetch_rates(&created, &db, fiat_exchanger.inner()).await;
+    Ok(report)
+}
+
+#[tauri::command]
+pub async fn save_import_mapping(
+    name: String,
+    mapping: ColumnMapping,
+    db: State<'_, Db>,
+) -> Result<ImportMapping, String> {
+    ImportService::save_mapping(&name, mapping, &db)
+        .await
+        .map_err(|e| format!("failed to save import mapping: {e:#}"))
+}
+
+#[tauri::command]
+pub async fn get_import_mappings(db: State<'_, Db>) -> Result<Vec<ImportMapping>, String> {
+    ImportService::get_mappings(&db)
+        .await
+        .map_err(|e| format!("failed to get import mappings: {e:#}"))
+}
+
+#[tauri::command]
+pub async fn delete_import_mapping(id: String, db: State<'_, Db>) -> Result<u64, String> {
+    ImportService::delete_mapping(&id, &db)
+        .await
+        .map_err(|e| format!("failed to delete import mapping: {e:#}"))
+}
+
+#[tauri::command]
+pub async fn get_statement_parsers() -> Result<Vec<StatementParserInfo>, String> {
+    Ok(StatementImportService::get_parsers())
+}
+
+/// Parse an exchange statement, nothing is written
+/// - the layout is detected from the headers when `parser_id` is None
+#[tauri::command]
+pub async fn preview_statement_import(
+    file_path: PathBuf,
+    parser_id: Option<String>,
+    db: State<'_, Db>,
+) -> Result<StatementImportReport, String> {
+    StatementImportService::preview(&file_path, parser_id.as_deref(), &db)
+        .await
+        .map_err(|e| format!("failed to preview statement import: {e:#}"))
+}
+
+/// Create the new fiat ramps and trades of an exchange statement
+/// - fiat ramps already recorded are skipped unless `duplicate_policy` is merge
+#[tauri::command]
+pub async fn import_statement(
+    file_path: PathBuf,
+    parser_id: Option<String>,
+    duplicate_policy: Option<DuplicatePolicy>,
+    db: State<'_, Db>,
+    fiat_exchanger: State<'_, FiatExchangerManager>,
+) -> Result<StatementImportReport, String> {
+    let policy = duplicate_policy.unwrap_or_default();
+    let (mut report, created) =
+        StatementImportService::commit(&file_path, parser_id.as_deref(), policy, &db)
+            .await
+            .map_err(|e| format!("failed to import statement: {e:#}"))?;
+    report.rate_errors = fetch_rates(&created, &db, fiat_exchanger.inner()).await;
+    Ok(report)
+}
+
+/// Fetch the rates of imported fiat ramps, returns the failures for the report
+/// - the dates are backfilled with one range request per currency first, so that the per ramp
+///   lookups are served from the stored rates
+/// - a ramp whose rate the providers cannot serve is queued in `fiat_rate_missing` by
+///   get_rate_for_ramp and retried later
+async fn fetch_rates(fiat_ramps: &[FiatRamp], db: &Db, api: &FiatExchangerManager) -> Vec<String> {
+    let mut errors = Vec::new();
+    if let Err(e) = backfill::backfill_rates(db, api).await {
+        errors.push(format!("failed to backfill rates: {e:#}"));
+    }
+    let futures = fiat_ramps.iter().map(|fiat_ramp| async move {
+        fiat_rate::get_rate_for_ramp(db, api, &fiat_ramp.id)
+            .await
+            .map_err(|e| {
+                format!(
+                    "failed to get rate of the fiat ramp on {}: {e:#}",
+                    fiat_ramp.ramp_date
+                )
+            })
+    });
+    errors.extend(
+        futures::future::join_all(futures)
+            .await
+            .into_iter()
+            .filter_map(Result::err),
+    );
+    errors
+}
@@ -0,0 +1,324 @@
+use super::{ImportRow, ImportRowStatus};
+use crate::db::StringRowId;
+use crate::exchange::{Exchange, ExchangeRef, ExchangeService};
+use crate::fiat::Fiat;
+use crate::fiat_ramp::{fingerprint, CreateFiatRamp, FiatRamp, FiatRampService, UpdateFiatRamp};
+use crate::trade::fingerprint::{self as trade_fingerprint, TradeIdentity};
+use crate::trade::{CreateTrade, TradeService};
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use sqlx::SqliteConnection;
+use std::collections::{HashMap, HashSet};
+
+/// What a commit does with the rows matching a fiat ramp or trade already recorded
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicatePolicy {
+    /// leave the recorded ramp or trade untouched
+    #[default]
+    Skip,
+    /// duplicates lend their external reference to the recorded ramp or trade,
+    /// conflicts overwrite the recorded ramp or trade with the values of the file
+    Merge,
+}
+
+/// Recorded fiat ramps and trades overwritten by `merge`
+pub struct Merged {
+    /// the ramps need their rates fetched again
+    pub ramps: Vec<FiatRamp>,
+    pub trade_count: usize,
+}
+
+#[derive(FromRow)]
+struct Recorded {
+    id: StringRowId,
+    fingerprint: Option<String>,
+}
+
+/// The table a row is matched against
+#[derive(Clone, Copy)]
+enum Table {
+    FiatRamp,
+    Trade,
+}
+
+impl Table {
+    fn name(self) -> &'static str {
+        match self {
+            Table::FiatRamp => "fiat_ramp",
+            Table::Trade => "trade",
+        }
+    }
+
+    fn date_column(self) -> &'static str {
+        match self {
+            Table::FiatRamp => "ramp_date",
+            Table::Trade => "trade_date",
+        }
+    }
+}
+
+/// Mark the accepted rows whose ramp or trade is already recorded as duplicates or conflicts
+/// - a ramp or trade with an external reference matches the one of the same exchange and
+///   reference, it is a conflict when their fingerprints differ
+/// - others match a recorded ramp or trade with the same fingerprint, each recorded one is
+///   matched at most once so a file listing the same ramp twice keeps both
+/// - a reference repeated within the rows is a duplicate of its first row
+pub async fn classify(
+    rows: &mut [ImportRow],
+    fiats: &[Fiat],
+    conn: &mut SqliteConnection,
+) -> Result<()> {
+    fingerprint::refresh(conn).await?;
+    trade_fingerprint::refresh(conn).await?;
+
+    let mut exchanges: HashMap<ExchangeRef, Option<Exchange>> = HashMap::new();
+    let mut matched: HashSet<StringRowId> = HashSet::new();
+    let mut references: HashSet<(&'static str, String, String)> = HashSet::new();
+
+    for row in rows.iter_mut() {
+        if row.status != ImportRowStatus::Accepted {
+            continue;
+        }
+        let (table, exchange_ref, external_ref) = match (&row.ramp, &row.trade) {
+            (Some(ramp), _) => (
+                Table::FiatRamp,
+                Some(&ramp.via_exchange),
+                &ramp.external_ref,
+            ),
+            (None, Some(trade)) => (
+                Table::Trade,
+                trade.via_exchange.as_ref(),
+                &trade.external_ref,
+            ),
+            (None, None) => continue,
+        };
+
+        let exchange = match exchange_ref {
+            Some(exchange_ref) => {
+                if !exchanges.contains_key(exchange_ref) {
+                    let exchange = match exchange_ref {
+                        ExchangeRef::Id { id } => {
+                            Some(ExchangeService::get_by_id_in(id, conn).await?)
+                        }
+                        ExchangeRef::Name(name) => {
+                            ExchangeService::find_by_name_in(name, conn).await?
+                        }
+                    };
+                    exchanges.insert(exchange_ref.clone(), exchange);
+                }
+                exchanges[exchange_ref].as_ref()
+            }
+            None => None,
+        };
+        let exchange_name = match (exchange, exchange_ref) {
+            (Some(exchange), _) => exchange.name.clone(),
+            (None, Some(ExchangeRef::Name(name))) => name.clone(),
+            (None, Some(ExchangeRef::Id { id })) => id.clone(),
+            (None, None) => String::new(),
+        };
+        let fingerprint = match (&row.ramp, &row.trade) {
+            (Some(ramp), _) => ramp_fingerprint(ramp, fiats, &exchange_name)?,
+            (None, Some(trade)) => trade_fingerprint::fingerprint(&TradeIdentity {
+                trade_date: trade.trade_date,
+                kind: trade.kind,
+                asset_id: &trade.asset_id,
+                quantity: trade.quantity,
+                price: trade.price,
+                to_asset_id: trade.to_asset_id.as_deref(),
+                to_quantity: trade.to_quantity,
+                exchange: &exchange_name,
+            }),
+            (None, None) => continue,
+        };
+
+        if let Some(external_ref) = external_ref {
+            let key = (
+                table.name(),
+                exchange_name.to_lowercase(),
+                external_ref.clone(),
+            );
+            if !references.insert(key) {
+                row.status = ImportRowStatus::Duplicate;
+                continue;
+            }
+
+            if let Some(exchange) = exchange {
+                let recorded = sqlx::query_as::<sqlx::Sqlite, Recorded>(&format!(
+                    "SELECT id, fingerprint FROM {} WHERE exchange_id = ? AND external_ref = ?",
+                    table.name()
+                ))
+                .bind(&exchange.id)
+                .bind(external_ref)
+                .fetch_optional(&mut *conn)
+                .await
+                .with_context(|| format!("failed to get {} by external reference", table.name()))?;
+                if let Some(recorded) = recorded {
+                    row.status = if recorded.fingerprint.as_deref() == Some(&fingerprint) {
+                        ImportRowStatus::Duplicate
+                    } else {
+                        ImportRowStatus::Conflict
+                    };
+                    matched.insert(recorded.id.clone());
+                    row.duplicate_of = Some(recorded.id);
+                    continue;
+                }
+            }
+        }
+
+        // one recorded with another reference is another ramp or trade, whatever its fingerprint
+        let candidates: Vec<StringRowId> = sqlx::query_scalar(&format!(
+            r#"
+            SELECT id FROM {}
+            WHERE fingerprint = ? AND (external_ref IS NULL OR ? IS NULL)
+            ORDER BY {}, id
+            "#,
+            table.name(),
+            table.date_column()
+        ))
+        .bind(&fingerprint)
+        .bind(external_ref)
+        .fetch_all(&mut *conn)
+        .await
+        .with_context(|| format!("failed to get {} by fingerprint", table.name()))?;
+        if let Some(id) = candidates.into_iter().find(|id| !matched.contains(id)) {
+            matched.insert(id.clone());
+            row.status = ImportRowStatus::Duplicate;
+            row.duplicate_of = Some(id);
+        }
+    }
+    Ok(())
+}
+
+fn ramp_fingerprint(ramp: &CreateFiatRamp, fiats: &[Fiat], exchange_name: &str) -> Result<String> {
+    let fiat = fiats
+        .iter()
+        .find(|fiat| fiat.id == ramp.fiat_id)
+        .ok_or_else(|| anyhow!("fiat {} not found", ramp.fiat_id))?;
+    Ok(fingerprint::fingerprint(
+        ramp.ramp_date,
+        ramp.kind,
+        ramp.fiat_amount,
+        &fiat.symbol,
+        exchange_name,
+    ))
+}
+
+/// Apply `DuplicatePolicy::Merge` to the duplicate and conflict rows
+pub async fn merge(rows: &[ImportRow], conn: &mut SqliteConnection) -> Result<Merged> {
+    let mut merged = Merged {
+        ramps: Vec::new(),
+        trade_count: 0,
+    };
+    for row in rows {
+        let Some(id) = &row.duplicate_of else {
+            continue;
+        };
+        match (row.status, &row.ramp, &row.trade) {
+            (ImportRowStatus::Duplicate, Some(ramp), _) => {
+                set_external_ref(Table::FiatRamp, id, &ramp.external_ref, conn).await?;
+            }
+            (ImportRowStatus::Duplicate, None, Some(trade)) => {
+                set_external_ref(Table::Trade, id, &trade.external_ref, conn).await?;
+            }
+            (ImportRowStatus::Conflict, Some(ramp), _) => {
+                merged
+                    .ramps
+                    .push(overwrite(id, ramp, conn).await.with_context(|| {
+                        format!("failed to merge line {} into fiat ramp", row.row_number)
+                    })?);
+            }
+            (ImportRowStatus::Conflict, None, Some(trade)) => {
+                overwrite_trade(id, trade, conn).await.with_context(|| {
+                    format!("failed to merge line {} into trade", row.row_number)
+                })?;
+                merged.trade_count += 1;
+            }
+            _ => {}
+        }
+    }
+    Ok(merged)
+}
+
+async fn set_external_ref(
+    table: Table,
+    id: &StringRowId,
+    external_ref: &Option<String>,
+    conn: &mut SqliteConnection,
+) -> Result<()> {
+    let updated_at = match table {
+        Table::FiatRamp => ", updated_at = CURRENT_TIMESTAMP",
+        Table::Trade => "",
+    };
+    sqlx::query(&format!(
+        "UPDATE {} SET external_ref = ?{updated_at} WHERE id = ? AND external_ref IS NULL",
+        table.name()
+    ))
+    .bind(external_ref)
+    .bind(id)
+    .execute(&mut *conn)
+    .await
+    .with_context(|| format!("failed to set external reference of {}", table.name()))?;
+    Ok(())
+}
+
+async fn overwrite(
+    id: &StringRowId,
+    ramp: &CreateFiatRamp,
+    conn: &mut SqliteConnection,
+) -> Result<FiatRamp> {
+    FiatRampService::update_in(
+        UpdateFiatRamp {
+            id: id.clone(),
+            fiat_id: Some(ramp.fiat_id),
+            fiat_amount: Some(ramp.fiat_amount),
+            ramp_date: Some(ramp.ramp_date),
+            kind: Some(ramp.kind),
+            via_exchange: Some(ramp.via_exchange.clone()),
+            asset_id: ramp.asset_id.clone(),
+            fee_fiat_id: ramp.fee_fiat_id,
+            fee_amount: ramp.fee_amount,
+        },
+        conn,
+    )
+    .await
+    .map_err(|e| anyhow!(e))?;
+
+    sqlx::query_as::<sqlx::Sqlite, FiatRamp>(
+        r#"
+        SELECT id, fiat_id, asset_id, fiat_amount, ramp_date, kind, via_exchange, exchange_id,
+            external_ref, fee_fiat_id, fee_amount
+        FROM fiat_ramp WHERE id = ?
+        "#,
+    )
+    .bind(id)
+    .fetch_one(&mut *conn)
+    .await
+    .context("failed to get merged fiat ramp")
+}
+
+/// Overwrite the recorded trade, its link to a fiat ramp and its note are kept
+async fn overwrite_trade(
+    id: &StringRowId,
+    trade: &CreateTrade,
+    conn: &mut SqliteConnection,
+) -> Result<()> {
+    let (fiat_ramp_id, note): (Option<StringRowId>, Option<String>) =
+        sqlx::query_as("SELECT fiat_ramp_id, note FROM trade WHERE id = ?")
+            .bind(id)
+            .fetch_one(&mut *conn)
+            .await
+            .context("failed to get recorded trade")?;
+    TradeService::update_in(
+        id,
+        CreateTrade {
+            fiat_ramp_id,
+            note,
+            ..trade.clone()
+        },
+        conn,
+    )
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,865 @@
+pub mod command;
+pub mod duplicate;
+pub mod reader;
+pub mod statement;
+use crate::db::{Db, RowId, StringRowId};
+use crate::exchange::ExchangeRef;
+use crate::fiat::{Fiat, FiatService};
+use crate::fiat_exchanger::frankfurter_exchanger::FrankfurterExchangerApi;
+use crate::fiat_ramp::{CreateFiatRamp, FiatRamp, FiatRampService, RampKind};
+use crate::trade::CreateTrade;
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{NaiveDate, NaiveDateTime};
+use duplicate::DuplicatePolicy;
+use reader::Sheet;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::{prelude::FromRow, types::Json, SqliteConnection};
+use std::collections::HashMap;
+use std::path::Path;
+use uuid::Uuid;
+
+/// Exchange recorded when neither the file nor the mapping names one
+const DEFAULT_IMPORT_EXCHANGE: &str = "Imported";
+
+/// Date layouts tried in order by `DateFormat::Auto`
+/// - a date that reads both day first and month first is rejected, see `is_ambiguous_day_month`
+const AUTO_DATE_FORMATS: [&str; 6] = [
+    "%Y-%m-%d", "%d/%m/%Y", "%m/%d/%Y", "%d-%m-%Y", "%Y/%m/%d", "%d %b %Y",
+];
+const AUTO_DATE_TIME_FORMATS: [&str; 4] = [
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%d/%m/%Y %H:%M:%S",
+    "%Y-%m-%d %H:%M",
+];
+
+/// How the date column is written
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DateFormat {
+    /// ISO dates and the common day first layouts
+    /// - a date whose day and month can be swapped, e.g. `03/04/2024`, is rejected, a pattern
+    ///   must be set for such files
+    #[default]
+    Auto,
+    /// chrono strftime pattern, e.g. `%d.%m.%Y`
+    Pattern { format: String },
+    /// unix timestamp in seconds
+    TimestampS,
+    /// unix timestamp in milliseconds
+    TimestampMs,
+    /// spreadsheet serial day number
+    Excel,
+}
+
+/// Which columns of the file hold the fields of a fiat ramp, columns are matched by header
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ColumnMapping {
+    pub date: String,
+    pub amount: String,
+    /// fiat symbol or name, `default_fiat_id` is used when None or blank
+    pub currency: Option<String>,
+    /// when None the sign of the amount decides, positive is a deposit
+    pub kind: Option<String>,
+    /// when None or blank `via_exchange` is used
+    pub exchange: Option<String>,
+    /// transaction id at the exchange, re-imported rows are matched on it
+    pub external_ref: Option<String>,
+    #[serde(default)]
+    pub date_format: DateFormat,
+    /// raw value of the kind column to its ramp kind, null to skip the row
+    /// - unmapped values are accepted when they read "deposit" or "withdraw"
+    #[serde(default)]
+    pub kind_values: HashMap<String, Option<RampKind>>,
+    pub default_fiat_id: Option<RowId>,
+    pub via_exchange: Option<String>,
+}
+
+/// A column mapping saved under a name, e.g. per exchange the statements come from
+#[derive(Debug, FromRow, Serialize, Deserialize)]
+pub struct ImportMapping {
+    pub id: StringRowId,
+    pub name: String,
+    pub mapping: Json<ColumnMapping>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportRowStatus {
+    Accepted,
+    Rejected,
+    /// the kind value is mapped to be ignored
+    Skipped,
+    /// the ramp is already recorded
+    Duplicate,
+    /// a ramp with the same external reference is recorded with other values
+    Conflict,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImportRow {
+    /// line of the row in the file, starting at 1
+    pub row_number: usize,
+    pub status: ImportRowStatus,
+    pub raw: Vec<String>,
+    /// the ramp to be created, set when an accepted row is a deposit or withdrawal
+    pub ramp: Option<CreateFiatRamp>,
+    /// the trade to be created, set when an accepted row is a trade
+    pub trade: Option<CreateTrade>,
+    /// the recorded fiat ramp or trade a duplicate or conflict row matches
+    pub duplicate_of: Option<StringRowId>,
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FiatRampImportReport {
+    pub rows: Vec<ImportRow>,
+    /// number of new fiat ramps
+    pub accepted_count: usize,
+    pub rejected_count: usize,
+    pub skipped_count: usize,
+    pub duplicate_count: usize,
+    pub conflict_count: usize,
+    /// number of fiat ramps created, 0 for a preview
+    pub imported_count: usize,
+    /// number of recorded fiat ramps updated from duplicate and conflict rows, 0 for a preview
+    pub merged_count: usize,
+    /// rates of the created and merged ramps that could not be fetched, empty for a preview
+    pub rate_errors: Vec<String>,
+}
+
+impl FiatRampImportReport {
+    /// Count the rows by status, the counts are only ever set here
+    fn recount(&mut self) {
+        let count = |status| self.rows.iter().filter(|row| row.status == status).count();
+        self.accepted_count = count(ImportRowStatus::Accepted);
+        self.rejected_count = count(ImportRowStatus::Rejected);
+        self.skipped_count = count(ImportRowStatus::Skipped);
+        self.duplicate_count = count(ImportRowStatus::Duplicate);
+        self.conflict_count = count(ImportRowStatus::Conflict);
+    }
+}
+
+pub struct ImportService {}
+
+impl ImportService {
+    /// Save the mapping under `name`, replacing the mapping already saved under that name
+    pub async fn save_mapping(
+        name: &str,
+        mapping: ColumnMapping,
+        db: &Db,
+    ) -> Result<ImportMapping> {
+        let name = name.trim();
+        if name.is_empty() {
+            bail!("mapping name must not be empty");
+        }
+
+        sqlx::query_as::<sqlx::Sqlite, ImportMapping>(
+            r#"
+            INSERT INTO import_mapping (id, name, mapping)
+            VALUES (?, ?, ?)
+            ON CONFLICT (name) DO UPDATE SET mapping = excluded.mapping
+            RETURNING id, name, mapping
+            "#,
+        )
+        .bind(Uuid::now_v7().to_string())
+        .bind(name)
+        .bind(Json(mapping))
+        .fetch_one(&db.0)
+        .await
+        .context("failed to save import mapping")
+    }
+
+    /// Get all saved mappings ordered by name
+    pub async fn get_mappings(db: &Db) -> Result<Vec<ImportMapping>> {
+        sqlx::query_as::<sqlx::Sqlite, ImportMapping>(
+            "SELECT id, name, mapping FROM import_mapping ORDER BY name",
+        )
+        .fetch_all(&db.0)
+        .await
+        .context("failed to get import mappings")
+    }
+
+    /// Delete the saved mapping, returns the number of rows affected
+    pub async fn delete_mapping(id: &str, db: &Db) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM import_mapping WHERE id = ?")
+            .bind(id)
+            .execute(&db.0)
+            .await
+            .context("failed to delete import mapping")?;
+        Ok(result.rows_affected())
+    }
+
+    /// Headers of the file, to build a mapping from
+    pub fn get_headers(file_path: &Path) -> Result<Vec<String>> {
+        Ok(reader::read_sheet(file_path)?.headers)
+    }
+
+    /// Validate every row of the file and look for ramps already recorded, nothing is written
+    /// - the fingerprints refreshed to look for duplicates are rolled back with the transaction
+    pub async fn preview(
+        file_path: &Path,
+        mapping: &ColumnMapping,
+        db: &Db,
+    ) -> Result<FiatRampImportReport> {
+        let fiats = FiatService::<FrankfurterExchangerApi>::get_all_fiat(db).await?;
+        let mut tx =
+            db.0.begin_with("BEGIN IMMEDIATE")
+                .await
+                .context("failed to begin transaction")?;
+        let report = Self::read(file_path, mapping, &fiats, &mut tx).await?;
+        tx.rollback()
+            .await
+            .context("failed to roll back transaction")?;
+        Ok(report)
+    }
+
+    /// Validate the file and create the new rows, rejected and skipped rows are left out
+    /// - duplicates and conflicts are skipped or merged depending on `policy`
+    /// - returns the report along with the created and merged fiat ramps
+    /// - the rows are classified, created and merged in one write transaction, nothing is
+    ///   written when any of it fails
+    pub async fn commit(
+        file_path: &Path,
+        mapping: &ColumnMapping,
+        policy: DuplicatePolicy,
+        db: &Db,
+    ) -> Result<(FiatRampImportReport, Vec<FiatRamp>)> {
+        let fiats = FiatService::<FrankfurterExchangerApi>::get_all_fiat(db).await?;
+        let mut tx =
+            db.0.begin_with("BEGIN IMMEDIATE")
+                .await
+                .context("failed to begin transaction")?;
+        let mut report = Self::read(file_path, mapping, &fiats, &mut tx).await?;
+        let ramps: Vec<CreateFiatRamp> = report
+            .rows
+            .iter()
+            .filter(|row| row.status == ImportRowStatus::Accepted)
+            .filter_map(|row| row.ramp.clone())
+            .collect();
+        let mut created = if ramps.is_empty() {
+            Vec::new()
+        } else {
+            FiatRampService::create_bulk_in(ramps, &mut tx)
+                .await
+                .map_err(|e| anyhow!(e))
+                .context("failed to create imported fiat ramps")?
+        };
+        report.imported_count = created.len();
+
+        if policy == DuplicatePolicy::Merge {
+            let merged = duplicate::merge(&report.rows, &mut tx).await?;
+            report.merged_count = merged.ramps.len();
+            created.extend(merged.ramps);
+        }
+        tx.commit().await.context("failed to commit transaction")?;
+        Ok((report, created))
+    }
+
+    /// Validate every row of the file and mark the rows already recorded, on `conn`
+    async fn read(
+        file_path: &Path,
+        mapping: &ColumnMapping,
+        fiats: &[Fiat],
+        conn: &mut SqliteConnection,
+    ) -> Result<FiatRampImportReport> {
+        let sheet = reader::read_sheet(file_path)?;
+        let mut report = validate(&sheet, mapping, fiats)?;
+        duplicate::classify(&mut report.rows, fiats, conn).await?;
+        report.recount();
+        Ok(report)
+    }
+}
+
+/// Turn every row of the sheet into an accepted, rejected or skipped row
+fn validate(
+    sheet: &Sheet,
+    mapping: &ColumnMapping,
+    fiats: &[Fiat],
+) -> Result<FiatRampImportReport> {
+    let column = |name: &str| {
+        sheet
+            .column_index(name)
+            .ok_or_else(|| anyhow!("column '{name}' not found in the file"))
+    };
+    let optional_column = |name: &Option<String>| match name.as_deref().map(str::trim) {
+        Some(name) if !name.is_empty() => column(name).map(Some),
+        _ => Ok(None),
+    };
+    let date_column = column(&mapping.date)?;
+    let amount_column = column(&mapping.amount)?;
+    let currency_column = optional_column(&mapping.currency)?;
+    let kind_column = optional_column(&mapping.kind)?;
+    let exchange_column = optional_column(&mapping.exchange)?;
+    let external_ref_column = optional_column(&mapping.external_ref)?;
+
+    let default_fiat = match mapping.default_fiat_id {
+        Some(id) => Some(
+            fiats
+                .iter()
+                .find(|fiat| fiat.id == id)
+                .ok_or_else(|| anyhow!("default fiat {id} not found"))?,
+        ),
+        None => None,
+    };
+    let default_exchange = mapping
+        .via_exchange
+        .as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .unwrap_or(DEFAULT_IMPORT_EXCHANGE);
+
+    let mut report = FiatRampImportReport {
+        rows: Vec::with_capacity(sheet.rows.len()),
+        accepted_count: 0,
+        rejected_count: 0,
+        skipped_count: 0,
+        duplicate_count: 0,
+        conflict_count: 0,
+        imported_count: 0,
+        merged_count: 0,
+        rate_errors: Vec::new(),
+    };
+
+    for row in &sheet.rows {
+        let cell = |column: Option<usize>| column.and_then(|column| row.cell(column));
+        let mut errors = Vec::new();
+
+        let ramp_date = match cell(Some(date_column)) {
+            Some(value) => parse_date(value, &mapping.date_format)
+                .map_err(|e| errors.push(format!("{e:#}")))
+                .ok(),
+            None => {
+                errors.push("date is missing".to_string());
+                None
+            }
+        };
+
+        let amount = match cell(Some(amount_column)) {
+            Some(value) => parse_amount(value)
+                .map_err(|e| errors.push(format!("{e:#}")))
+                .ok(),
+            None => {
+                errors.push("amount is missing".to_string());
+                None
+            }
+        };
+        if amount == Some(Decimal::ZERO) {
+            errors.push("amount must not be zero".to_string());
+        }
+
+        let kind = match (cell(kind_column), amount) {
+            (Some(value), _) => match parse_kind(value, &mapping.kind_values) {
+                Ok(Some(kind)) => Some(kind),
+                Ok(None) => {
+                    report.rows.push(ImportRow {
+                        row_number: row.line,
+                        status: ImportRowStatus::Skipped,
+                        raw: row.cells.clone(),
+                        ramp: None,
+                        trade: None,
+                        duplicate_of: None,
+                        errors: Vec::new(),
+                    });
+                    continue;
+                }
+                Err(e) => {
+                    errors.push(format!("{e:#}"));
+                    None
+                }
+            },
+            (None, Some(amount)) if kind_column.is_none() => Some(if amount.is_sign_negative() {
+                RampKind::Withdraw
+            } else {
+                RampKind::Deposit
+            }),
+            (None, _) => {
+                if kind_column.is_some() {
+                    errors.push("kind is missing".to_string());
+                }
+                None
+            }
+        };
+
+        let fiat = match cell(currency_column) {
+            Some(value) => {
+                let fiat = fiats.iter().find(|fiat| {
+                    fiat.symbol.eq_ignore_ascii_case(value) || fiat.name.eq_ignore_ascii_case(value)
+                });
+                if fiat.is_none() {
+                    errors.push(format!("unknown currency '{value}'"));
+                }
+                fiat
+            }
+            None => {
+                if default_fiat.is_none() {
+                    errors.push("currency is missing and no default currency is set".to_string());
+                }
+                default_fiat
+            }
+        };
+
+        let via_exchange = cell(exchange_column).unwrap_or(default_exchange);
+
+        let (status, ramp) = match (ramp_date, amount, kind, fiat) {
+            (Some(ramp_date), Some(amount), Some(kind), Some(fiat)) if errors.is_empty() => {
+                let ramp = CreateFiatRamp {
+                    fiat_id: fiat.id,
+                    fiat_amount: amount.abs(),
+                    ramp_date,
+                    via_exchange: ExchangeRef::Name(via_exchange.to_string()),
+                    kind,
+                    external_ref: cell(external_ref_column).map(str::to_string),
+                    asset_id: None,
+                    fee_fiat_id: None,
+                    fee_amount: None,
+                };
+                (ImportRowStatus::Accepted, Some(ramp))
+            }
+            _ => (ImportRowStatus::Rejected, None),
+        };
+        report.rows.push(ImportRow {
+            row_number: row.line,
+            status,
+            raw: row.cells.clone(),
+            ramp,
+            trade: None,
+            duplicate_of: None,
+            errors,
+        });
+    }
+
+    report.recount();
+    Ok(report)
+}
+
+/// Parse the date of a row, the time of day is dropped
+pub fn parse_date(value: &str, format: &DateFormat) -> Result<NaiveDate> {
+    let date = match format {
+        DateFormat::Auto if is_ambiguous_day_month(value) => {
+            bail!("ambiguous date '{value}', day and month can be swapped, set the date format")
+        }
+        DateFormat::Auto => chrono::DateTime::parse_from_rfc3339(value)
+            .map(|date_time| date_time.date_naive())
+            .ok()
+            .or_else(|| {
+                AUTO_DATE_TIME_FORMATS.iter().find_map(|format| {
+                    NaiveDateTime::parse_from_str(value, format)
+                        .map(|date_time| date_time.date())
+                        .ok()
+                })
+            })
+            .or_else(|| {
+                AUTO_DATE_FORMATS
+                    .iter()
+                    .find_map(|format| NaiveDate::parse_from_str(value, format).ok())
+            }),
+        DateFormat::Pattern { format } => {
+            NaiveDate::parse_from_str(value, format).ok().or_else(|| {
+                NaiveDateTime::parse_from_str(value, format)
+                    .map(|date_time| date_time.date())
+                    .ok()
+            })
+        }
+        DateFormat::TimestampS | DateFormat::TimestampMs => {
+            let timestamp: i64 = value
+                .parse::<f64>()
+                .with_context(|| format!("invalid timestamp '{value}'"))?
+                as i64;
+            let millis = if *format == DateFormat::TimestampS {
+                timestamp.saturating_mul(1000)
+            } else {
+                timestamp
+            };
+            chrono::DateTime::from_timestamp_millis(millis).map(|date_time| date_time.date_naive())
+        }
+        DateFormat::Excel => {
+            let serial: f64 = value
+                .parse()
+                .with_context(|| format!("invalid spreadsheet date '{value}'"))?;
+            // serial day 1 is 1900-01-01, counted as if 1900 were a leap year
+            NaiveDate::from_ymd_opt(1899, 12, 30)
+                .and_then(|epoch| epoch.checked_add_signed(chrono::Duration::days(serial as i64)))
+        }
+    };
+    date.ok_or_else(|| anyhow!("invalid date '{value}'"))
+}
+
+/// Whether a date with the year last could be read day first and month first, e.g. `03/04/2024`
+/// - the same number twice, e.g. `04/04/2024`, reads the same either way
+fn is_ambiguous_day_month(value: &str) -> bool {
+    let date = value.split([' ', 'T']).next().unwrap_or(value);
+    let parts: Vec<&str> = date.split(['/', '-']).collect();
+    match parts.as_slice() {
+        [first, second, year] if year.len() == 4 => {
+            match (first.parse::<u32>(), second.parse::<u32>()) {
+                (Ok(first), Ok(second)) => first != second && first <= 12 && second <= 12,
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+/// Parse an amount written with or without thousands separators, e.g. `1,234.50`, `1.234,50` or `-20`
+/// - a currency symbol or code around the number is ignored, e.g. `€1,000.00` or `100 EUR`
+pub fn parse_amount(value: &str) -> Result<Decimal> {
+    normalize_number(value)
+        .and_then(|number| Decimal::from_str_exact(&number).ok())
+        .ok_or_else(|| anyhow!("invalid amount '{value}'"))
+}
+
+/// Same as `parse_amount`, for quantities and prices
+pub fn parse_decimal(value: &str) -> Result<Decimal> {
+    normalize_number(value)
+        .and_then(|number| Decimal::from_str_exact(&number).ok())
+        .ok_or_else(|| anyhow!("invalid number '{value}'"))
+}
+
+/// Rewrite a number in the plain `-1234.5` form
+fn normalize_number(value: &str) -> Option<String> {
+    let is_number_char = |c: char| c.is_ascii_digit() || c == '.' || c == ',';
+    let start = value.find(is_number_char)?;
+    let negative = value[..start].contains('-');
+    let mut cleaned: String = value[start..]
+        .trim_end_matches(|c: char| !is_number_char(c))
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '\'' && *c != '_')
+        .collect();
+
+    let decimal_separator = match (cleaned.rfind('.'), cleaned.rfind(',')) {
+        (Some(dot), Some(comma)) => Some(if dot > comma { '.' } else { ',' }),
+        (None, Some(comma)) => {
+            // a single comma followed by up to two digits reads as a decimal comma
+            let is_decimal = cleaned.matches(',').count() == 1 && cleaned.len() - comma <= 3;
+            is_decimal.then_some(',')
+        }
+        _ => Some('.'),
+    };
+    cleaned = cleaned
+        .chars()
+        .filter_map(|c| match c {
+            '.' | ',' if Some(c) == decimal_separator => Some('.'),
+            '.' | ',' => None,
+            c => Some(c),
+        })
+        .collect();
+
+    if negative {
+        cleaned.insert(0, '-');
+    }
+    Some(cleaned)
+}
+
+/// Kind of a row, None when the value is mapped to be skipped
+fn parse_kind(
+    value: &str,
+    kind_values: &HashMap<String, Option<RampKind>>,
+) -> Result<Option<RampKind>> {
+    if let Some(kind) = kind_values.get(value) {
+        return Ok(*kind);
+    }
+    match value.to_lowercase().as_str() {
+        "deposit" => Ok(Some(RampKind::Deposit)),
+        "withdraw" | "withdrawal" => Ok(Some(RampKind::Withdraw)),
+        _ => bail!("unmapped kind '{value}'"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reader::SheetRow;
+
+    fn fiats() -> Vec<Fiat> {
+        vec![
+            Fiat {
+                id: 1,
+                symbol: "EUR".to_string(),
+                name: "Euro".to_string(),
+                ..Default::default()
+            },
+            Fiat {
+                id: 2,
+                symbol: "USD".to_string(),
+                name: "US Dollar".to_string(),
+                ..Default::default()
+            },
+        ]
+    }
+
+    fn sheet(rows: &[&[&str]]) -> Sheet {
+        Sheet {
+            headers: vec!["Date", "Amount", "Currency", "Type"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            rows: rows
+                .iter()
+                .enumerate()
+                .map(|(index, row)| SheetRow {
+                    line: index + 2,
+                    cells: row.iter().map(|cell| cell.to_string()).collect(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_parse_date_and_amount() {
+        let date = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+        assert_eq!(parse_date("2024-03-15", &DateFormat::Auto).unwrap(), date);
+        assert_eq!(parse_date("15/03/2024", &DateFormat::Auto).unwrap(), date);
+        assert_eq!(
+            parse_date("2024-03-15T10:30:00Z", &DateFormat::Auto).unwrap(),
+            date
+        );
+        assert_eq!(
+            parse_date("2024-03-15 10:30:00", &DateFormat::Auto).unwrap(),
+            date
+        );
+        assert_eq!(
+            parse_date("2024-03-15 10:30:00.1234", &DateFormat::Auto).unwrap(),
+            date
+        );
+        let pattern = DateFormat::Pattern {
+            format: "%d.%m.%Y".to_string(),
+        };
+        assert_eq!(parse_date("15.03.2024", &pattern).unwrap(), date);
+        assert_eq!(
+            parse_date("1710498600", &DateFormat::TimestampS).unwrap(),
+            date
+        );
+        assert_eq!(
+            parse_date("1710498600000", &DateFormat::TimestampMs).unwrap(),
+            date
+        );
+        assert_eq!(parse_date("45366", &DateFormat::Excel).unwrap(), date);
+        assert!(parse_date("yesterday", &DateFormat::Auto).is_err());
+        assert!(parse_date("03/04/2024", &DateFormat::Auto).is_err());
+        assert!(parse_date("03/04/2024 10:30:00", &DateFormat::Auto).is_err());
+        assert_eq!(
+            parse_date("04/04/2024", &DateFormat::Auto).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 4, 4).unwrap()
+        );
+        assert_eq!(
+            parse_date("12/25/2024", &DateFormat::Auto).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 12, 25).unwrap()
+        );
+
+        assert_eq!(parse_amount("1,234.50").unwrap(), Decimal::new(123_450, 2));
+        assert_eq!(parse_amount("1.234,50").unwrap(), Decimal::new(123_450, 2));
+        assert_eq!(parse_amount("12,5").unwrap(), Decimal::new(125, 1));
+        assert_eq!(parse_amount("1,234").unwrap(), Decimal::new(1234, 0));
+        assert_eq!(parse_amount("-20").unwrap(), Decimal::new(-20, 0));
+        assert_eq!(parse_amount("€1,000.00").unwrap(), Decimal::new(1000, 0));
+        assert_eq!(parse_amount("-€5.25").unwrap(), Decimal::new(-525, 2));
+        assert_eq!(parse_amount("100 EUR").unwrap(), Decimal::ONE_HUNDRED);
+        assert!(parse_amount("abc").is_err());
+        assert!(parse_amount("12abc34").is_err());
+        assert_eq!(
+            parse_decimal("0.00012345").unwrap(),
+            Decimal::new(12_345, 8)
+        );
+    }
+
+    #[test]
+    fn test_validate_rows() {
+        let mapping = ColumnMapping {
+            date: "date".to_string(),
+            amount: "amount".to_string(),
+            currency: Some("Currency".to_string()),
+            kind: Some("Type".to_string()),
+            kind_values: HashMap::from([
+                ("Top up".to_string(), Some(RampKind::Deposit)),
+                ("Fee".to_string(), None),
+            ]),
+            via_exchange: Some("Revolut".to_string()),
+            ..Default::default()
+        };
+        let sheet = sheet(&[
+            &["2024-01-02", "100", "eur", "Top up"],
+            &["2024-01-03", "-50", "US Dollar", "Withdrawal"],
+            &["2024-01-04", "1", "EUR", "Fee"],
+            &["2024-01-05", "10", "XYZ", "Top up"],
+            &["not a date", "", "EUR", "Cashback"],
+        ]);
+
+        let report = validate(&sheet, &mapping, &fiats()).unwrap();
+        assert_eq!(report.accepted_count, 2);
+        assert_eq!(report.skipped_count, 1);
+        assert_eq!(report.rejected_count, 2);
+
+        let withdraw = report.rows[1].ramp.as_ref().unwrap();
+        assert_eq!(withdraw.fiat_id, 2);
+        assert_eq!(withdraw.fiat_amount, Decimal::new(50, 0));
+        assert_eq!(withdraw.kind, RampKind::Withdraw);
+        assert_eq!(
+            withdraw.via_exchange,
+            ExchangeRef::Name("Revolut".to_string())
+        );
+
+        assert_eq!(report.rows[3].row_number, 5);
+        assert_eq!(report.rows[3].errors, vec!["unknown currency 'XYZ'"]);
+        assert_eq!(report.rows[4].status, ImportRowStatus::Rejected);
+        assert_eq!(report.rows[4].errors.len(), 3);
+
+        let missing = ColumnMapping {
+            exchange: Some("Exchange".to_string()),
+            ..mapping
+        };
+        assert!(validate(&sheet, &missing, &fiats()).is_err());
+    }
+
+    #[test]
+    fn test_validate_kind_from_sign() {
+        let mapping = ColumnMapping {
+            date: "Date".to_string(),
+            amount: "Amount".to_string(),
+            default_fiat_id: Some(1),
+            ..Default::default()
+        };
+        let sheet = sheet(&[&["2024-01-02", "100"], &["2024-01-03", "-40"]]);
+
+        let report = validate(&sheet, &mapping, &fiats()).unwrap();
+        assert_eq!(report.accepted_count, 2);
+        let deposit = report.rows[0].ramp.as_ref().unwrap();
+        assert_eq!(deposit.kind, RampKind::Deposit);
+        assert_eq!(deposit.fiat_id, 1);
+        assert_eq!(
+            deposit.via_exchange,
+            ExchangeRef::Name(DEFAULT_IMPORT_EXCHANGE.to_string())
+        );
+        assert_eq!(
+            report.rows[1].ramp.as_ref().unwrap().kind,
+            RampKind::Withdraw
+        );
+    }
+
+    #[tokio::test]
+    async fn test_commit_import() {
+        let db = Db::in_memory().await.unwrap();
+        sqlx::query("INSERT INTO fiat (id, symbol, name) VALUES (1, 'EUR', 'Euro')")
+            .execute(&db.0)
+            .await
+            .unwrap();
+        let file_path = std::env::temp_dir().join(format!("{}.csv", Uuid::now_v7()));
+        std::fs::write(
+            &file_path,
+            "Date,Amount,Currency\n2024-01-02,100,EUR\n2024-01-03,20,GBP\n2024-01-04,-30,EUR\n",
+        )
+        .unwrap();
+        let mapping = ColumnMapping {
+            date: "Date".to_string(),
+            amount: "Amount".to_string(),
+            currency: Some("Currency".to_string()),
+            via_exchange: Some("Kraken".to_string()),
+            ..Default::default()
+        };
+
+        let saved = ImportService::save_mapping("kraken", mapping.clone(), &db)
+            .await
+            .unwrap();
+        ImportService::save_mapping("Kraken", mapping, &db)
+            .await
+            .unwrap();
+        let mappings = ImportService::get_mappings(&db).await.unwrap();
+        assert_eq!(mappings.len(), 1);
+        assert_eq!(mappings[0].id, saved.id);
+
+        let (report, created) =
+            ImportService::commit(&file_path, &mappings[0].mapping, DuplicatePolicy::Skip, &db)
+                .await
+                .unwrap();
+        std::fs::remove_file(&file_path).unwrap();
+
+        assert_eq!(report.accepted_count, 2);
+        assert_eq!(report.rejected_count, 1);
+        assert_eq!(report.imported_count, 2);
+        assert_eq!(created.len(), 2);
+        assert_eq!(created[0].via_exchange, "Kraken");
+        assert_eq!(created[1].kind, RampKind::Withdraw);
+
+        assert_eq!(
+            ImportService::delete_mapping(&saved.id, &db).await.unwrap(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reimport_duplicates() {
+        let db = Db::in_memory().await.unwrap();
+        sqlx::query("INSERT INTO fiat (id, symbol, name) VALUES (1, 'EUR', 'Euro')")
+            .execute(&db.0)
+            .await
+            .unwrap();
+        let file_path = std::env::temp_dir().join(format!("{}.csv", Uuid::now_v7()));
+        let mapping = ColumnMapping {
+            date: "Date".to_string(),
+            amount: "Amount".to_string(),
+            external_ref: Some("Ref".to_string()),
+            default_fiat_id: Some(1),
+            via_exchange: Some("Bitstamp".to_string()),
+            ..Default::default()
+        };
+        // the same deposit twice on the same day, without references
+        std::fs::write(
+            &file_path,
+            "Date,Amount,Ref\n2024-01-02,100,A1\n2024-01-03,50,\n2024-01-03,50,\n",
+        )
+        .unwrap();
+        let (report, _) = ImportService::commit(&file_path, &mapping, DuplicatePolicy::Skip, &db)
+            .await
+            .unwrap();
+        assert_eq!(report.imported_count, 3);
+
+        std::fs::write(
+            &file_path,
+            "Date,Amount,Ref\n2024-01-02,100,A1\n2024-01-03,50,\n2024-01-03,50,\n2024-01-03,50,\n",
+        )
+        .unwrap();
+        let (report, _) = ImportService::commit(&file_path, &mapping, DuplicatePolicy::Skip, &db)
+            .await
+            .unwrap();
+        assert_eq!(report.duplicate_count, 3);
+        assert_eq!(report.accepted_count, 1);
+        assert_eq!(report.imported_count, 1);
+
+        // the amount of A1 was corrected at the exchange
+        std::fs::write(&file_path, "Date,Amount,Ref\n2024-01-02,120,A1\n").unwrap();
+        // fingerprints cleared by an edit are refreshed by the preview, then rolled back
+        sqlx::query("UPDATE fiat_ramp SET fingerprint = NULL")
+            .execute(&db.0)
+            .await
+            .unwrap();
+        let preview = ImportService::preview(&file_path, &mapping, &db)
+            .await
+            .unwrap();
+        assert_eq!(preview.conflict_count, 1);
+        let fingerprinted: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM fiat_ramp WHERE fingerprint IS NOT NULL")
+                .fetch_one(&db.0)
+                .await
+                .unwrap();
+        assert_eq!(fingerprinted, 0);
+        let recorded = preview.rows[0].duplicate_of.clone().unwrap();
+
+        let (report, merged) =
+            ImportService::commit(&file_path, &mapping, DuplicatePolicy::Merge, &db)
+                .await
+                .unwrap();
+        std::fs::remove_file(&file_path).unwrap();
+        assert_eq!(report.imported_count, 0);
+        assert_eq!(report.merged_count, 1);
+        assert_eq!(merged[0].id, recorded);
+        assert_eq!(merged[0].fiat_amount, Decimal::new(120, 0));
+        assert_eq!(merged[0].external_ref.as_deref(), Some("A1"));
+
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM fiat_ramp")
+            .fetch_one(&db.0)
+            .await
+            .unwrap();
+        assert_eq!(count, 4);
+    }
+}
@@ -0,0 +1,172 @@
+use anyhow::{bail, Context, Result};
+use calamine::{open_workbook_auto, Data, Reader};
+use std::path::Path;
+
+/// Rows of a CSV file or of the first worksheet of a spreadsheet, every cell as text
+#[derive(Debug, Default)]
+pub struct Sheet {
+    pub headers: Vec<String>,
+    pub rows: Vec<SheetRow>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct SheetRow {
+    /// line of the row in the file, starting at 1
+    pub line: usize,
+    pub cells: Vec<String>,
+}
+
+impl SheetRow {
+    /// Trimmed cell at `column`, None when missing or blank
+    pub fn cell(&self, column: usize) -> Option<&str> {
+        self.cells
+            .get(column)
+            .map(|value| value.trim())
+            .filter(|value| !value.is_empty())
+    }
+}
+
+impl Sheet {
+    /// Position of the column with the header `name`, ignoring case and surrounding spaces
+    pub fn column_index(&self, name: &str) -> Option<usize> {
+        let name = name.trim();
+        self.headers
+            .iter()
+            .position(|header| header.trim().eq_ignore_ascii_case(name))
+    }
+
+    /// Use `rows[index]` as headers, the headers and rows above it are dropped
+    /// - some exports put a few lines of text above the table
+    pub fn with_headers_at(mut self, index: usize) -> Sheet {
+        if index >= self.rows.len() {
+            return self;
+        }
+        let mut rows = self.rows.split_off(index);
+        self.headers = rows.remove(0).cells;
+        self.rows = rows;
+        self
+    }
+}
+
+/// Read a CSV, XLSX, XLS or ODS file, the first row holds the headers
+/// - spreadsheet dates are rendered as `%Y-%m-%d %H:%M:%S`
+pub fn read_sheet(file_path: &Path) -> Result<Sheet> {
+    let extension = file_path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_lowercase)
+        .unwrap_or_default();
+
+    let mut rows = match extension.as_str() {
+        "csv" | "txt" => read_csv(file_path)?,
+        "xlsx" | "xlsm" | "xlsb" | "xls" | "ods" => read_workbook(file_path)?,
+        _ => bail!("unsupported file type '{extension}', expected a csv or a spreadsheet"),
+    };
+    if rows.is_empty() {
+        bail!("the file is empty");
+    }
+
+    let headers = rows.remove(0).cells;
+    // trailing blank lines are common in exports
+    rows.retain(|row| row.cells.iter().any(|cell| !cell.trim().is_empty()));
+    Ok(Sheet { headers, rows })
+}
+
+fn read_csv(file_path: &Path) -> Result<Vec<SheetRow>> {
+    let content = std::fs::read(file_path)
+        .with_context(|| format!("failed to open {}", file_path.display()))?;
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(content.as_slice());
+
+    // blank lines are skipped by the reader and belong to the record after them,
+    // the line is counted from the first byte of the record that is not a line break
+    let line_of = |byte: usize| {
+        let blank = content[byte..]
+            .iter()
+            .take_while(|b| **b == b'\n' || **b == b'\r')
+            .count();
+        1 + content[..byte + blank]
+            .iter()
+            .filter(|b| **b == b'\n')
+            .count()
+    };
+    reader
+        .records()
+        .map(|record| {
+            let record = record.context("failed to read csv record")?;
+            Ok(SheetRow {
+                line: record
+                    .position()
+                    .map_or(0, |position| line_of(position.byte() as usize)),
+                cells: record.iter().map(str::to_string).collect(),
+            })
+        })
+        .collect()
+}
+
+fn read_workbook(file_path: &Path) -> Result<Vec<SheetRow>> {
+    let mut workbook = open_workbook_auto(file_path)
+        .with_context(|| format!("failed to open {}", file_path.display()))?;
+    let range = workbook
+        .worksheet_range_at(0)
+        .context("the workbook has no worksheet")?
+        .context("failed to read the first worksheet")?;
+
+    // the range starts at the first non-empty row
+    let first_line = range.start().map_or(1, |(row, _)| row as usize + 1);
+    Ok(range
+        .rows()
+        .enumerate()
+        .map(|(index, row)| SheetRow {
+            line: first_line + index,
+            cells: row.iter().map(cell_to_string).collect(),
+        })
+        .collect())
+}
+
+fn cell_to_string(cell: &Data) -> String {
+    match cell {
+        Data::Empty | Data::Error(_) => String::new(),
+        Data::DateTime(date_time) => date_time
+            .as_datetime()
+            .map(|date_time| date_time.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_default(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_csv_sheet() {
+        let file_path = std::env::temp_dir().join(format!("{}.csv", uuid::Uuid::now_v7()));
+        std::fs::write(
+            &file_path,
+            "Date, Amount ,Currency\n2024-01-02,100.5,EUR\n\n2024-01-03,-20\n",
+        )
+        .unwrap();
+
+        let sheet = read_sheet(&file_path).unwrap();
+        std::fs::remove_file(&file_path).unwrap();
+
+        assert_eq!(sheet.headers, vec!["Date", " Amount ", "Currency"]);
+        assert_eq!(sheet.rows.len(), 2);
+        assert_eq!(sheet.rows[1].cells, vec!["2024-01-03", "-20"]);
+        assert_eq!(sheet.rows[1].line, 4);
+        assert_eq!(sheet.rows[1].cell(1), Some("-20"));
+        assert_eq!(sheet.rows[1].cell(2), None);
+        assert_eq!(sheet.column_index("amount"), Some(1));
+        assert_eq!(sheet.column_index("exchange"), None);
+
+        let sheet = sheet.with_headers_at(0);
+        assert_eq!(sheet.headers, vec!["2024-01-02", "100.5", "EUR"]);
+        assert_eq!(sheet.rows.len(), 1);
+
+        let unsupported = read_sheet(Path::new("statement.pdf"));
+        assert!(unsupported.is_err());
+    }
+}
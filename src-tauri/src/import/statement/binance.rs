@@ -0,0 +1,239 @@
+use super::{
+    fiat_ramp, find_column, has_columns, net_legs, parse_timestamp, report_row, require_column,
+    required_cell, trade_from_legs, Leg, Record, StatementContext, StatementParser,
+};
+use crate::fiat_ramp::RampKind;
+use crate::import::reader::{Sheet, SheetRow};
+use crate::import::{parse_decimal, ImportRow};
+use crate::trade::CreateTrade;
+use anyhow::{bail, Result};
+use rust_decimal::Decimal;
+
+const EXCHANGE: &str = "Binance";
+
+/// Binance transaction history, "Generate all statements" in the wallet overview
+/// - columns `User_ID, UTC_Time, Account, Operation, Coin, Change, Remark`
+/// - a trade is written as several rows sharing the same `UTC_Time`, one per coin spent,
+///   received or paid as fee
+pub struct BinanceParser;
+
+enum Operation {
+    Deposit,
+    Withdraw,
+    /// leg of a trade
+    Trade,
+    /// fee of a trade
+    Fee,
+    Other,
+}
+
+fn operation(value: &str) -> Operation {
+    match value.to_lowercase().as_str() {
+        "deposit" | "fiat deposit" => Operation::Deposit,
+        "withdraw" | "fiat withdraw" | "fiat withdrawal" => Operation::Withdraw,
+        "buy"
+        | "sell"
+        | "transaction buy"
+        | "transaction spend"
+        | "transaction sold"
+        | "transaction revenue"
+        | "buy crypto" => Operation::Trade,
+        "fee" | "transaction fee" => Operation::Fee,
+        _ => Operation::Other,
+    }
+}
+
+struct Columns {
+    time: usize,
+    account: Option<usize>,
+    operation: usize,
+    coin: usize,
+    change: usize,
+}
+
+struct TradeGroup<'a> {
+    key: (String, String),
+    rows: Vec<&'a SheetRow>,
+}
+
+impl StatementParser for BinanceParser {
+    fn id(&self) -> &'static str {
+        "binance"
+    }
+
+    fn exchange(&self) -> &'static str {
+        EXCHANGE
+    }
+
+    fn detect(&self, headers: &[String]) -> bool {
+        has_columns(headers, &["UTC_Time", "Operation", "Coin", "Change"])
+    }
+
+    fn parse(&self, sheet: &Sheet, context: &StatementContext) -> Result<Vec<ImportRow>> {
+        let columns = Columns {
+            time: require_column(sheet, &["UTC_Time"])?,
+            account: find_column(sheet, &["Account"]),
+            operation: require_column(sheet, &["Operation"])?,
+            coin: require_column(sheet, &["Coin"])?,
+            change: require_column(sheet, &["Change"])?,
+        };
+
+        let mut rows = Vec::new();
+        let mut groups: Vec<TradeGroup> = Vec::new();
+        for row in &sheet.rows {
+            let operation = operation(row.cell(columns.operation).unwrap_or_default());
+            match operation {
+                Operation::Deposit | Operation::Withdraw => {
+                    let kind = match operation {
+                        Operation::Deposit => RampKind::Deposit,
+                        _ => RampKind::Withdraw,
+                    };
+                    rows.push(report_row(row, parse_ramp(row, &columns, kind, context)));
+                }
+                Operation::Trade | Operation::Fee => {
+                    let key = (
+                        row.cell(columns.time).unwrap_or_default().to_string(),
+                        columns
+                            .account
+                            .and_then(|column| row.cell(column))
+                            .unwrap_or_default()
+                            .to_string(),
+                    );
+                    match groups.iter_mut().find(|group| group.key == key) {
+                        Some(group) => group.rows.push(row),
+                        None => groups.push(TradeGroup {
+                            key,
+                            rows: vec![row],
+                        }),
+                    }
+                }
+                Operation::Other => rows.push(report_row(row, Ok(Record::Skipped))),
+            }
+        }
+
+        for group in &groups {
+            let record = parse_trade(&group.rows, &columns, context).map(Record::Trade);
+            rows.push(report_row(group.rows[0], record));
+        }
+        rows.sort_by_key(|row| row.row_number);
+        Ok(rows)
+    }
+}
+
+fn parse_ramp(
+    row: &SheetRow,
+    columns: &Columns,
+    kind: RampKind,
+    context: &StatementContext,
+) -> Result<Record> {
+    let ramp_date = parse_timestamp(required_cell(row, columns.time, "UTC_Time")?)?;
+    let coin = required_cell(row, columns.coin, "Coin")?;
+    let change = parse_decimal(required_cell(row, columns.change, "Change")?)?;
+    Ok(fiat_ramp(
+        kind,
+        &Leg::new(coin, change),
+        ramp_date,
+        EXCHANGE,
+        None,
+        context,
+    )?
+    .into())
+}
+
+fn parse_trade(
+    rows: &[&SheetRow],
+    columns: &Columns,
+    context: &StatementContext,
+) -> Result<CreateTrade> {
+    let trade_date = parse_timestamp(required_cell(rows[0], columns.time, "UTC_Time")?)?;
+
+    let mut legs = Vec::new();
+    let mut fees: Vec<(String, Decimal)> = Vec::new();
+    for row in rows {
+        let coin = required_cell(row, columns.coin, "Coin")?.to_uppercase();
+        let change = parse_decimal(required_cell(row, columns.change, "Change")?)?;
+        match operation(row.cell(columns.operation).unwrap_or_default()) {
+            Operation::Fee => match fees.iter_mut().find(|(symbol, _)| *symbol == coin) {
+                Some((_, total)) => *total += change.abs(),
+                None => fees.push((coin, change.abs())),
+            },
+            _ => legs.push((coin, change)),
+        }
+    }
+
+    let (given, received) = net_legs(&legs)?;
+    let fee = match fees.as_slice() {
+        [] => None,
+        [(symbol, amount)] => Some(Leg::new(symbol, *amount)),
+        _ => bail!("fees paid in more than one coin"),
+    };
+    trade_from_legs(
+        &given,
+        &received,
+        fee.as_ref(),
+        None,
+        trade_date,
+        EXCHANGE,
+        context,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{assets, fiats, fixture};
+    use super::*;
+    use crate::exchange::ExchangeRef;
+    use crate::import::ImportRowStatus;
+    use crate::trade::TradeKind;
+
+    #[test]
+    fn test_parse_binance_statement() {
+        let (fiats, assets) = (fiats(), assets());
+        let context = StatementContext {
+            fiats: &fiats,
+            assets: &assets,
+        };
+        let sheet = fixture("binance.csv");
+        assert!(BinanceParser.detect(&sheet.headers));
+
+        let rows = BinanceParser.parse(&sheet, &context).unwrap();
+        let statuses: Vec<ImportRowStatus> = rows.iter().map(|row| row.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ImportRowStatus::Accepted,
+                ImportRowStatus::Accepted,
+                ImportRowStatus::Skipped,
+                ImportRowStatus::Rejected,
+                ImportRowStatus::Skipped,
+                ImportRowStatus::Accepted,
+            ]
+        );
+
+        let deposit = rows[0].ramp.as_ref().unwrap();
+        assert_eq!(deposit.fiat_id, 1);
+        assert_eq!(deposit.fiat_amount, Decimal::new(1000, 0));
+        assert_eq!(deposit.kind, RampKind::Deposit);
+
+        // spend, buy and fee rows of the same second make one trade
+        let buy = rows[1].trade.as_ref().unwrap();
+        assert_eq!(buy.kind, TradeKind::Buy);
+        assert_eq!(buy.asset_id, "btc");
+        assert_eq!(buy.quantity, Decimal::new(2, 2));
+        assert_eq!(buy.price, Decimal::new(40_000, 0));
+        assert_eq!(buy.fee_asset_id.as_deref(), Some("bnb"));
+        assert_eq!(buy.fee_amount, Some(Decimal::new(15, 4)));
+        assert_eq!(
+            buy.via_exchange,
+            Some(ExchangeRef::Name("Binance".to_string()))
+        );
+
+        // BTC bought with USDT has no fiat price
+        assert_eq!(rows[3].row_number, 7);
+        assert!(rows[3].errors[0].contains("no fiat price"));
+
+        let withdraw = rows[5].ramp.as_ref().unwrap();
+        assert_eq!(withdraw.kind, RampKind::Withdraw);
+        assert_eq!(withdraw.fiat_amount, Decimal::new(2505, 1));
+    }
+}
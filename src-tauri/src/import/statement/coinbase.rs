@@ -0,0 +1,223 @@
+use super::{
+    fiat_ramp, find_column, has_columns, parse_timestamp, report_row, require_column,
+    required_cell, trade_from_legs, Leg, Record, StatementContext, StatementParser,
+};
+use crate::fiat_ramp::RampKind;
+use crate::import::reader::{Sheet, SheetRow};
+use crate::import::{parse_decimal, ImportRow};
+use crate::trade::CreateTrade;
+use anyhow::{anyhow, Result};
+use rust_decimal::Decimal;
+
+const EXCHANGE: &str = "Coinbase";
+
+/// Coinbase transaction history report, Reports > Generate report > Transaction history
+/// - columns `ID, Timestamp, Transaction Type, Asset, Quantity Transacted, Price Currency,
+///   Price at Transaction, Subtotal, Total (inclusive of fees and/or spread),
+///   Fees and/or Spread, Notes`, older reports name the price columns `Spot Price ...`
+/// - the report starts with a few lines of text above the table
+pub struct CoinbaseParser;
+
+struct Columns {
+    id: Option<usize>,
+    timestamp: usize,
+    kind: usize,
+    asset: usize,
+    quantity: usize,
+    price_currency: usize,
+    price: usize,
+    subtotal: Option<usize>,
+    fees: Option<usize>,
+    notes: Option<usize>,
+}
+
+impl StatementParser for CoinbaseParser {
+    fn id(&self) -> &'static str {
+        "coinbase"
+    }
+
+    fn exchange(&self) -> &'static str {
+        EXCHANGE
+    }
+
+    fn detect(&self, headers: &[String]) -> bool {
+        has_columns(
+            headers,
+            &[
+                "Timestamp",
+                "Transaction Type",
+                "Asset",
+                "Quantity Transacted",
+            ],
+        )
+    }
+
+    fn parse(&self, sheet: &Sheet, context: &StatementContext) -> Result<Vec<ImportRow>> {
+        let columns = Columns {
+            id: find_column(sheet, &["ID"]),
+            timestamp: require_column(sheet, &["Timestamp"])?,
+            kind: require_column(sheet, &["Transaction Type"])?,
+            asset: require_column(sheet, &["Asset"])?,
+            quantity: require_column(sheet, &["Quantity Transacted"])?,
+            price_currency: require_column(sheet, &["Price Currency", "Spot Price Currency"])?,
+            price: require_column(
+                sheet,
+                &["Price at Transaction", "Spot Price at Transaction"],
+            )?,
+            subtotal: find_column(sheet, &["Subtotal"]),
+            fees: find_column(sheet, &["Fees and/or Spread", "Fees"]),
+            notes: find_column(sheet, &["Notes"]),
+        };
+
+        Ok(sheet
+            .rows
+            .iter()
+            .map(|row| report_row(row, parse_row(row, &columns, context)))
+            .collect())
+    }
+}
+
+fn parse_row(row: &SheetRow, columns: &Columns, context: &StatementContext) -> Result<Record> {
+    let kind = row.cell(columns.kind).unwrap_or_default().to_lowercase();
+    let ramp_kind = match kind.as_str() {
+        "deposit" => Some(RampKind::Deposit),
+        "withdrawal" | "withdraw" => Some(RampKind::Withdraw),
+        "buy" | "advanced trade buy" | "sell" | "advanced trade sell" | "convert" => None,
+        _ => return Ok(Record::Skipped),
+    };
+
+    let date = parse_timestamp(required_cell(row, columns.timestamp, "Timestamp")?)?;
+    let asset = Leg::new(
+        required_cell(row, columns.asset, "Asset")?,
+        parse_decimal(required_cell(row, columns.quantity, "Quantity Transacted")?)?,
+    );
+    if let Some(ramp_kind) = ramp_kind {
+        let external_ref = columns.id.and_then(|column| row.cell(column));
+        return Ok(fiat_ramp(ramp_kind, &asset, date, EXCHANGE, external_ref, context)?.into());
+    }
+
+    let currency = required_cell(row, columns.price_currency, "Price Currency")?;
+    let price = parse_decimal(required_cell(row, columns.price, "Price at Transaction")?)?;
+    let optional_amount = |column: Option<usize>| {
+        column
+            .and_then(|column| row.cell(column))
+            .map(parse_decimal)
+            .transpose()
+    };
+    // the subtotal leaves the spread out of the price
+    let value = Leg::new(
+        currency,
+        optional_amount(columns.subtotal)?.unwrap_or(asset.amount * price),
+    );
+    let fee = optional_amount(columns.fees)?.map(|fee| Leg::new(currency, fee));
+
+    let trade = match kind.as_str() {
+        "buy" | "advanced trade buy" => {
+            trade_from_legs(&value, &asset, fee.as_ref(), None, date, EXCHANGE, context)?
+        }
+        "sell" | "advanced trade sell" => {
+            trade_from_legs(&asset, &value, fee.as_ref(), None, date, EXCHANGE, context)?
+        }
+        _ => {
+            let notes = columns.notes.and_then(|column| row.cell(column));
+            let received = notes
+                .and_then(converted_to)
+                .ok_or_else(|| anyhow!("the asset received is not in the notes"))?;
+            let market_price = Leg::new(currency, price);
+            trade_from_legs(
+                &asset,
+                &received,
+                fee.as_ref(),
+                Some(&market_price),
+                date,
+                EXCHANGE,
+                context,
+            )?
+        }
+    };
+    Ok(Record::Trade(CreateTrade {
+        external_ref: columns
+            .id
+            .and_then(|column| row.cell(column))
+            .map(str::to_string),
+        ..trade
+    }))
+}
+
+/// Asset received in a convert, from notes such as `Converted 0.5 ETH to 0.02 BTC`
+fn converted_to(notes: &str) -> Option<Leg> {
+    let (_, received) = notes.rsplit_once(" to ")?;
+    let mut parts = received.split_whitespace();
+    let amount: Decimal = parse_decimal(parts.next()?).ok()?;
+    let symbol = parts.next()?;
+    Some(Leg::new(symbol, amount))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::select_parser;
+    use super::super::tests::{assets, fiats, fixture};
+    use super::*;
+    use crate::exchange::ExchangeRef;
+    use crate::import::ImportRowStatus;
+    use crate::trade::TradeKind;
+
+    #[test]
+    fn test_parse_coinbase_statement() {
+        let (fiats, assets) = (fiats(), assets());
+        let context = StatementContext {
+            fiats: &fiats,
+            assets: &assets,
+        };
+        let (parser, sheet) = select_parser(fixture("coinbase.csv"), Some("coinbase")).unwrap();
+
+        let rows = parser.parse(&sheet, &context).unwrap();
+        let statuses: Vec<ImportRowStatus> = rows.iter().map(|row| row.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ImportRowStatus::Accepted,
+                ImportRowStatus::Accepted,
+                ImportRowStatus::Accepted,
+                ImportRowStatus::Skipped,
+                ImportRowStatus::Accepted,
+                ImportRowStatus::Rejected,
+                ImportRowStatus::Accepted,
+            ]
+        );
+        assert_eq!(rows[0].row_number, 5);
+
+        let deposit = rows[0].ramp.as_ref().unwrap();
+        assert_eq!(deposit.fiat_id, 2);
+        assert_eq!(deposit.fiat_amount, Decimal::new(2000, 0));
+
+        let buy = rows[1].trade.as_ref().unwrap();
+        assert_eq!(buy.kind, TradeKind::Buy);
+        assert_eq!(buy.quantity, Decimal::new(25, 3));
+        assert_eq!(buy.price, Decimal::new(40_000, 0));
+        assert_eq!(buy.fee_amount, Some(Decimal::new(1495, 2)));
+        assert_eq!(
+            buy.via_exchange,
+            Some(ExchangeRef::Name("Coinbase".to_string()))
+        );
+
+        let sell = rows[2].trade.as_ref().unwrap();
+        assert_eq!(sell.kind, TradeKind::Sell);
+        assert_eq!(sell.asset_id, "eth");
+        assert_eq!(sell.price, Decimal::new(2_300, 0));
+
+        let swap = rows[4].trade.as_ref().unwrap();
+        assert_eq!(swap.kind, TradeKind::Swap);
+        assert_eq!(swap.asset_id, "eth");
+        assert_eq!(swap.to_asset_id.as_deref(), Some("btc"));
+        assert_eq!(swap.to_quantity, Some(Decimal::new(2, 2)));
+        assert_eq!(swap.price, Decimal::new(2_400, 0));
+
+        // SOL is not an asset yet
+        assert!(rows[5].errors[0].contains("unknown asset 'SOL'"));
+
+        let withdraw = rows[6].ramp.as_ref().unwrap();
+        assert_eq!(withdraw.kind, RampKind::Withdraw);
+        assert_eq!(withdraw.fiat_amount, Decimal::new(500, 0));
+    }
+}
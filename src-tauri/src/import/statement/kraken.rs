@@ -0,0 +1,256 @@
+use super::{
+    fiat_ramp, has_columns, net_legs, parse_timestamp, report_row, require_column, required_cell,
+    trade_from_legs, Leg, Record, StatementContext, StatementParser,
+};
+use crate::fiat_ramp::{CreateFiatRamp, RampKind};
+use crate::import::reader::{Sheet, SheetRow};
+use crate::import::{parse_decimal, ImportRow};
+use crate::trade::CreateTrade;
+use anyhow::{bail, Result};
+use rust_decimal::Decimal;
+
+const EXCHANGE: &str = "Kraken";
+
+/// Kraken ledger export, History > Export > Ledgers
+/// - columns `txid, refid, time, type, subtype, aclass, asset, wallet, amount, fee, balance`
+/// - both sides of a trade are rows sharing the same `refid`, fees are in the `fee` column
+///   of the side they were paid in
+pub struct KrakenParser;
+
+struct Columns {
+    txid: usize,
+    refid: usize,
+    time: usize,
+    kind: usize,
+    asset: usize,
+    amount: usize,
+    fee: usize,
+}
+
+struct TradeGroup<'a> {
+    refid: String,
+    rows: Vec<&'a SheetRow>,
+}
+
+impl StatementParser for KrakenParser {
+    fn id(&self) -> &'static str {
+        "kraken_ledger"
+    }
+
+    fn exchange(&self) -> &'static str {
+        EXCHANGE
+    }
+
+    fn detect(&self, headers: &[String]) -> bool {
+        has_columns(
+            headers,
+            &["txid", "refid", "time", "type", "asset", "amount", "fee"],
+        )
+    }
+
+    fn parse(&self, sheet: &Sheet, context: &StatementContext) -> Result<Vec<ImportRow>> {
+        let columns = Columns {
+            txid: require_column(sheet, &["txid"])?,
+            refid: require_column(sheet, &["refid"])?,
+            time: require_column(sheet, &["time"])?,
+            kind: require_column(sheet, &["type"])?,
+            asset: require_column(sheet, &["asset"])?,
+            amount: require_column(sheet, &["amount"])?,
+            fee: require_column(sheet, &["fee"])?,
+        };
+
+        let mut rows = Vec::new();
+        let mut groups: Vec<TradeGroup> = Vec::new();
+        for row in &sheet.rows {
+            // a deposit or withdrawal is listed twice, the row without txid is the pending one
+            if row.cell(columns.txid).is_none() {
+                rows.push(report_row(row, Ok(Record::Skipped)));
+                continue;
+            }
+
+            match row.cell(columns.kind).unwrap_or_default() {
+                "deposit" => rows.push(report_row(
+                    row,
+                    parse_ramp(row, &columns, RampKind::Deposit, context),
+                )),
+                "withdrawal" => rows.push(report_row(
+                    row,
+                    parse_ramp(row, &columns, RampKind::Withdraw, context),
+                )),
+                "trade" | "spend" | "receive" => {
+                    let refid = row.cell(columns.refid).unwrap_or_default().to_string();
+                    match groups.iter_mut().find(|group| group.refid == refid) {
+                        Some(group) => group.rows.push(row),
+                        None => groups.push(TradeGroup {
+                            refid,
+                            rows: vec![row],
+                        }),
+                    }
+                }
+                _ => rows.push(report_row(row, Ok(Record::Skipped))),
+            }
+        }
+
+        for group in &groups {
+            // the refid shared by both sides of a trade is its external reference
+            let record = parse_trade(&group.rows, &columns, context).map(|trade| {
+                Record::Trade(CreateTrade {
+                    external_ref: Some(group.refid.clone()),
+                    ..trade
+                })
+            });
+            rows.push(report_row(group.rows[0], record));
+        }
+        rows.sort_by_key(|row| row.row_number);
+        Ok(rows)
+    }
+}
+
+/// Symbol of a Kraken asset code, e.g. `ZEUR` is EUR and `XXBT` is BTC
+pub fn symbol(code: &str) -> String {
+    let code = code.trim().to_uppercase();
+    let code = match code.len() {
+        4 if code.starts_with('X') || code.starts_with('Z') => &code[1..],
+        _ => &code,
+    };
+    match code {
+        "XBT" => "BTC".to_string(),
+        "XDG" => "DOGE".to_string(),
+        code => code.to_string(),
+    }
+}
+
+/// Deposit or withdrawal, its fee is taken in the asset of the ramp
+/// - a deposit credits `amount` less the fee, a withdrawal debits `amount` plus the fee, the
+///   ramp is recorded gross of the fee in both cases
+fn parse_ramp(
+    row: &SheetRow,
+    columns: &Columns,
+    kind: RampKind,
+    context: &StatementContext,
+) -> Result<Record> {
+    let ramp_date = parse_timestamp(required_cell(row, columns.time, "time")?)?;
+    let asset = symbol(required_cell(row, columns.asset, "asset")?);
+    let amount = parse_decimal(required_cell(row, columns.amount, "amount")?)?.abs();
+    let fee = match row.cell(columns.fee) {
+        Some(fee) => parse_decimal(fee)?.abs(),
+        None => Decimal::ZERO,
+    };
+    let gross = match kind {
+        RampKind::Deposit => amount,
+        RampKind::Withdraw => amount + fee,
+    };
+    let ramp = fiat_ramp(
+        kind,
+        &Leg::new(&asset, gross),
+        ramp_date,
+        EXCHANGE,
+        row.cell(columns.txid),
+        context,
+    )?
+    .map(|ramp| CreateFiatRamp {
+        fee_amount: (!fee.is_zero()).then_some(fee),
+        ..ramp
+    });
+    Ok(ramp.into())
+}
+
+fn parse_trade(
+    rows: &[&SheetRow],
+    columns: &Columns,
+    context: &StatementContext,
+) -> Result<CreateTrade> {
+    let trade_date = parse_timestamp(required_cell(rows[0], columns.time, "time")?)?;
+
+    let mut legs = Vec::new();
+    let mut fees = Vec::new();
+    for row in rows {
+        let asset = symbol(required_cell(row, columns.asset, "asset")?);
+        let amount = parse_decimal(required_cell(row, columns.amount, "amount")?)?;
+        let fee = match row.cell(columns.fee) {
+            Some(fee) => parse_decimal(fee)?,
+            None => Decimal::ZERO,
+        };
+        if !fee.is_zero() {
+            fees.push(Leg::new(&asset, fee));
+        }
+        legs.push((asset, amount));
+    }
+
+    let (given, received) = net_legs(&legs)?;
+    let fee = match fees.as_slice() {
+        [] => None,
+        [fee] => Some(fee),
+        _ => bail!("fees paid in more than one asset"),
+    };
+    trade_from_legs(&given, &received, fee, None, trade_date, EXCHANGE, context)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{assets, fiats, fixture};
+    use super::*;
+    use crate::import::ImportRowStatus;
+    use crate::trade::TradeKind;
+
+    #[test]
+    fn test_kraken_symbol() {
+        assert_eq!(symbol("ZEUR"), "EUR");
+        assert_eq!(symbol("XXBT"), "BTC");
+        assert_eq!(symbol("XETH"), "ETH");
+        assert_eq!(symbol("DOT"), "DOT");
+        assert_eq!(symbol("SOL"), "SOL");
+    }
+
+    #[test]
+    fn test_parse_kraken_ledger() {
+        let (fiats, assets) = (fiats(), assets());
+        let context = StatementContext {
+            fiats: &fiats,
+            assets: &assets,
+        };
+        let sheet = fixture("kraken_ledger.csv");
+        assert!(KrakenParser.detect(&sheet.headers));
+
+        let rows = KrakenParser.parse(&sheet, &context).unwrap();
+        let statuses: Vec<ImportRowStatus> = rows.iter().map(|row| row.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ImportRowStatus::Skipped,
+                ImportRowStatus::Accepted,
+                ImportRowStatus::Accepted,
+                ImportRowStatus::Accepted,
+                ImportRowStatus::Skipped,
+                ImportRowStatus::Accepted,
+            ]
+        );
+
+        let deposit = rows[1].ramp.as_ref().unwrap();
+        assert_eq!(deposit.fiat_amount, Decimal::new(5000, 0));
+        assert_eq!(deposit.kind, RampKind::Deposit);
+
+        let buy = rows[2].trade.as_ref().unwrap();
+        assert_eq!(buy.kind, TradeKind::Buy);
+        assert_eq!(buy.asset_id, "btc");
+        assert_eq!(buy.quantity, Decimal::new(1, 1));
+        assert_eq!(buy.price, Decimal::new(42_000, 0));
+        assert_eq!(buy.fee_asset_id, None);
+        assert_eq!(buy.fee_amount, Some(Decimal::new(1092, 2)));
+
+        let sell = rows[3].trade.as_ref().unwrap();
+        assert_eq!(sell.kind, TradeKind::Sell);
+        assert_eq!(sell.asset_id, "eth");
+        assert_eq!(sell.price, Decimal::new(2_500, 0));
+
+        assert_eq!(buy.external_ref.as_deref(), Some("TJKLXX-PESWQ-XR4XS6"));
+
+        // the withdrawal fee is paid on top of the amount
+        let withdraw = rows[5].ramp.as_ref().unwrap();
+        assert_eq!(withdraw.kind, RampKind::Withdraw);
+        assert_eq!(withdraw.fiat_amount, Decimal::new(120_009, 2));
+        assert_eq!(withdraw.fee_amount, Some(Decimal::new(9, 2)));
+        assert_eq!(withdraw.fee_fiat_id, None);
+        assert_eq!(deposit.fee_amount, None);
+    }
+}
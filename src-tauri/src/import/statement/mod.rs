@@ -0,0 +1,726 @@
+pub mod binance;
+pub mod coinbase;
+pub mod kraken;
+use super::duplicate::{self, DuplicatePolicy};
+use super::reader::{self, Sheet, SheetRow};
+use super::{parse_date, DateFormat, ImportRow, ImportRowStatus};
+use crate::asset::{Asset, AssetService};
+use crate::db::Db;
+use crate::exchange::ExchangeRef;
+use crate::fiat::{Fiat, FiatService};
+use crate::fiat_exchanger::frankfurter_exchanger::FrankfurterExchangerApi;
+use crate::fiat_ramp::{CreateFiatRamp, FiatRamp, FiatRampService, RampKind};
+use crate::trade::{CreateTrade, TradeKind, TradeService};
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::SqliteConnection;
+use std::path::Path;
+
+/// Number of leading rows searched for the headers, exports may start with a preamble
+const MAX_PREAMBLE_ROWS: usize = 10;
+
+/// Decimal places kept of a price derived from the amounts of a trade
+const PRICE_DECIMAL_PLACES: u32 = 12;
+
+/// Fiats and assets the symbols of a statement are resolved against
+pub struct StatementContext<'a> {
+    pub fiats: &'a [Fiat],
+    pub assets: &'a [Asset],
+}
+
+impl StatementContext<'_> {
+    pub fn fiat(&self, symbol: &str) -> Option<&Fiat> {
+        self.fiats
+            .iter()
+            .find(|fiat| fiat.symbol.eq_ignore_ascii_case(symbol))
+    }
+
+    pub fn asset(&self, symbol: &str) -> Option<&Asset> {
+        self.assets
+            .iter()
+            .find(|asset| asset.symbol.eq_ignore_ascii_case(symbol))
+    }
+}
+
+/// Parser of the CSV statement layout of an exchange
+pub trait StatementParser: Send + Sync {
+    /// stable id of the layout, e.g. `kraken_ledger`
+    fn id(&self) -> &'static str;
+
+    /// name of the exchange, recorded as `via_exchange`
+    fn exchange(&self) -> &'static str;
+
+    /// Whether the headers are the ones of this layout
+    fn detect(&self, headers: &[String]) -> bool;
+
+    /// Turn the rows into fiat ramps and trades, one report row per ramp or trade
+    /// - rows spanning a single trade are reported once, on the first of them
+    fn parse(&self, sheet: &Sheet, context: &StatementContext) -> Result<Vec<ImportRow>>;
+}
+
+/// Every supported statement layout
+pub fn parsers() -> Vec<Box<dyn StatementParser>> {
+    vec![
+        Box::new(binance::BinanceParser),
+        Box::new(coinbase::CoinbaseParser),
+        Box::new(kraken::KrakenParser),
+    ]
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StatementParserInfo {
+    pub id: String,
+    pub exchange: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StatementImportReport {
+    pub parser_id: String,
+    pub exchange: String,
+    pub rows: Vec<ImportRow>,
+    /// number of new fiat ramps and trades
+    pub accepted_count: usize,
+    pub rejected_count: usize,
+    pub skipped_count: usize,
+    pub duplicate_count: usize,
+    pub conflict_count: usize,
+    /// number of fiat ramps created, 0 for a preview
+    pub imported_ramp_count: usize,
+    /// number of trades created, 0 for a preview
+    pub imported_trade_count: usize,
+    /// number of recorded fiat ramps updated from duplicate and conflict rows, 0 for a preview
+    pub merged_ramp_count: usize,
+    /// number of recorded trades updated from conflict rows, 0 for a preview
+    pub merged_trade_count: usize,
+    /// rates of the created and merged ramps that could not be fetched, empty for a preview
+    pub rate_errors: Vec<String>,
+}
+
+pub struct StatementImportService {}
+
+impl StatementImportService {
+    pub fn get_parsers() -> Vec<StatementParserInfo> {
+        parsers()
+            .iter()
+            .map(|parser| StatementParserInfo {
+                id: parser.id().to_string(),
+                exchange: parser.exchange().to_string(),
+            })
+            .collect()
+    }
+
+    /// Parse the statement and look for fiat ramps and trades already recorded, nothing is written
+    /// - the fingerprints refreshed to look for duplicates are rolled back with the transaction
+    /// - `parser_id` picks the layout, when None it is detected from the headers
+    pub async fn preview(
+        file_path: &Path,
+        parser_id: Option<&str>,
+        db: &Db,
+    ) -> Result<StatementImportReport> {
+        let fiats = FiatService::<FrankfurterExchangerApi>::get_all_fiat(db).await?;
+        let assets = AssetService::get_all(db).await?;
+        let mut tx =
+            db.0.begin_with("BEGIN IMMEDIATE")
+                .await
+                .context("failed to begin transaction")?;
+        let report = Self::read(file_path, parser_id, &fiats, &assets, &mut tx).await?;
+        tx.rollback()
+            .await
+            .context("failed to roll back transaction")?;
+        Ok(report)
+    }
+
+    /// Parse the statement and create the new fiat ramps and trades
+    /// - duplicate and conflicting fiat ramps and trades are skipped or merged depending on `policy`
+    /// - returns the report along with the created and merged fiat ramps
+    /// - the rows are classified, created and merged in one write transaction, nothing is
+    ///   written when any of it fails
+    pub async fn commit(
+        file_path: &Path,
+        parser_id: Option<&str>,
+        policy: DuplicatePolicy,
+        db: &Db,
+    ) -> Result<(StatementImportReport, Vec<FiatRamp>)> {
+        let fiats = FiatService::<FrankfurterExchangerApi>::get_all_fiat(db).await?;
+        let assets = AssetService::get_all(db).await?;
+        let mut tx =
+            db.0.begin_with("BEGIN IMMEDIATE")
+                .await
+                .context("failed to begin transaction")?;
+        let mut report = Self::read(file_path, parser_id, &fiats, &assets, &mut tx).await?;
+
+        let accepted = || {
+            report
+                .rows
+                .iter()
+                .filter(|row| row.status == ImportRowStatus::Accepted)
+        };
+        let ramps: Vec<CreateFiatRamp> = accepted().filter_map(|row| row.ramp.clone()).collect();
+        let trades: Vec<(usize, CreateTrade)> = accepted()
+            .filter_map(|row| row.trade.clone().map(|trade| (row.row_number, trade)))
+            .collect();
+
+        let mut created = if ramps.is_empty() {
+            Vec::new()
+        } else {
+            FiatRampService::create_bulk_in(ramps, &mut tx)
+                .await
+                .map_err(|e| anyhow!(e))
+                .context("failed to create imported fiat ramps")?
+        };
+        report.imported_ramp_count = created.len();
+
+        for (row_number, trade) in trades {
+            TradeService::create_in(trade, &mut tx)
+                .await
+                .with_context(|| format!("failed to create trade of line {row_number}"))?;
+            report.imported_trade_count += 1;
+        }
+
+        if policy == DuplicatePolicy::Merge {
+            let merged = duplicate::merge(&report.rows, &mut tx).await?;
+            report.merged_ramp_count = merged.ramps.len();
+            report.merged_trade_count = merged.trade_count;
+            created.extend(merged.ramps);
+        }
+        tx.commit().await.context("failed to commit transaction")?;
+        Ok((report, created))
+    }
+
+    /// Parse the statement and mark the rows already recorded, on `conn`
+    async fn read(
+        file_path: &Path,
+        parser_id: Option<&str>,
+        fiats: &[Fiat],
+        assets: &[Asset],
+        conn: &mut SqliteConnection,
+    ) -> Result<StatementImportReport> {
+        let sheet = reader::read_sheet(file_path)?;
+        let (parser, sheet) = select_parser(sheet, parser_id)?;
+        let context = StatementContext { fiats, assets };
+        let mut rows = parser.parse(&sheet, &context)?;
+        duplicate::classify(&mut rows, fiats, conn).await?;
+
+        let count = |status| rows.iter().filter(|row| row.status == status).count();
+        Ok(StatementImportReport {
+            parser_id: parser.id().to_string(),
+            exchange: parser.exchange().to_string(),
+            accepted_count: count(ImportRowStatus::Accepted),
+            rejected_count: count(ImportRowStatus::Rejected),
+            skipped_count: count(ImportRowStatus::Skipped),
+            duplicate_count: count(ImportRowStatus::Duplicate),
+            conflict_count: count(ImportRowStatus::Conflict),
+            imported_ramp_count: 0,
+            imported_trade_count: 0,
+            merged_ramp_count: 0,
+            merged_trade_count: 0,
+            rate_errors: Vec::new(),
+            rows,
+        })
+    }
+}
+
+/// Find the parser of the sheet, by id or else by its headers
+/// - returns the sheet with its headers moved past any preamble
+pub fn select_parser(
+    sheet: Sheet,
+    parser_id: Option<&str>,
+) -> Result<(Box<dyn StatementParser>, Sheet)> {
+    let candidates: Vec<Box<dyn StatementParser>> = parsers()
+        .into_iter()
+        .filter(|parser| parser_id.is_none_or(|id| parser.id() == id))
+        .collect();
+    if let Some(id) = parser_id.filter(|_| candidates.is_empty()) {
+        bail!("unknown statement layout '{id}'");
+    }
+
+    let header_rows = std::iter::once((None, &sheet.headers)).chain(
+        sheet
+            .rows
+            .iter()
+            .take(MAX_PREAMBLE_ROWS)
+            .enumerate()
+            .map(|(index, row)| (Some(index), &row.cells)),
+    );
+    let mut found = None;
+    for (index, headers) in header_rows {
+        if let Some(position) = candidates.iter().position(|parser| parser.detect(headers)) {
+            found = Some((index, position));
+            break;
+        }
+    }
+
+    match found {
+        Some((index, position)) => {
+            let parser = candidates
+                .into_iter()
+                .nth(position)
+                .expect("position of a candidate");
+            let sheet = match index {
+                Some(index) => sheet.with_headers_at(index),
+                None => sheet,
+            };
+            Ok((parser, sheet))
+        }
+        None => match parser_id {
+            Some(id) => bail!("the file is not a '{id}' statement"),
+            None => bail!("the file is not a known exchange statement"),
+        },
+    }
+}
+
+/// Position of the first of `names` found in the headers, names are alternatives of a column
+pub(crate) fn find_column(sheet: &Sheet, names: &[&str]) -> Option<usize> {
+    names.iter().find_map(|name| sheet.column_index(name))
+}
+
+pub(crate) fn require_column(sheet: &Sheet, names: &[&str]) -> Result<usize> {
+    find_column(sheet, names).ok_or_else(|| anyhow!("column '{}' not found", names[0]))
+}
+
+/// Whether every one of `names` is a header, ignoring case and surrounding spaces
+pub(crate) fn has_columns(headers: &[String], names: &[&str]) -> bool {
+    names.iter().all(|name| {
+        headers
+            .iter()
+            .any(|header| header.trim().eq_ignore_ascii_case(name))
+    })
+}
+
+/// Read a statement timestamp, e.g. `2024-01-05 10:00:00 UTC` or `2024-01-05T10:00:00Z`
+pub(crate) fn parse_timestamp(value: &str) -> Result<NaiveDate> {
+    parse_date(value.trim_end_matches(" UTC"), &DateFormat::Auto)
+}
+
+/// Cell of a row that must be present
+pub(crate) fn required_cell<'a>(row: &'a SheetRow, column: usize, name: &str) -> Result<&'a str> {
+    row.cell(column).ok_or_else(|| anyhow!("{name} is missing"))
+}
+
+/// An amount of a fiat or an asset that left or entered the account
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Leg {
+    pub symbol: String,
+    /// always positive
+    pub amount: Decimal,
+}
+
+impl Leg {
+    pub fn new(symbol: &str, amount: Decimal) -> Leg {
+        Leg {
+            symbol: symbol.to_uppercase(),
+            amount: amount.abs(),
+        }
+    }
+}
+
+/// Deposit or withdrawal of a fiat, None when `symbol` is not a fiat (a crypto transfer)
+/// - `external_ref` is the transaction id of the statement, when it has one
+pub(crate) fn fiat_ramp(
+    kind: RampKind,
+    leg: &Leg,
+    ramp_date: NaiveDate,
+    exchange: &str,
+    external_ref: Option<&str>,
+    context: &StatementContext,
+) -> Result<Option<CreateFiatRamp>> {
+    let Some(fiat) = context.fiat(&leg.symbol) else {
+        return Ok(None);
+    };
+    if leg.amount.is_zero() {
+        bail!("amount must not be zero");
+    }
+    Ok(Some(CreateFiatRamp {
+        fiat_id: fiat.id,
+        fiat_amount: leg.amount,
+        ramp_date,
+        via_exchange: ExchangeRef::Name(exchange.to_string()),
+        kind,
+        external_ref: external_ref.map(str::to_string),
+        asset_id: None,
+        fee_fiat_id: None,
+        fee_amount: None,
+    }))
+}
+
+/// Buy, sell or swap from what was given and what was received
+/// - a swap needs the fiat `market_price` of one unit of the given asset, statements of
+///   crypto to crypto trades without it are rejected
+pub(crate) fn trade_from_legs(
+    given: &Leg,
+    received: &Leg,
+    fee: Option<&Leg>,
+    market_price: Option<&Leg>,
+    trade_date: NaiveDate,
+    exchange: &str,
+    context: &StatementContext,
+) -> Result<CreateTrade> {
+    if given.amount.is_zero() || received.amount.is_zero() {
+        bail!("trade amounts must not be zero");
+    }
+    let asset = |leg: &Leg| {
+        context
+            .asset(&leg.symbol)
+            .ok_or_else(|| anyhow!("unknown asset '{}', add it before importing", leg.symbol))
+    };
+
+    let (kind, asset_leg, fiat, price, to_asset) =
+        match (context.fiat(&given.symbol), context.fiat(&received.symbol)) {
+            (Some(_), Some(_)) => bail!(
+                "conversion of {} to {} is not a trade",
+                given.symbol,
+                received.symbol
+            ),
+            (Some(fiat), None) => (
+                TradeKind::Buy,
+                received,
+                fiat,
+                given.amount / received.amount,
+                None,
+            ),
+            (None, Some(fiat)) => (
+                TradeKind::Sell,
+                given,
+                fiat,
+                received.amount / given.amount,
+                None,
+            ),
+            (None, None) => {
+                let Some(market_price) = market_price else {
+                    bail!(
+                        "trade of {} for {} has no fiat price in the statement",
+                        given.symbol,
+                        received.symbol
+                    );
+                };
+                let fiat = context
+                    .fiat(&market_price.symbol)
+                    .ok_or_else(|| anyhow!("unknown currency '{}'", market_price.symbol))?;
+                (
+                    TradeKind::Swap,
+                    given,
+                    fiat,
+                    market_price.amount,
+                    Some(asset(received)?),
+                )
+            }
+        };
+
+    let (fee_asset_id, fee_amount) = match fee.filter(|fee| !fee.amount.is_zero()) {
+        Some(fee) if fee.symbol.eq_ignore_ascii_case(&fiat.symbol) => (None, Some(fee.amount)),
+        Some(fee) => match context.fiat(&fee.symbol) {
+            Some(_) => bail!(
+                "fee in {} while the trade is in {}",
+                fee.symbol,
+                fiat.symbol
+            ),
+            None => (Some(asset(fee)?.id.clone()), Some(fee.amount)),
+        },
+        None => (None, None),
+    };
+
+    Ok(CreateTrade {
+        kind,
+        trade_date,
+        asset_id: asset(asset_leg)?.id.clone(),
+        quantity: asset_leg.amount,
+        price: price.round_dp(PRICE_DECIMAL_PLACES).normalize(),
+        fiat_id: fiat.id,
+        to_asset_id: to_asset.map(|asset| asset.id.clone()),
+        to_quantity: to_asset.map(|_| received.amount),
+        fee_asset_id,
+        fee_amount,
+        via_exchange: Some(ExchangeRef::Name(exchange.to_string())),
+        fiat_ramp_id: None,
+        note: None,
+        external_ref: None,
+    })
+}
+
+/// Report row of a parsed ramp or trade, rejected when parsing failed
+pub(crate) fn report_row(row: &SheetRow, record: Result<Record>) -> ImportRow {
+    let (status, ramp, trade, errors) = match record {
+        Ok(Record::FiatRamp(ramp)) => (ImportRowStatus::Accepted, Some(ramp), None, Vec::new()),
+        Ok(Record::Trade(trade)) => (ImportRowStatus::Accepted, None, Some(trade), Vec::new()),
+        Ok(Record::Skipped) => (ImportRowStatus::Skipped, None, None, Vec::new()),
+        Err(e) => (
+            ImportRowStatus::Rejected,
+            None,
+            None,
+            vec![format!("{e:#}")],
+        ),
+    };
+    ImportRow {
+        row_number: row.line,
+        status,
+        raw: row.cells.clone(),
+        ramp,
+        trade,
+        duplicate_of: None,
+        errors,
+    }
+}
+
+/// What a row, or a group of rows, of a statement turned into
+pub(crate) enum Record {
+    FiatRamp(CreateFiatRamp),
+    Trade(CreateTrade),
+    /// neither a fiat ramp nor a trade, e.g. a crypto transfer or staking reward
+    Skipped,
+}
+
+impl From<Option<CreateFiatRamp>> for Record {
+    fn from(ramp: Option<CreateFiatRamp>) -> Self {
+        ramp.map_or(Record::Skipped, Record::FiatRamp)
+    }
+}
+
+/// Sum the signed amounts of the legs of a trade spread over several rows
+/// - returns the single asset given and the single asset received
+pub(crate) fn net_legs(amounts: &[(String, Decimal)]) -> Result<(Leg, Leg)> {
+    let mut totals: Vec<(String, Decimal)> = Vec::new();
+    for (symbol, amount) in amounts {
+        match totals
+            .iter_mut()
+            .find(|(total_symbol, _)| total_symbol == symbol)
+        {
+            Some((_, total)) => *total += *amount,
+            None => totals.push((symbol.clone(), *amount)),
+        }
+    }
+
+    let given: Vec<_> = totals
+        .iter()
+        .filter(|(_, total)| total.is_sign_negative() && !total.is_zero())
+        .collect();
+    let received: Vec<_> = totals
+        .iter()
+        .filter(|(_, total)| total.is_sign_positive() && !total.is_zero())
+        .collect();
+    match (given.as_slice(), received.as_slice()) {
+        ([(given_symbol, given_amount)], [(received_symbol, received_amount)]) => Ok((
+            Leg::new(given_symbol, *given_amount),
+            Leg::new(received_symbol, *received_amount),
+        )),
+        _ => bail!("expected a single asset given and a single asset received"),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::asset::AssetKind;
+    use std::path::PathBuf;
+
+    pub fn fixture(name: &str) -> Sheet {
+        let file_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("src/import/statement/fixtures")
+            .join(name);
+        reader::read_sheet(&file_path).unwrap()
+    }
+
+    pub fn fiats() -> Vec<Fiat> {
+        ["EUR", "USD", "GBP"]
+            .iter()
+            .enumerate()
+            .map(|(index, symbol)| Fiat {
+                id: index as i64 + 1,
+                symbol: symbol.to_string(),
+                name: symbol.to_string(),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    pub fn assets() -> Vec<Asset> {
+        ["BTC", "ETH", "BNB"]
+            .iter()
+            .map(|symbol| Asset {
+                id: symbol.to_lowercase(),
+                name: symbol.to_string(),
+                symbol: symbol.to_string(),
+                kind: AssetKind::Cryptocoin,
+                coin_id: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_select_parser() {
+        let (parser, sheet) = select_parser(fixture("coinbase.csv"), None).unwrap();
+        assert_eq!(parser.id(), "coinbase");
+        // the preamble above the table is dropped
+        assert_eq!(sheet.headers[0], "ID");
+
+        let (parser, _) = select_parser(fixture("kraken_ledger.csv"), None).unwrap();
+        assert_eq!(parser.id(), "kraken_ledger");
+
+        assert!(select_parser(fixture("kraken_ledger.csv"), Some("coinbase")).is_err());
+        assert!(select_parser(fixture("kraken_ledger.csv"), Some("unknown")).is_err());
+    }
+
+    #[test]
+    fn test_trade_from_legs() {
+        let (fiats, assets) = (fiats(), assets());
+        let context = StatementContext {
+            fiats: &fiats,
+            assets: &assets,
+        };
+        let date = NaiveDate::from_ymd_opt(2024, 1, 5).unwrap();
+        let eur = |amount| Leg::new("EUR", Decimal::new(amount, 0));
+        let btc = Leg::new("btc", Decimal::new(5, 1));
+
+        let buy = trade_from_legs(
+            &eur(20_000),
+            &btc,
+            Some(&eur(10)),
+            None,
+            date,
+            "Kraken",
+            &context,
+        )
+        .unwrap();
+        assert_eq!(buy.kind, TradeKind::Buy);
+        assert_eq!(buy.asset_id, "btc");
+        assert_eq!(buy.price, Decimal::new(40_000, 0));
+        assert_eq!(buy.fee_asset_id, None);
+        assert_eq!(buy.fee_amount, Some(Decimal::new(10, 0)));
+
+        let sell =
+            trade_from_legs(&btc, &eur(21_000), None, None, date, "Kraken", &context).unwrap();
+        assert_eq!(sell.kind, TradeKind::Sell);
+        assert_eq!(sell.price, Decimal::new(42_000, 0));
+
+        let eth = Leg::new("ETH", Decimal::new(8, 0));
+        assert!(trade_from_legs(&btc, &eth, None, None, date, "Kraken", &context).is_err());
+        let swap = trade_from_legs(
+            &btc,
+            &eth,
+            None,
+            Some(&eur(40_000)),
+            date,
+            "Kraken",
+            &context,
+        )
+        .unwrap();
+        assert_eq!(swap.kind, TradeKind::Swap);
+        assert_eq!(swap.to_asset_id.as_deref(), Some("eth"));
+        assert_eq!(swap.to_quantity, Some(Decimal::new(8, 0)));
+
+        let doge = Leg::new("DOGE", Decimal::new(100, 0));
+        assert!(trade_from_legs(&eur(10), &doge, None, None, date, "Kraken", &context).is_err());
+        assert!(trade_from_legs(
+            &eur(10),
+            &Leg::new("USD", Decimal::ONE),
+            None,
+            None,
+            date,
+            "Kraken",
+            &context
+        )
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_commit_statement() {
+        let db = Db::in_memory().await.unwrap();
+        sqlx::query("INSERT INTO fiat (id, symbol, name) VALUES (1, 'EUR', 'Euro')")
+            .execute(&db.0)
+            .await
+            .unwrap();
+        for symbol in ["BTC", "ETH"] {
+            sqlx::query(
+                "INSERT INTO asset (id, name, symbol, kind) VALUES (?, ?, ?, 'cryptocoin')",
+            )
+            .bind(symbol.to_lowercase())
+            .bind(symbol)
+            .bind(symbol)
+            .execute(&db.0)
+            .await
+            .unwrap();
+        }
+        let file_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("src/import/statement/fixtures/kraken_ledger.csv");
+
+        let (report, created) =
+            StatementImportService::commit(&file_path, None, DuplicatePolicy::Skip, &db)
+                .await
+                .unwrap();
+        assert_eq!(report.exchange, "Kraken");
+        assert_eq!(report.accepted_count, 4);
+        assert_eq!(report.imported_ramp_count, 2);
+        assert_eq!(report.imported_trade_count, 2);
+        assert!(created.iter().all(|ramp| ramp.via_exchange == "Kraken"));
+
+        let trades = TradeService::get_all(&db).await.unwrap();
+        assert_eq!(trades.len(), 2);
+        assert_eq!(trades[0].via_exchange.as_deref(), Some("Kraken"));
+
+        // the ledger txid of a deposit or withdrawal is its external reference
+        assert_eq!(
+            created[0].external_ref.as_deref(),
+            Some("L4UESK-KG3EQ-UFO4T5")
+        );
+        let preview = StatementImportService::preview(&file_path, None, &db)
+            .await
+            .unwrap();
+        assert_eq!(preview.duplicate_count, 4);
+        assert_eq!(preview.conflict_count, 0);
+    }
+
+    #[tokio::test]
+    async fn test_reimport_statement() {
+        let db = Db::in_memory().await.unwrap();
+        sqlx::query("INSERT INTO fiat (id, symbol, name) VALUES (1, 'EUR', 'Euro')")
+            .execute(&db.0)
+            .await
+            .unwrap();
+        for symbol in ["BTC", "ETH", "BNB"] {
+            sqlx::query(
+                "INSERT INTO asset (id, name, symbol, kind) VALUES (?, ?, ?, 'cryptocoin')",
+            )
+            .bind(symbol.to_lowercase())
+            .bind(symbol)
+            .bind(symbol)
+            .execute(&db.0)
+            .await
+            .unwrap();
+        }
+        let count = |table: &'static str| {
+            let db = &db;
+            async move {
+                sqlx::query_scalar::<_, i64>(&format!("SELECT COUNT(*) FROM {table}"))
+                    .fetch_one(&db.0)
+                    .await
+                    .unwrap()
+            }
+        };
+
+        // kraken trades are matched by their refid, binance trades by their fingerprint
+        for fixture in ["kraken_ledger.csv", "binance.csv"] {
+            let file_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .join("src/import/statement/fixtures")
+                .join(fixture);
+            let (report, _) =
+                StatementImportService::commit(&file_path, None, DuplicatePolicy::Skip, &db)
+                    .await
+                    .unwrap();
+            assert!(report.imported_trade_count > 0);
+            let (trade_count, ramp_count) = (count("trade").await, count("fiat_ramp").await);
+
+            let (report, created) =
+                StatementImportService::commit(&file_path, None, DuplicatePolicy::Merge, &db)
+                    .await
+                    .unwrap();
+            assert_eq!(report.accepted_count, 0);
+            assert_eq!(report.imported_trade_count, 0);
+            assert_eq!(report.imported_ramp_count, 0);
+            assert_eq!(report.merged_trade_count, 0);
+            assert!(created.is_empty());
+            assert_eq!(count("trade").await, trade_count);
+            assert_eq!(count("fiat_ramp").await, ramp_count);
+        }
+    }
+}
@@ -1,20 +1,39 @@
+mod asset;
+mod backup;
 mod crypto_exchange;
+mod crypto_price;
 mod db;
+mod encryption;
+mod exchange;
 mod fiat;
 mod fiat_exchanger;
 mod fiat_ramp;
 mod fiat_rate;
+mod import;
+mod portfolio;
 mod sys_tracker;
+mod trade;
 mod user_settings;
 mod utils;
 
 use crate::{
+    crypto_exchange::{CoinGeckoService, CryptoExchangeManager},
     db::{init_db, Db},
-    fiat_exchanger::frankfurter_exchanger::FrankfurterExchangerApi,
+    fiat_exchanger::{frankfurter_exchanger::FrankfurterExchangerApi, FiatExchangerManager},
 };
+use asset::command as asset_command;
+use backup::command as backup_command;
+use crypto_exchange::command as crypto_exchange_command;
+use crypto_price::command as crypto_price_command;
+use encryption::command as encryption_command;
+use exchange::command as exchange_command;
 use fiat::command as fiat_command;
 use fiat_ramp::command as fiat_ramp_command;
-use tauri::Manager;
+use fiat_rate::command as fiat_rate_command;
+use import::command as import_command;
+use portfolio::command as portfolio_command;
+use tauri::{AppHandle, Manager};
+use trade::command as trade_command;
 use user_settings::command as user_settings_command;
 
 #[derive(Default)]
@@ -32,33 +51,22 @@ pub fn run() {
             let handle = app.handle().clone();
             //1. Initialize App Config - this should be done first as it is used by other components
             handle.manage(AppConfig { env });
+            // Crypto price providers in priority order
+            handle.manage(CryptoExchangeManager::new(vec![Box::new(
+                CoinGeckoService::new(),
+            )]));
+            // Fiat rate providers in priority order
+            handle.manage(FiatExchangerManager::default());
             //2. Initialize Database Pool Connection - this should be done second as it depends on App Config
             tauri::async_runtime::block_on(async move {
-                let pool = init_db(&handle).await?;
-                let db = Db(pool);
-                if let Err(e) = fiat::FiatService::<FrankfurterExchangerApi>::default()
-                    .update_currencies(&db)
-                    .await
-                {
-                    eprintln!("Failed to update currencies: {}", e);
+                let db_path = db::get_db_path(&handle).await?;
+                db::apply_pending_replacement(&db_path)?;
+                // An encrypted database is opened by `unlock_database` once the user enters the passphrase
+                if db::is_encrypted(&db_path)? {
+                    return Ok::<(), String>(());
                 }
-
-                if let Err(e) = user_settings::ensure_exists::<FrankfurterExchangerApi>(&db).await {
-                    eprintln!("Failed to ensure user settings: {}", e);
-                }
-
-                // Background task: Process missing rates queue
-                let db_for_task = Db(db.0.clone());
-                tauri::async_runtime::spawn(async move {
-                    let api = FrankfurterExchangerApi::default();
-                    if let Err(e) =
-                        crate::fiat_rate::process_missing_rates(&db_for_task, &api).await
-                    {
-                        eprintln!("Failed to process missing rates: {}", e);
-                    }
-                });
-
-                handle.manage(db);
+                let pool = init_db(&handle, None).await?;
+                on_db_ready(&handle, Db(pool)).await;
                 Ok::<(), String>(())
             })?;
             Ok(())
@@ -67,16 +75,156 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             fiat_command::get_all_currencies,
             fiat_command::get_currencies_by_symbol,
+            fiat_command::format_fiat_amount,
             fiat_ramp_command::create_fiat_ramp,
             fiat_ramp_command::get_fiat_ramps,
             fiat_ramp_command::update_fiat_ramp,
             fiat_ramp_command::delete_fiat_ramp,
+            fiat_ramp_command::create_recurring_ramp,
+            fiat_ramp_command::get_recurring_ramps,
+            fiat_ramp_command::update_recurring_ramp,
+            fiat_ramp_command::set_recurring_ramp_paused,
+            fiat_ramp_command::delete_recurring_ramp,
+            fiat_ramp_command::preview_recurring_ramp,
+            fiat_ramp_command::preview_recurring_schedule,
+            fiat_ramp_command::get_recurring_ramp_proposals,
+            fiat_ramp_command::confirm_recurring_ramp_proposal,
+            fiat_ramp_command::dismiss_recurring_ramp_proposal,
             fiat_ramp_command::get_fiat_ramp_summary,
+            fiat_ramp_command::get_fiat_ramp_breakdown,
             fiat_ramp_command::get_fiat_ramp_date_range,
             fiat_ramp_command::create_fiat_ramps_bulk,
+            fiat_rate_command::get_fiat_rate,
+            fiat_rate_command::get_fiat_pair_rate,
+            fiat_rate_command::backfill_fiat_rates,
+            fiat_rate_command::set_fiat_ramp_manual_rate,
+            fiat_rate_command::set_manual_fiat_rate,
+            fiat_rate_command::get_manual_fiat_rates,
+            fiat_rate_command::delete_manual_fiat_rate,
+            import_command::get_import_file_headers,
+            import_command::preview_fiat_ramp_import,
+            import_command::import_fiat_ramps,
+            import_command::save_import_mapping,
+            import_command::get_import_mappings,
+            import_command::delete_import_mapping,
+            import_command::get_statement_parsers,
+            import_command::preview_statement_import,
+            import_command::import_statement,
+            backup_command::export_backup,
+            backup_command::inspect_backup,
+            backup_command::restore_backup,
+            backup_command::list_database_backups,
+            backup_command::create_database_backup,
+            backup_command::restore_database_backup,
+            encryption_command::get_database_status,
+            encryption_command::unlock_database,
+            encryption_command::change_database_passphrase,
             user_settings_command::get_user_settings,
             user_settings_command::update_user_settings,
+            crypto_price_command::get_crypto_price,
+            crypto_price_command::get_crypto_price_history,
+            crypto_exchange_command::list_crypto_coins,
+            crypto_exchange_command::search_crypto_coins,
+            crypto_exchange_command::get_crypto_coin_providers,
+            asset_command::create_asset,
+            asset_command::get_assets,
+            asset_command::get_asset_by_symbol,
+            asset_command::update_asset,
+            asset_command::delete_asset,
+            asset_command::link_asset_coin,
+            asset_command::get_asset_provider_coin_ids,
+            asset_command::set_stablecoin_peg,
+            asset_command::get_stablecoin_pegs,
+            asset_command::delete_stablecoin_peg,
+            exchange_command::create_exchange,
+            exchange_command::get_exchanges,
+            exchange_command::find_exchange_by_name,
+            exchange_command::update_exchange,
+            exchange_command::delete_exchange,
+            exchange_command::merge_exchanges,
+            trade_command::create_trade,
+            trade_command::get_trades,
+            trade_command::update_trade,
+            trade_command::delete_trade,
+            portfolio_command::get_portfolio_valuation,
+            portfolio_command::get_portfolio_pnl,
+            portfolio_command::get_tax_report,
+            portfolio_command::export_tax_report,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+/// Start up tasks once the database is open, then hand the database to the commands
+pub(crate) async fn on_db_ready(handle: &AppHandle, db: Db) {
+    if let Err(e) = fiat::FiatService::new(FiatExchangerManager::default())
+        .update_currencies(&db)
+        .await
+    {
+        eprintln!("Failed to update currencies: {}", e);
+    }
+
+    if let Err(e) = user_settings::ensure_exists::<FrankfurterExchangerApi>(&db).await {
+        eprintln!("Failed to ensure user settings: {}", e);
+    }
+
+    // Link fiat ramps and trades recorded with a free-text exchange name to an exchange
+    if let Err(e) = exchange::ExchangeService::link_unlinked_once(&db).await {
+        eprintln!("Failed to link fiat ramps and trades to exchanges: {:#}", e);
+    }
+
+    // Background task: Create the due ramps of the recurring ramps, backfill the rates of ramp dates
+    // in one pass, then get the rates of the new ramps and process missing rates queue
+    let db_for_task = Db(db.0.clone());
+    let handle_for_task = handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let api = handle_for_task.state::<FiatExchangerManager>();
+        let recurring_ramps = fiat_ramp::recurring::RecurringRampService::materialize(
+            chrono::Local::now().date_naive(),
+            &db_for_task,
+        )
+        .await
+        .unwrap_or_else(|e| {
+            eprintln!("Failed to create recurring ramps: {e:#}");
+            Vec::new()
+        });
+        if let Err(e) = crate::fiat_rate::backfill::backfill_rates(&db_for_task, api.inner()).await
+        {
+            eprintln!("Failed to backfill rates: {}", e);
+        }
+        for ramp in &recurring_ramps {
+            let _ = crate::fiat_rate::get_rate_for_ramp(&db_for_task, api.inner(), &ramp.id)
+                .await
+                .ok();
+        }
+        if let Err(e) = crate::fiat_rate::process_missing_rates(&db_for_task, api.inner()).await {
+            eprintln!("Failed to process missing rates: {}", e);
+        }
+    });
+
+    // Background task: Refresh the offline coin catalogue
+    let db_for_task = Db(db.0.clone());
+    let handle_for_task = handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let manager = handle_for_task.state::<CryptoExchangeManager>();
+        if let Err(e) = manager.update_supported_coins(&db_for_task).await {
+            eprintln!("Failed to update supported coins: {}", e);
+        }
+        if let Err(e) =
+            asset::peg::StablecoinPegService::fetch_missing_prices(&db_for_task, &manager).await
+        {
+            eprintln!("Failed to fetch stablecoin prices: {e:#}");
+        }
+    });
+
+    // Background task: Back up the database on the schedule of the user settings
+    match db::get_db_path(handle).await {
+        Ok(db_path) => {
+            let db_for_task = Db(db.0.clone());
+            tauri::async_runtime::spawn(backup::schedule::run(db_for_task, db_path));
+        }
+        Err(e) => eprintln!("Failed to schedule backups: {}", e),
+    }
+
+    handle.manage(db);
+}
@@ -0,0 +1,67 @@
+use crate::crypto_exchange::CryptoExchangeManager;
+use crate::db::Db;
+use crate::fiat_exchanger::FiatExchangerManager;
+use crate::portfolio::tax_report::{self, ReportFormat, TaxReport};
+use crate::portfolio::{PortfolioPnl, PortfolioService, PortfolioValuation};
+use chrono::NaiveDate;
+use tauri::State;
+
+/// Get the holdings valued in the user's default fiat -- date is optional and defaults to today
+#[tauri::command]
+pub async fn get_portfolio_valuation(
+    db: State<'_, Db>,
+    fiat_exchanger: State<'_, FiatExchangerManager>,
+    manager: State<'_, CryptoExchangeManager>,
+    date: Option<NaiveDate>,
+) -> Result<PortfolioValuation, String> {
+    let api = fiat_exchanger.inner();
+    PortfolioService::get_valuation(&db, &manager, api, date)
+        .await
+        .map_err(|e| format!("failed to get portfolio valuation: {e:#}"))
+}
+
+/// Get the realized and unrealized gains per asset with the cost basis method of the user settings
+#[tauri::command]
+pub async fn get_portfolio_pnl(
+    db: State<'_, Db>,
+    fiat_exchanger: State<'_, FiatExchangerManager>,
+    manager: State<'_, CryptoExchangeManager>,
+) -> Result<PortfolioPnl, String> {
+    let api = fiat_exchanger.inner();
+    PortfolioService::get_pnl(&db, &manager, api)
+        .await
+        .map_err(|e| format!("failed to get portfolio pnl: {e:#}"))
+}
+
+/// Get the capital gains of every disposal in the calendar year `tax_year`
+#[tauri::command]
+pub async fn get_tax_report(
+    db: State<'_, Db>,
+    fiat_exchanger: State<'_, FiatExchangerManager>,
+    tax_year: i32,
+) -> Result<TaxReport, String> {
+    let api = fiat_exchanger.inner();
+    tax_report::generate(&db, api, tax_year)
+        .await
+        .map_err(|e| format!("failed to generate tax report: {e:#}"))
+}
+
+/// Write the tax report of `tax_year` to `file_path` -- returns the path written to
+#[tauri::command]
+pub async fn export_tax_report(
+    db: State<'_, Db>,
+    fiat_exchanger: State<'_, FiatExchangerManager>,
+    tax_year: i32,
+    format: ReportFormat,
+    file_path: String,
+) -> Result<String, String> {
+    let api = fiat_exchanger.inner();
+    let report = tax_report::generate(&db, api, tax_year)
+        .await
+        .map_err(|e| format!("failed to generate tax report: {e:#}"))?;
+    let content = tax_report::export(&report, format)
+        .map_err(|e| format!("failed to export tax report: {e:#}"))?;
+    std::fs::write(&file_path, content)
+        .map_err(|e| format!("failed to write tax report to {file_path}: {e}"))?;
+    Ok(file_path)
+}
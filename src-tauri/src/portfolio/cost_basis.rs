@@ -0,0 +1,544 @@
+use crate::db::{Db, StringRowId};
+use crate::fiat::Fiat;
+use crate::fiat_exchanger::FiatExchanger;
+use crate::fiat_rate::{self, FiatExchangeRate};
+use crate::trade::{Trade, TradeKind, TradeService};
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// How disposals are matched against the lots acquired before them
+#[derive(Debug, Serialize, Deserialize, sqlx::Type, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum CostBasisMethod {
+    /// first in, first out
+    #[default]
+    Fifo,
+    /// last in, first out
+    Lifo,
+    /// highest cost, first out
+    Hifo,
+    /// every lot of the asset is pooled at its average cost
+    Average,
+}
+
+/// Quality of the fiat rates a value was converted with
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+pub struct RateFlags {
+    pub is_estimated: bool,
+    pub is_non_working_day: bool,
+}
+
+impl RateFlags {
+    fn from_rate(rate: &FiatExchangeRate) -> Self {
+        RateFlags {
+            is_estimated: rate.is_estimated,
+            is_non_working_day: rate.is_non_working_day,
+        }
+    }
+
+    fn merge(self, other: RateFlags) -> Self {
+        RateFlags {
+            is_estimated: self.is_estimated || other.is_estimated,
+            is_non_working_day: self.is_non_working_day || other.is_non_working_day,
+        }
+    }
+}
+
+/// Acquisition of an asset which is still (partly) held
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Lot {
+    pub trade_id: StringRowId,
+    pub asset_id: StringRowId,
+    pub acquired_date: NaiveDate,
+    /// quantity still held
+    pub quantity: Decimal,
+    /// cost of one unit in the user's default fiat
+    pub unit_cost: Decimal,
+    #[serde(flatten)]
+    pub rate_flags: RateFlags,
+}
+
+/// Part of a disposal matched against a single lot
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DisposalLine {
+    pub disposal_trade_id: StringRowId,
+    pub asset_id: StringRowId,
+    pub disposal_date: NaiveDate,
+    /// None when there was no lot left to match
+    pub acquisition_trade_id: Option<StringRowId>,
+    /// None with the average method or when there was no lot left to match
+    pub acquisition_date: Option<NaiveDate>,
+    pub quantity: Decimal,
+    pub proceeds: Decimal,
+    pub cost_basis: Decimal,
+    pub gain: Decimal,
+    /// quantity disposed without any lot left, its cost basis is zero
+    pub is_unmatched: bool,
+    /// fee paid in the asset, disposed without proceeds
+    pub is_fee: bool,
+    /// rates of the disposal and of the matched lots
+    #[serde(flatten)]
+    pub rate_flags: RateFlags,
+}
+
+/// Disposal of an asset valued in the user's default fiat
+#[derive(Debug, Clone)]
+pub struct Disposal {
+    pub trade_id: StringRowId,
+    pub asset_id: StringRowId,
+    pub date: NaiveDate,
+    pub quantity: Decimal,
+    pub proceeds: Decimal,
+    pub is_fee: bool,
+    pub rate_flags: RateFlags,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CostBasisResult {
+    pub method: CostBasisMethod,
+    pub disposals: Vec<DisposalLine>,
+    /// lots still held per asset id
+    pub open_lots: HashMap<StringRowId, Vec<Lot>>,
+}
+
+/// Matches disposals against the open lots, events must be applied in chronological order
+pub struct CostBasisEngine {
+    method: CostBasisMethod,
+    lots: HashMap<StringRowId, Vec<Lot>>,
+    disposals: Vec<DisposalLine>,
+}
+
+impl CostBasisEngine {
+    pub fn new(method: CostBasisMethod) -> Self {
+        CostBasisEngine {
+            method,
+            lots: HashMap::new(),
+            disposals: Vec::new(),
+        }
+    }
+
+    pub fn acquire(&mut self, lot: Lot) {
+        if lot.quantity > Decimal::ZERO {
+            self.lots.entry(lot.asset_id.clone()).or_default().push(lot);
+        }
+    }
+
+    pub fn dispose(&mut self, disposal: Disposal) {
+        if disposal.quantity <= Decimal::ZERO {
+            return;
+        }
+        let lots = self.lots.entry(disposal.asset_id.clone()).or_default();
+        let mut remaining = disposal.quantity;
+        let mut proceeds_left = disposal.proceeds;
+
+        if self.method == CostBasisMethod::Average && !lots.is_empty() {
+            let held: Decimal = lots.iter().map(|lot| lot.quantity).sum();
+            let held_cost: Decimal = lots.iter().map(|lot| lot.quantity * lot.unit_cost).sum();
+            let taken = remaining.min(held);
+            let cost_basis = held_cost * taken / held;
+            let rate_flags = lots.iter().fold(disposal.rate_flags, |flags, lot| {
+                flags.merge(lot.rate_flags)
+            });
+
+            // every lot shrinks by the same share, the last one takes the rounding difference
+            let kept = held - taken;
+            let mut kept_left = kept;
+            let last = lots.len() - 1;
+            for (index, lot) in lots.iter_mut().enumerate() {
+                lot.quantity = if index == last {
+                    kept_left
+                } else {
+                    lot.quantity * kept / held
+                };
+                kept_left -= lot.quantity;
+            }
+            lots.retain(|lot| lot.quantity > Decimal::ZERO);
+
+            let proceeds = if taken == remaining {
+                proceeds_left
+            } else {
+                disposal.proceeds * taken / disposal.quantity
+            };
+            self.disposals.push(line(
+                &disposal, None, None, taken, proceeds, cost_basis, rate_flags,
+            ));
+            remaining -= taken;
+            proceeds_left -= proceeds;
+        }
+
+        while remaining > Decimal::ZERO {
+            let Some(index) = self.method.next_lot(lots) else {
+                break;
+            };
+            let lot = &mut lots[index];
+            let taken = remaining.min(lot.quantity);
+            let proceeds = if taken == remaining {
+                proceeds_left
+            } else {
+                disposal.proceeds * taken / disposal.quantity
+            };
+            let cost_basis = taken * lot.unit_cost;
+            let rate_flags = disposal.rate_flags.merge(lot.rate_flags);
+            self.disposals.push(line(
+                &disposal,
+                Some(lot.trade_id.clone()),
+                Some(lot.acquired_date),
+                taken,
+                proceeds,
+                cost_basis,
+                rate_flags,
+            ));
+
+            lot.quantity -= taken;
+            if lot.quantity.is_zero() {
+                lots.remove(index);
+            }
+            remaining -= taken;
+            proceeds_left -= proceeds;
+        }
+
+        if remaining > Decimal::ZERO {
+            let mut unmatched = line(
+                &disposal,
+                None,
+                None,
+                remaining,
+                proceeds_left,
+                Decimal::ZERO,
+                disposal.rate_flags,
+            );
+            unmatched.is_unmatched = true;
+            self.disposals.push(unmatched);
+        }
+    }
+
+    pub fn finish(mut self) -> CostBasisResult {
+        self.lots.retain(|_, lots| !lots.is_empty());
+        CostBasisResult {
+            method: self.method,
+            disposals: self.disposals,
+            open_lots: self.lots,
+        }
+    }
+}
+
+impl CostBasisMethod {
+    /// Index of the lot the next disposal is matched against
+    fn next_lot(&self, lots: &[Lot]) -> Option<usize> {
+        if lots.is_empty() {
+            return None;
+        }
+        match self {
+            CostBasisMethod::Fifo | CostBasisMethod::Average => Some(0),
+            CostBasisMethod::Lifo => Some(lots.len() - 1),
+            // earliest lot wins a tie
+            CostBasisMethod::Hifo => lots
+                .iter()
+                .enumerate()
+                .rev()
+                .max_by_key(|(_, lot)| lot.unit_cost)
+                .map(|(index, _)| index),
+        }
+    }
+}
+
+fn line(
+    disposal: &Disposal,
+    acquisition_trade_id: Option<StringRowId>,
+    acquisition_date: Option<NaiveDate>,
+    quantity: Decimal,
+    proceeds: Decimal,
+    cost_basis: Decimal,
+    rate_flags: RateFlags,
+) -> DisposalLine {
+    DisposalLine {
+        disposal_trade_id: disposal.trade_id.clone(),
+        asset_id: disposal.asset_id.clone(),
+        disposal_date: disposal.date,
+        acquisition_trade_id,
+        acquisition_date,
+        quantity,
+        proceeds,
+        cost_basis,
+        gain: proceeds - cost_basis,
+        is_unmatched: false,
+        is_fee: disposal.is_fee,
+        rate_flags,
+    }
+}
+
+/// Run the whole trade ledger through the cost basis engine, valued in `fiat`
+/// - trades in another fiat are converted with `fiat_rate::get_rate` on the trade date
+/// - a fee paid in fiat is added to the cost of a buy and removed from the proceeds of a sell or swap
+/// - a fee paid in an asset is a disposal of that asset without proceeds
+pub async fn compute<A: FiatExchanger>(
+    db: &Db,
+    exchange_api: &A,
+    method: CostBasisMethod,
+    fiat: &Fiat,
+) -> Result<CostBasisResult> {
+    let trades = TradeService::get_all(db).await?;
+    let mut rates: HashMap<NaiveDate, FiatExchangeRate> = HashMap::new();
+    let mut engine = CostBasisEngine::new(method);
+
+    for trade in trades {
+        if trade.fiat_id != fiat.id && !rates.contains_key(&trade.trade_date) {
+            let rate = fiat_rate::get_rate(db, exchange_api, &trade.trade_date, None)
+                .await
+                .with_context(|| format!("failed to get fiat rate of {}", trade.trade_date))?;
+            rates.insert(trade.trade_date, rate);
+        }
+
+        let mut rate_flags = RateFlags::default();
+        let mut to_fiat = |amount: Decimal| -> Result<Decimal> {
+            if trade.fiat_id == fiat.id {
+                return Ok(amount);
+            }
+            let rate = &rates[&trade.trade_date];
+            rate_flags = RateFlags::from_rate(rate);
+            rate.convert(amount, &trade.fiat_symbol, &fiat.symbol)
+        };
+
+        let value = to_fiat(trade.price * trade.quantity)?;
+        let fiat_fee = match (&trade.fee_asset_id, trade.fee_amount) {
+            (None, Some(fee_amount)) => to_fiat(fee_amount)?,
+            _ => Decimal::ZERO,
+        };
+        apply_trade(&mut engine, &trade, value, fiat_fee, rate_flags);
+    }
+
+    Ok(engine.finish())
+}
+
+fn apply_trade(
+    engine: &mut CostBasisEngine,
+    trade: &Trade,
+    value: Decimal,
+    fiat_fee: Decimal,
+    rate_flags: RateFlags,
+) {
+    let disposal = |asset_id: &str, quantity, proceeds, is_fee| Disposal {
+        trade_id: trade.id.clone(),
+        asset_id: asset_id.to_string(),
+        date: trade.trade_date,
+        quantity,
+        proceeds,
+        is_fee,
+        rate_flags,
+    };
+
+    match trade.kind {
+        TradeKind::Buy => engine.acquire(Lot {
+            trade_id: trade.id.clone(),
+            asset_id: trade.asset_id.clone(),
+            acquired_date: trade.trade_date,
+            quantity: trade.quantity,
+            unit_cost: (value + fiat_fee) / trade.quantity,
+            rate_flags,
+        }),
+        TradeKind::Sell => engine.dispose(disposal(
+            &trade.asset_id,
+            trade.quantity,
+            value - fiat_fee,
+            false,
+        )),
+        TradeKind::Swap => {
+            engine.dispose(disposal(
+                &trade.asset_id,
+                trade.quantity,
+                value - fiat_fee,
+                false,
+            ));
+            if let (Some(to_asset_id), Some(to_quantity)) = (&trade.to_asset_id, trade.to_quantity)
+            {
+                engine.acquire(Lot {
+                    trade_id: trade.id.clone(),
+                    asset_id: to_asset_id.clone(),
+                    acquired_date: trade.trade_date,
+                    quantity: to_quantity,
+                    unit_cost: value / to_quantity,
+                    rate_flags,
+                });
+            }
+        }
+    }
+
+    if let (Some(fee_asset_id), Some(fee_amount)) = (&trade.fee_asset_id, trade.fee_amount) {
+        engine.dispose(disposal(fee_asset_id, fee_amount, Decimal::ZERO, true));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset::{AssetKind, AssetService, CreateAsset};
+    use crate::fiat_exchanger::{MockFiatExchanger, Rates};
+    use crate::trade::CreateTrade;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 1, day).unwrap()
+    }
+
+    fn lot(trade_id: &str, day: u32, quantity: i64, unit_cost: i64) -> Lot {
+        Lot {
+            trade_id: trade_id.to_string(),
+            asset_id: "btc".to_string(),
+            acquired_date: date(day),
+            quantity: Decimal::from(quantity),
+            unit_cost: Decimal::from(unit_cost),
+            rate_flags: RateFlags::default(),
+        }
+    }
+
+    fn sell(quantity: i64, proceeds: i64) -> Disposal {
+        Disposal {
+            trade_id: "sell".to_string(),
+            asset_id: "btc".to_string(),
+            date: date(10),
+            quantity: Decimal::from(quantity),
+            proceeds: Decimal::from(proceeds),
+            is_fee: false,
+            rate_flags: RateFlags::default(),
+        }
+    }
+
+    /// buys 1 @ 100, 1 @ 300, 1 @ 200 and sells 2 for 800
+    fn run(method: CostBasisMethod) -> CostBasisResult {
+        let mut engine = CostBasisEngine::new(method);
+        engine.acquire(lot("a", 1, 1, 100));
+        engine.acquire(lot("b", 2, 1, 300));
+        engine.acquire(lot("c", 3, 1, 200));
+        engine.dispose(sell(2, 800));
+        engine.finish()
+    }
+
+    fn matched(result: &CostBasisResult) -> Vec<&str> {
+        result
+            .disposals
+            .iter()
+            .filter_map(|line| line.acquisition_trade_id.as_deref())
+            .collect()
+    }
+
+    #[test]
+    fn test_lot_matching_methods() {
+        let fifo = run(CostBasisMethod::Fifo);
+        assert_eq!(matched(&fifo), vec!["a", "b"]);
+        assert_eq!(fifo.disposals[0].gain, Decimal::from(300));
+        assert_eq!(fifo.disposals[1].gain, Decimal::from(100));
+
+        let lifo = run(CostBasisMethod::Lifo);
+        assert_eq!(matched(&lifo), vec!["c", "b"]);
+
+        let hifo = run(CostBasisMethod::Hifo);
+        assert_eq!(matched(&hifo), vec!["b", "c"]);
+        assert_eq!(hifo.open_lots["btc"][0].trade_id, "a");
+
+        let average = run(CostBasisMethod::Average);
+        assert_eq!(average.disposals.len(), 1);
+        assert_eq!(average.disposals[0].cost_basis, Decimal::from(400));
+        assert_eq!(average.disposals[0].gain, Decimal::from(400));
+        let held: Decimal = average.open_lots["btc"].iter().map(|l| l.quantity).sum();
+        assert_eq!(held, Decimal::ONE);
+    }
+
+    #[test]
+    fn test_dispose_without_lots() {
+        let mut engine = CostBasisEngine::new(CostBasisMethod::Fifo);
+        engine.acquire(lot("a", 1, 1, 100));
+        engine.dispose(sell(3, 900));
+        let result = engine.finish();
+
+        assert_eq!(result.disposals.len(), 2);
+        assert_eq!(result.disposals[0].proceeds, Decimal::from(300));
+        let unmatched = &result.disposals[1];
+        assert!(unmatched.is_unmatched);
+        assert_eq!(unmatched.quantity, Decimal::TWO);
+        assert_eq!(unmatched.proceeds, Decimal::from(600));
+        assert_eq!(unmatched.cost_basis, Decimal::ZERO);
+        assert!(result.open_lots.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_compute_converts_lots_on_trade_date() {
+        let db = Db::in_memory().await.unwrap();
+        let mut fiats = Vec::new();
+        for (symbol, name) in [("USD", "US Dollar"), ("EUR", "Euro")] {
+            let fiat = sqlx::query_as::<_, Fiat>(
+                "INSERT INTO fiat (symbol, name) VALUES (?, ?) RETURNING *",
+            )
+            .bind(symbol)
+            .bind(name)
+            .fetch_one(&db.0)
+            .await
+            .unwrap();
+            fiats.push(fiat);
+        }
+        let (usd, eur) = (&fiats[0], &fiats[1]);
+        let btc = AssetService::create(
+            CreateAsset {
+                name: "Bitcoin".to_string(),
+                symbol: "BTC".to_string(),
+                kind: AssetKind::Cryptocoin,
+                coin_id: None,
+            },
+            &db,
+        )
+        .await
+        .unwrap();
+
+        // bought for 1000 EUR + 10 EUR fee, sold for 1500 USD
+        let trade = |kind, day, price: i64, fiat_id, fee: Option<i64>| CreateTrade {
+            kind,
+            trade_date: date(day),
+            asset_id: btc.id.clone(),
+            quantity: Decimal::ONE,
+            price: Decimal::from(price),
+            fiat_id,
+            to_asset_id: None,
+            to_quantity: None,
+            fee_asset_id: None,
+            fee_amount: fee.map(Decimal::from),
+            via_exchange: None,
+            fiat_ramp_id: None,
+            note: None,
+            external_ref: None,
+        };
+        TradeService::create(trade(TradeKind::Buy, 2, 1000, eur.id, Some(10)), &db)
+            .await
+            .unwrap();
+        TradeService::create(trade(TradeKind::Sell, 9, 1500, usd.id, None), &db)
+            .await
+            .unwrap();
+
+        // only the EUR buy needs a rate: 1 USD = 0.5 EUR
+        let mut mock_api = MockFiatExchanger::new();
+        mock_api
+            .expect_get_latest_rates()
+            .times(1)
+            .returning(|_, requested| {
+                Ok(Rates {
+                    provider: "mock".to_string(),
+                    base: "USD".to_string(),
+                    date: *requested.unwrap(),
+                    rates: HashMap::from([("EUR".to_string(), Decimal::new(5, 1))]),
+                })
+            });
+
+        let result = compute(&db, &mock_api, CostBasisMethod::Fifo, usd)
+            .await
+            .unwrap();
+
+        assert_eq!(result.disposals.len(), 1);
+        let line = &result.disposals[0];
+        assert_eq!(line.cost_basis, Decimal::from(2020));
+        assert_eq!(line.proceeds, Decimal::from(1500));
+        assert_eq!(line.gain, Decimal::from(-520));
+        assert_eq!(line.acquisition_date, Some(date(2)));
+        assert!(!line.rate_flags.is_estimated);
+    }
+}
@@ -0,0 +1,582 @@
+pub mod command;
+pub mod cost_basis;
+pub mod tax_report;
+use crate::asset::{AssetKind, AssetService};
+use crate::crypto_exchange::CryptoExchangeManager;
+use crate::crypto_price;
+use crate::db::{Db, RowId, StringRowId};
+use crate::fiat::{Fiat, FiatService};
+use crate::fiat_exchanger::FiatExchanger;
+use crate::fiat_rate::{self, FiatExchangeRate};
+use crate::trade::{Trade, TradeKind, TradeService};
+use crate::user_settings;
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use cost_basis::CostBasisMethod;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Prices missing in the user's fiat are fetched in this fiat and converted through `fiat_exchange_rate`
+const PRICE_FALLBACK_FIAT_SYMBOL: &str = "USD";
+
+/// Position of a single asset valued in the user's default fiat
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Holding {
+    pub asset_id: StringRowId,
+    pub symbol: String,
+    pub name: String,
+    pub kind: AssetKind,
+    pub coin_id: Option<String>,
+    pub quantity: Decimal,
+    /// price of one unit, None when the asset could not be priced
+    pub price: Option<Decimal>,
+    pub value: Option<Decimal>,
+    /// fiat the price was quoted in before the conversion to the default fiat
+    pub price_fiat_symbol: Option<String>,
+    /// why the asset could not be priced
+    pub price_error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PortfolioValuation {
+    pub date: NaiveDate,
+    pub fiat_id: RowId,
+    pub fiat_symbol: String,
+    pub fiat_name: String,
+    pub holdings: Vec<Holding>,
+    /// sum of the valued holdings, holdings without a price are not part of it
+    pub total_value: Decimal,
+}
+
+/// Realized and unrealized gains of a single asset in the user's default fiat
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AssetPnl {
+    pub asset_id: StringRowId,
+    pub symbol: String,
+    pub name: String,
+    /// quantity still held in open lots
+    pub quantity: Decimal,
+    /// cost of the open lots
+    pub cost_basis: Decimal,
+    pub realized_gain: Decimal,
+    /// None when the asset could not be priced
+    pub market_value: Option<Decimal>,
+    pub unrealized_gain: Option<Decimal>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PortfolioPnl {
+    pub method: CostBasisMethod,
+    pub fiat_id: RowId,
+    pub fiat_symbol: String,
+    pub assets: Vec<AssetPnl>,
+    pub total_cost_basis: Decimal,
+    pub total_realized_gain: Decimal,
+    /// sum of the priced assets only
+    pub total_unrealized_gain: Decimal,
+}
+
+pub struct PortfolioService {}
+
+impl PortfolioService {
+    /// Value every open position on the given date in the user's default fiat
+    /// - `date` None means today
+    /// - quantities are derived from the trade ledger up to and including `date`
+    pub async fn get_valuation<A: FiatExchanger>(
+        db: &Db,
+        manager: &CryptoExchangeManager,
+        exchange_api: &A,
+        date: Option<NaiveDate>,
+    ) -> Result<PortfolioValuation> {
+        let date = date.unwrap_or_else(|| chrono::Local::now().date_naive());
+        let fiat = user_settings::get(db)
+            .await
+            .context("failed to get default fiat")?
+            .fiat;
+
+        let trades = TradeService::get_all(db).await?;
+        let trades: Vec<Trade> = trades
+            .into_iter()
+            .filter(|trade| trade.trade_date <= date)
+            .collect();
+        let quantities = compute_quantities(&trades);
+
+        let mut pricer = Pricer {
+            db,
+            manager,
+            exchange_api,
+            fiat: &fiat,
+            date,
+            fallback_rate: None,
+        };
+
+        let mut holdings = Vec::new();
+        for asset in AssetService::get_all(db).await? {
+            let Some(quantity) = quantities.get(&asset.id).copied() else {
+                continue;
+            };
+            if quantity.is_zero() {
+                continue;
+            }
+
+            let mut holding = Holding {
+                asset_id: asset.id,
+                symbol: asset.symbol,
+                name: asset.name,
+                kind: asset.kind,
+                coin_id: asset.coin_id,
+                quantity,
+                price: None,
+                value: None,
+                price_fiat_symbol: None,
+                price_error: None,
+            };
+
+            match &holding.coin_id {
+                Some(coin_id) => match pricer.price(coin_id).await {
+                    Ok((price, quoted_in)) => {
+                        holding.price = Some(price);
+                        holding.value = Some(price * quantity);
+                        holding.price_fiat_symbol = Some(quoted_in);
+                    }
+                    Err(e) => holding.price_error = Some(format!("{e:#}")),
+                },
+                None => {
+                    holding.price_error = Some("asset is not linked to a coin".to_string());
+                }
+            }
+            holdings.push(holding);
+        }
+
+        // most valuable first, unpriced holdings last
+        holdings.sort_by_key(|h| std::cmp::Reverse(h.value));
+        let total_value = holdings.iter().filter_map(|h| h.value).sum();
+
+        Ok(PortfolioValuation {
+            date,
+            fiat_id: fiat.id,
+            fiat_symbol: fiat.symbol,
+            fiat_name: fiat.name,
+            holdings,
+            total_value,
+        })
+    }
+}
+
+impl PortfolioService {
+    /// Realized and unrealized gains per asset with the cost basis method of the user settings
+    /// - unrealized gains are valued with today's prices
+    pub async fn get_pnl<A: FiatExchanger>(
+        db: &Db,
+        manager: &CryptoExchangeManager,
+        exchange_api: &A,
+    ) -> Result<PortfolioPnl> {
+        let settings = user_settings::get(db)
+            .await
+            .context("failed to get user settings")?;
+        let result =
+            cost_basis::compute(db, exchange_api, settings.cost_basis_method, &settings.fiat)
+                .await?;
+        let valuation = Self::get_valuation(db, manager, exchange_api, None).await?;
+        let prices: HashMap<&str, Decimal> = valuation
+            .holdings
+            .iter()
+            .filter_map(|h| h.price.map(|price| (h.asset_id.as_str(), price)))
+            .collect();
+
+        let mut assets = Vec::new();
+        for asset in AssetService::get_all(db).await? {
+            let lots = result.open_lots.get(&asset.id);
+            let lines: Vec<_> = result
+                .disposals
+                .iter()
+                .filter(|line| line.asset_id == asset.id)
+                .collect();
+            if lots.is_none() && lines.is_empty() {
+                continue;
+            }
+
+            let quantity: Decimal = lots.into_iter().flatten().map(|lot| lot.quantity).sum();
+            let cost_basis: Decimal = lots
+                .into_iter()
+                .flatten()
+                .map(|lot| lot.quantity * lot.unit_cost)
+                .sum();
+            let market_value = prices
+                .get(asset.id.as_str())
+                .map(|price| *price * quantity)
+                .or_else(|| quantity.is_zero().then_some(Decimal::ZERO));
+            assets.push(AssetPnl {
+                asset_id: asset.id,
+                symbol: asset.symbol,
+                name: asset.name,
+                quantity,
+                cost_basis,
+                realized_gain: lines.iter().map(|line| line.gain).sum(),
+                market_value,
+                unrealized_gain: market_value.map(|value| value - cost_basis),
+            });
+        }
+
+        Ok(PortfolioPnl {
+            method: result.method,
+            fiat_id: settings.fiat.id,
+            fiat_symbol: settings.fiat.symbol,
+            total_cost_basis: assets.iter().map(|a| a.cost_basis).sum(),
+            total_realized_gain: assets.iter().map(|a| a.realized_gain).sum(),
+            total_unrealized_gain: assets.iter().filter_map(|a| a.unrealized_gain).sum(),
+            assets,
+        })
+    }
+}
+
+/// Net quantity of every asset touched by the trades
+/// - buy adds, sell removes
+/// - swap removes `quantity` of the asset and adds `to_quantity` of the asset received
+/// - a fee paid in an asset removes the fee amount from that asset
+pub fn compute_quantities(trades: &[Trade]) -> HashMap<StringRowId, Decimal> {
+    let mut quantities: HashMap<StringRowId, Decimal> = HashMap::new();
+    for trade in trades {
+        match trade.kind {
+            TradeKind::Buy => {
+                *quantities.entry(trade.asset_id.clone()).or_default() += trade.quantity;
+            }
+            TradeKind::Sell => {
+                *quantities.entry(trade.asset_id.clone()).or_default() -= trade.quantity;
+            }
+            TradeKind::Swap => {
+                *quantities.entry(trade.asset_id.clone()).or_default() -= trade.quantity;
+                if let (Some(to_asset_id), Some(to_quantity)) =
+                    (&trade.to_asset_id, trade.to_quantity)
+                {
+                    *quantities.entry(to_asset_id.clone()).or_default() += to_quantity;
+                }
+            }
+        }
+        if let (Some(fee_asset_id), Some(fee_amount)) = (&trade.fee_asset_id, trade.fee_amount) {
+            *quantities.entry(fee_asset_id.clone()).or_default() -= fee_amount;
+        }
+    }
+    quantities
+}
+
+/// Prices coins in the default fiat for one valuation, the fallback rate is fetched at most once
+struct Pricer<'a, A: FiatExchanger> {
+    db: &'a Db,
+    manager: &'a CryptoExchangeManager,
+    exchange_api: &'a A,
+    fiat: &'a Fiat,
+    date: NaiveDate,
+    fallback_rate: Option<FiatExchangeRate>,
+}
+
+impl<A: FiatExchanger> Pricer<'_, A> {
+    /// Returns the price in the default fiat and the fiat it was quoted in
+    async fn price(&mut self, coin_id: &str) -> Result<(Decimal, String)> {
+        let direct =
+            crypto_price::get_price(self.db, self.manager, coin_id, self.fiat, Some(self.date))
+                .await;
+        let direct_error = match direct {
+            Ok(price) => return Ok((price.price, self.fiat.symbol.clone())),
+            Err(e) => e,
+        };
+        if self.fiat.symbol == PRICE_FALLBACK_FIAT_SYMBOL {
+            return Err(direct_error);
+        }
+
+        let fallback_fiat =
+            FiatService::<A>::get_fiat_by_symbol(self.db, PRICE_FALLBACK_FIAT_SYMBOL)
+                .await
+                .context("failed to get fallback fiat")?;
+        let price = crypto_price::get_price(
+            self.db,
+            self.manager,
+            coin_id,
+            &fallback_fiat,
+            Some(self.date),
+        )
+        .await
+        .with_context(|| format!("{direct_error:#}"))?;
+
+        if self.fallback_rate.is_none() {
+            self.fallback_rate =
+                Some(fiat_rate::get_rate(self.db, self.exchange_api, &self.date, None).await?);
+        }
+        let rate = self.fallback_rate.as_ref().expect("fallback rate is set");
+        let converted = rate.convert(price.price, &fallback_fiat.symbol, &self.fiat.symbol)?;
+        Ok((converted, fallback_fiat.symbol))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset::CreateAsset;
+    use crate::crypto_exchange::catalogue;
+    use crate::crypto_exchange::{
+        traits::MockCryptoExchange, CryptoExchangeError, ExchangeRateResponse, SupportedCoin,
+    };
+    use crate::fiat_exchanger::{MockFiatExchanger, Rates};
+    use crate::trade::CreateTrade;
+
+    struct Fixture {
+        db: Db,
+        usd_id: RowId,
+        btc_id: StringRowId,
+        eth_id: StringRowId,
+    }
+
+    /// EUR is the default fiat, BTC and ETH are linked to the coin catalogue
+    async fn setup() -> Fixture {
+        let db = Db::in_memory().await.unwrap();
+        let usd_id = sqlx::query_scalar::<_, i64>(
+            "INSERT INTO fiat (symbol, name) VALUES ('USD', 'US Dollar') RETURNING id",
+        )
+        .fetch_one(&db.0)
+        .await
+        .unwrap();
+        let eur_id = sqlx::query_scalar::<_, i64>(
+            "INSERT INTO fiat (symbol, name) VALUES ('EUR', 'Euro') RETURNING id",
+        )
+        .fetch_one(&db.0)
+        .await
+        .unwrap();
+        sqlx::query("INSERT INTO user_settings (id, locale, default_fiat_id) VALUES (1, 'en', ?)")
+            .bind(eur_id)
+            .execute(&db.0)
+            .await
+            .unwrap();
+
+        let coins = [
+            ("bitcoin", "btc", "Bitcoin"),
+            ("ethereum", "eth", "Ethereum"),
+        ];
+        catalogue::merge_provider_coins(
+            &db,
+            "mock_provider",
+            coins
+                .iter()
+                .map(|(id, symbol, name)| SupportedCoin {
+                    id: id.to_string(),
+                    symbol: symbol.to_string(),
+                    name: name.to_string(),
+                })
+                .collect(),
+        )
+        .await
+        .unwrap();
+
+        let mut asset_ids = Vec::new();
+        for (coin_id, symbol, name) in coins {
+            let asset = AssetService::create(
+                CreateAsset {
+                    name: name.to_string(),
+                    symbol: symbol.to_string(),
+                    kind: AssetKind::Cryptocoin,
+                    coin_id: Some(coin_id.to_string()),
+                },
+                &db,
+            )
+            .await
+            .unwrap();
+            asset_ids.push(asset.id);
+        }
+
+        Fixture {
+            db,
+            usd_id,
+            eth_id: asset_ids.pop().unwrap(),
+            btc_id: asset_ids.pop().unwrap(),
+        }
+    }
+
+    fn trade(f: &Fixture, kind: TradeKind, asset_id: &str, quantity: Decimal) -> CreateTrade {
+        CreateTrade {
+            kind,
+            trade_date: chrono::Local::now().date_naive() - chrono::Duration::days(10),
+            asset_id: asset_id.to_string(),
+            quantity,
+            price: Decimal::new(30_000, 0),
+            fiat_id: f.usd_id,
+            to_asset_id: None,
+            to_quantity: None,
+            fee_asset_id: None,
+            fee_amount: None,
+            via_exchange: None,
+            fiat_ramp_id: None,
+            note: None,
+            external_ref: None,
+        }
+    }
+
+    /// provider only quotes in USD: BTC 40000, ETH 2000
+    fn usd_only_manager() -> CryptoExchangeManager {
+        let mut mock_provider = MockCryptoExchange::new();
+        mock_provider.expect_id().return_const("mock_provider");
+        mock_provider
+            .expect_get_exchange_rate()
+            .returning(|request| {
+                if request.fiat_currency != "USD" {
+                    return Err(CryptoExchangeError::ApiError(format!(
+                        "unsupported fiat {}",
+                        request.fiat_currency
+                    )));
+                }
+                let rate = match request.coin_id.as_str() {
+                    "bitcoin" => Decimal::new(40_000, 0),
+                    _ => Decimal::new(2_000, 0),
+                };
+                Ok(ExchangeRateResponse {
+                    rate,
+                    date: chrono::Local::now().date_naive(),
+                })
+            });
+        CryptoExchangeManager::new(vec![Box::new(mock_provider)])
+    }
+
+    #[test]
+    fn test_compute_quantities() {
+        let buy = |asset_id: &str, quantity| Trade {
+            id: String::new(),
+            kind: TradeKind::Buy,
+            trade_date: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            asset_id: asset_id.to_string(),
+            asset_symbol: String::new(),
+            quantity,
+            price: Decimal::ONE,
+            fiat_id: 1,
+            fiat_symbol: String::new(),
+            to_asset_id: None,
+            to_asset_symbol: None,
+            to_quantity: None,
+            fee_asset_id: None,
+            fee_asset_symbol: None,
+            fee_amount: None,
+            via_exchange: None,
+            exchange_id: None,
+            fiat_ramp_id: None,
+            note: None,
+            external_ref: None,
+        };
+
+        let mut sell = buy("btc", Decimal::new(5, 1));
+        sell.kind = TradeKind::Sell;
+        let mut swap = buy("btc", Decimal::new(5, 1));
+        swap.kind = TradeKind::Swap;
+        swap.to_asset_id = Some("eth".to_string());
+        swap.to_quantity = Some(Decimal::TEN);
+        swap.fee_asset_id = Some("eth".to_string());
+        swap.fee_amount = Some(Decimal::new(1, 2));
+
+        let quantities = compute_quantities(&[buy("btc", Decimal::TWO), sell, swap]);
+        assert_eq!(quantities["btc"], Decimal::ONE);
+        assert_eq!(quantities["eth"], Decimal::new(999, 2));
+    }
+
+    #[tokio::test]
+    async fn test_get_valuation_converts_through_fiat_rate() {
+        let f = setup().await;
+        TradeService::create(trade(&f, TradeKind::Buy, &f.btc_id, Decimal::TWO), &f.db)
+            .await
+            .unwrap();
+        let mut swap = trade(&f, TradeKind::Swap, &f.btc_id, Decimal::ONE);
+        swap.to_asset_id = Some(f.eth_id.clone());
+        swap.to_quantity = Some(Decimal::TEN);
+        TradeService::create(swap, &f.db).await.unwrap();
+
+        let mut mock_api = MockFiatExchanger::new();
+        mock_api
+            .expect_get_latest_rates()
+            .times(1)
+            .returning(|_, _| {
+                Ok(Rates {
+                    provider: "mock".to_string(),
+                    base: "USD".to_string(),
+                    date: chrono::Local::now().date_naive(),
+                    rates: HashMap::from([("EUR".to_string(), Decimal::new(5, 1))]),
+                })
+            });
+
+        let valuation =
+            PortfolioService::get_valuation(&f.db, &usd_only_manager(), &mock_api, None)
+                .await
+                .unwrap();
+
+        assert_eq!(valuation.fiat_symbol, "EUR");
+        assert_eq!(valuation.holdings.len(), 2);
+        let btc = &valuation.holdings[0];
+        assert_eq!(btc.symbol, "BTC");
+        assert_eq!(btc.quantity, Decimal::ONE);
+        assert_eq!(btc.price, Some(Decimal::new(20_000, 0)));
+        assert_eq!(btc.price_fiat_symbol.as_deref(), Some("USD"));
+        let eth = &valuation.holdings[1];
+        assert_eq!(eth.value, Some(Decimal::new(10_000, 0)));
+        assert_eq!(valuation.total_value, Decimal::new(30_000, 0));
+    }
+
+    #[tokio::test]
+    async fn test_get_pnl() {
+        let f = setup().await;
+        // bought 2 BTC for 30000 USD each and sold 1 for 35000 USD
+        TradeService::create(trade(&f, TradeKind::Buy, &f.btc_id, Decimal::TWO), &f.db)
+            .await
+            .unwrap();
+        let mut sell = trade(&f, TradeKind::Sell, &f.btc_id, Decimal::ONE);
+        sell.price = Decimal::new(35_000, 0);
+        TradeService::create(sell, &f.db).await.unwrap();
+
+        // 1 USD = 0.5 EUR every day
+        let mut mock_api = MockFiatExchanger::new();
+        mock_api.expect_get_latest_rates().returning(|_, date| {
+            Ok(Rates {
+                provider: "mock".to_string(),
+                base: "USD".to_string(),
+                date: *date.unwrap(),
+                rates: HashMap::from([("EUR".to_string(), Decimal::new(5, 1))]),
+            })
+        });
+
+        let pnl = PortfolioService::get_pnl(&f.db, &usd_only_manager(), &mock_api)
+            .await
+            .unwrap();
+
+        assert_eq!(pnl.method, CostBasisMethod::Fifo);
+        assert_eq!(pnl.assets.len(), 1);
+        let btc = &pnl.assets[0];
+        assert_eq!(btc.quantity, Decimal::ONE);
+        assert_eq!(btc.cost_basis, Decimal::new(15_000, 0));
+        assert_eq!(btc.realized_gain, Decimal::new(2_500, 0));
+        // valued at 40000 USD
+        assert_eq!(btc.unrealized_gain, Some(Decimal::new(5_000, 0)));
+        assert_eq!(pnl.total_realized_gain, Decimal::new(2_500, 0));
+    }
+
+    #[tokio::test]
+    async fn test_get_valuation_skips_closed_and_future_positions() {
+        let f = setup().await;
+        TradeService::create(trade(&f, TradeKind::Buy, &f.btc_id, Decimal::ONE), &f.db)
+            .await
+            .unwrap();
+        TradeService::create(trade(&f, TradeKind::Sell, &f.btc_id, Decimal::ONE), &f.db)
+            .await
+            .unwrap();
+
+        let mut future_buy = trade(&f, TradeKind::Buy, &f.eth_id, Decimal::ONE);
+        future_buy.trade_date = chrono::Local::now().date_naive();
+        TradeService::create(future_buy, &f.db).await.unwrap();
+
+        let valuation_date = chrono::Local::now().date_naive() - chrono::Duration::days(1);
+        let valuation = PortfolioService::get_valuation(
+            &f.db,
+            &usd_only_manager(),
+            &MockFiatExchanger::new(),
+            Some(valuation_date),
+        )
+        .await
+        .unwrap();
+
+        assert!(valuation.holdings.is_empty());
+        assert_eq!(valuation.total_value, Decimal::ZERO);
+    }
+}
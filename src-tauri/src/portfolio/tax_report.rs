@@ -0,0 +1,218 @@
+use crate::asset::AssetService;
+use crate::db::{Db, StringRowId};
+use crate::fiat::format::round_amount;
+use crate::fiat_exchanger::FiatExchanger;
+use crate::portfolio::cost_basis::{self, CostBasisMethod, DisposalLine};
+use crate::user_settings;
+use anyhow::{bail, Context, Result};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    Csv,
+    Json,
+}
+
+/// A disposal of the tax year, matched against a single acquisition
+/// - flat on purpose so that it maps to a CSV row
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TaxReportLine {
+    pub asset_id: StringRowId,
+    pub asset_symbol: String,
+    pub disposal_trade_id: StringRowId,
+    /// None with the average cost method or when no acquisition was recorded
+    pub acquisition_date: Option<NaiveDate>,
+    pub disposal_date: NaiveDate,
+    pub quantity: Decimal,
+    pub proceeds: Decimal,
+    pub cost_basis: Decimal,
+    pub gain: Decimal,
+    /// no acquisition was left to match, the cost basis is zero
+    pub is_unmatched: bool,
+    /// fee paid in the asset
+    pub is_fee: bool,
+    /// the line relies on a fallback fiat rate
+    pub is_estimated: bool,
+    /// the line relies on a rate of the previous working day
+    pub is_non_working_day: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TaxReport {
+    pub tax_year: i32,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub method: CostBasisMethod,
+    pub fiat_symbol: String,
+    pub lines: Vec<TaxReportLine>,
+    pub total_proceeds: Decimal,
+    pub total_cost_basis: Decimal,
+    pub total_gain: Decimal,
+    /// number of lines relying on an estimated or non-working-day rate
+    pub estimated_line_count: usize,
+}
+
+/// Capital gains of every disposal in the calendar year `tax_year`, in the user's default fiat
+pub async fn generate<A: FiatExchanger>(
+    db: &Db,
+    exchange_api: &A,
+    tax_year: i32,
+) -> Result<TaxReport> {
+    let (Some(start_date), Some(end_date)) = (
+        NaiveDate::from_ymd_opt(tax_year, 1, 1),
+        NaiveDate::from_ymd_opt(tax_year, 12, 31),
+    ) else {
+        bail!("invalid tax year {tax_year}");
+    };
+
+    let settings = user_settings::get(db)
+        .await
+        .context("failed to get user settings")?;
+    let result =
+        cost_basis::compute(db, exchange_api, settings.cost_basis_method, &settings.fiat).await?;
+    let symbols: HashMap<StringRowId, String> = AssetService::get_all(db)
+        .await?
+        .into_iter()
+        .map(|asset| (asset.id, asset.symbol))
+        .collect();
+
+    let mut lines: Vec<TaxReportLine> = result
+        .disposals
+        .into_iter()
+        .filter(|line| line.disposal_date >= start_date && line.disposal_date <= end_date)
+        .map(|line| {
+            let asset_symbol = symbols.get(&line.asset_id).cloned().unwrap_or_default();
+            report_line(line, asset_symbol, settings.fiat.minor_units)
+        })
+        .collect();
+    lines.sort_by_key(|line| line.disposal_date);
+
+    Ok(TaxReport {
+        tax_year,
+        start_date,
+        end_date,
+        method: result.method,
+        fiat_symbol: settings.fiat.symbol,
+        total_proceeds: lines.iter().map(|line| line.proceeds).sum(),
+        total_cost_basis: lines.iter().map(|line| line.cost_basis).sum(),
+        total_gain: lines.iter().map(|line| line.gain).sum(),
+        estimated_line_count: lines
+            .iter()
+            .filter(|line| line.is_estimated || line.is_non_working_day)
+            .count(),
+        lines,
+    })
+}
+
+/// Amounts of the line rounded to the minor units of the report currency
+fn report_line(line: DisposalLine, asset_symbol: String, minor_units: u32) -> TaxReportLine {
+    let proceeds = round_amount(line.proceeds, minor_units);
+    let cost_basis = round_amount(line.cost_basis, minor_units);
+    TaxReportLine {
+        asset_id: line.asset_id,
+        asset_symbol,
+        disposal_trade_id: line.disposal_trade_id,
+        acquisition_date: line.acquisition_date,
+        disposal_date: line.disposal_date,
+        quantity: line.quantity.normalize(),
+        proceeds,
+        cost_basis,
+        // from the rounded amounts so that every line adds up
+        gain: proceeds - cost_basis,
+        is_unmatched: line.is_unmatched,
+        is_fee: line.is_fee,
+        is_estimated: line.rate_flags.is_estimated,
+        is_non_working_day: line.rate_flags.is_non_working_day,
+    }
+}
+
+/// Render the report -- CSV holds the lines only, JSON the whole report with its totals
+pub fn export(report: &TaxReport, format: ReportFormat) -> Result<String> {
+    match format {
+        ReportFormat::Json => {
+            serde_json::to_string_pretty(report).context("failed to serialize tax report")
+        }
+        ReportFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(Vec::new());
+            for line in &report.lines {
+                writer
+                    .serialize(line)
+                    .context("failed to write tax report line")?;
+            }
+            let bytes = writer.into_inner().context("failed to write tax report")?;
+            String::from_utf8(bytes).context("tax report is not valid utf-8")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::portfolio::cost_basis::RateFlags;
+
+    fn line(disposal_date: NaiveDate, rate_flags: RateFlags) -> DisposalLine {
+        DisposalLine {
+            disposal_trade_id: "sell".to_string(),
+            asset_id: "btc".to_string(),
+            disposal_date,
+            acquisition_trade_id: Some("buy".to_string()),
+            acquisition_date: NaiveDate::from_ymd_opt(2023, 3, 1),
+            quantity: Decimal::new(50, 2),
+            proceeds: Decimal::new(1_000_005, 3),
+            cost_basis: Decimal::new(400_004, 3),
+            gain: Decimal::new(600_001, 3),
+            is_unmatched: false,
+            is_fee: false,
+            rate_flags,
+        }
+    }
+
+    #[test]
+    fn test_export_csv_and_json() {
+        let date = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+        let estimated = RateFlags {
+            is_estimated: true,
+            is_non_working_day: true,
+        };
+        let lines = vec![
+            report_line(line(date, RateFlags::default()), "BTC".to_string(), 2),
+            report_line(line(date, estimated), "BTC".to_string(), 2),
+        ];
+        let report = TaxReport {
+            tax_year: 2024,
+            start_date: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            end_date: NaiveDate::from_ymd_opt(2024, 12, 31).unwrap(),
+            method: CostBasisMethod::Fifo,
+            fiat_symbol: "EUR".to_string(),
+            total_proceeds: lines.iter().map(|l| l.proceeds).sum(),
+            total_cost_basis: lines.iter().map(|l| l.cost_basis).sum(),
+            total_gain: lines.iter().map(|l| l.gain).sum(),
+            estimated_line_count: 1,
+            lines,
+        };
+        assert_eq!(report.lines[0].proceeds, Decimal::new(100_000, 2));
+        assert_eq!(report.lines[0].gain, Decimal::new(60_000, 2));
+
+        let csv = export(&report, ReportFormat::Csv).unwrap();
+        let rows: Vec<&str> = csv.lines().collect();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(
+            rows[0],
+            "asset_id,asset_symbol,disposal_trade_id,acquisition_date,disposal_date,quantity,proceeds,cost_basis,gain,is_unmatched,is_fee,is_estimated,is_non_working_day"
+        );
+        assert_eq!(
+            rows[2],
+            "btc,BTC,sell,2023-03-01,2024-06-01,0.5,1000.00,400.00,600.00,false,false,true,true"
+        );
+
+        let json: serde_json::Value =
+            serde_json::from_str(&export(&report, ReportFormat::Json).unwrap()).unwrap();
+        assert_eq!(json["tax_year"], 2024);
+        assert_eq!(json["lines"].as_array().unwrap().len(), 2);
+        assert_eq!(json["total_gain"], "1200.00");
+    }
+}
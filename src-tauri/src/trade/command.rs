@@ -0,0 +1,43 @@
+use crate::db::{Db, StringRowId};
+use crate::trade::{CreateTrade, Trade, TradeFilter, TradePagination, TradeService};
+use tauri::State;
+
+#[tauri::command]
+pub async fn create_trade(create_trade: CreateTrade, db: State<'_, Db>) -> Result<Trade, String> {
+    TradeService::create(create_trade, &db)
+        .await
+        .map_err(|e| format!("failed to create trade: {e:#}"))
+}
+
+/// Get trades with pagination -- limit and offset are optional but default to 50 and 0 respectively
+#[tauri::command]
+pub async fn get_trades(
+    db: State<'_, Db>,
+    limit: Option<u32>,
+    offset: Option<u32>,
+    filter: Option<TradeFilter>,
+) -> Result<TradePagination, String> {
+    let limit = limit.unwrap_or(50);
+    let offset = offset.unwrap_or(0);
+    TradeService::get(limit, offset, filter.unwrap_or_default(), &db)
+        .await
+        .map_err(|e| format!("failed to get trades: {e}"))
+}
+
+#[tauri::command]
+pub async fn update_trade(
+    id: StringRowId,
+    trade: CreateTrade,
+    db: State<'_, Db>,
+) -> Result<Trade, String> {
+    TradeService::update(&id, trade, &db)
+        .await
+        .map_err(|e| format!("failed to update trade: {e:#}"))
+}
+
+#[tauri::command]
+pub async fn delete_trade(id: StringRowId, db: State<'_, Db>) -> Result<u64, String> {
+    TradeService::delete(&id, &db)
+        .await
+        .map_err(|e| format!("failed to delete trade: {e}"))
+}
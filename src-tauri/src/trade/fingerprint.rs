@@ -0,0 +1,140 @@
+use crate::db::{SqlDecimal, SqlOptionDecimal, StringRowId};
+use crate::trade::TradeKind;
+use crate::utils::string_utils::normalize_name;
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use sqlx::prelude::FromRow;
+use sqlx::SqliteConnection;
+use uuid::Uuid;
+
+/// Namespace of the name based uuids used as fingerprints
+const FINGERPRINT_NAMESPACE: Uuid = Uuid::from_u128(0x0195_6c0a_41d2_7e58_b3f6_5c19_8e2a_d704);
+
+/// What a trade fingerprint is built from, the fields of a trade that identify it at its exchange
+pub struct TradeIdentity<'a> {
+    pub trade_date: NaiveDate,
+    pub kind: TradeKind,
+    pub asset_id: &'a str,
+    pub quantity: Decimal,
+    pub price: Decimal,
+    pub to_asset_id: Option<&'a str>,
+    pub to_quantity: Option<Decimal>,
+    pub exchange: &'a str,
+}
+
+/// Deterministic identity of a trade, the same trade imported twice gets the same fingerprint
+/// - built from the date, the kind, the assets, quantities and price and the exchange name
+///   ignoring case, spaces and punctuation
+/// - the fee and the note are left out, like the fee of a fiat ramp
+pub fn fingerprint(trade: &TradeIdentity) -> String {
+    let kind = match trade.kind {
+        TradeKind::Buy => "buy",
+        TradeKind::Sell => "sell",
+        TradeKind::Swap => "swap",
+    };
+    let key = format!(
+        "{}|{kind}|{}|{}|{}|{}|{}|{}",
+        trade.trade_date,
+        trade.asset_id,
+        // normalized so that 0.10 and 0.1 match
+        trade.quantity.normalize(),
+        trade.price.normalize(),
+        trade.to_asset_id.unwrap_or_default(),
+        trade
+            .to_quantity
+            .map(|quantity| quantity.normalize().to_string())
+            .unwrap_or_default(),
+        normalize_name(trade.exchange)
+    );
+    Uuid::new_v5(&FINGERPRINT_NAMESPACE, key.as_bytes()).to_string()
+}
+
+#[derive(FromRow)]
+struct Unfingerprinted {
+    id: StringRowId,
+    trade_date: NaiveDate,
+    kind: TradeKind,
+    asset_id: StringRowId,
+    #[sqlx(try_from = "SqlDecimal")]
+    quantity: Decimal,
+    #[sqlx(try_from = "SqlDecimal")]
+    price: Decimal,
+    to_asset_id: Option<StringRowId>,
+    #[sqlx(try_from = "SqlOptionDecimal")]
+    to_quantity: Option<Decimal>,
+    via_exchange: Option<String>,
+}
+
+/// Fill in the fingerprint of the trades without one
+/// - new and edited trades are left without a fingerprint until then
+/// - returns the number of trades updated
+/// - runs on `conn`, an import classifies its rows inside its own transaction
+pub async fn refresh(conn: &mut SqliteConnection) -> Result<u64> {
+    let trades = sqlx::query_as::<sqlx::Sqlite, Unfingerprinted>(
+        r#"
+        SELECT id, trade_date, kind, asset_id, quantity, price, to_asset_id, to_quantity,
+            via_exchange
+        FROM trade
+        WHERE fingerprint IS NULL
+        "#,
+    )
+    .fetch_all(&mut *conn)
+    .await
+    .context("failed to get trades without fingerprint")?;
+
+    for trade in &trades {
+        sqlx::query("UPDATE trade SET fingerprint = ? WHERE id = ?")
+            .bind(fingerprint(&TradeIdentity {
+                trade_date: trade.trade_date,
+                kind: trade.kind,
+                asset_id: &trade.asset_id,
+                quantity: trade.quantity,
+                price: trade.price,
+                to_asset_id: trade.to_asset_id.as_deref(),
+                to_quantity: trade.to_quantity,
+                exchange: trade.via_exchange.as_deref().unwrap_or_default(),
+            }))
+            .bind(&trade.id)
+            .execute(&mut *conn)
+            .await
+            .context("failed to update trade fingerprint")?;
+    }
+    Ok(trades.len() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint() {
+        let buy = TradeIdentity {
+            trade_date: NaiveDate::from_ymd_opt(2024, 1, 2).unwrap(),
+            kind: TradeKind::Buy,
+            asset_id: "btc",
+            quantity: Decimal::new(10, 2),
+            price: Decimal::new(42_000, 0),
+            to_asset_id: None,
+            to_quantity: None,
+            exchange: "Kraken",
+        };
+        let same = TradeIdentity {
+            quantity: Decimal::new(1, 1),
+            exchange: " kraken ",
+            ..buy
+        };
+        assert_eq!(fingerprint(&buy), fingerprint(&same));
+
+        let sell = TradeIdentity {
+            kind: TradeKind::Sell,
+            ..buy
+        };
+        assert_ne!(fingerprint(&buy), fingerprint(&sell));
+        let other_price = TradeIdentity {
+            price: Decimal::new(41_000, 0),
+            ..buy
+        };
+        assert_ne!(fingerprint(&buy), fingerprint(&other_price));
+    }
+}
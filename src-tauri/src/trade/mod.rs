@@ -0,0 +1,558 @@
+pub mod command;
+pub mod fingerprint;
+use crate::db::{Db, RowId, SqlDecimal, SqlOptionDecimal, StringRowId};
+use crate::exchange::{Exchange, ExchangeRef, ExchangeService};
+use anyhow::{bail, Context, Result};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use sqlx::SqliteConnection;
+use uuid::Uuid;
+
+#[derive(Debug, Serialize, Deserialize, sqlx::Type, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum TradeKind {
+    Buy,
+    Sell,
+    Swap,
+}
+
+/// A trade of an asset
+/// - buy: `quantity` of `asset_id` acquired at `price` per unit in `fiat_id`
+/// - sell: `quantity` of `asset_id` disposed at `price` per unit in `fiat_id`
+/// - swap: `quantity` of `asset_id` given up for `to_quantity` of `to_asset_id`,
+///   `price` is the market value of one unit of `asset_id` in `fiat_id` at the time of the swap
+#[derive(Debug, FromRow, Serialize, Deserialize, Clone)]
+pub struct Trade {
+    pub id: StringRowId,
+    pub kind: TradeKind,
+    pub trade_date: NaiveDate,
+    pub asset_id: StringRowId,
+    #[sqlx(default)]
+    pub asset_symbol: String,
+    #[sqlx(try_from = "SqlDecimal")]
+    pub quantity: Decimal,
+    #[sqlx(try_from = "SqlDecimal")]
+    pub price: Decimal,
+    pub fiat_id: RowId,
+    #[sqlx(default)]
+    pub fiat_symbol: String,
+    pub to_asset_id: Option<StringRowId>,
+    #[sqlx(default)]
+    pub to_asset_symbol: Option<String>,
+    #[sqlx(try_from = "SqlOptionDecimal")]
+    pub to_quantity: Option<Decimal>,
+    /// asset the fee was paid in, None means the fee was paid in `fiat_id`
+    pub fee_asset_id: Option<StringRowId>,
+    #[sqlx(default)]
+    pub fee_asset_symbol: Option<String>,
+    #[sqlx(try_from = "SqlOptionDecimal")]
+    pub fee_amount: Option<Decimal>,
+    /// canonical name of the exchange
+    pub via_exchange: Option<String>,
+    pub exchange_id: Option<StringRowId>,
+    pub fiat_ramp_id: Option<StringRowId>,
+    pub note: Option<String>,
+    /// reference of the trade at the exchange, e.g. the order id of a statement
+    pub external_ref: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CreateTrade {
+    pub kind: TradeKind,
+    pub trade_date: NaiveDate,
+    pub asset_id: StringRowId,
+    pub quantity: Decimal,
+    pub price: Decimal,
+    pub fiat_id: RowId,
+    pub to_asset_id: Option<StringRowId>,
+    pub to_quantity: Option<Decimal>,
+    pub fee_asset_id: Option<StringRowId>,
+    pub fee_amount: Option<Decimal>,
+    /// exchange id or name, see `ExchangeRef` -- defaults to the exchange of the linked fiat ramp
+    pub via_exchange: Option<ExchangeRef>,
+    pub fiat_ramp_id: Option<StringRowId>,
+    pub note: Option<String>,
+    /// reference of the trade at the exchange, kept on update when None
+    #[serde(default)]
+    pub external_ref: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TradePagination {
+    pub total_count: i64,
+    pub trades: Vec<Trade>,
+}
+
+/// Filters for trade queries, every filter is optional
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct TradeFilter {
+    /// matches the asset traded as well as the asset received in a swap
+    pub asset_id: Option<StringRowId>,
+    pub exchange_id: Option<StringRowId>,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+}
+
+const SELECT_TRADE: &str = r#"
+    SELECT
+        trade.*,
+        asset.symbol as asset_symbol,
+        fiat.symbol as fiat_symbol,
+        to_asset.symbol as to_asset_symbol,
+        fee_asset.symbol as fee_asset_symbol
+    FROM trade
+    JOIN asset ON asset.id = trade.asset_id
+    JOIN fiat ON fiat.id = trade.fiat_id
+    LEFT JOIN asset as to_asset ON to_asset.id = trade.to_asset_id
+    LEFT JOIN asset as fee_asset ON fee_asset.id = trade.fee_asset_id
+"#;
+
+const TRADE_FILTER: &str = r#"
+    WHERE (trade.asset_id = ? OR trade.to_asset_id = ? OR ? IS NULL)
+    AND (trade.exchange_id = ? OR ? IS NULL)
+    AND (trade.trade_date >= ? OR ? IS NULL)
+    AND (trade.trade_date <= ? OR ? IS NULL)
+"#;
+
+pub struct TradeService {}
+
+impl TradeService {
+    /// Create a new trade
+    pub async fn create(create_trade: CreateTrade, db: &Db) -> Result<Trade> {
+        let mut tx = db.0.begin().await.context("failed to begin transaction")?;
+        let trade = Self::create_in(create_trade, &mut tx).await?;
+        tx.commit().await.context("failed to commit transaction")?;
+        Ok(trade)
+    }
+
+    /// `create` on `conn`, e.g. inside the transaction of an import
+    pub(crate) async fn create_in(
+        create_trade: CreateTrade,
+        conn: &mut SqliteConnection,
+    ) -> Result<Trade> {
+        let (create_trade, exchange) = Self::prepare(create_trade, conn).await?;
+        let id = Uuid::now_v7().to_string();
+
+        sqlx::query(
+            r#"
+            INSERT INTO trade
+            (id, kind, trade_date, asset_id, quantity, price, fiat_id, to_asset_id, to_quantity,
+            fee_asset_id, fee_amount, via_exchange, exchange_id, fiat_ramp_id, note, external_ref)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&id)
+        .bind(create_trade.kind)
+        .bind(create_trade.trade_date)
+        .bind(create_trade.asset_id)
+        .bind(create_trade.quantity.to_string())
+        .bind(create_trade.price.to_string())
+        .bind(create_trade.fiat_id)
+        .bind(create_trade.to_asset_id)
+        .bind(create_trade.to_quantity.map(|q| q.to_string()))
+        .bind(create_trade.fee_asset_id)
+        .bind(create_trade.fee_amount.map(|f| f.to_string()))
+        .bind(exchange.as_ref().map(|exchange| &exchange.name))
+        .bind(exchange.as_ref().map(|exchange| &exchange.id))
+        .bind(create_trade.fiat_ramp_id)
+        .bind(create_trade.note)
+        .bind(create_trade.external_ref)
+        .execute(&mut *conn)
+        .await
+        .context("failed to insert into trade table")?;
+
+        Self::get_by_id(&id, conn).await
+    }
+
+    /// Get the trade by id
+    async fn get_by_id(id: &str, conn: &mut SqliteConnection) -> Result<Trade> {
+        sqlx::query_as::<sqlx::Sqlite, Trade>(&format!("{SELECT_TRADE} WHERE trade.id = ?"))
+            .bind(id)
+            .fetch_one(&mut *conn)
+            .await
+            .context("failed to get trade by id")
+    }
+
+    /// Get trades with pagination, newest first
+    pub async fn get(
+        limit: u32,
+        offset: u32,
+        filter: TradeFilter,
+        db: &Db,
+    ) -> Result<TradePagination> {
+        let total_count: i64 =
+            sqlx::query_scalar(&format!("SELECT COUNT(*) FROM trade {TRADE_FILTER}"))
+                .bind(&filter.asset_id)
+                .bind(&filter.asset_id)
+                .bind(&filter.asset_id)
+                .bind(&filter.exchange_id)
+                .bind(&filter.exchange_id)
+                .bind(filter.start_date)
+                .bind(filter.start_date)
+                .bind(filter.end_date)
+                .bind(filter.end_date)
+                .fetch_one(&db.0)
+                .await
+                .context("failed to get total count of trades")?;
+
+        let trades = sqlx::query_as::<sqlx::Sqlite, Trade>(&format!(
+            "{SELECT_TRADE} {TRADE_FILTER} ORDER BY trade.trade_date DESC, trade.id DESC LIMIT ? OFFSET ?"
+        ))
+        .bind(&filter.asset_id)
+        .bind(&filter.asset_id)
+        .bind(&filter.asset_id)
+        .bind(&filter.exchange_id)
+        .bind(&filter.exchange_id)
+        .bind(filter.start_date)
+        .bind(filter.start_date)
+        .bind(filter.end_date)
+        .bind(filter.end_date)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&db.0)
+        .await
+        .context("failed to get trades")?;
+
+        Ok(TradePagination {
+            total_count,
+            trades,
+        })
+    }
+
+    /// Get every trade in chronological order -- trades of the same day are kept in insertion order
+    pub async fn get_all(db: &Db) -> Result<Vec<Trade>> {
+        sqlx::query_as::<sqlx::Sqlite, Trade>(&format!(
+            "{SELECT_TRADE} ORDER BY trade.trade_date ASC, trade.id ASC"
+        ))
+        .fetch_all(&db.0)
+        .await
+        .context("failed to get all trades")
+    }
+
+    /// Replace the trade with the given values
+    pub async fn update(id: &str, update_trade: CreateTrade, db: &Db) -> Result<Trade> {
+        let mut tx = db.0.begin().await.context("failed to begin transaction")?;
+        let trade = Self::update_in(id, update_trade, &mut tx).await?;
+        tx.commit().await.context("failed to commit transaction")?;
+        Ok(trade)
+    }
+
+    /// `update` on `conn`, e.g. inside the transaction of an import
+    pub(crate) async fn update_in(
+        id: &str,
+        update_trade: CreateTrade,
+        conn: &mut SqliteConnection,
+    ) -> Result<Trade> {
+        let (update_trade, exchange) = Self::prepare(update_trade, conn).await?;
+
+        let result = sqlx::query(
+            r#"
+            UPDATE trade
+            SET
+            kind = ?,
+            trade_date = ?,
+            asset_id = ?,
+            quantity = ?,
+            price = ?,
+            fiat_id = ?,
+            to_asset_id = ?,
+            to_quantity = ?,
+            fee_asset_id = ?,
+            fee_amount = ?,
+            via_exchange = ?,
+            exchange_id = ?,
+            fiat_ramp_id = ?,
+            note = ?,
+            external_ref = COALESCE(?, external_ref),
+            fingerprint = NULL
+            WHERE id = ?
+            "#,
+        )
+        .bind(update_trade.kind)
+        .bind(update_trade.trade_date)
+        .bind(update_trade.asset_id)
+        .bind(update_trade.quantity.to_string())
+        .bind(update_trade.price.to_string())
+        .bind(update_trade.fiat_id)
+        .bind(update_trade.to_asset_id)
+        .bind(update_trade.to_quantity.map(|q| q.to_string()))
+        .bind(update_trade.fee_asset_id)
+        .bind(update_trade.fee_amount.map(|f| f.to_string()))
+        .bind(exchange.as_ref().map(|exchange| &exchange.name))
+        .bind(exchange.as_ref().map(|exchange| &exchange.id))
+        .bind(update_trade.fiat_ramp_id)
+        .bind(update_trade.note)
+        .bind(update_trade.external_ref)
+        .bind(id)
+        .execute(&mut *conn)
+        .await
+        .context("failed to update trade table")?;
+
+        if result.rows_affected() == 0 {
+            bail!("trade {id} not found");
+        }
+        Self::get_by_id(id, conn).await
+    }
+
+    /// Delete the trade
+    /// - returns the number of rows affected
+    pub async fn delete(id: &str, db: &Db) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM trade WHERE id = ?")
+            .bind(id)
+            .execute(&db.0)
+            .await
+            .context("failed to delete from trade table")?;
+        Ok(result.rows_affected())
+    }
+
+    /// Validate the trade and resolve its exchange, the exchange of the linked fiat ramp when not
+    /// given
+    async fn prepare(
+        trade: CreateTrade,
+        conn: &mut SqliteConnection,
+    ) -> Result<(CreateTrade, Option<Exchange>)> {
+        validate(&trade)?;
+
+        let mut exchange_ref = trade
+            .via_exchange
+            .clone()
+            .filter(|exchange_ref| !matches!(exchange_ref, ExchangeRef::Name(name) if name.trim().is_empty()));
+        if let Some(fiat_ramp_id) = &trade.fiat_ramp_id {
+            let ramp_exchange_id: Option<Option<StringRowId>> =
+                sqlx::query_scalar("SELECT exchange_id FROM fiat_ramp WHERE id = ?")
+                    .bind(fiat_ramp_id)
+                    .fetch_optional(&mut *conn)
+                    .await
+                    .context("failed to get fiat ramp of trade")?;
+            match ramp_exchange_id {
+                Some(ramp_exchange_id) => {
+                    if exchange_ref.is_none() {
+                        exchange_ref = ramp_exchange_id.map(|id| ExchangeRef::Id { id });
+                    }
+                }
+                None => bail!("fiat ramp {fiat_ramp_id} not found"),
+            }
+        }
+
+        let exchange = match exchange_ref {
+            Some(exchange_ref) => Some(
+                ExchangeService::resolve_in(&exchange_ref, conn)
+                    .await
+                    .context("failed to resolve exchange of trade")?,
+            ),
+            None => None,
+        };
+        Ok((trade, exchange))
+    }
+}
+
+fn validate(trade: &CreateTrade) -> Result<()> {
+    if trade.quantity <= Decimal::ZERO {
+        bail!("quantity must be greater than 0");
+    }
+    if trade.price < Decimal::ZERO {
+        bail!("price cannot be negative");
+    }
+    if trade.fee_amount.is_some_and(|fee| fee < Decimal::ZERO) {
+        bail!("fee amount cannot be negative");
+    }
+    if trade.fee_asset_id.is_some() && trade.fee_amount.is_none() {
+        bail!("fee asset is set without a fee amount");
+    }
+
+    match trade.kind {
+        TradeKind::Swap => {
+            let Some(to_asset_id) = &trade.to_asset_id else {
+                bail!("swap requires the asset received");
+            };
+            if *to_asset_id == trade.asset_id {
+                bail!("swap must be between two different assets");
+            }
+            if trade.to_quantity.is_none_or(|q| q <= Decimal::ZERO) {
+                bail!("swap requires a quantity received greater than 0");
+            }
+        }
+        TradeKind::Buy | TradeKind::Sell => {
+            if trade.to_asset_id.is_some() || trade.to_quantity.is_some() {
+                bail!("only a swap can have an asset received");
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset::{AssetKind, AssetService, CreateAsset};
+
+    struct Fixture {
+        db: Db,
+        usd_id: RowId,
+        btc_id: StringRowId,
+        eth_id: StringRowId,
+    }
+
+    async fn init_db() -> Fixture {
+        let db = Db::in_memory().await.unwrap();
+        let usd_id = sqlx::query_scalar::<_, i64>(
+            "INSERT INTO fiat (symbol, name) VALUES ('USD', 'US Dollar') RETURNING id",
+        )
+        .fetch_one(&db.0)
+        .await
+        .unwrap();
+
+        let mut asset_ids = Vec::new();
+        for (symbol, name) in [("BTC", "Bitcoin"), ("ETH", "Ethereum")] {
+            let asset = AssetService::create(
+                CreateAsset {
+                    name: name.to_string(),
+                    symbol: symbol.to_string(),
+                    kind: AssetKind::Cryptocoin,
+                    coin_id: None,
+                },
+                &db,
+            )
+            .await
+            .unwrap();
+            asset_ids.push(asset.id);
+        }
+
+        Fixture {
+            db,
+            usd_id,
+            eth_id: asset_ids.pop().unwrap(),
+            btc_id: asset_ids.pop().unwrap(),
+        }
+    }
+
+    fn buy(f: &Fixture, asset_id: &str, quantity: Decimal) -> CreateTrade {
+        CreateTrade {
+            kind: TradeKind::Buy,
+            trade_date: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            asset_id: asset_id.to_string(),
+            quantity,
+            price: Decimal::new(42_000, 0),
+            fiat_id: f.usd_id,
+            to_asset_id: None,
+            to_quantity: None,
+            fee_asset_id: None,
+            fee_amount: Some(Decimal::new(150, 2)),
+            via_exchange: Some(ExchangeRef::Name("Binance".to_string())),
+            fiat_ramp_id: None,
+            note: None,
+            external_ref: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_trade() {
+        let f = init_db().await;
+        let trade = TradeService::create(buy(&f, &f.btc_id, Decimal::new(5, 1)), &f.db)
+            .await
+            .unwrap();
+
+        assert_eq!(trade.kind, TradeKind::Buy);
+        assert_eq!(trade.asset_symbol, "BTC");
+        assert_eq!(trade.fiat_symbol, "USD");
+        assert_eq!(trade.quantity, Decimal::new(5, 1));
+        assert_eq!(trade.fee_amount, Some(Decimal::new(150, 2)));
+        assert_eq!(trade.to_quantity, None);
+    }
+
+    #[tokio::test]
+    async fn test_create_swap_validation() {
+        let f = init_db().await;
+        let mut swap = buy(&f, &f.btc_id, Decimal::ONE);
+        swap.kind = TradeKind::Swap;
+
+        // swap without the asset received
+        assert!(TradeService::create(swap.clone(), &f.db).await.is_err());
+
+        swap.to_asset_id = Some(f.eth_id.clone());
+        swap.to_quantity = Some(Decimal::new(1_812_345_678, 8));
+        let trade = TradeService::create(swap, &f.db).await.unwrap();
+        assert_eq!(trade.to_asset_symbol.as_deref(), Some("ETH"));
+        assert_eq!(trade.to_quantity, Some(Decimal::new(1_812_345_678, 8)));
+
+        // buy with an asset received
+        let mut invalid_buy = buy(&f, &f.btc_id, Decimal::ONE);
+        invalid_buy.to_asset_id = Some(f.eth_id.clone());
+        assert!(TradeService::create(invalid_buy, &f.db).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_via_exchange_from_fiat_ramp() {
+        let f = init_db().await;
+        sqlx::query("INSERT INTO exchange (id, name) VALUES ('kraken', 'Kraken')")
+            .execute(&f.db.0)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO fiat_ramp (id, fiat_id, fiat_amount, ramp_date, via_exchange, exchange_id, kind) VALUES ('ramp-1', ?, 1000, '2024-01-01', 'Kraken', 'kraken', 'deposit')")
+            .bind(f.usd_id)
+            .execute(&f.db.0)
+            .await
+            .unwrap();
+
+        let mut trade = buy(&f, &f.btc_id, Decimal::ONE);
+        trade.via_exchange = None;
+        trade.fiat_ramp_id = Some("ramp-1".to_string());
+        let trade = TradeService::create(trade, &f.db).await.unwrap();
+        assert_eq!(trade.via_exchange.as_deref(), Some("Kraken"));
+        assert_eq!(trade.exchange_id.as_deref(), Some("kraken"));
+
+        // a name is matched against the exchanges, typos included
+        let mut trade = buy(&f, &f.btc_id, Decimal::ONE);
+        trade.via_exchange = Some(ExchangeRef::Name("kraken ".to_string()));
+        let trade = TradeService::create(trade, &f.db).await.unwrap();
+        assert_eq!(trade.exchange_id.as_deref(), Some("kraken"));
+        let filter = TradeFilter {
+            exchange_id: Some("kraken".to_string()),
+            ..Default::default()
+        };
+        let result = TradeService::get(10, 0, filter, &f.db).await.unwrap();
+        assert_eq!(result.total_count, 2);
+
+        let mut trade = buy(&f, &f.btc_id, Decimal::ONE);
+        trade.fiat_ramp_id = Some("missing-ramp".to_string());
+        assert!(TradeService::create(trade, &f.db).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_get_update_delete_trade() {
+        let f = init_db().await;
+        let btc_trade = TradeService::create(buy(&f, &f.btc_id, Decimal::ONE), &f.db)
+            .await
+            .unwrap();
+        TradeService::create(buy(&f, &f.eth_id, Decimal::TEN), &f.db)
+            .await
+            .unwrap();
+
+        let filter = TradeFilter {
+            asset_id: Some(f.btc_id.clone()),
+            ..Default::default()
+        };
+        let result = TradeService::get(10, 0, filter, &f.db).await.unwrap();
+        assert_eq!(result.total_count, 1);
+        assert_eq!(result.trades[0].id, btc_trade.id);
+
+        let mut update = buy(&f, &f.btc_id, Decimal::TWO);
+        update.kind = TradeKind::Sell;
+        update.fee_amount = None;
+        let updated = TradeService::update(&btc_trade.id, update, &f.db)
+            .await
+            .unwrap();
+        assert_eq!(updated.kind, TradeKind::Sell);
+        assert_eq!(updated.quantity, Decimal::TWO);
+        assert_eq!(updated.fee_amount, None);
+
+        assert_eq!(TradeService::delete(&btc_trade.id, &f.db).await.unwrap(), 1);
+        let result = TradeService::get(10, 0, TradeFilter::default(), &f.db)
+            .await
+            .unwrap();
+        assert_eq!(result.total_count, 1);
+    }
+}
@@ -1,5 +1,5 @@
 pub mod command;
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use serde::{Deserialize, Serialize};
 use sqlx::prelude::FromRow;
 
@@ -7,6 +7,7 @@ use crate::{
     db::{Db, RowId},
     fiat::{Fiat, FiatService},
     fiat_exchanger::FiatExchanger,
+    portfolio::cost_basis::CostBasisMethod,
 };
 
 #[derive(Debug, FromRow, Serialize, Default)]
@@ -14,6 +15,13 @@ pub struct UserSettings {
     pub id: RowId,
     pub locale: String,
     pub default_fiat_id: RowId,
+    pub cost_basis_method: CostBasisMethod,
+    /// hours between scheduled backups, 0 when they are off
+    pub backup_interval_hours: i64,
+    /// number of days whose last backup is kept
+    pub backup_keep_daily: i64,
+    /// number of weeks whose last backup is kept
+    pub backup_keep_weekly: i64,
     #[sqlx(skip)]
     pub fiat: Fiat,
 }
@@ -27,6 +35,10 @@ pub struct CreateUserSettings {
 pub struct UpdateUserSettings {
     pub locale: Option<String>,
     pub default_fiat_id: Option<RowId>,
+    pub cost_basis_method: Option<CostBasisMethod>,
+    pub backup_interval_hours: Option<i64>,
+    pub backup_keep_daily: Option<i64>,
+    pub backup_keep_weekly: Option<i64>,
 }
 
 const DEFAULT_LOCALE: &str = "en";
@@ -86,11 +98,33 @@ pub async fn exists(db: &Db) -> Result<bool> {
 
 /// Update user settings
 pub async fn update(data: UpdateUserSettings, db: &Db) -> Result<UserSettings> {
+    let counts = [
+        data.backup_interval_hours,
+        data.backup_keep_daily,
+        data.backup_keep_weekly,
+    ];
+    if counts.into_iter().flatten().any(|count| count < 0) {
+        bail!("backup interval and retention must not be negative");
+    }
+
     sqlx::query_as::<sqlx::Sqlite, UserSettings>(
-        "UPDATE user_settings SET locale = COALESCE(?, locale), default_fiat_id = COALESCE(?, default_fiat_id) WHERE id = ? RETURNING *",
+        r#"
+        UPDATE user_settings SET
+            locale = COALESCE(?, locale),
+            default_fiat_id = COALESCE(?, default_fiat_id),
+            cost_basis_method = COALESCE(?, cost_basis_method),
+            backup_interval_hours = COALESCE(?, backup_interval_hours),
+            backup_keep_daily = COALESCE(?, backup_keep_daily),
+            backup_keep_weekly = COALESCE(?, backup_keep_weekly)
+        WHERE id = ? RETURNING *
+        "#,
     )
     .bind(data.locale)
     .bind(data.default_fiat_id)
+    .bind(data.cost_basis_method)
+    .bind(data.backup_interval_hours)
+    .bind(data.backup_keep_daily)
+    .bind(data.backup_keep_weekly)
     .bind(ONLY_ONE_USER_SETTINGS)
     .fetch_one(&db.0)
     .await
@@ -102,8 +136,11 @@ pub async fn get(db: &Db) -> Result<UserSettings> {
     let row = sqlx::query(
         r#"
         SELECT
-            u.id, u.locale, u.default_fiat_id,
-            f.id as f_id, f.symbol as f_symbol, f.name as f_name
+            u.id, u.locale, u.default_fiat_id, u.cost_basis_method,
+            u.backup_interval_hours, u.backup_keep_daily, u.backup_keep_weekly,
+            f.id as f_id, f.symbol as f_symbol, f.name as f_name,
+            f.numeric_code as f_numeric_code, f.minor_units as f_minor_units,
+            f.display_symbol as f_display_symbol, f.is_active as f_is_active
         FROM user_settings u
         JOIN fiat f ON u.default_fiat_id = f.id
         LIMIT 1
@@ -117,10 +154,18 @@ pub async fn get(db: &Db) -> Result<UserSettings> {
         id: row.try_get("id")?,
         locale: row.try_get("locale")?,
         default_fiat_id: row.try_get("default_fiat_id")?,
+        cost_basis_method: row.try_get("cost_basis_method")?,
+        backup_interval_hours: row.try_get("backup_interval_hours")?,
+        backup_keep_daily: row.try_get("backup_keep_daily")?,
+        backup_keep_weekly: row.try_get("backup_keep_weekly")?,
         fiat: Fiat {
             id: row.try_get("f_id")?,
             symbol: row.try_get("f_symbol")?,
             name: row.try_get("f_name")?,
+            numeric_code: row.try_get("f_numeric_code")?,
+            minor_units: row.try_get("f_minor_units")?,
+            display_symbol: row.try_get("f_display_symbol")?,
+            is_active: row.try_get("f_is_active")?,
         },
     })
 }
@@ -0,0 +1,140 @@
+use anyhow::{anyhow, bail, Result};
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+
+/// Marks data encrypted by `encrypt`, followed by the version of the layout
+const MAGIC: &[u8; 8] = b"AURAENC1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+const KEY_LEN: usize = 32;
+/// magic, argon2 memory, iterations and parallelism, salt and nonce
+const HEADER_LEN: usize = MAGIC.len() + 3 * 4 + SALT_LEN + NONCE_LEN;
+
+/// Argon2id cost, the OWASP recommendation of 19 MiB and 2 iterations
+const MEMORY_COST_KIB: u32 = 19 * 1024;
+const TIME_COST: u32 = 2;
+const PARALLELISM: u32 = 1;
+
+/// Highest Argon2id cost accepted from a header, a forged header cannot make `decrypt` allocate
+/// gigabytes or spin for minutes before the data is authenticated
+const MAX_MEMORY_COST_KIB: u32 = 256 * 1024;
+const MAX_TIME_COST: u32 = 16;
+const MAX_PARALLELISM: u32 = 8;
+
+/// Whether `data` was encrypted by `encrypt`
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+/// Encrypt with a key derived from the passphrase
+/// - the key is derived with Argon2id from a random salt, the data is sealed with XChaCha20-Poly1305
+/// - the salt and the Argon2 cost are stored in the header so that the cost can grow later
+pub fn encrypt(plaintext: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+
+    let mut data = Vec::with_capacity(HEADER_LEN + plaintext.len() + 16);
+    data.extend_from_slice(MAGIC);
+    for cost in [MEMORY_COST_KIB, TIME_COST, PARALLELISM] {
+        data.extend_from_slice(&cost.to_le_bytes());
+    }
+    data.extend_from_slice(&salt);
+    data.extend_from_slice(&nonce);
+
+    let key = derive_key(passphrase, &salt, MEMORY_COST_KIB, TIME_COST, PARALLELISM)?;
+    let cipher = XChaCha20Poly1305::new(&key.into());
+    // the header is authenticated along with the data
+    let ciphertext = cipher
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: plaintext,
+                aad: &data,
+            },
+        )
+        .map_err(|_| anyhow!("failed to encrypt"))?;
+    data.extend_from_slice(&ciphertext);
+    Ok(data)
+}
+
+/// Decrypt data written by `encrypt`, fails on a wrong passphrase or altered data
+pub fn decrypt(data: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    if !is_encrypted(data) || data.len() < HEADER_LEN {
+        bail!("the data is not encrypted");
+    }
+    let (header, ciphertext) = data.split_at(HEADER_LEN);
+    let cost = |index: usize| {
+        let start = MAGIC.len() + index * 4;
+        u32::from_le_bytes(header[start..start + 4].try_into().expect("4 bytes"))
+    };
+    let salt_start = MAGIC.len() + 3 * 4;
+    let salt = &header[salt_start..salt_start + SALT_LEN];
+    let nonce = XNonce::from_slice(&header[salt_start + SALT_LEN..]);
+
+    let (memory_cost, time_cost, parallelism) = (cost(0), cost(1), cost(2));
+    if memory_cost > MAX_MEMORY_COST_KIB
+        || time_cost > MAX_TIME_COST
+        || parallelism > MAX_PARALLELISM
+    {
+        bail!("the key derivation cost of the data is too high");
+    }
+
+    let key = derive_key(passphrase, salt, memory_cost, time_cost, parallelism)?;
+    let cipher = XChaCha20Poly1305::new(&key.into());
+    cipher
+        .decrypt(
+            nonce,
+            Payload {
+                msg: ciphertext,
+                aad: header,
+            },
+        )
+        .map_err(|_| anyhow!("wrong passphrase or corrupted data"))
+}
+
+fn derive_key(
+    passphrase: &str,
+    salt: &[u8],
+    memory_cost: u32,
+    time_cost: u32,
+    parallelism: u32,
+) -> Result<[u8; KEY_LEN]> {
+    let params = Params::new(memory_cost, time_cost, parallelism, Some(KEY_LEN))
+        .map_err(|e| anyhow!("invalid key derivation parameters: {e}"))?;
+    let mut key = [0u8; KEY_LEN];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| anyhow!("failed to derive key: {e}"))?;
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_and_decrypt() {
+        let plaintext = b"{\"tables\":{}}";
+        let data = encrypt(plaintext, "correct horse").unwrap();
+        assert!(is_encrypted(&data));
+        assert!(!is_encrypted(plaintext));
+        assert_eq!(decrypt(&data, "correct horse").unwrap(), plaintext);
+        assert!(decrypt(&data, "wrong horse").is_err());
+
+        // a fresh salt and nonce every time
+        assert_ne!(data, encrypt(plaintext, "correct horse").unwrap());
+
+        let mut altered = data.clone();
+        *altered.last_mut().unwrap() ^= 1;
+        assert!(decrypt(&altered, "correct horse").is_err());
+
+        // a header asking for 4 GiB of memory is rejected before deriving the key
+        let mut costly = data.clone();
+        costly[MAGIC.len()..MAGIC.len() + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        let error = decrypt(&costly, "correct horse").unwrap_err();
+        assert!(error.to_string().contains("too high"));
+    }
+}
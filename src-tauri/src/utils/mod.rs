@@ -1 +1,3 @@
+pub mod crypto;
 pub mod date_utils;
+pub mod string_utils;
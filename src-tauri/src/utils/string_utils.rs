@@ -0,0 +1,76 @@
+/// Normalize a free-text name for comparison
+/// - lower case, only alphanumeric characters are kept: "Binance ", "binance" and "BINANCE" are equal
+pub fn normalize_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Number of single character insertions, deletions, substitutions and adjacent transpositions
+/// needed to turn `a` into `b` (optimal string alignment distance)
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in rows[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = distance;
+        }
+    }
+    rows[a.len()][b.len()]
+}
+
+/// Whether two normalized names are most likely the same name with a typo
+/// - short names must match exactly, longer names allow one typo, long names two
+pub fn is_similar_name(a: &str, b: &str) -> bool {
+    let max_distance = match a.chars().count().min(b.chars().count()) {
+        0..=4 => 0,
+        5..=9 => 1,
+        _ => 2,
+    };
+    edit_distance(a, b) <= max_distance
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_name() {
+        assert_eq!(normalize_name(" Binance "), "binance");
+        assert_eq!(normalize_name("Crypto.com"), "cryptocom");
+        assert_eq!(normalize_name("crypto com"), "cryptocom");
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("binance", "binance"), 0);
+        assert_eq!(edit_distance("binanace", "binance"), 1);
+        assert_eq!(edit_distance("binacne", "binance"), 1);
+        assert_eq!(edit_distance("", "luno"), 4);
+    }
+
+    #[test]
+    fn test_is_similar_name() {
+        assert!(is_similar_name("binanace", "binance"));
+        assert!(is_similar_name("coinbasepro", "coinbaspro"));
+        // short names must match exactly
+        assert!(!is_similar_name("luno", "lune"));
+        assert!(!is_similar_name("kraken", "coinbase"));
+    }
+}
@@ -354,7 +354,7 @@ export function AddFundingViaFile({ className }: FundingViaFileProps) {
 
         payload.push({
           fiat_id: row.fiatId,
-          fiat_amount: Number(row.amount),
+          fiat_amount: String(row.amount).trim(),
           ramp_date: new Date(row.date),
           via_exchange: row.exchange || "Imported",
           kind: row.kind,
@@ -55,7 +55,7 @@ export default function FundingChartSummary({ refreshTrigger, startDate, endDate
 
     fiatRamps.forEach((ramp) => {
       const date = ramp.ramp_date;
-      const amount = ramp.converted_amount ?? 0;
+      const amount = Number(ramp.converted_amount ?? 0);
       const currentTotal = dateMap.get(date) ?? 0;
       
       // Add for deposits, subtract for withdrawals
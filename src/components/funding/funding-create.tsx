@@ -56,7 +56,7 @@ export default function FundingCreateForm({ onCancel, onCreate }: FundingCreateF
         try {
             await FiatRampCommand.create({
                 fiat_id: parseInt(value.fiat),
-                fiat_amount: value.fiatAmount,
+                fiat_amount: value.fiatAmount.toString(),
                 ramp_date: value.rampDate,
                 via_exchange: value.viaExchange,
                 kind: value.kind
@@ -35,7 +35,7 @@ export default function FundingEditForm({ fiatRamp, onUpdated, onCancel }: Fundi
     const { showSuccess, showError } = useNotification();
     // Initialize state with props
     const [fiat, setFiat] = useState<string>(fiatRamp.from_fiat_id.toString());
-    const [fiatAmount, setFiatAmount] = useState<number>(fiatRamp.fiat_amount);
+    const [fiatAmount, setFiatAmount] = useState<string>(fiatRamp.fiat_amount);
     const [fiats, setFiats] = useState<Fiat[]>([]);
     const [rampDate, setRampDate] = useState<Date | undefined>(fiatRamp.ramp_date ? new Date(fiatRamp.ramp_date) : undefined);
     const [viaExchange, setViaExchange] = useState(fiatRamp.via_exchange);
@@ -70,6 +70,10 @@ export default function FundingEditForm({ fiatRamp, onUpdated, onCancel }: Fundi
             ramp_date: rampDate,
             via_exchange: viaExchange,
             kind: kind,
+            // the form does not edit the stablecoin nor the fee, they are kept
+            asset_id: fiatRamp.asset_id,
+            fee_fiat_id: fiatRamp.fee_fiat_id,
+            fee_amount: fiatRamp.fee_amount,
         };
 
         await FiatRampCommand.update(updatedRamp).then(() => {
@@ -120,7 +124,7 @@ export default function FundingEditForm({ fiatRamp, onUpdated, onCancel }: Fundi
                     id="fiatAmount"
                     step="0.01"
                     value={fiatAmount}
-                    onChange={(e) => setFiatAmount(parseFloat(e.target.value))}
+                    onChange={(e) => setFiatAmount(e.target.value)}
                     className="shadow-sm"
                 />
             </div>
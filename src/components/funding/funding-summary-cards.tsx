@@ -41,7 +41,7 @@ export default function FundingSummaryCards({
   }, [refreshTrigger, startDate, endDate]);
 
   const totalInvested =
-    (summary?.total_deposit ?? 0) - (summary?.total_withdraw ?? 0);
+    Number(summary?.total_deposit ?? 0) - Number(summary?.total_withdraw ?? 0);
   const currencySymbol = summary?.fiat_symbol ?? "$";
 
   return (
@@ -65,7 +65,7 @@ export default function FundingSummaryCards({
             <div className="text-2xl font-bold">
               {loading
                 ? "..."
-                : `${currencySymbol} ${summary?.total_deposit?.toFixed(2) ?? "0.00"}`}
+                : `${currencySymbol} ${Number(summary?.total_deposit ?? 0).toFixed(2)}`}
             </div>
           </CardContent>
         </Card>
@@ -87,7 +87,7 @@ export default function FundingSummaryCards({
             <div className="text-2xl font-bold">
               {loading
                 ? "..."
-                : `${currencySymbol} ${summary?.total_withdraw?.toFixed(2) ?? "0.00"}`}
+                : `${currencySymbol} ${Number(summary?.total_withdraw ?? 0).toFixed(2)}`}
             </div>
           </CardContent>
         </Card>
@@ -255,13 +255,20 @@ export default function FundingTable({ refreshTrigger, onDataChange, startDate,
           );
         },
         cell: ({ row }) => {
-          const amount = parseFloat(row.getValue("fiat_amount"));
+          const amount = Number(row.original.fiat_amount);
           const symbol = row.original.from_fiat_symbol || "USD";
-          const name = row.original.from_fiat_name || "US Dollar";
-          const formatted = new Intl.NumberFormat("en-US", {
-            style: "currency",
-            currency: symbol,
-          }).format(amount);
+          const name =
+            row.original.asset_name || row.original.from_fiat_name || "US Dollar";
+          // a stablecoin is not a currency of Intl, its symbol follows the amount
+          const formatted = row.original.asset_symbol
+            ? `${new Intl.NumberFormat("en-US", {
+                minimumFractionDigits: 2,
+                maximumFractionDigits: 8,
+              }).format(amount)} ${row.original.asset_symbol}`
+            : new Intl.NumberFormat("en-US", {
+                style: "currency",
+                currency: symbol,
+              }).format(amount);
           return (
             <Tooltip>
               <TooltipTrigger asChild>
@@ -295,7 +302,8 @@ export default function FundingTable({ refreshTrigger, onDataChange, startDate,
         cell: ({ row }) => {
           const amount = row.original.converted_amount;
           const symbol = row.original.to_fiat_symbol;
-          const fromSymbol = row.original.from_fiat_symbol;
+          const fromSymbol =
+            row.original.asset_symbol ?? row.original.from_fiat_symbol;
           const conversionRate = row.original.conversion_rate;
           const kind = row.original.kind;
 
@@ -305,7 +313,7 @@ export default function FundingTable({ refreshTrigger, onDataChange, startDate,
           const formatted = new Intl.NumberFormat("en-US", {
             style: "currency",
             currency: symbol || "USD",
-          }).format(amount);
+          }).format(Number(amount));
 
           // Use subtle red for withdrawals, green for deposits
           const colorClass = kind === "withdraw" ? "text-rose-400" : "text-emerald-600";
@@ -325,7 +333,7 @@ export default function FundingTable({ refreshTrigger, onDataChange, startDate,
               </TooltipTrigger>
               <TooltipContent>
                 <p>
-                  1 {fromSymbol} = {conversionRate.toFixed(4)} {symbol}
+                  1 {fromSymbol} = {Number(conversionRate).toFixed(4)} {symbol}
                   {row.original.is_non_working_day &&
                     ` (non-working day: ${
                       row.original.non_working_day_reason === "weekend"
@@ -1,10 +1,14 @@
+export type CostBasisMethod = "fifo" | "lifo" | "hifo" | "average";
+
 export interface UserSettings {
     locale: string;
     default_fiat_id: number;
+    cost_basis_method: CostBasisMethod;
 }
 
 export interface UpdateUserSettings {
     id: number;
     locale?: string;
     default_fiat_id?: number;
+    cost_basis_method?: CostBasisMethod;
 }
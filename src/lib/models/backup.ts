@@ -0,0 +1,21 @@
+export interface BackupTableSummary {
+  name: string;
+  row_count: number;
+}
+
+export interface BackupSummary {
+  /** last migration applied to the database the backup was made from */
+  schema_version: number;
+  app_version: string;
+  created_at: string;
+  include_rate_cache: boolean;
+  tables: BackupTableSummary[];
+}
+
+/** a scheduled copy of the database in the backups folder */
+export interface BackupFile {
+  file_name: string;
+  created_at: string;
+  /** in bytes */
+  size: number;
+}
@@ -0,0 +1,6 @@
+export interface DatabaseStatus {
+  /** the database file is encrypted with a passphrase */
+  encrypted: boolean;
+  /** the database is open, an encrypted one stays closed until unlock_database */
+  unlocked: boolean;
+}
@@ -0,0 +1,27 @@
+import { StringRowId } from "./common";
+
+export type ExchangeKind = "cex" | "dex" | "bank" | "wallet";
+
+/** exchange id or a free-text name, an unknown name creates the exchange */
+export type ExchangeRef = string | { id: StringRowId };
+
+export interface Exchange {
+  id: StringRowId;
+  name: string;
+  kind: ExchangeKind;
+  provider_id: string | null;
+}
+
+export interface CreateExchange {
+  name: string;
+  kind: ExchangeKind;
+  provider_id?: string;
+}
+
+// must have id
+export interface UpdateExchange {
+  id: StringRowId;
+  name?: string;
+  kind?: ExchangeKind;
+  provider_id?: string;
+}
@@ -2,5 +2,47 @@ export interface FiatRate {
     id: number;
     base_fiat_id: number;
     date: string;
-    rate: [string, number][];
-}
\ No newline at end of file
+    rate: [string, string][];
+}
+
+/** rate of one currency in another on a date */
+export interface PairRate {
+    from_symbol: string;
+    to_symbol: string;
+    date: string;
+    /** amount of to_symbol for one from_symbol */
+    rate: string;
+    is_estimated: boolean;
+    is_non_working_day: boolean;
+    non_working_day_reason: string | null;
+    /** id of the provider which supplied the rate, null for a currency in itself */
+    provider: string | null;
+    source: RateSource;
+}
+
+/** a manual rate comes before the provider rates */
+export type RateSource = "provider" | "manual";
+
+/** rate entered by the user, for one ramp or for a pair on a date */
+export interface ManualRate {
+    id: string;
+    from_fiat_id: number;
+    from_fiat_symbol: string;
+    to_fiat_id: number;
+    to_fiat_symbol: string;
+    date: string | null;
+    fiat_ramp_id: string | null;
+    /** amount of to_fiat_symbol for one from_fiat_symbol */
+    rate: string;
+    source: RateSource;
+    note: string | null;
+    created_at: string;
+    updated_at: string;
+}
+
+export interface BackfillReport {
+    /** ramp dates with a rate, exact or of the last working day */
+    filled: number;
+    /** ramp dates left to the per date fetch */
+    unresolved: number;
+}
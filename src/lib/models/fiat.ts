@@ -2,6 +2,14 @@ export interface Fiat {
     id: number;
     symbol: string;
     name: string;
+    /** ISO 4217 numeric code, e.g. "978", null outside ISO 4217 */
+    numeric_code: string | null;
+    /** decimal places of the minor unit, amounts are rounded to them */
+    minor_units: number;
+    /** display symbol, e.g. "€", null outside ISO 4217 */
+    display_symbol: string | null;
+    /** false once the currency was replaced, e.g. HRK by EUR */
+    is_active: boolean;
     created_at: Date;
     updated_at: Date;
 }
@@ -1,4 +1,6 @@
 import { RowId, StringRowId } from "./common";
+import { ExchangeRef } from "./exchange";
+import { RateSource } from "./fiat-rate";
 
 export type RampKind = "deposit" | "withdraw";
 
@@ -11,10 +13,32 @@ export interface SortOptions {
 
 export interface CreateFiatRamp {
   fiat_id: RowId;
-  fiat_amount: number;
+  fiat_amount: string;
   ramp_date: Date;
-  via_exchange: string;
+  via_exchange: ExchangeRef;
+  kind: RampKind;
+  /** reference of the ramp at the exchange, e.g. the transaction id of a statement */
+  external_ref?: string | null;
+  /** stablecoin the ramp was made in, fiat_id is then replaced by the fiat of its peg */
+  asset_id?: StringRowId | null;
+  /** fiat the fee was paid in, null means the fee was paid in the currency of the ramp */
+  fee_fiat_id?: RowId | null;
+  /** part of fiat_amount, the ramp nets fiat_amount less the fee */
+  fee_amount?: string | null;
+}
+
+export interface FiatRamp {
+  id: StringRowId;
+  fiat_id: RowId;
+  asset_id: StringRowId | null;
+  fiat_amount: string;
+  ramp_date: string;
   kind: RampKind;
+  via_exchange: string;
+  exchange_id: StringRowId | null;
+  external_ref: string | null;
+  fee_fiat_id: RowId | null;
+  fee_amount: string | null;
 }
 
 export interface FiatRampView {
@@ -25,25 +49,42 @@ export interface FiatRampView {
   to_fiat_id: RowId;
   to_fiat_symbol: string;
   to_fiat_name: string;
-  conversion_rate: number | null;
+  to_fiat_minor_units: number;
+  conversion_rate: string | null;
   ramp_date: string;
-  fiat_amount: number;
+  fiat_amount: string;
   kind: RampKind;
   via_exchange: string;
+  exchange_id: StringRowId | null;
   is_estimated: boolean;
   is_non_working_day: boolean;
   non_working_day_reason: string | null;
-  converted_amount: number | null;
+  converted_amount: string | null;
+  /** where the conversion rate came from, null without a rate */
+  rate_source: RateSource | null;
+  /** stablecoin the ramp was made in, from_fiat_* is then the fiat of its peg */
+  asset_id: StringRowId | null;
+  asset_symbol: string | null;
+  asset_name: string | null;
+  fee_fiat_id: RowId | null;
+  fee_fiat_symbol: string | null;
+  fee_amount: string | null;
+  /** null without a fee or without a rate for it */
+  converted_fee: string | null;
 }
 
 // must have id
 export interface UpdateFiatRamp {
   id: StringRowId;
   fiat_id?: RowId;
-  fiat_amount?: number;
+  fiat_amount?: string;
   ramp_date?: Date;
-  via_exchange?: string;
+  via_exchange?: ExchangeRef;
   kind?: RampKind;
+  /** a ramp updated without asset_id is a ramp in fiat_id */
+  asset_id?: StringRowId | null;
+  fee_fiat_id?: RowId | null;
+  fee_amount?: string | null;
 }
 
 export interface FiatRampPagination {
@@ -51,9 +92,64 @@ export interface FiatRampPagination {
   fiat_ramps: FiatRampView[];
 }
 
+export type SummaryInterval = "day" | "week" | "month" | "year";
+
+export interface FiatRampSummaryBucket {
+  period_start: string;
+  deposit: string;
+  withdraw: string;
+  fee: string;
+  net: string;
+  cumulative_net: string;
+}
+
 export interface FiatRampSummary {
-  total_deposit: number;
-  total_withdraw: number;
+  /** gross deposits, fees included */
+  total_deposit: string;
+  /** gross withdrawals, fees included */
+  total_withdraw: string;
+  deposit_fee: string;
+  withdraw_fee: string;
+  total_fee: string;
+  /** deposits less their fees */
+  net_deposit: string;
+  /** withdrawals less their fees */
+  net_withdraw: string;
+  opening_net_invested: string;
+  net_invested: string;
+  fiat_symbol: string;
+  fiat_name: string;
+  interval: SummaryInterval;
+  data: FiatRampSummaryBucket[];
+}
+
+export interface FiatRampExchangeTotal {
+  via_exchange: string;
+  deposit: string;
+  withdraw: string;
+  net: string;
+  ramp_count: number;
+  unconverted_count: number;
+}
+
+export interface FiatRampCurrencyTotal {
+  fiat_id: RowId;
+  /** stablecoin of the ramps, fiat_symbol and fiat_name are then those of the stablecoin */
+  asset_id: StringRowId | null;
+  fiat_symbol: string;
+  fiat_name: string;
+  deposit: string;
+  withdraw: string;
+  net: string;
+  source_deposit: string;
+  source_withdraw: string;
+  ramp_count: number;
+  unconverted_count: number;
+}
+
+export interface FiatRampBreakdown {
   fiat_symbol: string;
   fiat_name: string;
+  by_exchange: FiatRampExchangeTotal[];
+  by_currency: FiatRampCurrencyTotal[];
 }
@@ -0,0 +1,109 @@
+import { RowId, StringRowId } from "./common";
+import { ExchangeRef } from "./exchange";
+import { CreateFiatRamp, RampKind } from "./fiatRamp";
+
+export type DateFormat =
+  | { type: "auto" }
+  | { type: "pattern"; format: string }
+  | { type: "timestamp_s" }
+  | { type: "timestamp_ms" }
+  | { type: "excel" };
+
+export interface ColumnMapping {
+  date: string;
+  amount: string;
+  currency?: string;
+  /** when missing the sign of the amount decides */
+  kind?: string;
+  exchange?: string;
+  /** transaction id at the exchange, re-imported rows are matched on it */
+  external_ref?: string;
+  date_format?: DateFormat;
+  /** raw kind value to its ramp kind, null to skip the row */
+  kind_values?: Record<string, RampKind | null>;
+  default_fiat_id?: RowId;
+  via_exchange?: string;
+}
+
+export interface ImportMapping {
+  id: StringRowId;
+  name: string;
+  mapping: ColumnMapping;
+}
+
+export type ImportRowStatus =
+  | "accepted"
+  | "rejected"
+  | "skipped"
+  | "duplicate"
+  | "conflict";
+
+/** what an import does with rows matching a recorded fiat ramp */
+export type DuplicatePolicy = "skip" | "merge";
+
+export type TradeKind = "buy" | "sell" | "swap";
+
+/** trade read from a statement, decimals are strings */
+export interface ImportedTrade {
+  kind: TradeKind;
+  trade_date: string;
+  asset_id: StringRowId;
+  quantity: string;
+  price: string;
+  fiat_id: RowId;
+  to_asset_id: StringRowId | null;
+  to_quantity: string | null;
+  fee_asset_id: StringRowId | null;
+  fee_amount: string | null;
+  via_exchange: ExchangeRef | null;
+  fiat_ramp_id: StringRowId | null;
+  note: string | null;
+  external_ref: string | null;
+}
+
+export interface ImportRow {
+  /** line of the row in the file, starting at 1 */
+  row_number: number;
+  status: ImportRowStatus;
+  raw: string[];
+  ramp: (Omit<CreateFiatRamp, "ramp_date"> & { ramp_date: string }) | null;
+  trade: ImportedTrade | null;
+  /** the recorded fiat ramp or trade a duplicate or conflict row matches */
+  duplicate_of: StringRowId | null;
+  errors: string[];
+}
+
+export interface FiatRampImportReport {
+  rows: ImportRow[];
+  accepted_count: number;
+  rejected_count: number;
+  skipped_count: number;
+  duplicate_count: number;
+  conflict_count: number;
+  imported_count: number;
+  merged_count: number;
+  /** rates of the imported ramps that could not be fetched, the ramps are retried later */
+  rate_errors: string[];
+}
+
+export interface StatementParserInfo {
+  id: string;
+  exchange: string;
+}
+
+export interface StatementImportReport {
+  parser_id: string;
+  exchange: string;
+  rows: ImportRow[];
+  accepted_count: number;
+  rejected_count: number;
+  skipped_count: number;
+  duplicate_count: number;
+  conflict_count: number;
+  imported_ramp_count: number;
+  imported_trade_count: number;
+  merged_ramp_count: number;
+  merged_trade_count: number;
+  /** rates of the imported ramps that could not be fetched, the ramps are retried later */
+  rate_errors: string[];
+}
@@ -0,0 +1,55 @@
+import { RowId, StringRowId } from "./common";
+import { ExchangeRef } from "./exchange";
+import { RampKind } from "./fiatRamp";
+
+export interface RecurringRamp {
+  id: StringRowId;
+  name: string;
+  fiat_id: RowId;
+  fiat_symbol: string;
+  /** stablecoin the ramps are made in */
+  asset_id: StringRowId | null;
+  fiat_amount: string;
+  fee_fiat_id: RowId | null;
+  fee_amount: string | null;
+  via_exchange: string;
+  exchange_id: StringRowId | null;
+  kind: RampKind;
+  /** RRULE of RFC 5545 without DTSTART, e.g. FREQ=MONTHLY;BYMONTHDAY=1 */
+  rrule: string;
+  start_date: string;
+  /** the occurrences are proposed for confirmation rather than created */
+  needs_confirmation: boolean;
+  is_paused: boolean;
+  /** the occurrences up to this date are created or proposed already */
+  materialized_until: string | null;
+}
+
+export interface CreateRecurringRamp {
+  name: string;
+  fiat_id: RowId;
+  asset_id?: StringRowId | null;
+  fiat_amount: string;
+  fee_fiat_id?: RowId | null;
+  fee_amount?: string | null;
+  via_exchange: ExchangeRef;
+  kind: RampKind;
+  rrule: string;
+  start_date: Date;
+  needs_confirmation?: boolean;
+}
+
+export interface UpdateRecurringRamp extends CreateRecurringRamp {
+  id: StringRowId;
+}
+
+export interface RecurringRampProposal {
+  id: StringRowId;
+  recurring_ramp_id: StringRowId;
+  name: string;
+  ramp_date: string;
+  fiat_symbol: string;
+  fiat_amount: string;
+  kind: RampKind;
+  via_exchange: string;
+}
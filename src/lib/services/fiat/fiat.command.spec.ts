@@ -19,6 +19,10 @@ describe('FiatCommand', () => {
         id: 1,
         symbol: 'USD',
         name: 'US Dollar',
+        numeric_code: '840',
+        minor_units: 2,
+        display_symbol: '$',
+        is_active: true,
         created_at: new Date(),
         updated_at: new Date(),
     };
@@ -49,4 +53,19 @@ describe('FiatCommand', () => {
         expect(fiat).toEqual(mockFiat);
         clearMocks();
     });
+
+    it('should format an amount', async () => {
+        mockIPC((cmd, args) => {
+            if (cmd === 'format_fiat_amount') {
+                const payload = args as { amount: string; symbol: string };
+                if (payload?.amount === '-1234.5' && payload?.symbol === 'USD') {
+                    return '-$1,234.50';
+                }
+            }
+        });
+
+        const formatted = await FiatCommand.formatAmount('-1234.5', 'USD');
+        expect(formatted).toBe('-$1,234.50');
+        clearMocks();
+    });
 });
\ No newline at end of file
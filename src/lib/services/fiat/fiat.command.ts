@@ -3,7 +3,8 @@ import { Fiat } from "../../models/fiat"
 
 enum FiatCommandList {
     GET_ALL_CURRENCIES = 'get_all_currencies',
-    GET_CURRENCIES_BY_SYMBOL = 'get_currencies_by_symbol'
+    GET_CURRENCIES_BY_SYMBOL = 'get_currencies_by_symbol',
+    FORMAT_FIAT_AMOUNT = 'format_fiat_amount'
 }
 
 export class FiatCommand {
@@ -14,4 +15,14 @@ export class FiatCommand {
     public static getCurrencyBySymbol(symbol: string) {
         return invoke<Fiat>(FiatCommandList.GET_CURRENCIES_BY_SYMBOL, { symbol })
     }
+
+    /**
+     * Format an amount for the locale of the user settings, rounded to the minor units of the currency
+     * @param amount Decimal amount, e.g. "-1234.5"
+     * @param symbol Currency code, e.g. "EUR"
+     * @returns string = e.g. "-€1,234.50"
+     */
+    public static formatAmount(amount: string, symbol: string) {
+        return invoke<string>(FiatCommandList.FORMAT_FIAT_AMOUNT, { amount, symbol })
+    }
 }
@@ -1,5 +1,5 @@
 import { StringRowId } from "@/lib/models/common";
-import { CreateFiatRamp, FiatRampPagination, UpdateFiatRamp, SortOptions, FiatRampSummary } from "@/lib/models/fiatRamp";
+import { CreateFiatRamp, FiatRampPagination, UpdateFiatRamp, SortOptions, FiatRampSummary, SummaryInterval, FiatRampBreakdown } from "@/lib/models/fiatRamp";
 import { invoke } from "@tauri-apps/api/core";
 import { format } from "date-fns";
 
@@ -10,6 +10,7 @@ enum FiatRampCommandList {
     DELETE = 'delete_fiat_ramp',
     CREATE_BULK = 'create_fiat_ramps_bulk',
     GET_SUMMARY = 'get_fiat_ramp_summary',
+    GET_BREAKDOWN = 'get_fiat_ramp_breakdown',
     GET_DATE_RANGE = 'get_fiat_ramp_date_range'
 }
 
@@ -104,10 +105,25 @@ export class FiatRampCommand {
      * Get fiat ramp summary
      * @param startDate Optional start date filter
      * @param endDate Optional end date filter
+     * @param interval Optional bucket size of the series, defaults to month
      * @returns FiatRampSummary
      */
-    public static getSummary(startDate?: Date | null, endDate?: Date | null) {
+    public static getSummary(startDate?: Date | null, endDate?: Date | null, interval?: SummaryInterval) {
         return invoke<FiatRampSummary>(FiatRampCommandList.GET_SUMMARY, {
+            startDate: startDate ? format(startDate, 'yyyy-MM-dd') : undefined,
+            endDate: endDate ? format(endDate, 'yyyy-MM-dd') : undefined,
+            interval
+        });
+    }
+
+    /**
+     * Get the totals per exchange and per source currency
+     * @param startDate Optional start date filter
+     * @param endDate Optional end date filter
+     * @returns FiatRampBreakdown
+     */
+    public static getBreakdown(startDate?: Date | null, endDate?: Date | null) {
+        return invoke<FiatRampBreakdown>(FiatRampCommandList.GET_BREAKDOWN, {
             startDate: startDate ? format(startDate, 'yyyy-MM-dd') : undefined,
             endDate: endDate ? format(endDate, 'yyyy-MM-dd') : undefined
         });
@@ -0,0 +1,135 @@
+import { StringRowId } from "@/lib/models/common";
+import { FiatRamp } from "@/lib/models/fiatRamp";
+import { CreateRecurringRamp, RecurringRamp, RecurringRampProposal, UpdateRecurringRamp } from "@/lib/models/recurringRamp";
+import { invoke } from "@tauri-apps/api/core";
+import { format } from "date-fns";
+
+enum RecurringRampCommandList {
+    CREATE = 'create_recurring_ramp',
+    GET = 'get_recurring_ramps',
+    UPDATE = 'update_recurring_ramp',
+    SET_PAUSED = 'set_recurring_ramp_paused',
+    DELETE = 'delete_recurring_ramp',
+    PREVIEW = 'preview_recurring_ramp',
+    PREVIEW_SCHEDULE = 'preview_recurring_schedule',
+    GET_PROPOSALS = 'get_recurring_ramp_proposals',
+    CONFIRM_PROPOSAL = 'confirm_recurring_ramp_proposal',
+    DISMISS_PROPOSAL = 'dismiss_recurring_ramp_proposal'
+}
+
+const toPayload = (recurringRamp: CreateRecurringRamp) => ({
+    name: recurringRamp.name,
+    fiat_id: recurringRamp.fiat_id,
+    asset_id: recurringRamp.asset_id,
+    fiat_amount: recurringRamp.fiat_amount,
+    fee_fiat_id: recurringRamp.fee_fiat_id,
+    fee_amount: recurringRamp.fee_amount,
+    via_exchange: recurringRamp.via_exchange,
+    kind: recurringRamp.kind,
+    rrule: recurringRamp.rrule,
+    start_date: format(recurringRamp.start_date, 'yyyy-MM-dd'),
+    needs_confirmation: recurringRamp.needs_confirmation ?? false
+});
+
+export class RecurringRampCommand {
+
+    /**
+     * Create a new recurring ramp
+     * @param recurringRamp
+     * @returns RecurringRamp
+     */
+    public static create(recurringRamp: CreateRecurringRamp) {
+        return invoke<RecurringRamp>(RecurringRampCommandList.CREATE, {
+            recurringRamp: toPayload(recurringRamp)
+        });
+    }
+
+    /**
+     * Get all recurring ramps
+     * @returns RecurringRamp[]
+     */
+    public static get() {
+        return invoke<RecurringRamp[]>(RecurringRampCommandList.GET);
+    }
+
+    /**
+     * Replace the recurring ramp, the ramps it created already are left as they are
+     * @param recurringRamp
+     * @returns RecurringRamp
+     */
+    public static update(recurringRamp: UpdateRecurringRamp) {
+        return invoke<RecurringRamp>(RecurringRampCommandList.UPDATE, {
+            recurringRamp: { id: recurringRamp.id, ...toPayload(recurringRamp) }
+        });
+    }
+
+    /**
+     * Pause or resume the recurring ramp, the occurrences of the pause are skipped
+     * @param id
+     * @param isPaused
+     * @returns RecurringRamp
+     */
+    public static setPaused(id: StringRowId, isPaused: boolean) {
+        return invoke<RecurringRamp>(RecurringRampCommandList.SET_PAUSED, { id, isPaused });
+    }
+
+    /**
+     * Delete the recurring ramp with its proposals
+     * @param id
+     * @returns number = number of rows affected
+     */
+    public static delete(id: StringRowId) {
+        return invoke<number>(RecurringRampCommandList.DELETE, { id });
+    }
+
+    /**
+     * Get the next occurrences of the recurring ramp
+     * @param id
+     * @param count Optional number of occurrences, defaults to 12
+     * @returns string[] = dates of the occurrences
+     */
+    public static preview(id: StringRowId, count?: number) {
+        return invoke<string[]>(RecurringRampCommandList.PREVIEW, { id, count });
+    }
+
+    /**
+     * Get the first occurrences of a schedule before it is saved
+     * @param rrule
+     * @param startDate
+     * @param count Optional number of occurrences, defaults to 12
+     * @returns string[] = dates of the occurrences
+     */
+    public static previewSchedule(rrule: string, startDate: Date, count?: number) {
+        return invoke<string[]>(RecurringRampCommandList.PREVIEW_SCHEDULE, {
+            rrule,
+            startDate: format(startDate, 'yyyy-MM-dd'),
+            count
+        });
+    }
+
+    /**
+     * Get the occurrences waiting to be confirmed
+     * @returns RecurringRampProposal[]
+     */
+    public static getProposals() {
+        return invoke<RecurringRampProposal[]>(RecurringRampCommandList.GET_PROPOSALS);
+    }
+
+    /**
+     * Create the fiat ramp of the proposal
+     * @param id
+     * @returns FiatRamp
+     */
+    public static confirmProposal(id: StringRowId) {
+        return invoke<FiatRamp>(RecurringRampCommandList.CONFIRM_PROPOSAL, { id });
+    }
+
+    /**
+     * Dismiss the proposal without creating its fiat ramp
+     * @param id
+     * @returns number = number of rows affected
+     */
+    public static dismissProposal(id: StringRowId) {
+        return invoke<number>(RecurringRampCommandList.DISMISS_PROPOSAL, { id });
+    }
+}